@@ -0,0 +1,141 @@
+use super::{AIContext, CompletionLevel, CompletionResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+const DEFAULT_CAPACITY: usize = 128;
+const DEFAULT_TTL: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub capacity: usize,
+    pub ttl_secs: u64,
+}
+
+/// LRU cache of completion results keyed by a hash of the request context
+#[derive(Debug)]
+pub struct CompletionCache {
+    capacity: usize,
+    ttl: Duration,
+    entries: HashMap<u64, (Instant, CompletionResult)>,
+    order: VecDeque<u64>, // Least recently used at the front
+    hits: u64,
+    misses: u64,
+}
+
+impl Default for CompletionCache {
+    fn default() -> Self {
+        CompletionCache {
+            capacity: DEFAULT_CAPACITY,
+            ttl: DEFAULT_TTL,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+}
+
+impl CompletionCache {
+    /// Hash the normalized request; `file_content` covers edits the context alone can't see
+    pub fn key(context: &AIContext, level: &CompletionLevel, file_content: Option<&str>) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+
+        context.project_path.trim_end_matches('/').hash(&mut hasher);
+        context.current_file.hash(&mut hasher);
+        context
+            .selected_text
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .hash(&mut hasher);
+        context.cursor_position.line.hash(&mut hasher);
+        context.cursor_position.column.hash(&mut hasher);
+        context.prefix.hash(&mut hasher);
+        context.suffix.hash(&mut hasher);
+        level.hash(&mut hasher);
+        file_content.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    pub fn get(&mut self, key: u64) -> Option<CompletionResult> {
+        let fresh = match self.entries.get(&key) {
+            Some((stored_at, _)) => stored_at.elapsed() < self.ttl,
+            None => false,
+        };
+
+        if !fresh {
+            self.remove(key);
+            self.misses += 1;
+            return None;
+        }
+
+        self.hits += 1;
+        self.touch(key);
+        self.entries.get(&key).map(|(_, result)| result.clone())
+    }
+
+    pub fn insert(&mut self, key: u64, result: CompletionResult) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.remove(key);
+        while self.entries.len() >= self.capacity {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+
+        self.entries.insert(key, (Instant::now(), result));
+        self.order.push_back(key);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    pub fn configure(&mut self, capacity: usize, ttl: Duration) {
+        self.capacity = capacity;
+        self.ttl = ttl;
+
+        while self.entries.len() > self.capacity {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.entries.len(),
+            capacity: self.capacity,
+            ttl_secs: self.ttl.as_secs(),
+        }
+    }
+
+    fn touch(&mut self, key: u64) {
+        self.order.retain(|k| *k != key);
+        self.order.push_back(key);
+    }
+
+    fn remove(&mut self, key: u64) {
+        if self.entries.remove(&key).is_some() {
+            self.order.retain(|k| *k != key);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::error::CodifyError;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// Message of the Cancelled error returned by requests that were superseded
+/// or cancelled, so the frontend can tell them apart from real failures
+pub const CANCELLED_ERROR: &str = "Request was cancelled";
+
+/// In-flight AI requests keyed by what they are for (e.g. the current file)
+#[derive(Default)]
+pub struct RequestRegistry {
+    active: Mutex<HashMap<String, (u64, CancellationToken)>>,
+    next_id: std::sync::atomic::AtomicU64,
+}
+
+/// Handle for one registered request; unregisters itself when dropped
+pub struct ActiveRequest<'a> {
+    registry: &'a RequestRegistry,
+    key: String,
+    id: u64,
+    pub token: CancellationToken,
+}
+
+impl RequestRegistry {
+    /// Register a request under `key`, cancelling whatever was running for it
+    pub fn begin(&self, key: &str) -> ActiveRequest<'_> {
+        let id = self
+            .next_id
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let token = CancellationToken::new();
+
+        let previous = self
+            .active
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), (id, token.clone()));
+        if let Some((_, previous)) = previous {
+            previous.cancel();
+        }
+
+        ActiveRequest {
+            registry: self,
+            key: key.to_string(),
+            id,
+            token,
+        }
+    }
+
+    /// Cancel the in-flight request for `key`, if any
+    pub fn cancel(&self, key: &str) -> bool {
+        match self.active.lock().unwrap().remove(key) {
+            Some((_, token)) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl ActiveRequest<'_> {
+    /// Run `future` unless the request is cancelled first; dropping the
+    /// future aborts the underlying HTTP request
+    pub async fn run<T>(
+        &self,
+        future: impl std::future::Future<Output = Result<T, CodifyError>>,
+    ) -> Result<T, CodifyError> {
+        tokio::select! {
+            _ = self.token.cancelled() => Err(CodifyError::Cancelled(CANCELLED_ERROR.to_string())),
+            result = future => result,
+        }
+    }
+}
+
+impl Drop for ActiveRequest<'_> {
+    fn drop(&mut self) {
+        let mut active = self.registry.active.lock().unwrap();
+        // Only unregister if a newer request hasn't already replaced this one
+        if active.get(&self.key).map(|(id, _)| *id == self.id).unwrap_or(false) {
+            active.remove(&self.key);
+        }
+    }
+}
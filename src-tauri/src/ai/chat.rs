@@ -0,0 +1,162 @@
+use super::{count_tokens, ChatMessage};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::Manager;
+
+const CONVERSATIONS_DIR: &str = "conversations";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: String,
+    pub title: String,
+    pub messages: Vec<ChatMessage>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl Conversation {
+    pub fn new(title: Option<String>, system_prompt: Option<String>) -> Self {
+        let now = chrono::Utc::now().to_rfc3339();
+
+        Conversation {
+            id: uuid::Uuid::new_v4().to_string(),
+            title: title.unwrap_or_else(|| "New conversation".to_string()),
+            messages: system_prompt.into_iter().map(ChatMessage::system).collect(),
+            created_at: now.clone(),
+            updated_at: now,
+        }
+    }
+
+    pub fn push(&mut self, message: ChatMessage) {
+        self.messages.push(message);
+        self.updated_at = chrono::Utc::now().to_rfc3339();
+    }
+
+    /// The messages to send: system messages plus as much recent history as
+    /// fits in `budget` tokens. The newest message is always included.
+    pub fn history_within(&self, budget: usize) -> Vec<ChatMessage> {
+        let (system, turns): (Vec<&ChatMessage>, Vec<&ChatMessage>) =
+            self.messages.iter().partition(|m| m.role == "system");
+
+        let mut remaining =
+            budget.saturating_sub(system.iter().map(|m| count_tokens(&m.content)).sum());
+        let mut kept = vec![];
+        for message in turns.iter().rev() {
+            let tokens = count_tokens(&message.content);
+            if tokens > remaining && !kept.is_empty() {
+                break;
+            }
+            remaining = remaining.saturating_sub(tokens);
+            kept.push((*message).clone());
+        }
+        kept.reverse();
+
+        system.into_iter().cloned().chain(kept).collect()
+    }
+}
+
+/// Conversations persisted as one JSON file each under the app data dir
+#[derive(Default)]
+pub struct ConversationStore {
+    // Serializes updates per conversation so concurrent sends don't drop messages
+    locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl ConversationStore {
+    pub fn lock_for(&self, id: &str) -> Arc<tokio::sync::Mutex<()>> {
+        self.locks
+            .lock()
+            .unwrap()
+            .entry(id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    fn dir(app: &tauri::AppHandle) -> Result<PathBuf, CodifyError> {
+        app.path()
+            .app_data_dir()
+            .map(|dir| dir.join(CONVERSATIONS_DIR))
+            .map_err(|e| CodifyError::Io(format!("Failed to locate data directory: {}", e)))
+    }
+
+    fn path(app: &tauri::AppHandle, id: &str) -> Result<PathBuf, CodifyError> {
+        // Ids come from the frontend, so keep them from naming other files
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(CodifyError::InvalidInput(format!(
+                "Invalid conversation id: {}",
+                id
+            )));
+        }
+        Ok(Self::dir(app)?.join(format!("{}.json", id)))
+    }
+
+    pub fn load(&self, app: &tauri::AppHandle, id: &str) -> Result<Conversation, CodifyError> {
+        let path = Self::path(app, id)?;
+        let text = std::fs::read_to_string(&path)
+            .map_err(|_| CodifyError::NotFound(format!("Conversation not found: {}", id)))?;
+
+        serde_json::from_str(&text)
+            .map_err(|e| CodifyError::Internal(format!("Conversation {} is corrupt: {}", id, e)))
+    }
+
+    pub fn save(
+        &self,
+        app: &tauri::AppHandle,
+        conversation: &Conversation,
+    ) -> Result<(), CodifyError> {
+        let dir = Self::dir(app)?;
+        std::fs::create_dir_all(&dir).map_err(|e| {
+            CodifyError::Io(format!("Failed to create conversations directory: {}", e))
+        })?;
+
+        let json = serde_json::to_string_pretty(conversation)
+            .map_err(|e| CodifyError::Internal(format!("Failed to encode conversation: {}", e)))?;
+        crate::storage::write_project_file(
+            &dir.to_string_lossy(),
+            &format!("{}.json", conversation.id),
+            &json,
+            false,
+        )?;
+
+        Ok(())
+    }
+
+    /// Every saved conversation, most recently updated first
+    pub fn list(&self, app: &tauri::AppHandle) -> Result<Vec<Conversation>, CodifyError> {
+        let dir = Self::dir(app)?;
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            return Ok(vec![]);
+        };
+
+        let mut conversations: Vec<Conversation> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .path()
+                    .extension()
+                    .map(|e| e == "json")
+                    .unwrap_or(false)
+            })
+            .filter_map(|entry| {
+                let text = std::fs::read_to_string(entry.path()).ok()?;
+                match serde_json::from_str(&text) {
+                    Ok(conversation) => Some(conversation),
+                    Err(e) => {
+                        log::warn!(
+                            "Skipping corrupt conversation {}: {}",
+                            entry.path().display(),
+                            e
+                        );
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        conversations.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        Ok(conversations)
+    }
+}
@@ -0,0 +1,75 @@
+use crate::error::CodifyError;
+
+/// How documentation for a definition is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocStyle {
+    JsDoc,     // `/** */` with @param tags and types in braces
+    TsDoc,     // `/** */` with @param tags; types come from the signature
+    RustDoc,   // `///` lines
+    Docstring, // `"""` as the first statement of the body
+}
+
+/// Comment syntax for a language
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommentStyle {
+    pub line: Option<&'static str>, // None when the language only has block comments
+    pub block: Option<(&'static str, &'static str)>,
+    pub doc: Option<DocStyle>, // None when docstrings can't be generated for it
+}
+
+impl CommentStyle {
+    const fn new(
+        line: Option<&'static str>,
+        block: Option<(&'static str, &'static str)>,
+        doc: Option<DocStyle>,
+    ) -> Self {
+        CommentStyle { line, block, doc }
+    }
+
+    /// `text` as a one-line comment
+    pub fn comment(&self, text: &str) -> String {
+        match (self.line, self.block) {
+            (Some(marker), _) => format!("{} {}", marker, text).trim_end().to_string(),
+            (None, Some((open, close))) => format!("{} {} {}", open, text, close),
+            (None, None) => unreachable!("every known style has line or block comments"),
+        }
+    }
+}
+
+const C_BLOCK: Option<(&str, &str)> = Some(("/*", "*/"));
+const HTML_BLOCK: Option<(&str, &str)> = Some(("<!--", "-->"));
+
+/// The comment syntax of `language`, as named by detect_language
+///
+/// Unknown languages are an error rather than a guess, since a comment in
+/// the wrong syntax breaks the file it is inserted into. JSON has no
+/// comments; Vue and Svelte files mix syntaxes by section.
+pub fn comment_style(language: &str) -> Result<CommentStyle, CodifyError> {
+    let style = match language {
+        "typescript" | "typescriptreact" => {
+            CommentStyle::new(Some("//"), C_BLOCK, Some(DocStyle::TsDoc))
+        }
+        "javascript" | "javascriptreact" => {
+            CommentStyle::new(Some("//"), C_BLOCK, Some(DocStyle::JsDoc))
+        }
+        "rust" => CommentStyle::new(Some("//"), C_BLOCK, Some(DocStyle::RustDoc)),
+        "python" => CommentStyle::new(Some("#"), None, Some(DocStyle::Docstring)),
+        "go" | "scss" | "java" | "c" | "cpp" | "csharp" => {
+            CommentStyle::new(Some("//"), C_BLOCK, None)
+        }
+        "css" => CommentStyle::new(None, C_BLOCK, None),
+        "html" | "markdown" => CommentStyle::new(None, HTML_BLOCK, None),
+        "shell" | "yaml" | "toml" | "dockerfile" | "makefile" | "ruby" => {
+            CommentStyle::new(Some("#"), None, None)
+        }
+        "sql" => CommentStyle::new(Some("--"), C_BLOCK, None),
+        "lua" => CommentStyle::new(Some("--"), None, None),
+        _ => {
+            return Err(CodifyError::InvalidInput(format!(
+                "Comment style not supported for language: {}",
+                language
+            )))
+        }
+    };
+    Ok(style)
+}
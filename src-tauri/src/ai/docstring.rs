@@ -0,0 +1,210 @@
+use super::{comment_style, DocStyle};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocParam {
+    pub name: String,
+    #[serde(rename = "type", default)]
+    pub type_name: Option<String>,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocReturn {
+    #[serde(rename = "type", default)]
+    pub type_name: Option<String>,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// The documentation content the model is asked for; the comment syntax is
+/// produced here so it is always well-formed for the language
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocContent {
+    pub summary: String,
+    #[serde(default)]
+    pub params: Vec<DocParam>,
+    #[serde(default)]
+    pub returns: Option<DocReturn>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedDocstring {
+    pub docstring: String, // Indented comment lines, ending in a newline
+    pub insert_line: u32,  // 1-based; insert before this line
+    pub name: String,
+    pub kind: String,
+    pub language: String,
+}
+
+fn summary_lines(summary: &str) -> Vec<String> {
+    summary
+        .trim()
+        .lines()
+        .map(|line| line.trim_end().to_string())
+        .collect()
+}
+
+/// JSDoc for JavaScript (types in braces) or TSDoc for TypeScript (types
+/// come from the signature, so none are written)
+fn js_doc(content: &DocContent, typed: bool) -> Vec<String> {
+    let mut lines = summary_lines(&content.summary);
+
+    let type_tag = |type_name: &Option<String>| match type_name {
+        Some(t) if typed && !t.is_empty() => format!("{{{}}} ", t),
+        _ => String::new(),
+    };
+
+    if !content.params.is_empty() || content.returns.is_some() {
+        lines.push(String::new());
+    }
+    for param in &content.params {
+        lines.push(format!(
+            "@param {}{} - {}",
+            type_tag(&param.type_name),
+            param.name,
+            param.description
+        ));
+    }
+    if let Some(returns) = &content.returns {
+        lines.push(format!(
+            "@returns {}{}",
+            type_tag(&returns.type_name),
+            returns.description
+        ));
+    }
+
+    let mut doc = vec!["/**".to_string()];
+    doc.extend(lines.into_iter().map(|line| {
+        if line.is_empty() {
+            " *".to_string()
+        } else {
+            format!(" * {}", line)
+        }
+    }));
+    doc.push(" */".to_string());
+    doc
+}
+
+fn rust_doc(content: &DocContent) -> Vec<String> {
+    let mut lines = summary_lines(&content.summary);
+
+    if !content.params.is_empty() {
+        lines.extend([String::new(), "# Arguments".to_string(), String::new()]);
+        for param in &content.params {
+            lines.push(format!("* `{}` - {}", param.name, param.description));
+        }
+    }
+    if let Some(returns) = content
+        .returns
+        .as_ref()
+        .filter(|r| !r.description.is_empty())
+    {
+        lines.extend([String::new(), "# Returns".to_string(), String::new()]);
+        lines.push(returns.description.clone());
+    }
+
+    lines
+        .into_iter()
+        .map(|line| {
+            if line.is_empty() {
+                "///".to_string()
+            } else {
+                format!("/// {}", line)
+            }
+        })
+        .collect()
+}
+
+/// Google-style docstring
+fn python_doc(content: &DocContent) -> Vec<String> {
+    let mut lines = summary_lines(&content.summary);
+    if let Some(first) = lines.first_mut() {
+        first.insert_str(0, "\"\"\"");
+    }
+
+    if !content.params.is_empty() {
+        lines.extend([String::new(), "Args:".to_string()]);
+        for param in &content.params {
+            let type_name = param
+                .type_name
+                .as_deref()
+                .filter(|t| !t.is_empty())
+                .map(|t| format!(" ({})", t))
+                .unwrap_or_default();
+            lines.push(format!(
+                "    {}{}: {}",
+                param.name, type_name, param.description
+            ));
+        }
+    }
+    if let Some(returns) = &content.returns {
+        let type_name = returns
+            .type_name
+            .as_deref()
+            .filter(|t| !t.is_empty())
+            .unwrap_or("");
+        lines.extend([String::new(), "Returns:".to_string()]);
+        lines.push(match type_name {
+            "" => format!("    {}", returns.description),
+            t => format!("    {}: {}", t, returns.description),
+        });
+    }
+
+    if lines.len() == 1 {
+        lines[0].push_str("\"\"\"");
+    } else {
+        lines.push("\"\"\"".to_string());
+    }
+    lines
+}
+
+/// Render documentation as comment lines for `language`, each prefixed with
+/// `indent` and ending in a newline
+pub fn format_docstring(
+    content: &DocContent,
+    language: &str,
+    indent: &str,
+) -> Result<String, CodifyError> {
+    let lines = match comment_style(language)?.doc {
+        Some(DocStyle::TsDoc) => js_doc(content, false),
+        Some(DocStyle::JsDoc) => js_doc(content, true),
+        Some(DocStyle::RustDoc) => rust_doc(content),
+        Some(DocStyle::Docstring) => python_doc(content),
+        None => {
+            return Err(CodifyError::InvalidInput(format!(
+                "Docstring generation is not supported for {}",
+                language
+            )))
+        }
+    };
+
+    Ok(lines
+        .into_iter()
+        .map(|line| {
+            if line.is_empty() {
+                "\n".to_string()
+            } else {
+                format!("{}{}\n", indent, line)
+            }
+        })
+        .collect())
+}
+
+/// Prompt guidance for the documentation style
+pub fn docstring_guidance(language: &str, is_component: bool) -> &'static str {
+    match (language, is_component) {
+        (_, true) => {
+            "This is a React component. Document each prop as a param named props.<prop> \
+             (or by its destructured name) with its type, and describe what it renders."
+        }
+        ("rust", _) => {
+            "Write rustdoc: a one-line summary, then detail only if the behaviour is not \
+             obvious. Leave types out of param descriptions; rustdoc shows the signature."
+        }
+        ("python", _) => "Write a Google-style docstring; take types from annotations if present.",
+        _ => "Infer parameter and return types from the signature and how values are used.",
+    }
+}
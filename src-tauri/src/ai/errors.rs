@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// One error located in compiler or test-runner output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorLocation {
+    pub file_path: String, // As printed by the tool
+    pub line: u32,         // 1-based
+    pub column: Option<u32>,
+    pub code: Option<String>, // TS2322, E0308, ...
+    pub message: String,
+}
+
+impl ErrorLocation {
+    /// Whether this error is in `file_path`, comparing by path suffix since
+    /// tools print paths relative to wherever they were run
+    pub fn is_in(&self, file_path: &str) -> bool {
+        let ours = self.file_path.replace('\\', "/");
+        let theirs = file_path.replace('\\', "/");
+        let ours = ours.trim_start_matches("./");
+        let theirs = theirs.trim_start_matches("./");
+
+        ours == theirs
+            || ours.ends_with(&format!("/{}", theirs))
+            || theirs.ends_with(&format!("/{}", ours))
+    }
+}
+
+pub(crate) fn compiled(cell: &'static OnceLock<regex::Regex>, pattern: &str) -> &'static regex::Regex {
+    cell.get_or_init(|| regex::Regex::new(pattern).unwrap())
+}
+
+/// `src/a.ts(12,5): error TS2322: ...` and tsc --pretty's `src/a.ts:12:5 - error TS2322: ...`
+fn typescript_errors(output: &str) -> Vec<ErrorLocation> {
+    static TSC: OnceLock<regex::Regex> = OnceLock::new();
+    let tsc = compiled(
+        &TSC,
+        r"(?m)^(\S+?)(?:\((\d+),(\d+)\)|:(\d+):(\d+))\s*[:-]\s*(?:error|warning)\s+(TS\d+):\s*(.+)$",
+    );
+
+    tsc.captures_iter(output)
+        .filter_map(|c| {
+            Some(ErrorLocation {
+                file_path: c[1].to_string(),
+                line: c.get(2).or(c.get(4))?.as_str().parse().ok()?,
+                column: c.get(3).or(c.get(5)).and_then(|m| m.as_str().parse().ok()),
+                code: Some(c[6].to_string()),
+                message: c[7].trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// rustc's `error[E0308]: message` header followed by a `--> file:line:col` pointer
+fn rust_errors(output: &str) -> Vec<ErrorLocation> {
+    static HEADER: OnceLock<regex::Regex> = OnceLock::new();
+    static POINTER: OnceLock<regex::Regex> = OnceLock::new();
+    let header = compiled(&HEADER, r"^(?:error|warning)(?:\[(E\d+)\])?: (.+)$");
+    let pointer = compiled(&POINTER, r"^\s*--> (.+?):(\d+):(\d+)\s*$");
+
+    let mut errors = vec![];
+    let mut pending: Option<(Option<String>, String)> = None;
+    for line in output.lines() {
+        if let Some(c) = header.captures(line) {
+            pending = Some((
+                c.get(1).map(|m| m.as_str().to_string()),
+                c[2].trim().to_string(),
+            ));
+        } else if let Some(c) = pointer.captures(line) {
+            if let Some((code, message)) = pending.take() {
+                errors.push(ErrorLocation {
+                    file_path: c[1].to_string(),
+                    line: c[2].parse().unwrap_or(1),
+                    column: c[3].parse().ok(),
+                    code,
+                    message,
+                });
+            }
+        }
+    }
+    errors
+}
+
+/// Jest/Vitest failures: the `●` test header, then the first stack frame in
+/// project code (`at fn (src/a.test.ts:10:5)`); also plain Node stack traces
+fn stack_errors(output: &str) -> Vec<ErrorLocation> {
+    static FRAME: OnceLock<regex::Regex> = OnceLock::new();
+    static THROWN: OnceLock<regex::Regex> = OnceLock::new();
+    let frame = compiled(&FRAME, r"^\s*at (?:.*? \()?(.+?):(\d+):(\d+)\)?\s*$");
+    let thrown = compiled(&THROWN, r"^\s*(?:[A-Z]\w*Error|expect\().*");
+
+    let mut errors = vec![];
+    let mut title: Option<String> = None;
+    let mut detail: Option<String> = None;
+    let mut located = false;
+
+    for line in output.lines() {
+        if let Some(test) = line.trim().strip_prefix("● ") {
+            title = Some(test.to_string());
+            detail = None;
+            located = false;
+            continue;
+        }
+        if detail.is_none() && thrown.is_match(line) {
+            detail = Some(line.trim().to_string());
+            continue;
+        }
+
+        let Some(c) = frame.captures(line) else {
+            continue;
+        };
+        let path = &c[1];
+        let external = path.contains("node_modules")
+            || path.starts_with("node:")
+            || path.starts_with("internal/");
+        if located || external {
+            continue;
+        }
+
+        let message = match (&title, &detail) {
+            (Some(title), Some(detail)) => format!("{}: {}", title, detail),
+            (Some(only), None) | (None, Some(only)) => only.clone(),
+            (None, None) => "Uncaught error".to_string(),
+        };
+        errors.push(ErrorLocation {
+            file_path: path.trim_start_matches("file://").to_string(),
+            line: c[2].parse().unwrap_or(1),
+            column: c[3].parse().ok(),
+            code: None,
+            message,
+        });
+        located = true;
+    }
+    errors
+}
+
+/// Locate errors in TypeScript, rustc, and Jest/Vitest/Node output, in the
+/// order they appear per format; unrecognised output yields nothing
+pub fn parse_error_output(output: &str) -> Vec<ErrorLocation> {
+    let mut errors = typescript_errors(output);
+    errors.extend(rust_errors(output));
+    if errors.is_empty() {
+        errors.extend(stack_errors(output));
+    }
+    errors.dedup();
+    errors
+}
+
+/// One frame of a stack trace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackFrame {
+    pub file_path: String, // As printed
+    pub line: u32,
+    pub column: Option<u32>,
+    pub function: Option<String>,
+    pub external: bool, // In dependencies or the runtime rather than project code
+    /// Project-relative path when the file exists in the project, for a link
+    pub resolved_path: Option<String>,
+}
+
+fn is_external(path: &str) -> bool {
+    path.contains("node_modules")
+        || path.contains("site-packages")
+        || path.contains("/rustc/")
+        || path.contains(".cargo/registry")
+        || path.starts_with("node:")
+        || path.starts_with("internal/")
+        || path.starts_with('<')
+}
+
+/// Frames from Node/Jest (`at fn (file:line:col)`), Python (`File "x", line
+/// N, in fn`), Rust panics (`at src/x.rs:N:C` and `panicked at src/x.rs:N:C`)
+/// and Java/Kotlin (`at pkg.Class.fn(File.java:N)`), innermost first as printed
+pub fn parse_stack_frames(text: &str) -> Vec<StackFrame> {
+    static NODE: OnceLock<regex::Regex> = OnceLock::new();
+    static PYTHON: OnceLock<regex::Regex> = OnceLock::new();
+    static RUST: OnceLock<regex::Regex> = OnceLock::new();
+    static JAVA: OnceLock<regex::Regex> = OnceLock::new();
+    let node = compiled(
+        &NODE,
+        r"^\s*at (?:(.+?) \()?(?:file://)?(.+?):(\d+):(\d+)\)?\s*$",
+    );
+    let python = compiled(&PYTHON, r#"^\s*File "(.+?)", line (\d+)(?:, in (.+))?$"#);
+    let rust = compiled(&RUST, r"(?:^\s*at |panicked at )(\S+?\.rs):(\d+):(\d+)");
+    let java = compiled(&JAVA, r"^\s*at ([\w$.<>]+)\((\w+\.(?:java|kt)):(\d+)\)\s*$");
+
+    let number = |m: Option<regex::Match>| m.and_then(|m| m.as_str().parse::<u32>().ok());
+    let name = |m: Option<regex::Match>| m.map(|m| m.as_str().trim().to_string());
+
+    let mut frames = vec![];
+    for line in text.lines() {
+        let (file_path, line, column, function) = if let Some(c) = rust.captures(line) {
+            (c[1].to_string(), number(c.get(2)), number(c.get(3)), None)
+        } else if let Some(c) = java.captures(line) {
+            (c[2].to_string(), number(c.get(3)), None, name(c.get(1)))
+        } else if let Some(c) = node.captures(line) {
+            (
+                c[2].to_string(),
+                number(c.get(3)),
+                number(c.get(4)),
+                name(c.get(1)),
+            )
+        } else if let Some(c) = python.captures(line) {
+            (c[1].to_string(), number(c.get(2)), None, name(c.get(3)))
+        } else {
+            continue;
+        };
+
+        frames.push(StackFrame {
+            external: is_external(&file_path),
+            line: line.unwrap_or(1),
+            column,
+            function,
+            file_path,
+            resolved_path: None,
+        });
+    }
+    frames
+}
+
+/// Whether `text` reads as an error message or stack trace rather than code:
+/// compiler output, or an error header alongside at least one stack frame
+pub fn looks_like_error(text: &str) -> bool {
+    static HEADER: OnceLock<regex::Regex> = OnceLock::new();
+    let header = compiled(
+        &HEADER,
+        r"(?m)^\s*(?:Uncaught )?(?:[\w.$]*(?:Error|Exception)\b|error(?:\[E\d+\])?:|thread '.+' panicked at|Traceback \(most recent call last\):|panic:)",
+    );
+
+    !parse_error_output(text).is_empty()
+        || (header.is_match(text) && !parse_stack_frames(text).is_empty())
+}
@@ -0,0 +1,433 @@
+use super::compiled;
+use crate::commands::TextEdit;
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractedProp {
+    pub name: String,
+    pub type_annotation: String,
+    pub source: String, // Expression passed at the original site
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractedComponent {
+    pub component_name: String,
+    pub file_path: String, // Project-relative path of the new component
+    pub props: Vec<ExtractedProp>,
+    pub edits: Vec<TextEdit>, // Pass to apply_patch as-is
+}
+
+/// Where the selection sits in its file: whole lines, plus whatever shares
+/// the first and last line with it
+#[derive(Debug, Clone)]
+pub struct SelectionSite {
+    pub start_line: u32, // 1-based
+    pub end_line: u32,
+    pub before: String, // Text on start_line before the selection
+    pub after: String,  // Text on end_line after the selection
+    pub original: String,
+}
+
+/// A name the selection uses that is bound elsewhere in the file
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub name: String,
+    pub definition: Option<String>, // The line binding it; None for props or globals
+    pub from_hook: bool,            // Bound from a hook call, e.g. useContext or useState
+}
+
+/// Check that `selection` is one or more complete JSX elements: it starts
+/// with a tag, every tag is closed in order, and nothing trails the last one
+pub fn check_jsx(selection: &str) -> Result<(), CodifyError> {
+    let text = selection.trim();
+    if !text.starts_with('<') {
+        return Err(CodifyError::InvalidInput(
+            "Invalid selection: JSX must start with an element".to_string(),
+        ));
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut open: Vec<String> = vec![];
+    let mut depth = 0; // Inside {expressions}
+    let mut quote = None; // Inside a string within an expression
+    let mut i = 0;
+
+    while i < chars.len() {
+        if let Some(q) = quote {
+            if chars[i] == q {
+                quote = None;
+            }
+            i += 1;
+            continue;
+        }
+
+        match chars[i] {
+            '"' | '\'' | '`' if depth > 0 => quote = Some(chars[i]),
+            '{' => depth += 1,
+            '}' if depth == 0 => {
+                return Err(CodifyError::InvalidInput(
+                    "Invalid selection: unbalanced '}' in JSX".to_string(),
+                ))
+            }
+            '}' => depth -= 1,
+            '<' if depth == 0 => {
+                let (end, closing, name, self_closing) = read_tag(&chars, i)?;
+                if closing {
+                    match open.pop() {
+                        Some(expected) if expected == name => {}
+                        Some(expected) => {
+                            return Err(CodifyError::InvalidInput(format!(
+                                "Invalid selection: </{}> closes <{}>",
+                                name, expected
+                            )))
+                        }
+                        None => {
+                            return Err(CodifyError::InvalidInput(format!(
+                                "Invalid selection: </{}> has no opening tag",
+                                name
+                            )))
+                        }
+                    }
+                } else if !self_closing {
+                    open.push(name);
+                }
+                i = end;
+            }
+            c if depth == 0 && open.is_empty() && !c.is_whitespace() => {
+                return Err(CodifyError::InvalidInput(
+                    "Invalid selection: text outside any JSX element".to_string(),
+                ));
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    if depth != 0 {
+        return Err(CodifyError::InvalidInput(
+            "Invalid selection: unclosed '{' in JSX".to_string(),
+        ));
+    }
+    if let Some(name) = open.pop() {
+        return Err(CodifyError::InvalidInput(format!(
+            "Invalid selection: <{}> is never closed",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Read the tag starting at `chars[start]` ('<'), skipping over quoted
+/// attribute values and {expressions}; returns the index of its '>', whether
+/// it closes, its name ("" for fragments) and whether it self-closes
+fn read_tag(chars: &[char], start: usize) -> Result<(usize, bool, String, bool), CodifyError> {
+    let mut i = start + 1;
+    let closing = chars.get(i) == Some(&'/');
+    if closing {
+        i += 1;
+    }
+
+    let name: String = chars[i..]
+        .iter()
+        .take_while(|c| c.is_alphanumeric() || matches!(c, '.' | '-' | ':' | '_'))
+        .collect();
+    i += name.chars().count();
+
+    let (mut quote, mut depth) = (None, 0);
+    while i < chars.len() {
+        match (chars[i], quote) {
+            (c, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('"' | '\'' | '`', None) => quote = Some(chars[i]),
+            ('{', None) => depth += 1,
+            ('}', None) => depth -= 1,
+            ('>', None) if depth == 0 => {
+                let self_closing = chars[i - 1] == '/' && !closing;
+                return Ok((i, closing, name, self_closing));
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    Err(CodifyError::InvalidInput(format!(
+        "Invalid selection: tag <{}{}> is not terminated",
+        if closing { "/" } else { "" },
+        name
+    )))
+}
+
+/// Find `selected` in `content`, preferring the occurrence nearest the cursor
+pub fn locate_selection(
+    content: &str,
+    selected: &str,
+    cursor_line: u32,
+) -> Result<SelectionSite, CodifyError> {
+    let selected = selected.trim();
+    let start = content
+        .match_indices(selected)
+        .map(|(offset, _)| offset)
+        .min_by_key(|offset| {
+            let line = content[..*offset].matches('\n').count() as i64 + 1;
+            (line - cursor_line as i64).abs()
+        })
+        .ok_or_else(|| {
+            CodifyError::NotFound(
+                "Selection not found in the current file; save it and retry".to_string(),
+            )
+        })?;
+    let end = start + selected.len();
+
+    let line_start = content[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line_end = content[end..]
+        .find('\n')
+        .map(|i| end + i)
+        .unwrap_or(content.len());
+
+    Ok(SelectionSite {
+        start_line: content[..start].matches('\n').count() as u32 + 1,
+        end_line: content[..end].matches('\n').count() as u32 + 1,
+        before: content[line_start..start].to_string(),
+        after: content[end..line_end].trim_end_matches('\r').to_string(),
+        original: content[line_start..line_end].to_string(),
+    })
+}
+
+/// Names the selection needs from its surroundings: identifiers inside its
+/// {expressions} and the components it renders, each with the line of the
+/// file that binds it
+pub fn referenced_bindings(content: &str, selection: &str, site: &SelectionSite) -> Vec<Binding> {
+    static IDENTIFIER: OnceLock<regex::Regex> = OnceLock::new();
+    static COMPONENT: OnceLock<regex::Regex> = OnceLock::new();
+    static HOOK_CALL: OnceLock<regex::Regex> = OnceLock::new();
+    let identifier = compiled(&IDENTIFIER, r"(^|[^.\w$])([A-Za-z_$][\w$]*)");
+    let component = compiled(&COMPONENT, r"<([A-Z][\w$]*)");
+    let hook_call = compiled(&HOOK_CALL, r"=\s*use[A-Z]\w*\s*[(<]");
+
+    let mut names: Vec<String> = vec![];
+    let mut push = |name: &str| {
+        if !is_keyword(name) && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    };
+    for expression in expressions(selection) {
+        for c in identifier.captures_iter(&expression) {
+            push(&c[2]);
+        }
+    }
+    for c in component.captures_iter(selection) {
+        push(&c[1]);
+    }
+
+    let outside: Vec<&str> = content
+        .lines()
+        .enumerate()
+        .filter(|(i, _)| {
+            let line = *i as u32 + 1;
+            line < site.start_line || line > site.end_line
+        })
+        .map(|(_, line)| line)
+        .collect();
+
+    names
+        .into_iter()
+        .map(|name| {
+            let binds = regex::Regex::new(&format!(
+                r"\b(import\b.*\b{0}\b|(const|let|var)\s+([^=]*[^\w$])?{0}\b[^=]*=|function\s+{0}\b|class\s+{0}\b)",
+                regex::escape(&name)
+            ))
+            .ok();
+            let definition = binds.and_then(|binds| {
+                outside
+                    .iter()
+                    .find(|line| binds.is_match(line))
+                    .map(|line| line.trim().to_string())
+            });
+            Binding {
+                from_hook: definition
+                    .as_deref()
+                    .is_some_and(|line| hook_call.is_match(line)),
+                name,
+                definition,
+            }
+        })
+        .collect()
+}
+
+/// The text of each top-level {expression} in a JSX selection, strings excluded
+fn expressions(selection: &str) -> Vec<String> {
+    let mut found = vec![];
+    let mut current = String::new();
+    let (mut depth, mut quote) = (0, None);
+
+    for c in selection.chars() {
+        match (c, quote) {
+            (c, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => continue,
+            ('"' | '\'' | '`', None) if depth > 0 => quote = Some(c),
+            ('{', None) => {
+                depth += 1;
+                if depth == 1 {
+                    continue;
+                }
+            }
+            ('}', None) if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    found.push(std::mem::take(&mut current));
+                }
+            }
+            _ => {}
+        }
+        if depth > 0 {
+            current.push(c);
+        }
+    }
+    found
+}
+
+fn is_keyword(name: &str) -> bool {
+    matches!(
+        name,
+        "true"
+            | "false"
+            | "null"
+            | "undefined"
+            | "this"
+            | "new"
+            | "typeof"
+            | "instanceof"
+            | "void"
+            | "return"
+            | "function"
+            | "async"
+            | "await"
+            | "const"
+            | "let"
+            | "var"
+            | "if"
+            | "else"
+            | "in"
+            | "of"
+    )
+}
+
+/// Whether `name` can be used as a component: PascalCase identifier
+pub fn is_component_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Path for the new component: next to `current_file`, with its extension
+pub fn component_path(current_file: &str, component_name: &str) -> String {
+    let path = Path::new(current_file);
+    let extension = match path.extension().and_then(|e| e.to_str()) {
+        Some("jsx" | "js") => "jsx",
+        _ => "tsx",
+    };
+    let file_name = format!("{}.{}", component_name, extension);
+
+    match path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        Some(parent) => parent.join(file_name).to_string_lossy().replace('\\', "/"),
+        None => file_name,
+    }
+}
+
+/// 1-based line after which the new import goes: after the last import
+/// statement, or at the top when there are none
+pub fn import_line(content: &str) -> u32 {
+    let mut last = 0;
+    let mut in_import = false;
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("import ") || trimmed.starts_with("import{") {
+            in_import = !(trimmed.contains(" from ")
+                || trimmed.starts_with("import '")
+                || trimmed.starts_with("import \""));
+            if !in_import {
+                last = i as u32 + 1;
+            }
+        } else if in_import && (trimmed.starts_with("} from") || trimmed.contains(" from ")) {
+            in_import = false;
+            last = i as u32 + 1;
+        }
+    }
+    last
+}
+
+/// The apply_patch edits: create the component file, swap the selection for
+/// `usage`, and import the component where the selection was
+pub fn extraction_edits(
+    current_file: &str,
+    content: &str,
+    site: &SelectionSite,
+    component_name: &str,
+    component_path: &str,
+    component_code: &str,
+    usage: &str,
+) -> Vec<TextEdit> {
+    let import_after = import_line(content);
+    let module = Path::new(component_path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| component_name.to_string());
+    let same_dir = Path::new(component_path).parent() == Path::new(current_file).parent();
+    let specifier = if same_dir {
+        format!("./{}", module)
+    } else {
+        component_path.to_string()
+    };
+
+    // Keep the selection's indentation on every line of the usage
+    let indent: String = site
+        .before
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .collect();
+    let usage = usage
+        .trim()
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 {
+                line.to_string()
+            } else {
+                format!("{}{}", indent, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut component_code = component_code.trim_end().to_string();
+    component_code.push('\n');
+
+    vec![
+        TextEdit {
+            file_path: component_path.to_string(),
+            start_line: 1,
+            end_line: 0,
+            replacement: component_code,
+            original: None,
+        },
+        TextEdit {
+            file_path: current_file.to_string(),
+            start_line: import_after + 1,
+            end_line: import_after,
+            replacement: format!("import {{ {} }} from '{}';", component_name, specifier),
+            original: None,
+        },
+        TextEdit {
+            file_path: current_file.to_string(),
+            start_line: site.start_line,
+            end_line: site.end_line,
+            replacement: format!("{}{}{}", site.before, usage, site.after),
+            original: Some(site.original.clone()),
+        },
+    ]
+}
@@ -0,0 +1,213 @@
+use super::{CompletionLevel, OfferSummary};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+use tauri::Manager;
+
+const FEEDBACK_FILE: &str = "completion_feedback.jsonl";
+
+/// Events kept on disk; older ones are dropped when the log is loaded
+const FEEDBACK_CAPACITY: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletionOutcome {
+    Accepted,
+    Rejected,
+    Partial, // Some of the suggestion was kept, e.g. accepted word by word
+}
+
+/// One rated completion. Only sizes and labels are kept, never code or paths.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackEvent {
+    pub completion_id: String,
+    pub timestamp: String,
+    pub outcome: CompletionOutcome,
+    pub language: String,
+    pub level: CompletionLevel,
+    pub offered_chars: usize,
+    pub accepted_chars: usize,
+}
+
+impl FeedbackEvent {
+    /// `accepted_chars` defaults to the whole suggestion when accepted and is
+    /// required for a partial acceptance
+    pub fn new(
+        completion_id: &str,
+        offer: OfferSummary,
+        outcome: CompletionOutcome,
+        accepted_chars: Option<usize>,
+    ) -> Result<Self, CodifyError> {
+        let accepted_chars = match (outcome, accepted_chars) {
+            (CompletionOutcome::Rejected, Some(chars)) if chars > 0 => {
+                return Err(CodifyError::InvalidInput(
+                    "Invalid feedback: a rejected completion has no accepted_chars".into(),
+                ))
+            }
+            (CompletionOutcome::Rejected, _) => 0,
+            (CompletionOutcome::Accepted, chars) => chars.unwrap_or(offer.chars),
+            (CompletionOutcome::Partial, Some(chars)) => chars,
+            (CompletionOutcome::Partial, None) => {
+                return Err(CodifyError::InvalidInput(
+                    "Invalid feedback: a partial acceptance needs accepted_chars".into(),
+                ))
+            }
+        };
+        if accepted_chars > offer.max_chars {
+            return Err(CodifyError::InvalidInput(format!(
+                "Invalid accepted_chars {}: the completion offered {} characters",
+                accepted_chars, offer.max_chars
+            )));
+        }
+
+        Ok(FeedbackEvent {
+            completion_id: completion_id.to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            outcome,
+            language: offer.language,
+            level: offer.level,
+            offered_chars: offer.chars,
+            accepted_chars,
+        })
+    }
+}
+
+/// Counts over a set of feedback events
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FeedbackStats {
+    pub events: u64,
+    pub accepted: u64,
+    pub partial: u64,
+    pub rejected: u64,
+    pub offered_chars: u64,
+    pub accepted_chars: u64,
+}
+
+impl FeedbackStats {
+    fn add(&mut self, event: &FeedbackEvent) {
+        self.events += 1;
+        match event.outcome {
+            CompletionOutcome::Accepted => self.accepted += 1,
+            CompletionOutcome::Partial => self.partial += 1,
+            CompletionOutcome::Rejected => self.rejected += 1,
+        }
+        self.offered_chars += event.offered_chars as u64;
+        self.accepted_chars += event.accepted_chars as u64;
+    }
+
+    /// Share of rated completions kept in whole or in part
+    pub fn acceptance_rate(&self) -> Option<f64> {
+        (self.events > 0).then(|| (self.accepted + self.partial) as f64 / self.events as f64)
+    }
+}
+
+/// Aggregates get_ai_status reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackSummary {
+    pub enabled: bool,
+    pub acceptance_rate: Option<f64>,
+    pub overall: FeedbackStats,
+    pub by_language: BTreeMap<String, FeedbackStats>,
+}
+
+/// Completion feedback, appended as JSON lines under the app data dir; it
+/// never leaves the machine
+#[derive(Debug, Default)]
+pub struct FeedbackLog {
+    loaded: bool,
+    overall: FeedbackStats,
+    by_language: BTreeMap<String, FeedbackStats>,
+}
+
+fn feedback_path(app: &tauri::AppHandle) -> Result<PathBuf, CodifyError> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(FEEDBACK_FILE))
+        .map_err(|e| CodifyError::Io(format!("Failed to locate data directory: {}", e)))
+}
+
+impl FeedbackLog {
+    fn add(&mut self, event: &FeedbackEvent) {
+        self.overall.add(event);
+        self.by_language
+            .entry(event.language.clone())
+            .or_default()
+            .add(event);
+    }
+
+    /// Fold the saved events into the counts once per run, trimming the file
+    /// to the newest FEEDBACK_CAPACITY events
+    fn load(&mut self, app: &tauri::AppHandle) {
+        if self.loaded {
+            return;
+        }
+        self.loaded = true;
+
+        let Ok(path) = feedback_path(app) else {
+            return;
+        };
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            return;
+        };
+
+        let lines: Vec<&str> = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        let kept = &lines[lines.len().saturating_sub(FEEDBACK_CAPACITY)..];
+        for line in kept {
+            match serde_json::from_str::<FeedbackEvent>(line) {
+                Ok(event) => self.add(&event),
+                Err(e) => log::warn!("Skipping corrupt completion feedback: {}", e),
+            }
+        }
+
+        if kept.len() < lines.len() {
+            let mut trimmed = kept.join("\n");
+            trimmed.push('\n');
+            if let Err(e) = std::fs::write(&path, trimmed) {
+                log::warn!("Failed to trim completion feedback: {}", e);
+            }
+        }
+    }
+
+    pub fn record(
+        &mut self,
+        app: &tauri::AppHandle,
+        event: &FeedbackEvent,
+    ) -> Result<(), CodifyError> {
+        self.load(app);
+
+        let path = feedback_path(app)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| CodifyError::Io(format!("Failed to create data directory: {}", e)))?;
+        }
+        let line = serde_json::to_string(event).map_err(|e| {
+            CodifyError::Internal(format!("Failed to encode completion feedback: {}", e))
+        })?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| CodifyError::Io(format!("Failed to open {}: {}", path.display(), e)))?;
+        writeln!(file, "{}", line)
+            .map_err(|e| CodifyError::Io(format!("Failed to write {}: {}", path.display(), e)))?;
+
+        self.add(event);
+        Ok(())
+    }
+
+    pub fn summary(&mut self, app: &tauri::AppHandle, enabled: bool) -> FeedbackSummary {
+        self.load(app);
+
+        FeedbackSummary {
+            enabled,
+            acceptance_rate: self.overall.acceptance_rate(),
+            overall: self.overall.clone(),
+            by_language: self.by_language.clone(),
+        }
+    }
+}
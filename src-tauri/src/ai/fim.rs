@@ -0,0 +1,110 @@
+use super::{CompletionLevel, Position};
+use serde::{Deserialize, Serialize};
+
+/// Fill-in-the-middle prompt formats, named after the model families that use them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FimFormat {
+    Qwen,
+    StarCoder,
+    CodeLlama,
+    DeepSeek,
+    Codestral,
+}
+
+impl FimFormat {
+    /// Recognize FIM-capable models by name; None means use the chat fallback
+    pub fn for_model(model: &str) -> Option<Self> {
+        let model = model.to_lowercase();
+
+        if model.contains("qwen") && model.contains("coder") {
+            Some(FimFormat::Qwen)
+        } else if model.contains("starcoder") {
+            Some(FimFormat::StarCoder)
+        } else if model.contains("codellama") || model.contains("code-llama") {
+            Some(FimFormat::CodeLlama)
+        } else if model.contains("deepseek-coder") {
+            Some(FimFormat::DeepSeek)
+        } else if model.contains("codestral") {
+            Some(FimFormat::Codestral)
+        } else {
+            None
+        }
+    }
+
+    pub fn prompt(&self, prefix: &str, suffix: &str) -> String {
+        match self {
+            FimFormat::Qwen => {
+                format!("<|fim_prefix|>{prefix}<|fim_suffix|>{suffix}<|fim_middle|>")
+            }
+            FimFormat::StarCoder => format!("<fim_prefix>{prefix}<fim_suffix>{suffix}<fim_middle>"),
+            FimFormat::CodeLlama => format!("<PRE> {prefix} <SUF>{suffix} <MID>"),
+            FimFormat::DeepSeek => {
+                format!("<｜fim▁begin｜>{prefix}<｜fim▁hole｜>{suffix}<｜fim▁end｜>")
+            }
+            FimFormat::Codestral => format!("[SUFFIX]{suffix}[PREFIX]{prefix}"),
+        }
+    }
+
+    /// End-of-insertion markers the model may emit instead of stopping cleanly
+    pub fn stop_tokens(&self) -> &'static [&'static str] {
+        match self {
+            FimFormat::Qwen => &["<|endoftext|>", "<|fim_pad|>"],
+            FimFormat::StarCoder => &["<|endoftext|>", "<file_sep>"],
+            FimFormat::CodeLlama => &["<EOT>"],
+            FimFormat::DeepSeek => &["<｜end▁of▁sentence｜>"],
+            FimFormat::Codestral => &["</s>"],
+        }
+    }
+}
+
+/// Where an insertion at `level` should stop
+pub fn level_stop(level: &CompletionLevel) -> Option<&'static str> {
+    match level {
+        CompletionLevel::Line => Some("\n"),
+        CompletionLevel::Block => Some("\n\n"),
+        CompletionLevel::Component | CompletionLevel::Feature => None,
+    }
+}
+
+/// Cut a model's insertion down to what `level` allows
+pub fn trim_to_level(code: &str, level: &CompletionLevel) -> String {
+    let code = match level_stop(level) {
+        Some(stop) => code.split(stop).next().unwrap_or_default(),
+        None => code,
+    };
+    code.trim_end().to_string()
+}
+
+/// Split file content at the cursor (1-based line and column)
+pub fn split_at_cursor(content: &str, position: &Position) -> (String, String) {
+    let target_line = position.line.max(1) as usize - 1;
+    let mut offset = 0;
+
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        if index == target_line {
+            let text = line.trim_end_matches(['\n', '\r']);
+            let column = text
+                .char_indices()
+                .nth(position.column.max(1) as usize - 1)
+                .map(|(i, _)| i)
+                .unwrap_or(text.len());
+            offset += column;
+            return (content[..offset].to_string(), content[offset..].to_string());
+        }
+        offset += line.len();
+    }
+
+    // Cursor past the end of the file
+    (content.to_string(), String::new())
+}
+
+/// Drop text the model repeated from the end of the prefix before its insertion
+pub fn strip_echoed_prefix(code: &str, prefix: &str) -> String {
+    let last_line = prefix.rsplit('\n').next().unwrap_or_default();
+    if !last_line.trim().is_empty() {
+        if let Some(rest) = code.strip_prefix(last_line) {
+            return rest.to_string();
+        }
+    }
+    code.to_string()
+}
@@ -0,0 +1,378 @@
+use super::ModelConfig;
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+
+/// What get_ai_status reports about the on-device model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalModelInfo {
+    pub model_path: String,
+    pub size_mb: u64, // Weights, wherever they are resident
+    pub gpu_layers: u32,
+    pub gpu_offload: bool, // Layers were requested and the backend supports offload
+}
+
+/// In-process llama.cpp runtime for the Local provider
+///
+/// The GGUF model is loaded on first use and kept until the configured path or
+/// GPU layer count changes; each request gets its own short-lived context.
+/// Requests and responses use the OpenAI JSON shapes so the provider layer can
+/// treat this like any other endpoint.
+#[derive(Default)]
+pub struct LocalRuntime {
+    #[cfg(feature = "local-llm")]
+    loaded: std::sync::Mutex<Option<std::sync::Arc<llama::LoadedModel>>>,
+    #[cfg(feature = "local-llm")]
+    loading: tokio::sync::Mutex<()>, // Serializes loads so a model is only read once
+}
+
+#[cfg(not(feature = "local-llm"))]
+impl LocalRuntime {
+    pub async fn respond(
+        &self,
+        _config: &ModelConfig,
+        _path: &str,
+        _body: &serde_json::Value,
+    ) -> Result<serde_json::Value, CodifyError> {
+        Err(CodifyError::Unavailable("This build of Codify does not include local model support; rebuild with the local-llm feature".to_string()))
+    }
+
+    pub fn info(&self) -> Option<LocalModelInfo> {
+        None
+    }
+}
+
+#[cfg(feature = "local-llm")]
+impl LocalRuntime {
+    /// Answer a `chat/completions` or `completions` request on-device
+    pub async fn respond(
+        &self,
+        config: &ModelConfig,
+        path: &str,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value, CodifyError> {
+        let model_path = config
+            .model_path
+            .clone()
+            .filter(|p| !p.trim().is_empty())
+            .ok_or_else(|| {
+                CodifyError::InvalidInput(
+                    "The Local provider needs model_path set to a GGUF file".to_string(),
+                )
+            })?;
+        let loaded = self.model(&model_path, config.gpu_layers).await?;
+
+        let prompt = match path {
+            "chat/completions" => {
+                llama::Prompt::Chat(serde_json::from_value(body["messages"].clone()).map_err(
+                    |e| CodifyError::InvalidInput(format!("Invalid chat messages: {}", e)),
+                )?)
+            }
+            "completions" => {
+                llama::Prompt::Text(body["prompt"].as_str().unwrap_or_default().to_string())
+            }
+            other => {
+                return Err(CodifyError::InvalidInput(format!(
+                    "The Local provider does not support {}",
+                    other
+                )))
+            }
+        };
+
+        let request = llama::Request {
+            prompt,
+            max_tokens: body["max_tokens"].as_u64().unwrap_or(512) as usize,
+            temperature: body["temperature"].as_f64().unwrap_or(0.8) as f32,
+            top_p: body["top_p"].as_f64().unwrap_or(0.95) as f32,
+            stop: body["stop"]
+                .as_array()
+                .map(|stops| {
+                    stops
+                        .iter()
+                        .filter_map(|s| s.as_str())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            context_tokens: config.max_context_tokens,
+        };
+
+        let generated = tokio::task::spawn_blocking(move || llama::generate(&loaded, &request))
+            .await
+            .map_err(|e| CodifyError::Internal(format!("Local inference task failed: {}", e)))??;
+
+        let choice = if path == "completions" {
+            serde_json::json!({ "text": generated.text })
+        } else {
+            serde_json::json!({ "message": { "role": "assistant", "content": generated.text } })
+        };
+
+        Ok(serde_json::json!({
+            "model": config.model,
+            "choices": [choice],
+            "usage": {
+                "prompt_tokens": generated.prompt_tokens,
+                "completion_tokens": generated.completion_tokens,
+            },
+        }))
+    }
+
+    pub fn info(&self) -> Option<LocalModelInfo> {
+        self.loaded
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|loaded| loaded.info())
+    }
+
+    /// The cached model, loading it first if the path or GPU layers changed
+    async fn model(
+        &self,
+        path: &str,
+        gpu_layers: u32,
+    ) -> Result<std::sync::Arc<llama::LoadedModel>, CodifyError> {
+        let _loading = self.loading.lock().await;
+
+        if let Some(loaded) = self.loaded.lock().unwrap().as_ref() {
+            if loaded.path == path && loaded.gpu_layers == gpu_layers {
+                return Ok(loaded.clone());
+            }
+        }
+
+        // Free the old weights first so two models are never resident at once
+        *self.loaded.lock().unwrap() = None;
+
+        let owned_path = path.to_string();
+        let loaded = tokio::task::spawn_blocking(move || llama::load(&owned_path, gpu_layers))
+            .await
+            .map_err(|e| CodifyError::Internal(format!("Model load task failed: {}", e)))??;
+        let loaded = std::sync::Arc::new(loaded);
+
+        log::info!(
+            "Loaded local model {} ({} MB, {} GPU layers)",
+            path,
+            loaded.info().size_mb,
+            gpu_layers
+        );
+        *self.loaded.lock().unwrap() = Some(loaded.clone());
+        Ok(loaded)
+    }
+}
+
+#[cfg(feature = "local-llm")]
+mod llama {
+    use super::LocalModelInfo;
+    use crate::ai::ChatMessage;
+    use crate::error::CodifyError;
+    use llama_cpp_2::context::params::LlamaContextParams;
+    use llama_cpp_2::llama_backend::LlamaBackend;
+    use llama_cpp_2::llama_batch::LlamaBatch;
+    use llama_cpp_2::model::params::LlamaModelParams;
+    use llama_cpp_2::model::{AddBos, LlamaChatMessage, LlamaModel, Special};
+    use llama_cpp_2::sampling::LlamaSampler;
+    use std::num::NonZeroU32;
+
+    /// llama.cpp may only be initialised once per process
+    fn backend() -> Result<&'static LlamaBackend, CodifyError> {
+        static BACKEND: std::sync::OnceLock<Result<LlamaBackend, String>> =
+            std::sync::OnceLock::new();
+
+        BACKEND
+            .get_or_init(|| {
+                LlamaBackend::init().map_err(|e| format!("Failed to initialise llama.cpp: {}", e))
+            })
+            .as_ref()
+            .map_err(|e| CodifyError::Provider(e.clone()))
+    }
+
+    pub struct LoadedModel {
+        pub path: String,
+        pub gpu_layers: u32,
+        model: LlamaModel,
+    }
+
+    impl LoadedModel {
+        pub fn info(&self) -> LocalModelInfo {
+            let gpu_offload =
+                self.gpu_layers > 0 && backend().map(|b| b.supports_gpu_offload()).unwrap_or(false);
+
+            LocalModelInfo {
+                model_path: self.path.clone(),
+                size_mb: self.model.size() / (1024 * 1024),
+                gpu_layers: self.gpu_layers,
+                gpu_offload,
+            }
+        }
+    }
+
+    pub enum Prompt {
+        Chat(Vec<ChatMessage>),
+        Text(String),
+    }
+
+    pub struct Request {
+        pub prompt: Prompt,
+        pub max_tokens: usize,
+        pub temperature: f32,
+        pub top_p: f32,
+        pub stop: Vec<String>,
+        pub context_tokens: usize,
+    }
+
+    pub struct Generated {
+        pub text: String,
+        pub prompt_tokens: usize,
+        pub completion_tokens: usize,
+    }
+
+    pub fn load(path: &str, gpu_layers: u32) -> Result<LoadedModel, CodifyError> {
+        if !std::path::Path::new(path).is_file() {
+            return Err(CodifyError::NotFound(format!(
+                "Local model file not found: {}",
+                path
+            )));
+        }
+
+        let params = LlamaModelParams::default().with_n_gpu_layers(gpu_layers);
+        let model = LlamaModel::load_from_file(backend()?, path, &params).map_err(|e| {
+            CodifyError::Provider(format!("Failed to load local model {}: {}", path, e))
+        })?;
+
+        Ok(LoadedModel {
+            path: path.to_string(),
+            gpu_layers,
+            model,
+        })
+    }
+
+    /// Render chat messages with the model's own template, or ChatML when the
+    /// GGUF doesn't ship one
+    fn chat_prompt(model: &LlamaModel, messages: &[ChatMessage]) -> Result<String, CodifyError> {
+        let Ok(template) = model.chat_template(None) else {
+            let mut prompt = String::new();
+            for message in messages {
+                prompt.push_str(&format!(
+                    "<|im_start|>{}\n{}<|im_end|>\n",
+                    message.role, message.content
+                ));
+            }
+            prompt.push_str("<|im_start|>assistant\n");
+            return Ok(prompt);
+        };
+
+        let chat = messages
+            .iter()
+            .map(|m| LlamaChatMessage::new(m.role.clone(), m.content.clone()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| CodifyError::InvalidInput(format!("Invalid chat message: {}", e)))?;
+
+        model
+            .apply_chat_template(&template, &chat, true)
+            .map_err(|e| CodifyError::Provider(format!("Failed to apply chat template: {}", e)))
+    }
+
+    fn first_stop(text: &str, stops: &[String]) -> Option<usize> {
+        stops
+            .iter()
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| text.find(s.as_str()))
+            .min()
+    }
+
+    pub fn generate(loaded: &LoadedModel, request: &Request) -> Result<Generated, CodifyError> {
+        let model = &loaded.model;
+        let prompt = match &request.prompt {
+            Prompt::Chat(messages) => chat_prompt(model, messages)?,
+            Prompt::Text(text) => text.clone(),
+        };
+
+        // Chat templates already begin with the BOS token
+        let add_bos = match request.prompt {
+            Prompt::Chat(_) => AddBos::Never,
+            Prompt::Text(_) => AddBos::Always,
+        };
+        let tokens = model
+            .str_to_token(&prompt, add_bos)
+            .map_err(|e| CodifyError::Provider(format!("Failed to tokenize prompt: {}", e)))?;
+
+        let n_ctx = request.context_tokens;
+        if tokens.is_empty() {
+            return Err(CodifyError::InvalidInput("Prompt is empty".to_string()));
+        }
+        if tokens.len() + request.max_tokens > n_ctx {
+            return Err(CodifyError::InvalidInput(format!(
+                "Prompt is {} tokens and the reply may use {}, which exceeds the local model's {}-token context",
+                tokens.len(),
+                request.max_tokens,
+                n_ctx
+            )));
+        }
+
+        let context_params = LlamaContextParams::default()
+            .with_n_ctx(NonZeroU32::new(n_ctx as u32))
+            .with_n_batch(n_ctx as u32);
+        let mut ctx = model.new_context(backend()?, context_params).map_err(|e| {
+            CodifyError::Provider(format!("Failed to create inference context: {}", e))
+        })?;
+
+        let mut batch = LlamaBatch::new(n_ctx, 1);
+        let last = tokens.len() as i32 - 1;
+        for (position, token) in (0_i32..).zip(tokens.iter()) {
+            batch
+                .add(*token, position, &[0], position == last)
+                .map_err(|e| CodifyError::Provider(format!("Failed to queue prompt: {}", e)))?;
+        }
+        ctx.decode(&mut batch)
+            .map_err(|e| CodifyError::Provider(format!("Failed to evaluate prompt: {}", e)))?;
+
+        let mut sampler = if request.temperature <= 0.0 {
+            LlamaSampler::greedy()
+        } else {
+            LlamaSampler::chain_simple([
+                LlamaSampler::top_p(request.top_p, 1),
+                LlamaSampler::temp(request.temperature),
+                LlamaSampler::dist(rand::random()),
+            ])
+        };
+
+        let mut position = tokens.len() as i32;
+        let mut output = Vec::new();
+        let mut completion_tokens = 0;
+
+        while completion_tokens < request.max_tokens {
+            let token = sampler.sample(&ctx, batch.n_tokens() - 1);
+            sampler.accept(token);
+            if model.is_eog_token(token) {
+                break;
+            }
+
+            completion_tokens += 1;
+            output.extend(
+                model
+                    .token_to_bytes(token, Special::Tokenize)
+                    .map_err(|e| CodifyError::Provider(format!("Failed to decode token: {}", e)))?,
+            );
+            if first_stop(&String::from_utf8_lossy(&output), &request.stop).is_some() {
+                break;
+            }
+
+            batch.clear();
+            batch
+                .add(token, position, &[0], true)
+                .map_err(|e| CodifyError::Provider(format!("Failed to queue token: {}", e)))?;
+            position += 1;
+            ctx.decode(&mut batch)
+                .map_err(|e| CodifyError::Provider(format!("Failed to evaluate token: {}", e)))?;
+        }
+
+        let mut text = String::from_utf8_lossy(&output).to_string();
+        if let Some(cut) = first_stop(&text, &request.stop) {
+            text.truncate(cut);
+        }
+
+        Ok(Generated {
+            text,
+            prompt_tokens: tokens.len(),
+            completion_tokens,
+        })
+    }
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+/// A run of model output: prose, or a fenced code block to highlight
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum MarkdownSegment {
+    Text {
+        text: String, // Markdown, with inline code and emphasis left as-is
+    },
+    Code {
+        language: Option<String>, // The fence's info string, if any
+        code: String,
+    },
+}
+
+/// An open fence: its character, length and the block's language
+struct Fence {
+    marker: char,
+    length: usize,
+    language: Option<String>,
+}
+
+/// A line opening a ``` or ~~~ fence
+fn opening_fence(line: &str) -> Option<Fence> {
+    let trimmed = line.trim_start();
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let length = trimmed.chars().take_while(|c| *c == marker).count();
+    if length < 3 {
+        return None;
+    }
+
+    let info = trimmed[length..].trim();
+    // Backtick fences can't have backticks in their info string
+    if marker == '`' && info.contains('`') {
+        return None;
+    }
+    let language = info
+        .split_whitespace()
+        .next()
+        .map(|language| language.trim_matches(|c| c == '{' || c == '}' || c == '.'))
+        .filter(|language| !language.is_empty())
+        .map(str::to_string);
+    Some(Fence {
+        marker,
+        length,
+        language,
+    })
+}
+
+fn closes(fence: &Fence, line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.chars().all(|c| c == fence.marker) && trimmed.chars().count() >= fence.length
+}
+
+fn push_text(segments: &mut Vec<MarkdownSegment>, lines: &mut Vec<&str>) {
+    let text = lines.join("\n");
+    lines.clear();
+    let text = text.trim_matches('\n').trim_end();
+    if !text.trim().is_empty() {
+        segments.push(MarkdownSegment::Text {
+            text: text.to_string(),
+        });
+    }
+}
+
+/// Split markdown into prose and fenced code blocks, in order
+///
+/// A fence left open, as when a reply is cut off, runs to the end.
+pub fn markdown_segments(markdown: &str) -> Vec<MarkdownSegment> {
+    let mut segments = vec![];
+    let mut text: Vec<&str> = vec![];
+    let mut code: Vec<&str> = vec![];
+    let mut fence: Option<Fence> = None;
+
+    for line in markdown.lines() {
+        if fence.is_none() {
+            match opening_fence(line) {
+                Some(opened) => {
+                    push_text(&mut segments, &mut text);
+                    fence = Some(opened);
+                }
+                None => text.push(line),
+            }
+        } else if fence.as_ref().is_some_and(|open| closes(open, line)) {
+            segments.push(MarkdownSegment::Code {
+                language: fence.take().and_then(|fence| fence.language),
+                code: code.join("\n"),
+            });
+            code.clear();
+        } else {
+            code.push(line);
+        }
+    }
+
+    match fence {
+        Some(fence) => segments.push(MarkdownSegment::Code {
+            language: fence.language,
+            code: code.join("\n"),
+        }),
+        None => push_text(&mut segments, &mut text),
+    }
+    segments
+}
@@ -0,0 +1,45 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Number of recent completion latencies kept for the rolling average
+const LATENCY_WINDOW: usize = 20;
+
+/// Live provider metrics reported by get_ai_status
+#[derive(Debug, Default)]
+pub struct AIMetrics {
+    latencies: VecDeque<Duration>,
+    pub last_activity: Option<chrono::DateTime<chrono::Utc>>,
+    pub connection_ok: Option<bool>, // None until the provider has been contacted
+}
+
+impl AIMetrics {
+    pub fn touch(&mut self) {
+        self.last_activity = Some(chrono::Utc::now());
+    }
+
+    pub fn record_latency(&mut self, latency: Duration) {
+        if self.latencies.len() == LATENCY_WINDOW {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(latency);
+    }
+
+    pub fn average_latency_ms(&self) -> Option<u64> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+
+        let total: Duration = self.latencies.iter().sum();
+        Some((total / self.latencies.len() as u32).as_millis() as u64)
+    }
+
+    /// Human-readable speed bucket derived from the rolling average
+    pub fn inference_speed(&self) -> &'static str {
+        match self.average_latency_ms() {
+            None => "Unknown",
+            Some(ms) if ms < 1500 => "Fast",
+            Some(ms) if ms < 5000 => "Moderate",
+            Some(_) => "Slow",
+        }
+    }
+}
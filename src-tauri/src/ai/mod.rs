@@ -1,4 +1,69 @@
 use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::error::CodifyError;
+
+mod cache;
+mod cancellation;
+mod chat;
+mod comments;
+mod docstring;
+mod errors;
+mod extract;
+mod feedback;
+mod fim;
+mod local;
+mod markdown;
+mod metrics;
+mod models;
+mod overrides;
+mod params;
+mod pricing;
+mod provider;
+mod rag;
+mod retry;
+mod scaffold;
+mod secrets;
+mod selection;
+mod streaming;
+mod templates;
+mod testgen;
+mod throttle;
+mod tokenizer;
+mod usage;
+mod warmup;
+mod window;
+
+pub use cache::*;
+pub use cancellation::*;
+pub use chat::*;
+pub use comments::*;
+pub use docstring::*;
+pub use errors::*;
+pub use extract::*;
+pub use feedback::*;
+pub use fim::*;
+pub use local::*;
+pub use markdown::*;
+pub use metrics::*;
+pub use models::*;
+pub use overrides::*;
+pub use params::*;
+pub use pricing::*;
+pub use provider::*;
+pub use rag::*;
+pub use retry::*;
+pub use scaffold::*;
+pub use secrets::*;
+pub use selection::*;
+pub use streaming::*;
+pub use templates::*;
+pub use testgen::*;
+pub use throttle::*;
+pub use tokenizer::*;
+pub use usage::*;
+pub use warmup::*;
+pub use window::*;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
@@ -6,7 +71,7 @@ pub struct Position {
     pub column: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum CompletionLevel {
     Line,
     Block,
@@ -21,7 +86,12 @@ pub struct CompletionResult {
     pub confidence: f32,
     pub code: String,
     pub language: String,
-    pub alternatives: Vec<String>,
+    pub alternatives: Vec<CompletionAlternative>,
+    #[serde(default)]
+    pub truncated: bool, // File context was clipped to fit the model's context window
+    /// Indexed code injected into the prompt as examples, as `path:start-end`
+    #[serde(default)]
+    pub context_snippets: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,202 +100,1677 @@ pub struct AIContext {
     pub current_file: Option<String>,
     pub selected_text: Option<String>,
     pub cursor_position: Position,
+    #[serde(default)]
+    pub prefix: Option<String>, // Editor text before the cursor, when it differs from disk
+    #[serde(default)]
+    pub suffix: Option<String>, // Editor text after the cursor
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeExplanation {
+    pub summary: String,
+    pub details: Vec<String>,
+    pub language: String,
+    pub start_line: Option<u32>,
+    pub end_line: Option<u32>,
+    /// Summary and details as one markdown document
+    #[serde(default)]
+    pub raw_markdown: String,
+    /// `raw_markdown` split into prose and fenced code, for highlighting
+    #[serde(default)]
+    pub segments: Vec<MarkdownSegment>,
+    /// Set when the input was an error message or stack trace; summary and
+    /// details then hold the cause and fix steps
+    #[serde(default)]
+    pub error: Option<ErrorExplanation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorExplanation {
+    pub cause: String,
+    pub fix_steps: Vec<String>,
+    pub frames: Vec<StackFrame>,    // As parsed, innermost first
+    pub errors: Vec<ErrorLocation>, // Compiler and test-runner errors found in the input
+}
+
+/// One region of an ai_explain_code_batch request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplainBatchItem {
+    pub id: String, // Chosen by the caller to match results to regions
+    pub code: String,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub file_path: Option<String>, // Project-relative; its file is sent as context
+    #[serde(default)]
+    pub start_line: Option<u32>,
+    #[serde(default)]
+    pub end_line: Option<u32>,
+}
+
+/// The outcome of one batch item: an explanation or why it failed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplainBatchResult {
+    pub id: String,
+    pub explanation: Option<CodeExplanation>,
+    pub error: Option<CodifyError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefactorSuggestion {
+    pub title: String,
+    pub description: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub replacement_code: String,
+    pub confidence: f32,
 }
 
 /// AI Code Completion Command
 #[tauri::command]
 pub async fn ai_complete_code(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AIState>,
+    context: AIContext,
+    level: CompletionLevel,
+    bypass_cache: Option<bool>,
+    params: Option<GenerationParams>,
+) -> Result<CompletionResult, CodifyError> {
+    let project_path = context.project_path.clone();
+    in_project(
+        &project_path,
+        complete_code(&app, &state, context, level, bypass_cache, params),
+    )
+    .await
+}
+
+async fn complete_code(
+    app: &tauri::AppHandle,
+    state: &AIState,
     context: AIContext,
     level: CompletionLevel,
-) -> Result<CompletionResult, String> {
+    bypass_cache: Option<bool>,
+    params: Option<GenerationParams>,
+) -> Result<CompletionResult, CodifyError> {
     log::info!("AI completion requested for level: {:?}", level);
-    
-    // Simulate AI processing - in real implementation this would:
-    // 1. Load the appropriate AI model
-    // 2. Generate embeddings for context
-    // 3. Query vector database for similar code
-    // 4. Generate completion using LLM
-    
-    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-    
-    let completion = match level {
-        CompletionLevel::Line => CompletionResult {
-            id: uuid::Uuid::new_v4().to_string(),
-            level,
-            confidence: 0.85,
-            code: "const [state, setState] = useState(false);".to_string(),
-            language: "typescript".to_string(),
-            alternatives: vec![
-                "const [isActive, setIsActive] = useState(false);".to_string(),
-                "const [enabled, setEnabled] = useState(false);".to_string(),
-            ],
-        },
-        CompletionLevel::Block => CompletionResult {
-            id: uuid::Uuid::new_v4().to_string(),
-            level,
-            confidence: 0.92,
-            code: r#"const handleSubmit = async (event: FormEvent) => {
-  event.preventDefault();
-  try {
-    const result = await submitForm(formData);
-    setSuccess(true);
-  } catch (error) {
-    setError(error.message);
-  }
-};"#.to_string(),
-            language: "typescript".to_string(),
-            alternatives: vec![],
-        },
-        CompletionLevel::Component => CompletionResult {
-            id: uuid::Uuid::new_v4().to_string(),
-            level,
-            confidence: 0.91,
-            code: r#"interface ButtonProps {
-  variant: 'primary' | 'secondary' | 'outline';
-  size: 'sm' | 'md' | 'lg';
-  children: React.ReactNode;
-  onClick?: () => void;
-  disabled?: boolean;
-}
-
-const Button: React.FC<ButtonProps> = ({ 
-  variant = 'primary', 
-  size = 'md', 
-  children, 
-  onClick, 
-  disabled 
-}) => {
-  return (
-    <button
-      className={`btn btn-${variant} btn-${size}`}
-      onClick={onClick}
-      disabled={disabled}
-    >
-      {children}
-    </button>
-  );
-};"#.to_string(),
-            language: "typescript".to_string(),
-            alternatives: vec![
-                "styled-components implementation".to_string(),
-                "css modules implementation".to_string(),
-            ],
-        },
-        CompletionLevel::Feature => CompletionResult {
-            id: uuid::Uuid::new_v4().to_string(),
-            level,
-            confidence: 0.88,
-            code: r#"// Authentication Feature Implementation
-export const useAuth = () => {
-  const [user, setUser] = useState<User | null>(null);
-  const [loading, setLoading] = useState(true);
-
-  const login = async (email: string, password: string) => {
-    try {
-      const response = await authService.login(email, password);
-      setUser(response.user);
-      localStorage.setItem('token', response.token);
-    } catch (error) {
-      throw new Error('Login failed');
-    }
-  };
-
-  const logout = () => {
-    setUser(null);
-    localStorage.removeItem('token');
-  };
-
-  useEffect(() => {
-    const token = localStorage.getItem('token');
-    if (token) {
-      authService.validateToken(token)
-        .then(user => setUser(user))
-        .catch(() => localStorage.removeItem('token'))
-        .finally(() => setLoading(false));
-    } else {
-      setLoading(false);
+
+    // Don't serve cached completions while AI is off
+    state.ensure_enabled()?;
+    let params = state.resolve_params(params, GenerationParams::completion_defaults())?;
+
+    let file_content = load_current_file(&context);
+    let cache_key = CompletionCache::key(&context, &level, file_content.as_deref());
+
+    if !bypass_cache.unwrap_or(false) {
+        if let Some(cached) = state.completion_cache.lock().unwrap().get(cache_key) {
+            log::info!("Serving completion from cache");
+            return Ok(cached);
+        }
     }
-  }, []);
 
-  return { user, login, logout, loading };
-};"#.to_string(),
-            language: "typescript".to_string(),
-            alternatives: vec![],
-        },
+    let language = context
+        .current_file
+        .as_deref()
+        .map(|file| {
+            crate::storage::detect_language(std::path::Path::new(file), file_content.as_deref())
+        })
+        .unwrap_or_else(|| "plaintext".to_string());
+
+    // Prefer the editor's buffer, which may hold unsaved edits, over the file on disk
+    let (prefix, suffix) = match (&context.prefix, &context.suffix) {
+        (None, None) => file_content
+            .as_deref()
+            .map(|content| split_at_cursor(content, &context.cursor_position))
+            .unwrap_or_default(),
+        (prefix, suffix) => (
+            prefix.clone().unwrap_or_default(),
+            suffix.clone().unwrap_or_default(),
+        ),
+    };
+
+    // Only the code near the cursor matters; sending all of a large file is
+    // slow and costly
+    let window = state.completion_window.read().unwrap().clone();
+    let (prefix, suffix) = if window.enabled {
+        let file_path = context.current_file.as_deref().unwrap_or_default();
+        let (prefix, suffix, windowed) =
+            window_around_cursor(file_path, &language, &prefix, &suffix, &window, &level);
+        if windowed {
+            log::debug!("Completion context cut to the window around the cursor");
+        }
+        (prefix, suffix)
+    } else {
+        (prefix, suffix)
     };
-    
+
+    // A newer completion for the same file supersedes this one
+    let request_key = context
+        .current_file
+        .clone()
+        .unwrap_or_else(|| context.project_path.clone());
+    let request = state.requests.begin(&request_key);
+
+    let model = state.active_config().await.model;
+    let fim = FimFormat::for_model(&model).filter(|_| !(prefix.is_empty() && suffix.is_empty()));
+
+    // Similar code from the project's index, so completions follow its conventions
+    let examples = state
+        .completion_examples(
+            app,
+            &context.project_path,
+            context.current_file.as_deref(),
+            &language,
+            &prefix,
+        )
+        .await;
+
+    let (code, alternatives, confidence, truncated) = match fim {
+        Some(format) => {
+            let commented = examples_for_fim(&examples, &language);
+            let budget = state.prompt_budget(&params, count_tokens(&commented)).await;
+            let (prefix, suffix, truncated) = fit_around_cursor(&prefix, &suffix, budget);
+
+            let mut stops: Vec<&str> = format.stop_tokens().to_vec();
+            stops.extend(level_stop(&level));
+
+            let prompt = format.prompt(&format!("{}{}", commented, prefix), &suffix);
+            let raw = request
+                .run(state.complete_text("ai_complete_code", &prompt, &params, &stops))
+                .await?;
+
+            // Raw completions carry no self-reported confidence
+            (raw, vec![], 0.5, truncated)
+        }
+        None => {
+            let (messages, truncated) = chat_prompt(
+                state,
+                &context,
+                &level,
+                &language,
+                &examples_for_chat(&examples),
+                &prefix,
+                &suffix,
+                &params,
+            )
+            .await;
+            let reply = request
+                .run(state.chat_with("ai_complete_code", &messages, &params))
+                .await?;
+            let value: serde_json::Value = parse_json_reply(&reply)?;
+
+            let code = value["code"]
+                .as_str()
+                .ok_or_else(|| {
+                    CodifyError::Provider("Model output did not contain a completion".to_string())
+                })?
+                .to_string();
+            let confidence = value["confidence"].as_f64().unwrap_or(0.5).clamp(0.0, 1.0) as f32;
+            let alternatives = value["alternatives"]
+                .as_array()
+                .map(|items| rank_alternatives(items, confidence * 0.9))
+                .unwrap_or_default();
+
+            (code, alternatives, confidence, truncated)
+        }
+    };
+
+    let completion = CompletionResult {
+        id: uuid::Uuid::new_v4().to_string(),
+        level: level.clone(),
+        confidence,
+        code: trim_to_level(&strip_echoed_prefix(&code, &prefix), &level),
+        language,
+        alternatives: alternatives
+            .into_iter()
+            .map(|alternative| CompletionAlternative {
+                code: trim_to_level(&strip_echoed_prefix(&alternative.code, &prefix), &level),
+                ..alternative
+            })
+            .collect(),
+        truncated,
+        context_snippets: example_locations(&examples),
+    };
+
+    state.selections.lock().unwrap().record_offer(
+        &completion.id,
+        &completion.language,
+        &completion.level,
+        &completion.code,
+        &completion.alternatives,
+    );
+
+    state
+        .completion_cache
+        .lock()
+        .unwrap()
+        .insert(cache_key, completion.clone());
+
     Ok(completion)
 }
 
+/// Clip prefix and suffix to `budget` tokens, keeping the lines nearest the cursor
+fn fit_around_cursor(prefix: &str, suffix: &str, budget: usize) -> (String, String, bool) {
+    const MARKER: &str = "<CURSOR>";
+
+    let marked = format!("{}{}{}", prefix, MARKER, suffix);
+    let cursor_line = prefix.matches('\n').count();
+    let (window, clipped) = fit_around_line(&marked, cursor_line, budget);
+
+    match window.split_once(MARKER) {
+        Some((prefix, suffix)) => (prefix.to_string(), suffix.to_string(), clipped),
+        None => (prefix.to_string(), suffix.to_string(), false),
+    }
+}
+
+/// Chat-model fallback: ask for JSON with the insertion at a cursor marker.
+/// Returns the messages and whether the file context was clipped.
+#[allow(clippy::too_many_arguments)]
+async fn chat_prompt(
+    state: &AIState,
+    context: &AIContext,
+    level: &CompletionLevel,
+    language: &str,
+    examples: &str,
+    prefix: &str,
+    suffix: &str,
+    params: &GenerationParams,
+) -> (Vec<ChatMessage>, bool) {
+    let scope = match level {
+        CompletionLevel::Line => "the rest of the current line only",
+        CompletionLevel::Block => "the current statement or block",
+        CompletionLevel::Component => "a complete component or function",
+        CompletionLevel::Feature => "a complete feature, including any helpers it needs",
+    };
+
+    let selection = context
+        .selected_text
+        .as_ref()
+        .filter(|s| !s.trim().is_empty())
+        .map(|selected| format!("Selected text:\n```{}\n{}\n```\n", language, selected))
+        .unwrap_or_default();
+    let values = [
+        ("language", language),
+        ("scope", scope),
+        ("selection", selection.as_str()),
+        ("examples", examples),
+    ];
+
+    // Spend whatever the window has left on file context, centred on the cursor
+    let mut file_context = String::new();
+    let mut truncated = false;
+    if !prefix.is_empty() || !suffix.is_empty() {
+        let used = messages_tokens(&state.prompt("complete_code", &values));
+        let budget = state.prompt_budget(params, used).await;
+        let (before, after, clipped) = fit_around_cursor(prefix, suffix, budget);
+        truncated = clipped;
+
+        file_context = format!(
+            "File ({}):\n```{}\n{}<CURSOR>{}\n```\n",
+            language, language, before, after
+        );
+    }
+
+    let values = [&values[..], &[("context", file_context.as_str())]].concat();
+    (state.prompt("complete_code", &values), truncated)
+}
+
+/// Record which suggestion the user took from a completion: the primary
+/// (no index) or one of its alternatives, by position
+#[tauri::command]
+pub async fn select_completion(
+    state: tauri::State<'_, AIState>,
+    completion_id: String,
+    alternative_index: Option<usize>,
+) -> Result<CompletionSelection, CodifyError> {
+    log::info!("Completion {} selected: {:?}", completion_id, alternative_index);
+
+    state
+        .selections
+        .lock()
+        .unwrap()
+        .select(&completion_id, alternative_index)
+}
+
+/// Record whether a completion was accepted, rejected or partly accepted;
+/// returns false without recording when feedback is switched off
+#[tauri::command]
+pub async fn record_completion_feedback(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AIState>,
+    completion_id: String,
+    outcome: CompletionOutcome,
+    accepted_chars: Option<usize>,
+) -> Result<bool, CodifyError> {
+    log::info!("Completion {} feedback: {:?}", completion_id, outcome);
+
+    if !state.completion_feedback() {
+        return Ok(false);
+    }
+
+    let offer = state.selections.lock().unwrap().summary(&completion_id)?;
+    let event = FeedbackEvent::new(&completion_id, offer, outcome, accepted_chars)?;
+    state.feedback.lock().unwrap().record(&app, &event)?;
+    state.selections.lock().unwrap().mark_rated(&completion_id);
+
+    Ok(true)
+}
+
+/// Cancel the in-flight AI request registered under `key` (usually the file path)
+#[tauri::command]
+pub async fn cancel_ai_request(state: tauri::State<'_, AIState>, key: String) -> Result<bool, CodifyError> {
+    log::info!("Cancelling AI request for: {}", key);
+
+    Ok(state.requests.cancel(&key))
+}
+
+/// Likely credentials in `text`, so the UI can warn before it is sent to a
+/// remote provider
+#[tauri::command]
+pub async fn scan_for_secrets(text: String) -> Result<Vec<SecretMatch>, CodifyError> {
+    Ok(find_secrets(&text))
+}
+
+/// Drop every cached completion
+#[tauri::command]
+pub async fn clear_completion_cache(state: tauri::State<'_, AIState>) -> Result<(), CodifyError> {
+    log::info!("Clearing completion cache");
+
+    state.completion_cache.lock().unwrap().clear();
+    Ok(())
+}
+
+/// Change completion cache capacity and entry lifetime
+#[tauri::command]
+pub async fn configure_completion_cache(
+    state: tauri::State<'_, AIState>,
+    capacity: usize,
+    ttl_secs: u64,
+) -> Result<CacheStats, CodifyError> {
+    let mut cache = state.completion_cache.lock().unwrap();
+    cache.configure(capacity, std::time::Duration::from_secs(ttl_secs));
+    Ok(cache.stats())
+}
+
 /// AI Code Explanation Command
 #[tauri::command]
-pub async fn ai_explain_code(code: String) -> Result<String, String> {
+pub async fn ai_explain_code(
+    state: tauri::State<'_, AIState>,
+    code: String,
+    language: Option<String>,
+    start_line: Option<u32>,
+    end_line: Option<u32>,
+    context: Option<AIContext>,
+) -> Result<CodeExplanation, CodifyError> {
+    let project_path = context
+        .as_ref()
+        .map(|context| context.project_path.clone())
+        .unwrap_or_default();
+    in_project(
+        &project_path,
+        explain_code(&state, code, language, start_line, end_line, context, None),
+    )
+    .await
+}
+
+/// Streaming ai_explain_code: returns a request id at once, then emits the
+/// explanation's text as `ai-stream-chunk` events while it is generated and
+/// the finished CodeExplanation in `ai-stream-done`. Cancel with
+/// cancel_ai_request, passing the request id as the key.
+#[tauri::command]
+pub async fn ai_explain_code_stream(
+    app: tauri::AppHandle,
+    code: String,
+    language: Option<String>,
+    start_line: Option<u32>,
+    end_line: Option<u32>,
+    context: Option<AIContext>,
+) -> Result<String, CodifyError> {
+    log::info!("Streaming AI explanation requested for code snippet");
+
+    app.state::<AIState>().ensure_enabled()?;
+    let stream = StreamEmitter::new(&app);
+    let request_id = stream.request_id.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AIState>();
+        let project_path = context
+            .as_ref()
+            .map(|context| context.project_path.clone())
+            .unwrap_or_default();
+
+        let request = state.requests.begin(&stream.request_id);
+        let explanation = explain_code(
+            &state,
+            code,
+            language,
+            start_line,
+            end_line,
+            context,
+            Some(&stream),
+        );
+        let result = request.run(in_project(&project_path, explanation)).await;
+        stream.done(result);
+    });
+
+    Ok(request_id)
+}
+
+async fn explain_code(
+    state: &AIState,
+    code: String,
+    language: Option<String>,
+    start_line: Option<u32>,
+    end_line: Option<u32>,
+    context: Option<AIContext>,
+    stream: Option<&StreamEmitter>,
+) -> Result<CodeExplanation, CodifyError> {
     log::info!("AI explanation requested for code snippet");
-    
-    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
-    
-    let explanation = if code.contains("useState") {
-        "This code uses React's useState hook to create a state variable and its setter function. The useState hook allows functional components to have local state."
-    } else if code.contains("async") && code.contains("await") {
-        "This is an async function that uses await to handle asynchronous operations. The await keyword pauses execution until the promise resolves."
-    } else {
-        "This code snippet appears to be a standard JavaScript/TypeScript implementation. It follows common patterns for modern web development."
+
+    let language = language.unwrap_or_else(|| "plaintext".to_string());
+
+    // A pasted error is diagnosed rather than explained line by line
+    if looks_like_error(&code) {
+        return explain_error(state, &code, language, context, stream).await;
+    }
+
+    let range = match (start_line, end_line) {
+        (Some(start), Some(end)) => format!("Explain lines {}-{} of the file.\n", start, end),
+        _ => String::new(),
     };
-    
-    Ok(explanation.to_string())
+    let values = [
+        ("language", language.as_str()),
+        ("range", range.as_str()),
+        ("code", code.as_str()),
+    ];
+
+    // Optionally include the surrounding file so a fragment is explained in context,
+    // clipped around the highlighted region if it would overflow the window
+    let mut surrounding = String::new();
+    if let Some(file_content) = context.as_ref().and_then(load_current_file) {
+        let used = messages_tokens(&state.prompt("explain_code", &values));
+        let budget = state
+            .prompt_budget(&GenerationParams::default(), used)
+            .await;
+        let focus = start_line.unwrap_or(1).max(1) as usize - 1;
+        let (window, _) = fit_around_line(&file_content, focus, budget);
+        surrounding = format!("\nSurrounding file:\n```{}\n{}\n```\n", language, window);
+    }
+
+    let values = [&values[..], &[("context", surrounding.as_str())]].concat();
+    let messages = state.prompt("explain_code", &values);
+
+    let reply = state
+        .chat_streamed("ai_explain_code", &messages, stream)
+        .await?;
+
+    // Fall back to the raw reply if the model ignored the JSON instruction
+    let (summary, details) = match parse_json_reply::<serde_json::Value>(&reply) {
+        Ok(value) => (
+            value["summary"].as_str().unwrap_or_default().to_string(),
+            value["details"]
+                .as_array()
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|item| item.as_str().map(|s| s.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        ),
+        Err(_) => (reply.trim().to_string(), vec![]),
+    };
+
+    let raw_markdown = std::iter::once(summary.as_str())
+        .chain(details.iter().map(String::as_str))
+        .filter(|part| !part.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    Ok(CodeExplanation {
+        segments: markdown_segments(&raw_markdown),
+        raw_markdown,
+        summary,
+        details,
+        language,
+        start_line,
+        end_line,
+        error: None,
+    })
+}
+
+/// Source shown around the innermost project frame of an error
+const ERROR_SOURCE_TOKENS: usize = 600;
+
+/// Project-relative path of a file named in a stack trace, if it is in the project
+fn resolve_frame(project_path: &str, file_path: &str) -> Option<String> {
+    if project_path.is_empty() {
+        return None;
+    }
+    let root = std::path::Path::new(project_path).canonicalize().ok()?;
+    let full = crate::storage::resolve_in_project(project_path, file_path)
+        .ok()
+        .filter(|path| path.is_file())?;
+    let relative = full.strip_prefix(&root).ok()?;
+    Some(relative.to_string_lossy().replace('\\', "/"))
+}
+
+async fn explain_error(
+    state: &AIState,
+    text: &str,
+    language: String,
+    context: Option<AIContext>,
+    stream: Option<&StreamEmitter>,
+) -> Result<CodeExplanation, CodifyError> {
+    log::info!("Explaining an error message");
+
+    let project_path = context
+        .as_ref()
+        .map(|context| context.project_path.as_str())
+        .unwrap_or_default();
+    let mut frames = parse_stack_frames(text);
+    for frame in &mut frames {
+        frame.resolved_path = resolve_frame(project_path, &frame.file_path);
+    }
+    let errors = parse_error_output(text);
+
+    let mut located = String::new();
+    for frame in frames.iter().filter(|frame| !frame.external).take(10) {
+        located.push_str(&format!(
+            "- {} at {}:{}\n",
+            frame.function.as_deref().unwrap_or("(anonymous)"),
+            frame.resolved_path.as_deref().unwrap_or(&frame.file_path),
+            frame.line
+        ));
+    }
+    for error in errors.iter().take(10) {
+        located.push_str(&format!(
+            "- {}:{}: {}\n",
+            error.file_path, error.line, error.message
+        ));
+    }
+    if !located.is_empty() {
+        located = format!("\nLocations in project code:\n{}", located);
+    }
+
+    // The code where it went wrong: the innermost project frame, else the first error
+    let site = frames
+        .iter()
+        .filter(|frame| !frame.external)
+        .find_map(|frame| Some((frame.resolved_path.clone()?, frame.line)))
+        .or_else(|| {
+            errors.iter().find_map(|error| {
+                Some((resolve_frame(project_path, &error.file_path)?, error.line))
+            })
+        });
+    let mut source = String::new();
+    if let Some((path, line)) = site {
+        let full_path = std::path::Path::new(project_path).join(&path);
+        if let Ok(content) = tokio::fs::read_to_string(&full_path).await {
+            let focus = line.max(1) as usize - 1;
+            let (window, _) = fit_around_line(&content, focus, ERROR_SOURCE_TOKENS);
+            source = format!("\nCode around {}:{}:\n```\n{}\n```\n", path, line, window);
+        }
+    }
+
+    let (error, _) = fit_around_line(text, 0, ERROR_OUTPUT_TOKENS);
+    let values = [
+        ("language", language.as_str()),
+        ("error", error.as_str()),
+        ("locations", located.as_str()),
+        ("source", source.as_str()),
+    ];
+    let messages = state.prompt("explain_error", &values);
+
+    let reply = state
+        .chat_streamed("ai_explain_code", &messages, stream)
+        .await?;
+
+    // Fall back to the raw reply if the model ignored the JSON instruction
+    let (cause, fix_steps) = match parse_json_reply::<serde_json::Value>(&reply) {
+        Ok(value) => (
+            value["cause"].as_str().unwrap_or_default().to_string(),
+            value["fix_steps"]
+                .as_array()
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|item| item.as_str().map(|s| s.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        ),
+        Err(_) => (reply.trim().to_string(), vec![]),
+    };
+
+    let steps = fix_steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| format!("{}. {}", i + 1, step))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let raw_markdown = [cause.as_str(), steps.as_str()]
+        .into_iter()
+        .filter(|part| !part.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    Ok(CodeExplanation {
+        segments: markdown_segments(&raw_markdown),
+        raw_markdown,
+        summary: cause.clone(),
+        details: fix_steps.clone(),
+        language,
+        start_line: None,
+        end_line: None,
+        error: Some(ErrorExplanation {
+            cause,
+            fix_steps,
+            frames,
+            errors,
+        }),
+    })
+}
+
+/// Explain several regions at once, e.g. every hunk of a diff
+///
+/// Items run concurrently, at most the rate limit's max_concurrent at a time
+/// so none waits out the queue timeout. Each item succeeds or fails on its
+/// own; results come back in request order with the caller's ids.
+#[tauri::command]
+pub async fn ai_explain_code_batch(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AIState>,
+    items: Vec<ExplainBatchItem>,
+    project_path: Option<String>,
+) -> Result<Vec<ExplainBatchResult>, CodifyError> {
+    log::info!("AI batch explanation requested for {} regions", items.len());
+
+    let mut ids = std::collections::HashSet::new();
+    if let Some(item) = items.iter().find(|item| !ids.insert(item.id.as_str())) {
+        return Err(CodifyError::InvalidInput(format!(
+            "Duplicate batch item id: {}",
+            item.id
+        )));
+    }
+    state.ensure_enabled()?;
+
+    let project_path = project_path.unwrap_or_default();
+    let rate_limit = in_project(&project_path, state.active_config())
+        .await
+        .rate_limit;
+    let slots = match rate_limit.max_concurrent {
+        0 => items.len().max(1),
+        limit => limit,
+    };
+    let slots = std::sync::Arc::new(tokio::sync::Semaphore::new(slots));
+
+    let tasks: Vec<_> = items
+        .into_iter()
+        .map(|item| {
+            let (app, slots, project_path) = (app.clone(), slots.clone(), project_path.clone());
+            tauri::async_runtime::spawn(async move {
+                let _slot = slots.acquire_owned().await;
+                let state = app.state::<AIState>();
+                let context = batch_item_context(&project_path, &item);
+
+                let explanation = explain_code(
+                    &state,
+                    item.code,
+                    item.language,
+                    item.start_line,
+                    item.end_line,
+                    context,
+                    None,
+                );
+                match in_project(&project_path, explanation).await {
+                    Ok(explanation) => ExplainBatchResult {
+                        id: item.id,
+                        explanation: Some(explanation),
+                        error: None,
+                    },
+                    Err(e) => ExplainBatchResult {
+                        id: item.id,
+                        explanation: None,
+                        error: Some(e),
+                    },
+                }
+            })
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        results.push(
+            task.await
+                .map_err(|e| CodifyError::Internal(format!("Explain task failed: {}", e)))?,
+        );
+    }
+    Ok(results)
+}
+
+/// Context that sends an item's file along with it, when the batch names a project
+fn batch_item_context(project_path: &str, item: &ExplainBatchItem) -> Option<AIContext> {
+    if project_path.is_empty() {
+        return None;
+    }
+    Some(AIContext {
+        project_path: project_path.to_string(),
+        current_file: Some(item.file_path.clone()?),
+        selected_text: None,
+        cursor_position: Position {
+            line: item.start_line.unwrap_or(1),
+            column: 0,
+        },
+        prefix: None,
+        suffix: None,
+    })
+}
+
+/// Read the file referenced by the context, resolving it against the project root
+///
+/// Paths outside the project are refused, and credential files are skipped
+/// unless the secret policy is off, so neither ends up in a prompt.
+fn load_current_file(context: &AIContext) -> Option<String> {
+    let current_file = context.current_file.as_ref()?;
+    let path = match crate::storage::resolve_in_project(&context.project_path, current_file) {
+        Ok(path) => path,
+        Err(e) => {
+            log::warn!("Not reading {} into the prompt: {}", current_file, e);
+            return None;
+        }
+    };
+    let name = path.file_name()?.to_string_lossy();
+    if secret_policy() != SecretPolicy::Off && is_secret_file(&name) {
+        log::warn!(
+            "Not reading credential file {} into the prompt",
+            current_file
+        );
+        return None;
+    }
+    std::fs::read_to_string(path).ok()
 }
 
 /// AI Refactoring Suggestions Command
 #[tauri::command]
-pub async fn ai_suggest_refactor(code: String) -> Result<Vec<String>, String> {
+pub async fn ai_suggest_refactor(
+    state: tauri::State<'_, AIState>,
+    code: String,
+    context: AIContext,
+) -> Result<Vec<RefactorSuggestion>, CodifyError> {
     log::info!("AI refactoring suggestions requested");
-    
-    tokio::time::sleep(std::time::Duration::from_millis(400)).await;
-    
-    let suggestions = vec![
-        "Extract this logic into a custom hook for better reusability".to_string(),
-        "Consider using TypeScript interfaces for better type safety".to_string(),
-        "Add error boundaries to handle potential runtime errors".to_string(),
-        "Implement memoization with useMemo for performance optimization".to_string(),
-    ];
-    
+
+    let suggestions = in_project(
+        &context.project_path,
+        suggest_refactors(&state, &code, Some(&context), None),
+    )
+    .await?;
+
     Ok(suggestions)
 }
 
+/// Streaming ai_suggest_refactor: returns a request id at once, then emits
+/// each suggestion as an `ai-refactor-suggestion` event as soon as the model
+/// has finished writing it, and the full list, highest confidence first, in
+/// `ai-stream-done`. Cancel with cancel_ai_request, passing the request id.
+#[tauri::command]
+pub async fn ai_suggest_refactor_stream(
+    app: tauri::AppHandle,
+    code: String,
+    context: AIContext,
+) -> Result<String, CodifyError> {
+    log::info!("Streaming AI refactoring suggestions requested");
+
+    app.state::<AIState>().ensure_enabled()?;
+    let stream = StreamEmitter::new(&app);
+    let request_id = stream.request_id.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AIState>();
+        let request = state.requests.begin(&stream.request_id);
+        let suggestions = suggest_refactors(&state, &code, Some(&context), Some(&stream));
+        let result = request
+            .run(in_project(&context.project_path, suggestions))
+            .await;
+        stream.done(result);
+    });
+
+    Ok(request_id)
+}
+
+/// Deprecated: prose-only refactoring suggestions, kept while the frontend
+/// migrates to `ai_suggest_refactor`
+#[tauri::command]
+pub async fn ai_suggest_refactor_legacy(
+    state: tauri::State<'_, AIState>,
+    code: String,
+    project_path: Option<String>,
+) -> Result<Vec<String>, CodifyError> {
+    log::info!("Legacy AI refactoring suggestions requested");
+
+    let suggestions = suggest_refactors(&state, &code, None, None);
+    let suggestions = match project_path {
+        Some(project_path) => in_project(&project_path, suggestions).await?,
+        None => suggestions.await?,
+    };
+
+    Ok(suggestions
+        .into_iter()
+        .map(|s| format!("{}: {}", s.title, s.description))
+        .collect())
+}
+
+async fn suggest_refactors(
+    state: &AIState,
+    code: &str,
+    context: Option<&AIContext>,
+    stream: Option<&StreamEmitter>,
+) -> Result<Vec<RefactorSuggestion>, CodifyError> {
+    let numbered = number_lines(code, &[]);
+
+    let messages = state.prompt("suggest_refactor", &[("code", numbered.as_str())]);
+
+    let file_path = context
+        .and_then(|c| c.current_file.clone())
+        .unwrap_or_default();
+    let line_count = code.lines().count() as u32;
+
+    let reply = match stream {
+        None => state.chat("ai_suggest_refactor", &messages).await?,
+        Some(stream) => {
+            // Each array item is sent on as soon as it closes
+            let mut scanner = ReplyScanner::default();
+            let params = GenerationParams::default();
+            let on_delta = |delta: &str| {
+                for event in scanner.feed(delta) {
+                    let ScanEvent::Item(raw) = event else {
+                        continue;
+                    };
+                    let suggestion = serde_json::from_str::<serde_json::Value>(&raw)
+                        .ok()
+                        .and_then(|item| parse_suggestion(&item, &file_path, line_count));
+                    if let Some(suggestion) = suggestion {
+                        stream.suggestion(suggestion);
+                    }
+                }
+            };
+            state
+                .chat_stream("ai_suggest_refactor", &messages, &params, on_delta)
+                .await?
+        }
+    };
+
+    parse_suggestions(&reply, &file_path, line_count)
+}
+
+/// Number each line so the model can point at exact ranges
+fn number_lines(code: &str, marked: &[u32]) -> String {
+    code.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let marker = if marked.contains(&(i as u32 + 1)) { ">>" } else { "  " };
+            format!("{}{:>4} | {}\n", marker, i + 1, line)
+        })
+        .collect()
+}
+
+/// Turn a JSON array of edits into suggestions, dropping any whose lines fall
+/// outside the snippet, highest confidence first
+fn parse_suggestions(
+    reply: &str,
+    file_path: &str,
+    line_count: u32,
+) -> Result<Vec<RefactorSuggestion>, CodifyError> {
+    let raw: Vec<serde_json::Value> = parse_json_reply(reply)?;
+
+    let mut suggestions: Vec<RefactorSuggestion> = raw
+        .iter()
+        .filter_map(|item| parse_suggestion(item, file_path, line_count))
+        .collect();
+
+    suggestions.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+
+    Ok(suggestions)
+}
+
+/// One edit from the model's array, or None if its lines fall outside the snippet
+fn parse_suggestion(
+    item: &serde_json::Value,
+    file_path: &str,
+    line_count: u32,
+) -> Option<RefactorSuggestion> {
+    let start_line = item["start_line"].as_u64()? as u32;
+    let end_line = item["end_line"].as_u64()? as u32;
+
+    // Drop edits that point outside the snippet; the editor can't apply them
+    if start_line == 0 || start_line > end_line || end_line > line_count {
+        return None;
+    }
+
+    Some(RefactorSuggestion {
+        title: item["title"].as_str().unwrap_or_default().to_string(),
+        description: item["description"].as_str().unwrap_or_default().to_string(),
+        file_path: file_path.to_string(),
+        start_line,
+        end_line,
+        replacement_code: item["replacement_code"].as_str()?.to_string(),
+        confidence: item["confidence"].as_f64().unwrap_or(0.5).clamp(0.0, 1.0) as f32,
+    })
+}
+
+/// Most of a long build log is noise; the first errors matter most
+const ERROR_OUTPUT_TOKENS: usize = 1500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorFix {
+    pub errors: Vec<ErrorLocation>, // Errors parsed from the output that are in this file
+    pub fixes: Vec<RefactorSuggestion>, // Candidate fixes, highest confidence first
+}
+
+/// Propose fixes for a failed build or test run
+///
+/// `error_output` is raw tool output such as TerminalResponse.error. Errors
+/// from tsc, rustc and Jest/Vitest are located so the offending lines of
+/// `code` (the contents of `file_path`) can be pointed out to the model.
+#[tauri::command]
+pub async fn ai_fix_error(
+    state: tauri::State<'_, AIState>,
+    error_output: String,
+    file_path: String,
+    code: String,
+    project_path: Option<String>,
+) -> Result<ErrorFix, CodifyError> {
+    log::info!("AI fix requested for errors in: {}", file_path);
+
+    let fix = fix_error(&state, &error_output, &file_path, &code);
+    match project_path {
+        Some(project_path) => in_project(&project_path, fix).await,
+        None => fix.await,
+    }
+}
+
+async fn fix_error(
+    state: &AIState,
+    error_output: &str,
+    file_path: &str,
+    code: &str,
+) -> Result<ErrorFix, CodifyError> {
+    if error_output.trim().is_empty() {
+        return Err(CodifyError::InvalidInput("No error output to fix".to_string()));
+    }
+
+    let errors: Vec<ErrorLocation> = parse_error_output(error_output)
+        .into_iter()
+        .filter(|error| error.is_in(file_path))
+        .collect();
+    let marked: Vec<u32> = errors.iter().map(|error| error.line).collect();
+
+    let first_line = errors.first().map(|e| e.line.saturating_sub(1) as usize).unwrap_or(0);
+    let (output, _) = fit_around_line(error_output, 0, ERROR_OUTPUT_TOKENS);
+
+    let mut located = String::new();
+    if !errors.is_empty() {
+        located.push_str("\nLocated errors:\n");
+        for error in &errors {
+            located.push_str(&format!(
+                "- line {}: {}{}\n",
+                error.line,
+                error.code.as_deref().map(|c| format!("{} ", c)).unwrap_or_default(),
+                error.message
+            ));
+        }
+    }
+    let values = [
+        ("file_path", file_path),
+        ("error_output", output.as_str()),
+        ("located_errors", located.as_str()),
+    ];
+
+    // Keep the code around the first error if the whole file doesn't fit
+    let used = messages_tokens(&state.prompt("fix_error", &values));
+    let budget = state
+        .prompt_budget(&GenerationParams::default(), used + ERROR_OUTPUT_TOKENS)
+        .await;
+    let numbered = number_lines(code, &marked);
+    let (numbered, _) = fit_around_line(&numbered, first_line, budget);
+
+    let values = [&values[..], &[("code", numbered.as_str())]].concat();
+    let messages = state.prompt("fix_error", &values);
+
+    let reply = state.chat("ai_fix_error", &messages).await?;
+    let fixes = parse_suggestions(&reply, file_path, code.lines().count() as u32)?;
+
+    Ok(ErrorFix { errors, fixes })
+}
+
+/// Document the first function, class or type in `code`
+///
+/// Returns a JSDoc/TSDoc, rustdoc or Python docstring block and the line to
+/// insert it before; the snippet itself is never rewritten.
+#[tauri::command]
+pub async fn ai_generate_docstring(
+    state: tauri::State<'_, AIState>,
+    code: String,
+    language: String,
+    project_path: Option<String>,
+) -> Result<GeneratedDocstring, CodifyError> {
+    log::info!("AI docstring requested for {} snippet", language);
+
+    let docstring = generate_docstring(&state, &code, language);
+    match project_path {
+        Some(project_path) => in_project(&project_path, docstring).await,
+        None => docstring.await,
+    }
+}
+
+async fn generate_docstring(
+    state: &AIState,
+    code: &str,
+    language: String,
+) -> Result<GeneratedDocstring, CodifyError> {
+    let site = crate::storage::find_definition(code, &language).ok_or_else(|| {
+        CodifyError::InvalidInput(format!(
+            "No function or declaration found in the {} snippet",
+            language
+        ))
+    })?;
+
+    let is_component = crate::storage::is_js_family(&language)
+        && site.kind == "function"
+        && site.name.starts_with(|c: char| c.is_ascii_uppercase());
+
+    let budget = state.prompt_budget(&GenerationParams::default(), 0).await;
+    let (snippet, _) = fit_around_line(code, site.insert_row, budget);
+
+    let messages = state.prompt(
+        "generate_docstring",
+        &[
+            ("language", language.as_str()),
+            ("guidance", docstring_guidance(&language, is_component)),
+            ("name", site.name.as_str()),
+            ("kind", site.kind),
+            ("signature", site.signature.as_str()),
+            ("code", snippet.as_str()),
+        ],
+    );
+
+    let reply = state.chat("ai_generate_docstring", &messages).await?;
+    let content: DocContent = parse_json_reply(&reply)?;
+
+    let docstring = format_docstring(&content, &language, &site.indent)?;
+
+    Ok(GeneratedDocstring {
+        docstring,
+        insert_line: site.insert_row as u32 + 1,
+        name: site.name,
+        kind: site.kind.to_string(),
+        language,
+    })
+}
+
+/// Extract the selected JSX into a new component file
+///
+/// Props are inferred from what the selection references; the result is an
+/// edit set for apply_patch that creates the file, imports it and replaces
+/// the selection with the new element.
+#[tauri::command]
+pub async fn ai_extract_component(
+    state: tauri::State<'_, AIState>,
+    context: AIContext,
+    component_name: Option<String>,
+    params: Option<GenerationParams>,
+) -> Result<ExtractedComponent, CodifyError> {
+    let project_path = context.project_path.clone();
+    in_project(
+        &project_path,
+        extract_component(&state, &context, component_name, params),
+    )
+    .await
+}
+
+async fn extract_component(
+    state: &AIState,
+    context: &AIContext,
+    component_name: Option<String>,
+    params: Option<GenerationParams>,
+) -> Result<ExtractedComponent, CodifyError> {
+    log::info!("Extracting component from selection");
+
+    let params = state.resolve_params(params, GenerationParams::extract_defaults())?;
+
+    let selected = context
+        .selected_text
+        .as_deref()
+        .filter(|s| !s.trim().is_empty())
+        .ok_or_else(|| {
+            CodifyError::InvalidInput("Invalid selection: select the JSX to extract".to_string())
+        })?;
+    check_jsx(selected)?;
+
+    let current_file = context.current_file.clone().ok_or_else(|| {
+        CodifyError::InvalidInput(
+            "Invalid context: extracting a component needs the current file".to_string(),
+        )
+    })?;
+    let content = load_current_file(context)
+        .ok_or_else(|| CodifyError::NotFound(format!("File not found: {}", current_file)))?;
+    let site = locate_selection(&content, selected, context.cursor_position.line)?;
+
+    if let Some(name) = component_name.as_deref().filter(|n| !is_component_name(n)) {
+        return Err(CodifyError::InvalidInput(format!(
+            "Invalid component name '{}': use PascalCase",
+            name
+        )));
+    }
+
+    let bindings: Vec<String> = referenced_bindings(&content, selected, &site)
+        .into_iter()
+        .map(|binding| match binding.definition {
+            Some(line) if binding.from_hook => {
+                format!("- {} (from a hook): {}", binding.name, line)
+            }
+            Some(line) => format!("- {}: {}", binding.name, line),
+            None => format!(
+                "- {}: not bound in this file (a prop, loop variable or global)",
+                binding.name
+            ),
+        })
+        .collect();
+
+    let language =
+        crate::storage::detect_language(std::path::Path::new(&current_file), Some(&content));
+    let (start_line, end_line) = (site.start_line.to_string(), site.end_line.to_string());
+    let bindings = if bindings.is_empty() {
+        "- none".to_string()
+    } else {
+        bindings.join("\n")
+    };
+    let messages = state.prompt(
+        "extract_component",
+        &[
+            ("file_path", current_file.as_str()),
+            ("language", language.as_str()),
+            ("file", content.as_str()),
+            ("start_line", start_line.as_str()),
+            ("end_line", end_line.as_str()),
+            ("selection", selected.trim()),
+            ("bindings", bindings.as_str()),
+            (
+                "component_name",
+                component_name
+                    .as_deref()
+                    .unwrap_or("choose a descriptive PascalCase name"),
+            ),
+        ],
+    );
+
+    let reply = state
+        .chat_with("ai_extract_component", &messages, &params)
+        .await?;
+    let value: serde_json::Value = parse_json_reply(&reply)?;
+
+    let field = |name: &str| {
+        value[name]
+            .as_str()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| {
+                CodifyError::Provider(format!("Model output is missing the '{}' field", name))
+            })
+    };
+    let name = match component_name {
+        Some(name) => name,
+        None => field("component_name")?,
+    };
+    if !is_component_name(&name) {
+        return Err(CodifyError::Provider(format!(
+            "Model output named the component '{}', which is not PascalCase",
+            name
+        )));
+    }
+    let component_code = field("component_code")?;
+    let usage = field("usage")?;
+    check_jsx(&usage).map_err(|e| {
+        CodifyError::Provider(format!("Model output usage is not valid JSX: {}", e))
+    })?;
+
+    let props: Vec<ExtractedProp> = value["props"]
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| serde_json::from_value(item.clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let file_path = component_path(&current_file, &name);
+    if crate::storage::resolve_in_project(&context.project_path, &file_path)?.exists() {
+        return Err(CodifyError::InvalidInput(format!(
+            "File already exists: {}",
+            file_path
+        )));
+    }
+
+    Ok(ExtractedComponent {
+        edits: extraction_edits(
+            &current_file,
+            &content,
+            &site,
+            &name,
+            &file_path,
+            &component_code,
+            &usage,
+        ),
+        component_name: name,
+        file_path,
+        props,
+    })
+}
+
+/// Scaffold a feature as new files (component, hook, test, styles) laid out
+/// the way the project's existing files are
+///
+/// Nothing is written: the result is a preview tree plus an edit set for
+/// apply_patch that creates the files.
+#[tauri::command]
+pub async fn ai_scaffold_feature(
+    state: tauri::State<'_, AIState>,
+    project_path: String,
+    description: String,
+    feature_name: Option<String>,
+    params: Option<GenerationParams>,
+) -> Result<ScaffoldedFeature, CodifyError> {
+    in_project(
+        &project_path,
+        scaffold_feature(&state, &project_path, &description, feature_name, params),
+    )
+    .await
+}
+
+async fn scaffold_feature(
+    state: &AIState,
+    project_path: &str,
+    description: &str,
+    feature_name: Option<String>,
+    params: Option<GenerationParams>,
+) -> Result<ScaffoldedFeature, CodifyError> {
+    log::info!("Scaffolding feature in: {}", project_path);
+
+    let params = state.resolve_params(params, GenerationParams::scaffold_defaults())?;
+    if description.trim().is_empty() {
+        return Err(CodifyError::InvalidInput(
+            "Feature description must not be empty".to_string(),
+        ));
+    }
+    if let Some(name) = feature_name.as_deref().filter(|n| !is_component_name(n)) {
+        return Err(CodifyError::InvalidInput(format!(
+            "Invalid feature name '{}': use PascalCase",
+            name
+        )));
+    }
+
+    let root = project_path.to_string();
+    let files = tokio::task::spawn_blocking(move || crate::storage::walk_project(&root))
+        .await
+        .map_err(|e| CodifyError::Internal(format!("File traversal failed: {}", e)))??;
+    let conventions = detect_conventions(project_path, &files);
+    let framework = detect_test_framework(project_path);
+
+    let layout = conventions.describe();
+    let framework_name = format!("{:?}", framework);
+    let messages = state.prompt(
+        "scaffold_feature",
+        &[
+            ("description", description.trim()),
+            (
+                "feature_name",
+                feature_name
+                    .as_deref()
+                    .unwrap_or("choose a descriptive PascalCase name"),
+            ),
+            ("layout", layout.as_str()),
+            ("framework", framework_name.as_str()),
+            ("guidance", framework.prompt_guidance()),
+        ],
+    );
+
+    let reply = state
+        .chat_with("ai_scaffold_feature", &messages, &params)
+        .await?;
+    let value: serde_json::Value = parse_json_reply(&reply)?;
+
+    let field = |name: &str| {
+        value[name]
+            .as_str()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+    let missing =
+        |name: &str| CodifyError::Provider(format!("Model output is missing the '{}' field", name));
+    let name = match feature_name {
+        Some(name) => name,
+        None => field("feature_name").ok_or_else(|| missing("feature_name"))?,
+    };
+    if !is_component_name(&name) {
+        return Err(CodifyError::Provider(format!(
+            "Model output named the feature '{}', which is not PascalCase",
+            name
+        )));
+    }
+
+    // The hook is optional; the stylesheet is only asked for when the layout has one
+    let paths = conventions.paths(&name);
+    let mut files = vec![];
+    for (role, path) in [
+        (ScaffoldRole::Component, Some(paths.component)),
+        (ScaffoldRole::Hook, Some(paths.hook)),
+        (ScaffoldRole::Test, Some(paths.test)),
+        (ScaffoldRole::Styles, paths.styles),
+    ] {
+        let Some(path) = path else {
+            continue;
+        };
+        match field(role.field()) {
+            Some(content) => files.push(ScaffoldFile {
+                role,
+                path,
+                content,
+            }),
+            None if role.required() => return Err(missing(role.field())),
+            None => {}
+        }
+    }
+
+    for file in &files {
+        if crate::storage::resolve_in_project(project_path, &file.path)?.exists() {
+            return Err(CodifyError::InvalidInput(format!(
+                "File already exists: {}",
+                file.path
+            )));
+        }
+    }
+
+    let paths: Vec<&str> = files.iter().map(|file| file.path.as_str()).collect();
+    Ok(ScaffoldedFeature {
+        feature_name: name,
+        tree: preview_tree(&paths),
+        edits: creation_edits(&files),
+        files,
+        conventions,
+    })
+}
+
 /// AI Test Generation Command
 #[tauri::command]
-pub async fn ai_generate_tests(code: String) -> Result<String, String> {
+pub async fn ai_generate_tests(
+    state: tauri::State<'_, AIState>,
+    code: String,
+    config: Option<TestGenConfig>,
+    project_path: Option<String>,
+    source_path: Option<String>,
+    params: Option<GenerationParams>,
+    dry_run: Option<bool>,
+) -> Result<GeneratedTests, CodifyError> {
+    let project = project_path.clone().unwrap_or_default();
+    in_project(
+        &project,
+        generate_tests(
+            &state,
+            code,
+            config,
+            project_path,
+            source_path,
+            params,
+            dry_run.unwrap_or(false),
+        ),
+    )
+    .await
+}
+
+async fn generate_tests(
+    state: &AIState,
+    code: String,
+    config: Option<TestGenConfig>,
+    project_path: Option<String>,
+    source_path: Option<String>,
+    params: Option<GenerationParams>,
+    dry_run: bool,
+) -> Result<GeneratedTests, CodifyError> {
     log::info!("AI test generation requested");
-    
-    tokio::time::sleep(std::time::Duration::from_millis(600)).await;
-    
-    let tests = r#"import { render, screen, fireEvent } from '@testing-library/react';
-import { Button } from './Button';
-
-describe('Button Component', () => {
-  test('renders button with children', () => {
-    render(<Button>Click me</Button>);
-    expect(screen.getByRole('button')).toHaveTextContent('Click me');
-  });
-
-  test('calls onClick when clicked', () => {
-    const handleClick = jest.fn();
-    render(<Button onClick={handleClick}>Click me</Button>);
-    
-    fireEvent.click(screen.getByRole('button'));
-    expect(handleClick).toHaveBeenCalledTimes(1);
-  });
-
-  test('applies correct variant classes', () => {
-    render(<Button variant="secondary">Test</Button>);
-    expect(screen.getByRole('button')).toHaveClass('btn-secondary');
-  });
-
-  test('is disabled when disabled prop is true', () => {
-    render(<Button disabled>Disabled</Button>);
-    expect(screen.getByRole('button')).toBeDisabled();
-  });
-});"#;
-    
-    Ok(tests.to_string())
-}
\ No newline at end of file
+
+    let targets = find_test_targets(&code);
+    if targets.is_empty() {
+        return Err(CodifyError::InvalidInput(
+            "No testable exports found: export a function, component or class from the code"
+                .to_string(),
+        ));
+    }
+
+    let params = state.resolve_params(params, GenerationParams::test_defaults())?;
+
+    let project_path = project_path.unwrap_or_default();
+
+    // Without explicit config, follow whatever the project already uses
+    let config = config.unwrap_or_else(|| TestGenConfig {
+        framework: detect_test_framework(&project_path),
+        language: crate::commands::detect_project(&project_path)
+            .ok()
+            .filter(|detection| detection.is_javascript())
+            .map(|detection| detection.language)
+            .unwrap_or_else(|| "typescript".to_string()),
+        output_path: None,
+    });
+
+    let file_path = config.output_path.clone().unwrap_or_else(|| {
+        default_test_path(source_path.as_deref(), config.framework, &config.language)
+    });
+
+    // Both paths may be absolute or project-relative; compare them relative
+    let relative = |path: &str| {
+        path.strip_prefix(project_path.as_str())
+            .filter(|_| !project_path.is_empty())
+            .map(|path| path.trim_start_matches(['/', '\\']).to_string())
+            .unwrap_or_else(|| path.to_string())
+    };
+    let import_statement = source_path.as_deref().map(|source| {
+        let specifier = relative_import(&relative(&file_path), &relative(source));
+        import_statement(&targets, &specifier)
+    });
+
+    let file_exists = std::path::Path::new(&project_path).join(&file_path).exists();
+
+    if dry_run {
+        return Ok(GeneratedTests {
+            file_path,
+            code: import_statement.clone().unwrap_or_default(),
+            framework: config.framework,
+            file_exists,
+            targets,
+            import_statement,
+            dry_run,
+        });
+    }
+
+    let exports = targets
+        .iter()
+        .map(|target| {
+            let kind = match target.kind {
+                TestTargetKind::Function => "function",
+                TestTargetKind::Component => "React component",
+                TestTargetKind::Class => "class",
+            };
+            if target.is_default {
+                format!("- {} ({}, default export)", target.signature, kind)
+            } else {
+                format!("- {} ({})", target.signature, kind)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let imports = match &import_statement {
+        Some(import) => format!("Import the code under test with exactly:\n{}\n", import),
+        None => "Import the code under test from its module.\n".to_string(),
+    };
+
+    let framework = format!("{:?}", config.framework);
+    let messages = state.prompt(
+        "generate_tests",
+        &[
+            ("framework", framework.as_str()),
+            ("language", config.language.as_str()),
+            ("guidance", config.framework.prompt_guidance()),
+            ("file_path", file_path.as_str()),
+            ("imports", imports.as_str()),
+            ("exports", exports.as_str()),
+            ("code", code.as_str()),
+        ],
+    );
+
+    let reply = state.chat_with("ai_generate_tests", &messages, &params).await?;
+    let code = strip_code_fence(&reply);
+
+    Ok(GeneratedTests {
+        file_path,
+        code,
+        framework: config.framework,
+        file_exists,
+        targets,
+        import_statement,
+        dry_run,
+    })
+}
+
+/// Start a new conversation, optionally seeded with a system prompt
+#[tauri::command]
+pub async fn create_conversation(
+    app: tauri::AppHandle,
+    store: tauri::State<'_, ConversationStore>,
+    title: Option<String>,
+    system_prompt: Option<String>,
+) -> Result<Conversation, CodifyError> {
+    log::info!("Creating conversation");
+
+    let conversation = Conversation::new(title, system_prompt);
+    store.save(&app, &conversation)?;
+
+    Ok(conversation)
+}
+
+/// Append a user message, send the history that fits the context window,
+/// and return the conversation with the model's reply appended
+#[tauri::command]
+pub async fn send_message(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AIState>,
+    store: tauri::State<'_, ConversationStore>,
+    conversation_id: String,
+    content: String,
+    params: Option<GenerationParams>,
+) -> Result<Conversation, CodifyError> {
+    log::info!("Sending message to conversation: {}", conversation_id);
+
+    let params = state.resolve_params(params, GenerationParams::default())?;
+
+    let lock = store.lock_for(&conversation_id);
+    let _guard = lock.lock().await;
+
+    let mut conversation = store.load(&app, &conversation_id)?;
+    conversation.push(ChatMessage::user(content));
+
+    let budget = state.prompt_budget(&params, 0).await;
+    let history = conversation.history_within(budget);
+    let reply = state.chat_with("send_message", &history, &params).await?;
+
+    conversation.push(ChatMessage::assistant(reply));
+    store.save(&app, &conversation)?;
+
+    Ok(conversation)
+}
+
+#[tauri::command]
+pub async fn get_conversation(
+    app: tauri::AppHandle,
+    store: tauri::State<'_, ConversationStore>,
+    conversation_id: String,
+) -> Result<Conversation, CodifyError> {
+    store.load(&app, &conversation_id)
+}
+
+/// Saved conversations, most recently updated first
+#[tauri::command]
+pub async fn list_conversations(
+    app: tauri::AppHandle,
+    store: tauri::State<'_, ConversationStore>,
+) -> Result<Vec<Conversation>, CodifyError> {
+    store.list(&app)
+}
+
+/// Most recent provider calls with token counts and latency, newest first
+#[tauri::command]
+pub async fn get_ai_usage_log(
+    state: tauri::State<'_, AIState>,
+    limit: Option<usize>,
+) -> Result<Vec<UsageRecord>, CodifyError> {
+    Ok(state.usage.lock().unwrap().recent(limit.unwrap_or(usize::MAX)))
+}
+
+/// Estimate the tokens and dollar cost of an operation before running it,
+/// priced for the model it would use and for every model in the price table
+#[tauri::command]
+pub async fn estimate_cost(
+    state: tauri::State<'_, AIState>,
+    operation: CostOperation,
+) -> Result<CostEstimate, CodifyError> {
+    log::info!("Estimating cost of {:?}", operation);
+
+    state.estimate_cost(&operation).await
+}
+
+/// Check that the configured endpoint, API key and model actually work
+#[tauri::command]
+pub async fn test_ai_connection(state: tauri::State<'_, AIState>) -> Result<ConnectionTest, CodifyError> {
+    log::info!("Testing AI provider connection");
+
+    Ok(state.test_connection().await)
+}
+
+/// Models the configured provider offers, with what Codify knows about each
+#[tauri::command]
+pub async fn list_models(state: tauri::State<'_, AIState>) -> Result<Vec<ModelInfo>, CodifyError> {
+    log::info!("Listing provider models");
+
+    state.list_models().await
+}
+
+/// Make `id` the global chat model, saved and applied at once; it must be
+/// one list_models offers. Project overrides that set a model still win.
+#[tauri::command]
+pub async fn set_active_model(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AIState>,
+    id: String,
+) -> Result<ModelInfo, CodifyError> {
+    log::info!("Switching active model to {}", id);
+
+    let model = state
+        .list_models()
+        .await?
+        .into_iter()
+        .find(|model| model.id == id)
+        .ok_or_else(|| {
+            CodifyError::InvalidInput(format!("Model not supported by the provider: {}", id))
+        })?;
+    if model.embedding {
+        return Err(CodifyError::InvalidInput(format!(
+            "Model not supported for chat: {} is an embedding model",
+            id
+        )));
+    }
+
+    crate::settings::set_chat_model(&app, &state, &id).await?;
+    Ok(ModelInfo {
+        active: true,
+        ..model
+    })
+}
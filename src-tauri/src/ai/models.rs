@@ -0,0 +1,89 @@
+use super::{AIState, FimFormat, ProviderKind};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+
+/// A model the provider offers, for a model picker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub id: String,
+    pub owned_by: Option<String>,
+    pub context_length: Option<usize>, // None when neither the provider nor Codify knows it
+    pub supports_fim: bool,            // Completions use fill-in-the-middle prompts
+    pub embedding: bool,               // For embedding_model rather than model
+    pub active: bool,                  // The configured chat model
+}
+
+/// Context windows of well-known hosted models, which /models doesn't report
+fn known_context_length(id: &str) -> Option<usize> {
+    let known: &[(&str, usize)] = &[
+        ("gpt-4.1", 1_047_576),
+        ("gpt-4o", 128_000),
+        ("gpt-4-turbo", 128_000),
+        ("gpt-4", 8_192),
+        ("gpt-3.5-turbo", 16_385),
+        ("o1", 200_000),
+        ("o3", 200_000),
+        ("o4-mini", 200_000),
+        ("text-embedding-3", 8_191),
+        ("text-embedding-ada-002", 8_191),
+    ];
+    // Longest prefix first, so gpt-4o isn't taken for gpt-4
+    known
+        .iter()
+        .filter(|(prefix, _)| id.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, length)| *length)
+}
+
+fn model_info(id: &str, owned_by: Option<String>, active_model: &str) -> ModelInfo {
+    ModelInfo {
+        id: id.to_string(),
+        owned_by,
+        context_length: known_context_length(id),
+        supports_fim: FimFormat::for_model(id).is_some(),
+        embedding: id.to_lowercase().contains("embed"),
+        active: id == active_model,
+    }
+}
+
+impl AIState {
+    /// Models the configured provider offers, sorted by id
+    ///
+    /// OpenAI and Ollama list theirs at the OpenAI-compatible `models`
+    /// endpoint. The Local provider runs a single GGUF file, so its list is
+    /// just the configured model.
+    pub async fn list_models(&self) -> Result<Vec<ModelInfo>, CodifyError> {
+        self.ensure_enabled()?;
+        let config = self.active_config().await;
+
+        if config.provider == ProviderKind::Local {
+            return Ok(vec![ModelInfo {
+                context_length: Some(config.max_context_tokens),
+                ..model_info(&config.model, None, &config.model)
+            }]);
+        }
+
+        let value = self.get_json(&config, "models").await?;
+        let data = value["data"].as_array().ok_or_else(|| {
+            CodifyError::Provider("Provider response did not contain a model list".to_string())
+        })?;
+
+        let mut models: Vec<ModelInfo> = data
+            .iter()
+            .filter_map(|item| {
+                let id = item["id"].as_str()?;
+                let owned_by = item["owned_by"].as_str().map(str::to_string);
+                Some(model_info(id, owned_by, &config.model))
+            })
+            .collect();
+        // Local servers don't say; the configured context is the best guess
+        for model in models
+            .iter_mut()
+            .filter(|model| model.active && model.context_length.is_none())
+        {
+            model.context_length = Some(config.max_context_tokens);
+        }
+        models.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(models)
+    }
+}
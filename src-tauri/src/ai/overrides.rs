@@ -0,0 +1,88 @@
+use super::{AIState, ModelConfig, ProviderKind};
+use serde::{Deserialize, Serialize};
+
+tokio::task_local! {
+    /// Project whose override applies to provider calls made by this task
+    static ACTIVE_PROJECT: String;
+}
+
+/// Per-project model settings, merged over the global ModelConfig
+///
+/// Precedence, highest first: a field set here, then the user's global
+/// settings, then ModelConfig's built-in defaults. Unset fields inherit.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelOverride {
+    pub provider: Option<ProviderKind>,
+    pub model: Option<String>,
+    pub base_url: Option<String>,
+    pub embedding_model: Option<String>,
+    pub max_context_tokens: Option<usize>,
+    pub model_path: Option<String>,
+    pub gpu_layers: Option<u32>,
+    #[serde(skip)]
+    pub api_key: Option<String>, // From the keychain when the provider differs
+}
+
+impl ModelOverride {
+    pub fn apply(&self, mut config: ModelConfig) -> ModelConfig {
+        if let Some(provider) = &self.provider {
+            // The global key belongs to the global provider
+            if *provider != config.provider {
+                config.api_key = None;
+            }
+            config.provider = provider.clone();
+        }
+        if let Some(key) = &self.api_key {
+            config.api_key = Some(key.clone());
+        }
+        if let Some(model) = &self.model {
+            config.model = model.clone();
+        }
+        if let Some(base_url) = &self.base_url {
+            config.base_url = base_url.clone();
+        }
+        if let Some(embedding_model) = &self.embedding_model {
+            config.embedding_model = embedding_model.clone();
+        }
+        if let Some(max_context_tokens) = self.max_context_tokens {
+            config.max_context_tokens = max_context_tokens;
+        }
+        if let Some(model_path) = &self.model_path {
+            config.model_path = Some(model_path.clone());
+        }
+        if let Some(gpu_layers) = self.gpu_layers {
+            config.gpu_layers = gpu_layers;
+        }
+        config
+    }
+}
+
+/// Key overrides by project path regardless of trailing separators
+pub fn project_key(project_path: &str) -> String {
+    project_path.trim_end_matches(['/', '\\']).to_string()
+}
+
+/// Run `future` with `project_path`'s override applied to every provider
+/// call it makes
+pub async fn in_project<F: std::future::Future>(project_path: &str, future: F) -> F::Output {
+    ACTIVE_PROJECT
+        .scope(project_key(project_path), future)
+        .await
+}
+
+impl AIState {
+    /// The model config for the current task: the active project's override,
+    /// if any, merged over the global config
+    pub async fn active_config(&self) -> ModelConfig {
+        let config = self.config.read().await.clone();
+        let Ok(project) = ACTIVE_PROJECT.try_with(|project| project.clone()) else {
+            return config;
+        };
+
+        match self.project_overrides.read().unwrap().get(&project) {
+            Some(project_override) => project_override.apply(config),
+            None => config,
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::CodifyError;
+
+/// Sampling parameters forwarded to the provider; unset fields fall back to
+/// the command's defaults and then to the provider's own
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenerationParams {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub presence_penalty: Option<f32>,
+    pub timeout_ms: Option<u64>, // Abandon the request after this long; not sent to the provider
+}
+
+impl GenerationParams {
+    /// Near-deterministic output for inline completions
+    pub fn completion_defaults() -> Self {
+        GenerationParams {
+            temperature: Some(0.2),
+            top_p: Some(0.95),
+            max_tokens: Some(512),
+            presence_penalty: None,
+            timeout_ms: None,
+        }
+    }
+
+    pub fn test_defaults() -> Self {
+        GenerationParams {
+            temperature: Some(0.3),
+            top_p: None,
+            max_tokens: Some(2048),
+            presence_penalty: None,
+            timeout_ms: None,
+        }
+    }
+
+    /// More creative sampling for UI design generation
+    pub fn design_defaults() -> Self {
+        GenerationParams {
+            temperature: Some(0.8),
+            top_p: None,
+            max_tokens: Some(3072),
+            presence_penalty: Some(0.3),
+            timeout_ms: None,
+        }
+    }
+
+    /// Faithful rewrites of existing code; room for a whole component
+    pub fn extract_defaults() -> Self {
+        GenerationParams {
+            temperature: Some(0.2),
+            top_p: None,
+            max_tokens: Some(3072),
+            presence_penalty: None,
+            timeout_ms: None,
+        }
+    }
+
+    /// Focused, terse review comments
+    pub fn review_defaults() -> Self {
+        GenerationParams {
+            temperature: Some(0.2),
+            top_p: None,
+            max_tokens: Some(1500),
+            presence_penalty: None,
+            timeout_ms: None,
+        }
+    }
+
+    /// Several whole files in one reply
+    pub fn scaffold_defaults() -> Self {
+        GenerationParams {
+            temperature: Some(0.3),
+            top_p: None,
+            max_tokens: Some(6144),
+            presence_penalty: None,
+            timeout_ms: None,
+        }
+    }
+
+    /// Short, factual output for commit messages
+    pub fn commit_message_defaults() -> Self {
+        GenerationParams {
+            temperature: Some(0.2),
+            top_p: None,
+            max_tokens: Some(400),
+            presence_penalty: None,
+            timeout_ms: None,
+        }
+    }
+
+    /// Fill unset fields from `defaults`, then validate the result
+    pub fn resolve(
+        overrides: Option<GenerationParams>,
+        defaults: GenerationParams,
+    ) -> Result<Self, CodifyError> {
+        let params = overrides.unwrap_or_default().or(&defaults);
+
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Fields from `self`, with unset ones taken from `fallback`
+    pub fn or(&self, fallback: &GenerationParams) -> Self {
+        GenerationParams {
+            temperature: self.temperature.or(fallback.temperature),
+            top_p: self.top_p.or(fallback.top_p),
+            max_tokens: self.max_tokens.or(fallback.max_tokens),
+            presence_penalty: self.presence_penalty.or(fallback.presence_penalty),
+            timeout_ms: self.timeout_ms.or(fallback.timeout_ms),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), CodifyError> {
+        if let Some(t) = self.temperature {
+            if !(0.0..=2.0).contains(&t) {
+                return Err(CodifyError::InvalidInput(format!(
+                    "temperature must be between 0.0 and 2.0, got {}",
+                    t
+                )));
+            }
+        }
+        if let Some(p) = self.top_p {
+            if !(p > 0.0 && p <= 1.0) {
+                return Err(CodifyError::InvalidInput(format!(
+                    "top_p must be in (0.0, 1.0], got {}",
+                    p
+                )));
+            }
+        }
+        if self.max_tokens == Some(0) {
+            return Err(CodifyError::InvalidInput(
+                "max_tokens must be greater than 0".to_string(),
+            ));
+        }
+        if let Some(p) = self.presence_penalty {
+            if !(-2.0..=2.0).contains(&p) {
+                return Err(CodifyError::InvalidInput(format!(
+                    "presence_penalty must be between -2.0 and 2.0, got {}",
+                    p
+                )));
+            }
+        }
+        if self.timeout_ms == Some(0) {
+            return Err(CodifyError::InvalidInput(
+                "timeout_ms must be greater than 0".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Add the set parameters to a chat completions request body
+    pub fn apply(&self, body: &mut serde_json::Value) {
+        if let Some(t) = self.temperature {
+            body["temperature"] = serde_json::json!(t);
+        }
+        if let Some(p) = self.top_p {
+            body["top_p"] = serde_json::json!(p);
+        }
+        if let Some(m) = self.max_tokens {
+            body["max_tokens"] = serde_json::json!(m);
+        }
+        if let Some(p) = self.presence_penalty {
+            body["presence_penalty"] = serde_json::json!(p);
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use super::{in_project, AIState, GenerationParams, ModelConfig, ProviderKind};
+use crate::error::CodifyError;
+use crate::storage::count_index_tokens;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Dollars per 1,000 tokens for one model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_1k: f64,
+    pub output_per_1k: f64,
+}
+
+impl ModelPrice {
+    pub fn cost(&self, input_tokens: u64, output_tokens: u64) -> f64 {
+        (input_tokens as f64 * self.input_per_1k + output_tokens as f64 * self.output_per_1k)
+            / 1000.0
+    }
+
+    pub fn validate(&self, model: &str) -> Result<(), CodifyError> {
+        if !(self.input_per_1k >= 0.0 && self.output_per_1k >= 0.0) {
+            return Err(CodifyError::InvalidInput(format!(
+                "Invalid price for {}: must not be negative",
+                model
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Prices the table starts with; settings can change or add to them
+pub fn default_prices() -> BTreeMap<String, ModelPrice> {
+    [
+        ("gpt-4o", 0.0025, 0.01),
+        ("gpt-4o-mini", 0.00015, 0.0006),
+        ("text-embedding-3-small", 0.00002, 0.0),
+        ("text-embedding-3-large", 0.00013, 0.0),
+    ]
+    .into_iter()
+    .map(|(model, input_per_1k, output_per_1k)| {
+        (
+            model.to_string(),
+            ModelPrice {
+                input_per_1k,
+                output_per_1k,
+            },
+        )
+    })
+    .collect()
+}
+
+/// An operation to price before running it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CostOperation {
+    /// Embedding every chunk of a project from scratch
+    IndexProject { project_path: String },
+    /// `count` code completions; without `prompt_tokens` each is assumed to
+    /// fill the context window, so the estimate is an upper bound
+    Completions {
+        count: usize,
+        project_path: Option<String>,
+        prompt_tokens: Option<usize>,
+    },
+}
+
+/// An estimate priced for one model in the table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelCost {
+    pub model: String,
+    pub input_per_1k: f64,
+    pub output_per_1k: f64,
+    pub cost: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostEstimate {
+    pub model: String, // The model the operation would run on
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Dollars on `model`; zero for local providers and None when a hosted
+    /// model has no price in the table
+    pub cost: Option<f64>,
+    /// The same tokens priced for every model in the table, cheapest first
+    pub models: Vec<ModelCost>,
+}
+
+/// The config an operation would run with, honouring project overrides
+async fn config_for(state: &AIState, project_path: Option<&str>) -> ModelConfig {
+    match project_path {
+        Some(project_path) => in_project(project_path, state.active_config()).await,
+        None => state.active_config().await,
+    }
+}
+
+impl AIState {
+    /// Count the tokens an operation would use with the same tokenizer that
+    /// sizes prompts, and price them from the settings' table
+    pub async fn estimate_cost(
+        &self,
+        operation: &CostOperation,
+    ) -> Result<CostEstimate, CodifyError> {
+        let (model, provider, input_tokens, output_tokens) = match operation {
+            CostOperation::IndexProject { project_path } => {
+                let root = project_path.clone();
+                let count = tokio::task::spawn_blocking(move || count_index_tokens(&root))
+                    .await
+                    .map_err(|e| {
+                        CodifyError::Internal(format!("Cost estimate task failed: {}", e))
+                    })??;
+
+                let config = config_for(self, Some(project_path)).await;
+                (config.embedding_model, config.provider, count.tokens, 0)
+            }
+            CostOperation::Completions {
+                count,
+                project_path,
+                prompt_tokens,
+            } => {
+                let config = config_for(self, project_path.as_deref()).await;
+                let params = self.resolve_params(None, GenerationParams::completion_defaults())?;
+                let output = params.max_tokens.unwrap_or(0) as usize;
+                let input =
+                    prompt_tokens.unwrap_or(config.max_context_tokens.saturating_sub(output));
+
+                (config.model, config.provider, input * count, output * count)
+            }
+        };
+        let (input_tokens, output_tokens) = (input_tokens as u64, output_tokens as u64);
+
+        let prices = self.prices.read().unwrap().clone();
+        let cost = match prices.get(&model) {
+            Some(price) => Some(price.cost(input_tokens, output_tokens)),
+            None if provider != ProviderKind::OpenAI => Some(0.0),
+            None => None,
+        };
+
+        let mut models: Vec<ModelCost> = prices
+            .into_iter()
+            .map(|(model, price)| ModelCost {
+                cost: price.cost(input_tokens, output_tokens),
+                model,
+                input_per_1k: price.input_per_1k,
+                output_per_1k: price.output_per_1k,
+            })
+            .collect();
+        models.sort_by(|a, b| a.cost.total_cmp(&b.cost));
+
+        Ok(CostEstimate {
+            model,
+            input_tokens,
+            output_tokens,
+            cost,
+            models,
+        })
+    }
+}
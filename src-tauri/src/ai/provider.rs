@@ -0,0 +1,804 @@
+use super::{
+    count_tokens, default_prices, is_retryable_status, retry_after, screen_request, AIMetrics,
+    CompletionCache, CompletionRag, CompletionWindow, FeedbackLog, GenerationParams, LocalRuntime,
+    ModelOverride, ModelPrice, PromptTemplate, RateLimit, RequestRegistry, RetryPolicy,
+    SelectionLog, Throttle, UsageLog, UsageRecord, WarmupStatus, PROMPT_OVERHEAD_TOKENS,
+};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+
+/// Error from every provider call while AI is switched off in settings
+pub const AI_DISABLED_ERROR: &str = "AI unavailable: AI features are disabled in settings";
+
+/// How long a search-as-you-type query waits for the next keystroke
+pub const DEFAULT_SEARCH_DEBOUNCE_MS: u64 = 150;
+
+/// Longest debounce settings accept; more would make search feel broken
+pub const MAX_SEARCH_DEBOUNCE_MS: u64 = 2000;
+
+/// How long a provider call may take when neither its params, its command
+/// (see `command_timeout_ms`) nor the config set a timeout
+pub const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 120_000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ProviderKind {
+    OpenAI,
+    Ollama,
+    Local, // GGUF model run in-process through llama.cpp
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelConfig {
+    pub provider: ProviderKind,
+    pub model: String,
+    pub base_url: String,
+    pub api_key: Option<String>,
+    pub embedding_model: String,
+    pub max_context_tokens: usize,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    #[serde(default)]
+    pub rate_limit: RateLimit,
+    #[serde(default)]
+    pub model_path: Option<String>, // GGUF file for the Local provider
+    #[serde(default)]
+    pub gpu_layers: u32, // Layers the Local provider offloads to the GPU
+    #[serde(default)]
+    pub request_timeout_ms: Option<u64>, // For calls whose params and command set no timeout
+}
+
+impl Default for ModelConfig {
+    fn default() -> Self {
+        // Offline-first: talk to a local Ollama server through its
+        // OpenAI-compatible endpoint unless the user configures otherwise.
+        ModelConfig {
+            provider: ProviderKind::Ollama,
+            model: "qwen2.5-coder:7b".to_string(),
+            base_url: "http://localhost:11434/v1".to_string(),
+            api_key: None,
+            embedding_model: "nomic-embed-text".to_string(),
+            max_context_tokens: 8192,
+            retry: RetryPolicy::default(),
+            rate_limit: RateLimit::default(),
+            model_path: None,
+            gpu_layers: 0,
+            request_timeout_ms: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn system(content: impl Into<String>) -> Self {
+        ChatMessage {
+            role: "system".to_string(),
+            content: content.into(),
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        ChatMessage {
+            role: "user".to_string(),
+            content: content.into(),
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        ChatMessage {
+            role: "assistant".to_string(),
+            content: content.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionTest {
+    pub ok: bool,
+    pub provider: ProviderKind,
+    pub base_url: String,
+    pub model: String,                  // As configured
+    pub resolved_model: Option<String>, // As reported by the provider
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Turn a post_json error into advice the user can act on
+fn describe_connection_error(error: &CodifyError, config: &ModelConfig) -> String {
+    // Local errors (missing file, load failures) are already specific
+    if config.provider == ProviderKind::Local {
+        return error.to_string();
+    }
+
+    match error {
+        CodifyError::Auth(message) => {
+            format!("Authentication failed; check the API key ({})", message)
+        }
+        CodifyError::Provider(message) if message.starts_with("Provider returned 404") => format!(
+            "Model '{}' or endpoint not found at {} ({})",
+            config.model, config.base_url, message
+        ),
+        CodifyError::Provider(message) | CodifyError::Timeout(message)
+            if !message.starts_with("Provider returned") =>
+        {
+            format!("Could not reach {}: {}", config.base_url, message)
+        }
+        _ => error.to_string(),
+    }
+}
+
+/// The error for a provider reply with a failure status
+fn status_error(status: reqwest::StatusCode, text: &str) -> CodifyError {
+    let message = format!("Provider returned {}: {}", status, text);
+    match status.as_u16() {
+        429 => CodifyError::RateLimited(message),
+        401 | 403 => CodifyError::Auth(message),
+        _ => CodifyError::Provider(message),
+    }
+}
+
+/// Shared AI state managed by Tauri
+pub struct AIState {
+    pub config: tokio::sync::RwLock<ModelConfig>,
+    pub metrics: std::sync::Mutex<AIMetrics>,
+    pub completion_cache: std::sync::Mutex<CompletionCache>,
+    pub requests: RequestRegistry,
+    pub user_params: std::sync::RwLock<GenerationParams>, // From saved settings
+    pub project_overrides: std::sync::RwLock<std::collections::HashMap<String, ModelOverride>>,
+    pub usage: std::sync::Mutex<UsageLog>,
+    pub selections: std::sync::Mutex<SelectionLog>, // Which completion suggestions were taken
+    pub feedback: std::sync::Mutex<FeedbackLog>,
+    pub completion_feedback: std::sync::atomic::AtomicBool, // Record feedback events at all
+    pub local: LocalRuntime,
+    pub throttle: Throttle, // Shared by every provider call
+    pub enabled: std::sync::atomic::AtomicBool, // False keeps every provider call offline
+    pub auto_reindex: std::sync::atomic::AtomicBool, // Re-embed files as the watcher sees them change
+    pub search_debounce_ms: std::sync::atomic::AtomicU64,
+    pub prompt_templates: std::sync::RwLock<std::collections::HashMap<String, PromptTemplate>>,
+    pub completion_rag: std::sync::RwLock<CompletionRag>,
+    pub completion_window: std::sync::RwLock<CompletionWindow>,
+    pub warmup: std::sync::Mutex<WarmupStatus>,
+    pub prices: std::sync::RwLock<std::collections::BTreeMap<String, ModelPrice>>,
+    client: reqwest::Client,
+}
+
+impl Default for AIState {
+    fn default() -> Self {
+        AIState {
+            config: tokio::sync::RwLock::new(ModelConfig::default()),
+            metrics: std::sync::Mutex::new(AIMetrics::default()),
+            completion_cache: std::sync::Mutex::new(CompletionCache::default()),
+            requests: RequestRegistry::default(),
+            user_params: std::sync::RwLock::new(GenerationParams::default()),
+            project_overrides: std::sync::RwLock::new(std::collections::HashMap::new()),
+            usage: std::sync::Mutex::new(UsageLog::default()),
+            selections: std::sync::Mutex::new(SelectionLog::default()),
+            feedback: std::sync::Mutex::new(FeedbackLog::default()),
+            completion_feedback: std::sync::atomic::AtomicBool::new(true),
+            local: LocalRuntime::default(),
+            throttle: Throttle::default(),
+            enabled: std::sync::atomic::AtomicBool::new(true),
+            auto_reindex: std::sync::atomic::AtomicBool::new(true),
+            search_debounce_ms: std::sync::atomic::AtomicU64::new(DEFAULT_SEARCH_DEBOUNCE_MS),
+            prompt_templates: std::sync::RwLock::new(std::collections::HashMap::new()),
+            completion_rag: std::sync::RwLock::new(CompletionRag::default()),
+            completion_window: std::sync::RwLock::new(CompletionWindow::default()),
+            warmup: std::sync::Mutex::new(WarmupStatus::default()),
+            prices: std::sync::RwLock::new(default_prices()),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+impl AIState {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub fn auto_reindex(&self) -> bool {
+        self.auto_reindex.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// How long a search with a session id waits for the next keystroke
+    pub fn search_debounce(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(
+            self.search_debounce_ms
+                .load(std::sync::atomic::Ordering::Relaxed),
+        )
+    }
+
+    pub fn completion_feedback(&self) -> bool {
+        self.completion_feedback
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Fail with AI_DISABLED_ERROR when AI is switched off
+    pub fn ensure_enabled(&self) -> Result<(), CodifyError> {
+        if self.is_enabled() {
+            Ok(())
+        } else {
+            Err(CodifyError::Unavailable(AI_DISABLED_ERROR.to_string()))
+        }
+    }
+
+    /// Layer per-request overrides over the user's saved defaults and then
+    /// the command's own defaults
+    pub fn resolve_params(
+        &self,
+        overrides: Option<GenerationParams>,
+        defaults: GenerationParams,
+    ) -> Result<GenerationParams, CodifyError> {
+        let user = self.user_params.read().unwrap().clone();
+        GenerationParams::resolve(Some(overrides.unwrap_or_default().or(&user)), defaults)
+    }
+
+    /// Send a chat request to the configured provider and return the reply text;
+    /// `command` names the caller in the usage log
+    pub async fn chat(
+        &self,
+        command: &str,
+        messages: &[ChatMessage],
+    ) -> Result<String, CodifyError> {
+        self.chat_with(command, messages, &GenerationParams::default())
+            .await
+    }
+
+    /// Like `chat`, with explicit sampling parameters
+    pub async fn chat_with(
+        &self,
+        command: &str,
+        messages: &[ChatMessage],
+        params: &GenerationParams,
+    ) -> Result<String, CodifyError> {
+        let config = self
+            .active_config()
+            .await
+            .with_timeout(command, Some(params));
+        let prompt_tokens = fit_context(&config, messages, params)?;
+
+        let mut body = serde_json::json!({
+            "model": config.model,
+            "messages": messages,
+        });
+        params.apply(&mut body);
+
+        let started = std::time::Instant::now();
+        let value = self
+            .call(command, &config, &config.model, "chat/completions", &body, prompt_tokens)
+            .await?;
+        self.metrics.lock().unwrap().record_latency(started.elapsed());
+
+        value["choices"][0]["message"]["content"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| {
+                CodifyError::Provider("Provider response did not contain a message".to_string())
+            })
+    }
+
+    /// Like `chat_with`, but `on_delta` gets each piece of the reply as the
+    /// provider sends it; the whole reply is still returned at the end. The
+    /// Local provider answers in one piece.
+    pub async fn chat_stream(
+        &self,
+        command: &str,
+        messages: &[ChatMessage],
+        params: &GenerationParams,
+        mut on_delta: impl FnMut(&str) + Send,
+    ) -> Result<String, CodifyError> {
+        let config = self
+            .active_config()
+            .await
+            .with_timeout(command, Some(params));
+        if config.provider == ProviderKind::Local {
+            let reply = self.chat_with(command, messages, params).await?;
+            on_delta(&reply);
+            return Ok(reply);
+        }
+        let prompt_tokens = fit_context(&config, messages, params)?;
+
+        let mut body = serde_json::json!({
+            "model": config.model,
+            "messages": messages,
+            "stream": true,
+        });
+        params.apply(&mut body);
+
+        self.ensure_enabled()?;
+        let body = screen_request(&config, "chat/completions", &body)?;
+        let _permit = self.throttle.acquire(&config.rate_limit).await?;
+
+        let started = std::time::Instant::now();
+        // Dropping the stream on timeout closes the connection
+        let result = with_deadline(&config, self.read_stream(&config, &body, &mut on_delta)).await;
+        if result.is_ok() {
+            self.metrics
+                .lock()
+                .unwrap()
+                .record_latency(started.elapsed());
+        }
+
+        // Streamed replies carry no usage block, so the log estimates from the text
+        let logged = result
+            .as_ref()
+            .map(|reply| serde_json::json!({ "choices": [{ "message": { "content": reply } }] }))
+            .map_err(|e| e.clone());
+        self.log_usage(
+            command,
+            &config,
+            &config.model,
+            "chat/completions",
+            started,
+            prompt_tokens,
+            &logged,
+        );
+
+        result
+    }
+
+    /// Read a server-sent event stream of chat completion chunks
+    async fn read_stream(
+        &self,
+        config: &ModelConfig,
+        body: &serde_json::Value,
+        on_delta: &mut (impl FnMut(&str) + Send),
+    ) -> Result<String, CodifyError> {
+        let mut response = self
+            .send_with_retry(config, "chat/completions", body)
+            .await?;
+
+        let mut reply = String::new();
+        let mut pending: Vec<u8> = vec![];
+        while let Some(bytes) = response
+            .chunk()
+            .await
+            .map_err(|e| CodifyError::Provider(format!("Provider stream failed: {}", e)))?
+        {
+            pending.extend_from_slice(&bytes);
+            // Events are split on newlines, so a line is never cut mid-character
+            while let Some(end) = pending.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                let Some(data) = line.trim().strip_prefix("data:") else {
+                    continue;
+                };
+                let data = data.trim();
+                if data == "[DONE]" {
+                    return Ok(reply);
+                }
+
+                let value: serde_json::Value = serde_json::from_str(data).map_err(|e| {
+                    CodifyError::Provider(format!("Invalid provider response: {}", e))
+                })?;
+                if let Some(error) = value.get("error") {
+                    return Err(CodifyError::Provider(format!(
+                        "Provider returned an error: {}",
+                        error
+                    )));
+                }
+                if let Some(delta) = value["choices"][0]["delta"]["content"].as_str() {
+                    if !delta.is_empty() {
+                        reply.push_str(delta);
+                        on_delta(delta);
+                    }
+                }
+            }
+        }
+
+        Ok(reply)
+    }
+
+    /// Raw text completion, used for fill-in-the-middle prompts that must not
+    /// be wrapped in a chat template
+    pub async fn complete_text(
+        &self,
+        command: &str,
+        prompt: &str,
+        params: &GenerationParams,
+        stop: &[&str],
+    ) -> Result<String, CodifyError> {
+        let config = self
+            .active_config()
+            .await
+            .with_timeout(command, Some(params));
+
+        let mut body = serde_json::json!({
+            "model": config.model,
+            "prompt": prompt,
+        });
+        if !stop.is_empty() {
+            body["stop"] = serde_json::json!(stop);
+        }
+        params.apply(&mut body);
+
+        let started = std::time::Instant::now();
+        let value = self
+            .call(command, &config, &config.model, "completions", &body, count_tokens(prompt))
+            .await?;
+        self.metrics.lock().unwrap().record_latency(started.elapsed());
+
+        value["choices"][0]["text"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| {
+                CodifyError::Provider("Provider response did not contain a completion".to_string())
+            })
+    }
+
+    /// Make the cheapest possible chat call to check the endpoint, key and model
+    pub async fn test_connection(&self) -> ConnectionTest {
+        let mut config = self.active_config().await;
+        // A health check should answer quickly rather than back off and retry
+        config.retry.max_retries = 0;
+
+        let body = serde_json::json!({
+            "model": config.model,
+            "messages": [ChatMessage::user("ping")],
+            "max_tokens": 1,
+        });
+
+        let started = std::time::Instant::now();
+        let result = self
+            .call("test_ai_connection", &config, &config.model, "chat/completions", &body, 1)
+            .await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let (resolved_model, error) = match result {
+            Ok(value) => (value["model"].as_str().map(|m| m.to_string()), None),
+            Err(e) => (None, Some(describe_connection_error(&e, &config))),
+        };
+
+        ConnectionTest {
+            ok: error.is_none(),
+            provider: config.provider,
+            base_url: config.base_url,
+            model: config.model,
+            resolved_model,
+            latency_ms,
+            error,
+        }
+    }
+
+    /// Tokens left for context after `used` prompt tokens and the reply budget
+    pub async fn prompt_budget(&self, params: &GenerationParams, used: usize) -> usize {
+        let max_context = self.active_config().await.max_context_tokens;
+        let reserved = params.max_tokens.unwrap_or(0) as usize + PROMPT_OVERHEAD_TOKENS;
+
+        max_context.saturating_sub(used + reserved)
+    }
+
+    /// Embed each input with the configured embedding model
+    pub async fn embed(
+        &self,
+        command: &str,
+        inputs: &[String],
+    ) -> Result<Vec<Vec<f32>>, CodifyError> {
+        let config = self.active_config().await.with_timeout(command, None);
+
+        let body = serde_json::json!({
+            "model": config.embedding_model,
+            "input": inputs,
+        });
+
+        let prompt_tokens = inputs.iter().map(|input| count_tokens(input)).sum();
+        let value = self
+            .call(command, &config, &config.embedding_model, "embeddings", &body, prompt_tokens)
+            .await?;
+
+        let data = value["data"].as_array().ok_or_else(|| {
+            CodifyError::Provider("Provider response did not contain embeddings".to_string())
+        })?;
+
+        data.iter()
+            .map(|item| {
+                item["embedding"]
+                    .as_array()
+                    .map(|values| values.iter().filter_map(|v| v.as_f64()).map(|v| v as f32).collect())
+                    .ok_or_else(|| {
+                        CodifyError::Provider(
+                            "Malformed embedding in provider response".to_string(),
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// `post_json` (or the on-device runtime for the Local provider),
+    /// recording the call in the usage log
+    ///
+    /// Each call holds one throttle slot for its whole duration, retries
+    /// included, so backoff doesn't let queued requests pile in behind it.
+    /// The config's timeout covers the same span. Requests leaving the
+    /// machine are screened for secrets first.
+    async fn call(
+        &self,
+        command: &str,
+        config: &ModelConfig,
+        model: &str,
+        path: &str,
+        body: &serde_json::Value,
+        prompt_estimate: usize,
+    ) -> Result<serde_json::Value, CodifyError> {
+        self.ensure_enabled()?;
+        let body = screen_request(config, path, body)?;
+        let _permit = self.throttle.acquire(&config.rate_limit).await?;
+
+        let started = std::time::Instant::now();
+        // Embeddings still go over HTTP, so a Local setup can keep using Ollama for them
+        let request = async {
+            if config.provider == ProviderKind::Local && path != "embeddings" {
+                self.respond_locally(config, path, &body).await
+            } else {
+                self.post_json(config, path, &body).await
+            }
+        };
+        let result = with_deadline(config, request).await;
+
+        self.log_usage(
+            command,
+            config,
+            model,
+            path,
+            started,
+            prompt_estimate,
+            &result,
+        );
+        result
+    }
+
+    /// Record a finished call in the usage log
+    #[allow(clippy::too_many_arguments)]
+    fn log_usage(
+        &self,
+        command: &str,
+        config: &ModelConfig,
+        model: &str,
+        path: &str,
+        started: std::time::Instant,
+        prompt_estimate: usize,
+        result: &Result<serde_json::Value, CodifyError>,
+    ) {
+        let record = UsageRecord::from_response(
+            command,
+            model,
+            path,
+            started.elapsed(),
+            prompt_estimate,
+            result,
+            config.api_key.as_deref(),
+        );
+        log::info!(
+            "AI call command={} model={} endpoint={} prompt_tokens={} completion_tokens={}{} latency_ms={} success={}",
+            record.command,
+            record.model,
+            record.endpoint,
+            record.prompt_tokens,
+            record.completion_tokens,
+            if record.estimated { " (estimated)" } else { "" },
+            record.latency_ms,
+            record.success
+        );
+        self.usage.lock().unwrap().push(record);
+    }
+
+    async fn respond_locally(
+        &self,
+        config: &ModelConfig,
+        path: &str,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value, CodifyError> {
+        self.metrics.lock().unwrap().touch();
+
+        let result = self.local.respond(config, path, body).await;
+        // A prompt that overflows the context is no reason to report the model as down
+        self.metrics.lock().unwrap().connection_ok = Some(self.local.info().is_some());
+        result
+    }
+
+    /// GET an endpoint under the provider's base URL, without retries since
+    /// the caller is waiting on it
+    pub(crate) async fn get_json(
+        &self,
+        config: &ModelConfig,
+        path: &str,
+    ) -> Result<serde_json::Value, CodifyError> {
+        let url = format!("{}/{}", config.base_url.trim_end_matches('/'), path);
+        let mut request = self.client.get(&url);
+        if let Some(key) = &config.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let response = request.send().await.map_err(|e| {
+            CodifyError::Provider(format!(
+                "Failed to reach {:?} provider: {}",
+                config.provider, e
+            ))
+        })?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(status_error(status, &text));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| CodifyError::Provider(format!("Invalid provider response: {}", e)))
+    }
+
+    /// POST a JSON body to an endpoint under the provider's base URL,
+    /// retrying transient failures according to the configured policy
+    async fn post_json(
+        &self,
+        config: &ModelConfig,
+        path: &str,
+        body: &serde_json::Value,
+    ) -> Result<serde_json::Value, CodifyError> {
+        self.send_with_retry(config, path, body)
+            .await?
+            .json()
+            .await
+            .map_err(|e| CodifyError::Provider(format!("Invalid provider response: {}", e)))
+    }
+
+    /// POST a JSON body, retrying until the provider answers with a success
+    /// status; the body of that response is left for the caller to read
+    async fn send_with_retry(
+        &self,
+        config: &ModelConfig,
+        path: &str,
+        body: &serde_json::Value,
+    ) -> Result<reqwest::Response, CodifyError> {
+        self.metrics.lock().unwrap().touch();
+
+        let url = format!("{}/{}", config.base_url.trim_end_matches('/'), path);
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+
+            let mut request = self.client.post(&url).json(body);
+            if let Some(key) = &config.api_key {
+                request = request.bearer_auth(key);
+            }
+
+            let (error, wait) = match request.send().await {
+                Ok(response) if response.status().is_success() => {
+                    self.metrics.lock().unwrap().connection_ok = Some(true);
+                    return Ok(response);
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    // 401/403 mean the endpoint is reachable but unusable with these credentials
+                    if status == reqwest::StatusCode::UNAUTHORIZED
+                        || status == reqwest::StatusCode::FORBIDDEN
+                    {
+                        self.metrics.lock().unwrap().connection_ok = Some(false);
+                    }
+
+                    let wait = retry_after(response.headers());
+                    let text = response.text().await.unwrap_or_default();
+                    let error = status_error(status, &text);
+
+                    if !is_retryable_status(status) {
+                        return Err(error);
+                    }
+                    (error, wait)
+                }
+                Err(e) => {
+                    self.metrics.lock().unwrap().connection_ok = Some(false);
+                    let message = format!("Failed to reach {:?} provider: {}", config.provider, e);
+                    (CodifyError::Provider(message), None)
+                }
+            };
+
+            if attempt > config.retry.max_retries {
+                return Err(error);
+            }
+
+            let delay = config.retry.delay(attempt, wait);
+            log::warn!(
+                "Provider request to {} failed (attempt {}/{}), retrying in {}ms: {}",
+                path,
+                attempt,
+                config.retry.max_retries + 1,
+                delay.as_millis(),
+                error
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// Commands whose requests need a timeout other than the configured one
+fn command_timeout_ms(command: &str) -> Option<u64> {
+    match command {
+        "ai_complete_code" => Some(5_000), // Stale by the time it arrives otherwise
+        "ai_scaffold_feature" => Some(300_000), // Several whole files in one reply
+        _ => None,
+    }
+}
+
+impl ModelConfig {
+    /// This config with the timeout `command` runs under: the one in
+    /// `params`, else the command's own, else the configured one
+    fn with_timeout(mut self, command: &str, params: Option<&GenerationParams>) -> Self {
+        self.request_timeout_ms = params
+            .and_then(|params| params.timeout_ms)
+            .or(command_timeout_ms(command))
+            .or(self.request_timeout_ms);
+        self
+    }
+}
+
+/// Run a provider request, giving up once the config's timeout passes
+///
+/// Dropping the request aborts it over HTTP; on-device generation already
+/// under way finishes in the background, but its reply is discarded.
+async fn with_deadline<T>(
+    config: &ModelConfig,
+    request: impl std::future::Future<Output = Result<T, CodifyError>>,
+) -> Result<T, CodifyError> {
+    let timeout_ms = config
+        .request_timeout_ms
+        .unwrap_or(DEFAULT_REQUEST_TIMEOUT_MS);
+    match tokio::time::timeout(std::time::Duration::from_millis(timeout_ms), request).await {
+        Ok(result) => result,
+        Err(_) => Err(CodifyError::Timeout(format!(
+            "AI request timed out after {}ms",
+            timeout_ms
+        ))),
+    }
+}
+
+/// Check the prompt and reply fit the model's context window, failing with a
+/// clear message instead of an opaque provider error; returns the prompt's tokens
+fn fit_context(
+    config: &ModelConfig,
+    messages: &[ChatMessage],
+    params: &GenerationParams,
+) -> Result<usize, CodifyError> {
+    let prompt_tokens: usize = messages.iter().map(|m| count_tokens(&m.content)).sum();
+    let output_tokens = params.max_tokens.unwrap_or(0) as usize;
+    if prompt_tokens + output_tokens + PROMPT_OVERHEAD_TOKENS > config.max_context_tokens {
+        return Err(CodifyError::InvalidInput(format!(
+            "Prompt is ~{} tokens, which exceeds {}'s {}-token context window",
+            prompt_tokens, config.model, config.max_context_tokens
+        )));
+    }
+    Ok(prompt_tokens)
+}
+
+/// Parse a JSON value out of a model reply, tolerating ```json fences
+pub fn parse_json_reply<T: serde::de::DeserializeOwned>(reply: &str) -> Result<T, CodifyError> {
+    let trimmed = reply.trim();
+    let body = match trimmed.find("```") {
+        Some(start) => {
+            let after = &trimmed[start + 3..];
+            let after = after.find('\n').map(|i| &after[i + 1..]).unwrap_or(after);
+            after.rfind("```").map(|end| &after[..end]).unwrap_or(after)
+        }
+        None => trimmed,
+    };
+
+    serde_json::from_str(body.trim())
+        .map_err(|e| CodifyError::Provider(format!("Could not parse model output: {}", e)))
+}
+
+/// Strip a surrounding markdown code fence from a model reply
+pub fn strip_code_fence(reply: &str) -> String {
+    let trimmed = reply.trim();
+    if !trimmed.starts_with("```") {
+        return trimmed.to_string();
+    }
+
+    let body = trimmed.find('\n').map(|i| &trimmed[i + 1..]).unwrap_or("");
+    body.trim_end()
+        .strip_suffix("```")
+        .unwrap_or(body)
+        .trim_end()
+        .to_string()
+}
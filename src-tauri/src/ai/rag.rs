@@ -0,0 +1,188 @@
+use super::{comment_style, count_tokens, AIState};
+use crate::error::CodifyError;
+use crate::storage::{CodeEmbedding, SearchFilters, VectorDb, VectorStores};
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+/// Lines before the cursor embedded as the search query
+const QUERY_LINES: usize = 30;
+
+/// Most of the context window the injected examples may take up
+const EXAMPLE_TOKENS: usize = 1500;
+
+/// Extra results fetched to make up for ones dropped as too distant or from
+/// the file being edited
+const EXAMPLE_SLACK: usize = 5;
+
+/// Whether completions are grounded in similar code from the project's index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompletionRag {
+    pub enabled: bool,
+    pub max_snippets: usize,
+    pub min_similarity: f32, // Cosine similarity a chunk needs to be worth showing
+}
+
+impl Default for CompletionRag {
+    fn default() -> Self {
+        CompletionRag {
+            enabled: true,
+            max_snippets: 3,
+            min_similarity: 0.5,
+        }
+    }
+}
+
+impl CompletionRag {
+    pub fn validate(&self) -> Result<(), CodifyError> {
+        if !(0.0..=1.0).contains(&self.min_similarity) {
+            return Err(CodifyError::InvalidInput(
+                "completion_rag.min_similarity must be between 0 and 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Whether an index path and the editor's current file are the same file
+fn is_current_file(chunk: &CodeEmbedding, project_path: &str, current_file: &str) -> bool {
+    let normalize = |path: &str| {
+        let path = path.replace('\\', "/");
+        let root = project_path.replace('\\', "/");
+        path.strip_prefix(root.trim_end_matches('/'))
+            .unwrap_or(&path)
+            .trim_start_matches(['/', '.'])
+            .to_string()
+    };
+    normalize(&chunk.file_path) == normalize(current_file)
+}
+
+impl AIState {
+    /// Indexed chunks most like the code before the cursor, from files other
+    /// than the one being edited, closest first and within EXAMPLE_TOKENS
+    ///
+    /// Empty when RAG is off, the project has no index, or the search fails;
+    /// a completion never fails for want of examples.
+    pub async fn completion_examples(
+        &self,
+        app: &tauri::AppHandle,
+        project_path: &str,
+        current_file: Option<&str>,
+        language: &str,
+        prefix: &str,
+    ) -> Vec<CodeEmbedding> {
+        let rag = self.completion_rag.read().unwrap().clone();
+        if !rag.enabled
+            || rag.max_snippets == 0
+            || project_path.is_empty()
+            || !VectorDb::db_path(project_path).exists()
+        {
+            return vec![];
+        }
+
+        let lines: Vec<&str> = prefix.lines().collect();
+        let query = lines[lines.len().saturating_sub(QUERY_LINES)..].join("\n");
+        if query.trim().is_empty() {
+            return vec![];
+        }
+
+        let embedding = match self.embed("ai_complete_code", &[query]).await {
+            Ok(embeddings) => embeddings.into_iter().next(),
+            Err(e) => {
+                log::debug!("Skipping completion examples: {}", e);
+                None
+            }
+        };
+        let Some(embedding) = embedding else {
+            return vec![];
+        };
+
+        let (app, path) = (app.clone(), project_path.to_string());
+        let filters = SearchFilters {
+            languages: vec![language.to_string()],
+            ..Default::default()
+        };
+        let limit = rag.max_snippets + EXAMPLE_SLACK;
+        let found = tokio::task::spawn_blocking(move || {
+            app.state::<VectorStores>()
+                .open(&path)?
+                .search(&embedding, limit, &filters)
+        })
+        .await
+        .map_err(|e| CodifyError::Internal(e.to_string()))
+        .and_then(|found| found);
+
+        let found = match found {
+            Ok(found) => found,
+            Err(e) => {
+                log::debug!("Skipping completion examples: {}", e);
+                return vec![];
+            }
+        };
+
+        let mut tokens = 0;
+        found
+            .into_iter()
+            .filter(|chunk| 1.0 - chunk.distance.unwrap_or(1.0) >= rag.min_similarity)
+            .filter(|chunk| {
+                !current_file.is_some_and(|file| is_current_file(chunk, project_path, file))
+            })
+            .take(rag.max_snippets)
+            .take_while(|chunk| {
+                tokens += count_tokens(&chunk.content);
+                tokens <= EXAMPLE_TOKENS
+            })
+            .collect()
+    }
+}
+
+/// Examples rendered for the chat prompt; empty when there are none
+pub fn examples_for_chat(examples: &[CodeEmbedding]) -> String {
+    if examples.is_empty() {
+        return String::new();
+    }
+
+    let mut rendered =
+        "Code from elsewhere in this project; follow its conventions:\n\n".to_string();
+    for chunk in examples {
+        rendered.push_str(&format!(
+            "{} (lines {}-{}):\n```{}\n{}\n```\n\n",
+            chunk.file_path, chunk.start_line, chunk.end_line, chunk.language, chunk.content
+        ));
+    }
+    rendered
+}
+
+/// Examples as comments to put ahead of a fill-in-the-middle prefix, which
+/// has no other place for them; none when the language's comment syntax is
+/// unknown
+pub fn examples_for_fim(examples: &[CodeEmbedding], language: &str) -> String {
+    let Ok(style) = comment_style(language) else {
+        return String::new();
+    };
+    let mut rendered = String::new();
+    for chunk in examples {
+        rendered.push_str(&style.comment(&format!("Path: {}", chunk.file_path)));
+        rendered.push('\n');
+        for line in chunk.content.lines() {
+            rendered.push_str(&style.comment(line));
+            rendered.push('\n');
+        }
+        rendered.push_str(&style.comment(""));
+        rendered.push('\n');
+    }
+    rendered
+}
+
+/// `path:start-end` for each example, for CompletionResult
+pub fn example_locations(examples: &[CodeEmbedding]) -> Vec<String> {
+    examples
+        .iter()
+        .map(|chunk| {
+            format!(
+                "{}:{}-{}",
+                chunk.file_path, chunk.start_line, chunk.end_line
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,347 @@
+use super::is_component_name;
+use crate::commands::TextEdit;
+use crate::storage::ProjectFile;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Where and how a React project keeps its components, hooks, tests and
+/// styles, as seen in its existing files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectConventions {
+    pub component_dir: String,
+    pub folder_per_component: bool, // `Button/Button.tsx` rather than `Button.tsx`
+    pub kebab_case: bool,           // `shopping-cart.tsx` rather than `ShoppingCart.tsx`
+    pub extension: String,          // tsx or jsx
+    pub hook_dir: String,
+    pub test_suffix: String, // test or spec
+    pub tests_dir: bool,     // Tests live in `__tests__` beside the code
+    /// e.g. `module.css`; None when styling lives in the code (Tailwind,
+    /// CSS-in-JS) and no stylesheet is created
+    pub style_extension: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ScaffoldRole {
+    Component,
+    Hook,
+    Test,
+    Styles,
+}
+
+impl ScaffoldRole {
+    /// The reply field holding this file's content
+    pub fn field(&self) -> &'static str {
+        match self {
+            ScaffoldRole::Component => "component",
+            ScaffoldRole::Hook => "hook",
+            ScaffoldRole::Test => "test",
+            ScaffoldRole::Styles => "styles",
+        }
+    }
+
+    pub fn required(&self) -> bool {
+        matches!(self, ScaffoldRole::Component | ScaffoldRole::Test)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScaffoldFile {
+    pub role: ScaffoldRole,
+    pub path: String, // Project-relative
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScaffoldedFeature {
+    pub feature_name: String,
+    pub files: Vec<ScaffoldFile>,
+    pub tree: String, // The files to create as an indented tree, for preview
+    pub conventions: ProjectConventions,
+    pub edits: Vec<TextEdit>, // Pass to apply_patch as-is; nothing is written until then
+}
+
+/// Paths for each file of a feature
+pub struct ScaffoldPaths {
+    pub component: String,
+    pub hook: String,
+    pub test: String,
+    pub styles: Option<String>,
+}
+
+fn is_test_file(name: &str) -> bool {
+    name.contains(".test.") || name.contains(".spec.") || name.contains(".stories.")
+}
+
+fn parent_of(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(parent, _)| parent)
+}
+
+fn stem_of(name: &str) -> &str {
+    name.split('.').next().unwrap_or(name)
+}
+
+/// The value seen most often, ties going to the first seen
+fn most_common<'a>(values: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let mut counts: Vec<(&str, usize)> = vec![];
+    for value in values {
+        match counts.iter_mut().find(|(seen, _)| *seen == value) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((value, 1)),
+        }
+    }
+    counts
+        .into_iter()
+        .fold(
+            None,
+            |best: Option<(&str, usize)>, (value, count)| match best {
+                Some((_, best_count)) if best_count >= count => best,
+                _ => Some((value, count)),
+            },
+        )
+        .map(|(value, _)| value)
+}
+
+/// `ShoppingCart` -> `shopping-cart`
+fn kebab_case(name: &str) -> String {
+    let mut kebab = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                kebab.push('-');
+            }
+            kebab.push(c.to_ascii_lowercase());
+        } else {
+            kebab.push(c);
+        }
+    }
+    kebab
+}
+
+/// Read the project's conventions off its file list, falling back to a
+/// `src/components` and `src/hooks` layout for anything it doesn't show
+pub fn detect_conventions(project_path: &str, files: &[ProjectFile]) -> ProjectConventions {
+    let has_file = |name: &str| files.iter().any(|f| f.name == name);
+    let code: Vec<&ProjectFile> = files
+        .iter()
+        .filter(|f| !f.path.starts_with("node_modules/"))
+        .collect();
+
+    let components: Vec<&ProjectFile> = code
+        .iter()
+        .copied()
+        .filter(|f| f.name.ends_with(".tsx") || f.name.ends_with(".jsx"))
+        .filter(|f| !is_test_file(&f.name))
+        .collect();
+    let component_stems: Vec<&str> = components
+        .iter()
+        .map(|f| stem_of(&f.name))
+        .filter(|stem| *stem != "index" && !stem.starts_with("use"))
+        .collect();
+
+    // A component in a folder of its own name counts towards the folder's parent
+    let roots: Vec<(&str, bool)> = components
+        .iter()
+        .filter(|f| is_component_name(stem_of(&f.name)) || stem_of(&f.name).contains('-'))
+        .map(|f| {
+            let parent = parent_of(&f.path);
+            if parent.rsplit('/').next() == Some(stem_of(&f.name)) {
+                (parent_of(parent), true)
+            } else {
+                (parent, false)
+            }
+        })
+        .collect();
+    let component_dir = most_common(roots.iter().map(|(root, _)| *root))
+        .unwrap_or("src/components")
+        .to_string();
+    let in_dir: Vec<bool> = roots
+        .iter()
+        .filter(|(root, _)| *root == component_dir)
+        .map(|(_, folder)| *folder)
+        .collect();
+    let folder_per_component = in_dir.iter().filter(|folder| **folder).count() * 2 > in_dir.len();
+
+    let typescript = components.iter().any(|f| f.name.ends_with(".tsx"));
+    let extension = if typescript || components.is_empty() {
+        "tsx"
+    } else {
+        "jsx"
+    };
+    let kebab = component_stems.iter().filter(|s| s.contains('-')).count();
+    let pascal = component_stems
+        .iter()
+        .filter(|s| is_component_name(s))
+        .count();
+
+    let hook_dir = most_common(
+        code.iter()
+            .filter(|f| !is_test_file(&f.name))
+            .filter(|f| {
+                let stem = stem_of(&f.name);
+                stem.starts_with("use-")
+                    || stem
+                        .strip_prefix("use")
+                        .and_then(|rest| rest.chars().next())
+                        .is_some_and(|c| c.is_ascii_uppercase())
+            })
+            .map(|f| parent_of(&f.path)),
+    )
+    .unwrap_or("src/hooks")
+    .to_string();
+
+    let tests: Vec<&ProjectFile> = code
+        .iter()
+        .copied()
+        .filter(|f| f.name.contains(".test.") || f.name.contains(".spec."))
+        .collect();
+    let specs = tests.iter().filter(|f| f.name.contains(".spec.")).count();
+    let test_suffix = if specs * 2 > tests.len() {
+        "spec"
+    } else {
+        "test"
+    };
+    let in_tests_dir = tests
+        .iter()
+        .filter(|f| f.path.contains("__tests__/"))
+        .count();
+
+    let stylesheet = |suffix: &str| code.iter().any(|f| f.name.ends_with(suffix));
+    let package =
+        std::fs::read_to_string(Path::new(project_path).join("package.json")).unwrap_or_default();
+    let style_extension = if stylesheet(".module.scss") {
+        Some("module.scss")
+    } else if stylesheet(".module.css") {
+        Some("module.css")
+    } else if has_file("tailwind.config.js")
+        || has_file("tailwind.config.ts")
+        || package.contains("\"styled-components\"")
+        || package.contains("\"@emotion/styled\"")
+    {
+        None
+    } else if stylesheet(".scss") {
+        Some("scss")
+    } else {
+        Some("css")
+    };
+
+    ProjectConventions {
+        component_dir,
+        folder_per_component,
+        kebab_case: kebab > pascal,
+        extension: extension.to_string(),
+        hook_dir,
+        test_suffix: test_suffix.to_string(),
+        tests_dir: in_tests_dir * 2 > tests.len(),
+        style_extension: style_extension.map(str::to_string),
+    }
+}
+
+impl ProjectConventions {
+    /// Where each file goes, from the component's and hook's file stems
+    fn layout(&self, component: &str, hook: &str) -> ScaffoldPaths {
+        let join = |dir: &str, name: String| match dir {
+            "" => name,
+            dir => format!("{}/{}", dir, name),
+        };
+
+        let dir = if self.folder_per_component {
+            join(&self.component_dir, component.to_string())
+        } else {
+            self.component_dir.clone()
+        };
+        let test_dir = if self.tests_dir {
+            join(&dir, "__tests__".to_string())
+        } else {
+            dir.clone()
+        };
+        let hook_extension = self.extension.trim_end_matches('x');
+
+        ScaffoldPaths {
+            component: join(&dir, format!("{}.{}", component, self.extension)),
+            hook: join(&self.hook_dir, format!("{}.{}", hook, hook_extension)),
+            test: join(
+                &test_dir,
+                format!("{}.{}.{}", component, self.test_suffix, self.extension),
+            ),
+            styles: self
+                .style_extension
+                .as_ref()
+                .map(|extension| join(&dir, format!("{}.{}", component, extension))),
+        }
+    }
+
+    /// Paths for a feature named `name` (PascalCase)
+    pub fn paths(&self, name: &str) -> ScaffoldPaths {
+        if self.kebab_case {
+            self.layout(&kebab_case(name), &format!("use-{}", kebab_case(name)))
+        } else {
+            self.layout(name, &format!("use{}", name))
+        }
+    }
+
+    /// The layout for the prompt, with the name left as a placeholder
+    pub fn describe(&self) -> String {
+        let paths = if self.kebab_case {
+            self.layout("<name>", "use-<name>")
+        } else {
+            self.layout("<Name>", "use<Name>")
+        };
+
+        let mut lines = vec![
+            format!("- component: {}", paths.component),
+            format!("- hook: {}", paths.hook),
+            format!("- test: {}", paths.test),
+        ];
+        match &paths.styles {
+            Some(styles) => lines.push(format!("- styles: {}", styles)),
+            None => lines
+                .push("- styles: none; style within the component as the project does".to_string()),
+        }
+        lines.join("\n")
+    }
+}
+
+/// The new files as an indented tree, directories first appearing where
+/// their first file does
+pub fn preview_tree(paths: &[&str]) -> String {
+    let mut sorted = paths.to_vec();
+    sorted.sort();
+
+    let mut tree = String::new();
+    let mut open: Vec<&str> = vec![];
+    for path in sorted {
+        let parts: Vec<&str> = path.split('/').collect();
+        let (dirs, file) = parts.split_at(parts.len() - 1);
+
+        let shared = open
+            .iter()
+            .zip(dirs)
+            .take_while(|(open, dir)| open == dir)
+            .count();
+        open.truncate(shared);
+        for dir in &dirs[shared..] {
+            tree.push_str(&format!("{}{}/\n", "  ".repeat(open.len()), dir));
+            open.push(dir);
+        }
+        tree.push_str(&format!("{}{}\n", "  ".repeat(open.len()), file[0]));
+    }
+    tree
+}
+
+/// apply_patch edits creating each file
+pub fn creation_edits(files: &[ScaffoldFile]) -> Vec<TextEdit> {
+    files
+        .iter()
+        .map(|file| {
+            let mut content = file.content.trim_end().to_string();
+            content.push('\n');
+            TextEdit {
+                file_path: file.path.clone(),
+                start_line: 1,
+                end_line: 0,
+                replacement: content,
+                original: None,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,198 @@
+use super::CompletionLevel;
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Number of recent completions kept so a later selection can be matched to them
+const OFFERED_CAPACITY: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionAlternative {
+    pub id: String,
+    pub code: String,
+    pub confidence: f32,
+}
+
+/// How often completions for a language and level were taken as offered
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SelectionStats {
+    pub offered: u64,
+    pub accepted_primary: u64,
+    pub accepted_alternative: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionSelection {
+    pub completion_id: String,
+    pub alternative_id: Option<String>, // None when the primary suggestion was taken
+    pub code: String,
+    pub stats: SelectionStats,
+}
+
+#[derive(Debug)]
+struct Offered {
+    id: String,
+    key: (String, CompletionLevel), // Language and level
+    code: String,
+    alternatives: Vec<CompletionAlternative>,
+    selected: bool,
+    rated: bool, // Feedback was recorded for it
+}
+
+/// What was offered for a completion, without its code
+#[derive(Debug, Clone)]
+pub struct OfferSummary {
+    pub language: String,
+    pub level: CompletionLevel,
+    pub chars: usize,     // Length of the primary suggestion
+    pub max_chars: usize, // Length of the longest suggestion, alternatives included
+}
+
+/// Completions recently shown to the user and which of them were picked
+#[derive(Debug, Default)]
+pub struct SelectionLog {
+    offered: VecDeque<Offered>,
+    stats: HashMap<(String, CompletionLevel), SelectionStats>,
+}
+
+impl SelectionLog {
+    pub fn record_offer(
+        &mut self,
+        id: &str,
+        language: &str,
+        level: &CompletionLevel,
+        code: &str,
+        alternatives: &[CompletionAlternative],
+    ) {
+        if self.offered.len() == OFFERED_CAPACITY {
+            self.offered.pop_front();
+        }
+        let key = (language.to_string(), level.clone());
+        self.stats.entry(key.clone()).or_default().offered += 1;
+        self.offered.push_back(Offered {
+            id: id.to_string(),
+            key,
+            code: code.to_string(),
+            alternatives: alternatives.to_vec(),
+            selected: false,
+            rated: false,
+        });
+    }
+
+    /// Summarize `completion_id` for a feedback event; each completion can be
+    /// rated once, after which `mark_rated` is called
+    pub fn summary(&self, completion_id: &str) -> Result<OfferSummary, CodifyError> {
+        let offered = self
+            .offered
+            .iter()
+            .find(|offered| offered.id == completion_id)
+            .ok_or_else(|| {
+                CodifyError::NotFound(format!("Completion not found: {}", completion_id))
+            })?;
+        if offered.rated {
+            return Err(CodifyError::InvalidInput(format!(
+                "Feedback for completion {} already exists",
+                completion_id
+            )));
+        }
+
+        let chars = offered.code.chars().count();
+        let max_chars = offered
+            .alternatives
+            .iter()
+            .map(|alternative| alternative.code.chars().count())
+            .fold(chars, usize::max);
+        Ok(OfferSummary {
+            language: offered.key.0.clone(),
+            level: offered.key.1.clone(),
+            chars,
+            max_chars,
+        })
+    }
+
+    pub fn mark_rated(&mut self, completion_id: &str) {
+        if let Some(offered) = self.offered.iter_mut().find(|o| o.id == completion_id) {
+            offered.rated = true;
+        }
+    }
+
+    /// Record that the user took `completion_id`'s primary suggestion (no
+    /// index) or one of its alternatives; each completion counts once
+    pub fn select(
+        &mut self,
+        completion_id: &str,
+        alternative_index: Option<usize>,
+    ) -> Result<CompletionSelection, CodifyError> {
+        let offered = self
+            .offered
+            .iter_mut()
+            .find(|offered| offered.id == completion_id)
+            .ok_or_else(|| {
+                CodifyError::NotFound(format!("Completion not found: {}", completion_id))
+            })?;
+
+        let (alternative_id, code) = match alternative_index {
+            None => (None, offered.code.clone()),
+            Some(index) => {
+                let alternative = offered.alternatives.get(index).ok_or_else(|| {
+                    CodifyError::InvalidInput(format!(
+                        "Invalid alternative index {}: completion has {} alternative(s)",
+                        index,
+                        offered.alternatives.len()
+                    ))
+                })?;
+                (Some(alternative.id.clone()), alternative.code.clone())
+            }
+        };
+
+        let stats = self.stats.entry(offered.key.clone()).or_default();
+        if !offered.selected {
+            offered.selected = true;
+            match alternative_index {
+                None => stats.accepted_primary += 1,
+                Some(_) => stats.accepted_alternative += 1,
+            }
+        }
+
+        Ok(CompletionSelection {
+            completion_id: completion_id.to_string(),
+            alternative_id,
+            code,
+            stats: stats.clone(),
+        })
+    }
+}
+
+/// Alternatives as the model reported them, best first
+///
+/// Each item may be a bare string or `{code, confidence}`; bare strings get
+/// `default_confidence`, which callers set just under the primary's so an
+/// alternative never outranks it without saying so.
+pub fn rank_alternatives(
+    items: &[serde_json::Value],
+    default_confidence: f32,
+) -> Vec<CompletionAlternative> {
+    let mut alternatives: Vec<CompletionAlternative> = items
+        .iter()
+        .filter_map(|item| {
+            let (code, confidence) = match item {
+                serde_json::Value::String(code) => (code.clone(), default_confidence),
+                _ => (
+                    item["code"].as_str()?.to_string(),
+                    item["confidence"]
+                        .as_f64()
+                        .map(|c| c.clamp(0.0, 1.0) as f32)
+                        .unwrap_or(default_confidence),
+                ),
+            };
+            Some(CompletionAlternative {
+                id: uuid::Uuid::new_v4().to_string(),
+                code,
+                confidence,
+            })
+        })
+        .collect();
+
+    alternatives.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    alternatives
+}
@@ -0,0 +1,339 @@
+use super::{AIState, ChatMessage, GenerationParams, RefactorSuggestion};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+
+/// `ai-stream-chunk`: more of a streaming request's reply text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AIStreamChunk {
+    pub request_id: String,
+    pub text: String,
+}
+
+/// `ai-refactor-suggestion`: one suggestion, sent as soon as it is complete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AIRefactorSuggestionEvent {
+    pub request_id: String,
+    pub suggestion: RefactorSuggestion,
+}
+
+/// `ai-stream-done`: the request finished, with what the non-streaming
+/// command would have returned or why it failed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AIStreamDone<T> {
+    pub request_id: String,
+    pub result: Option<T>,
+    pub error: Option<CodifyError>,
+}
+
+impl<T> AIStreamDone<T> {
+    pub fn new(request_id: String, result: Result<T, CodifyError>) -> Self {
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(e) => (None, Some(e)),
+        };
+        AIStreamDone {
+            request_id,
+            result,
+            error,
+        }
+    }
+}
+
+/// Sends one streaming request's events to the frontend
+pub struct StreamEmitter {
+    app: tauri::AppHandle,
+    pub request_id: String,
+}
+
+impl StreamEmitter {
+    pub fn new(app: &tauri::AppHandle) -> Self {
+        StreamEmitter {
+            app: app.clone(),
+            request_id: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+
+    pub fn text(&self, text: String) {
+        let _ = self.app.emit(
+            "ai-stream-chunk",
+            AIStreamChunk {
+                request_id: self.request_id.clone(),
+                text,
+            },
+        );
+    }
+
+    pub fn suggestion(&self, suggestion: RefactorSuggestion) {
+        let _ = self.app.emit(
+            "ai-refactor-suggestion",
+            AIRefactorSuggestionEvent {
+                request_id: self.request_id.clone(),
+                suggestion,
+            },
+        );
+    }
+
+    pub fn done<T: Serialize + Clone>(&self, result: Result<T, CodifyError>) {
+        let _ = self.app.emit(
+            "ai-stream-done",
+            AIStreamDone::new(self.request_id.clone(), result),
+        );
+    }
+}
+
+/// What a ReplyScanner found in newly received text
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScanEvent {
+    /// Decoded text of JSON string values, or of a reply that isn't JSON
+    Text(String),
+    /// A complete object in a top-level JSON array, as raw JSON
+    Item(String),
+}
+
+#[derive(Debug, Default, PartialEq)]
+enum ScanMode {
+    #[default]
+    Start,
+    Seeking, // Inside a code fence, before the JSON
+    Json,
+    Plain, // The reply doesn't start like JSON
+    Done,  // The top-level JSON value has closed
+}
+
+/// Picks the readable parts out of a JSON reply while it is still arriving
+///
+/// String values (not keys) are passed on as text, separated by blank lines,
+/// so prose renders progressively; objects in a top-level array are passed
+/// on whole as soon as they close. A ```json fence is skipped, and a reply
+/// that doesn't start like JSON is passed on as it is.
+#[derive(Debug, Default)]
+pub struct ReplyScanner {
+    mode: ScanMode,
+    stack: Vec<char>, // Open containers, `{` or `[`
+    in_string: bool,
+    is_key: bool,           // The open string is an object key
+    expect_key: bool,       // The next string in the open object is a key
+    escape: Option<String>, // Escape sequence read so far, after the backslash
+    value_has_text: bool,   // The open string value has passed on text
+    wrote_text: bool,       // Any value has passed on text
+    item: Option<String>,   // Raw text of the array object being read
+}
+
+impl ReplyScanner {
+    pub fn feed(&mut self, delta: &str) -> Vec<ScanEvent> {
+        let mut events = vec![];
+        let mut text = String::new();
+
+        for c in delta.chars() {
+            match self.mode {
+                ScanMode::Start | ScanMode::Seeking if matches!(c, '{' | '[') => {
+                    self.mode = ScanMode::Json;
+                    self.scan_json(c, &mut text, &mut events);
+                }
+                ScanMode::Start if c == '`' => self.mode = ScanMode::Seeking,
+                ScanMode::Start if !c.is_whitespace() => {
+                    self.mode = ScanMode::Plain;
+                    text.push(c);
+                }
+                ScanMode::Json => self.scan_json(c, &mut text, &mut events),
+                ScanMode::Plain => text.push(c),
+                _ => {}
+            }
+        }
+
+        flush_text(&mut text, &mut events);
+        events
+    }
+
+    fn scan_json(&mut self, c: char, text: &mut String, events: &mut Vec<ScanEvent>) {
+        if !self.in_string && c == '{' && self.stack == ['['] {
+            self.item = Some(String::new());
+        }
+        if let Some(item) = &mut self.item {
+            item.push(c);
+        }
+
+        if self.in_string {
+            if let Some(sequence) = &mut self.escape {
+                sequence.push(c);
+                // \uXXXX needs all four hex digits
+                if sequence.starts_with('u') && sequence.len() < 5 {
+                    return;
+                }
+                let decoded = match sequence.as_str() {
+                    "n" => Some('\n'),
+                    "t" => Some('\t'),
+                    "r" | "b" | "f" => None,
+                    unicode if unicode.starts_with('u') => Some(
+                        u32::from_str_radix(&unicode[1..], 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .unwrap_or(char::REPLACEMENT_CHARACTER),
+                    ),
+                    other => other.chars().next(),
+                };
+                self.escape = None;
+                if let Some(decoded) = decoded {
+                    self.push_value_text(decoded, text);
+                }
+            } else if c == '\\' {
+                self.escape = Some(String::new());
+            } else if c == '"' {
+                self.in_string = false;
+            } else {
+                self.push_value_text(c, text);
+            }
+            return;
+        }
+
+        match c {
+            '"' => {
+                self.in_string = true;
+                self.is_key = self.expect_key && self.stack.last() == Some(&'{');
+                self.value_has_text = false;
+            }
+            '{' => {
+                self.stack.push(c);
+                self.expect_key = true;
+            }
+            '[' => self.stack.push(c),
+            '}' | ']' => {
+                self.stack.pop();
+                self.expect_key = false;
+                if self.stack.is_empty() {
+                    self.mode = ScanMode::Done;
+                } else if c == '}' && self.stack == ['['] {
+                    if let Some(item) = self.item.take() {
+                        flush_text(text, events);
+                        events.push(ScanEvent::Item(item));
+                    }
+                }
+            }
+            ':' => self.expect_key = false,
+            ',' => self.expect_key = self.stack.last() == Some(&'{'),
+            _ => {}
+        }
+    }
+
+    fn push_value_text(&mut self, c: char, text: &mut String) {
+        if self.is_key {
+            return;
+        }
+        if !self.value_has_text {
+            if self.wrote_text {
+                text.push_str("\n\n");
+            }
+            self.value_has_text = true;
+            self.wrote_text = true;
+        }
+        text.push(c);
+    }
+}
+
+fn flush_text(text: &mut String, events: &mut Vec<ScanEvent>) {
+    if !text.is_empty() {
+        events.push(ScanEvent::Text(std::mem::take(text)));
+    }
+}
+
+impl AIState {
+    /// `chat`, passing the prose of the reply to `stream` as it arrives when
+    /// there is one
+    pub async fn chat_streamed(
+        &self,
+        command: &str,
+        messages: &[ChatMessage],
+        stream: Option<&StreamEmitter>,
+    ) -> Result<String, CodifyError> {
+        let Some(stream) = stream else {
+            return self.chat(command, messages).await;
+        };
+
+        let mut scanner = ReplyScanner::default();
+        let params = GenerationParams::default();
+        self.chat_stream(command, messages, &params, |delta| {
+            for event in scanner.feed(delta) {
+                if let ScanEvent::Text(text) = event {
+                    stream.text(text);
+                }
+            }
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::parse_json_reply;
+
+    /// Feed `chunks` in order, returning all text and every item passed on
+    fn scan(chunks: &[&str]) -> (String, Vec<String>) {
+        let mut scanner = ReplyScanner::default();
+        let (mut text, mut items) = (String::new(), vec![]);
+        for chunk in chunks {
+            for event in scanner.feed(chunk) {
+                match event {
+                    ScanEvent::Text(t) => text.push_str(&t),
+                    ScanEvent::Item(raw) => items.push(raw),
+                }
+            }
+        }
+        (text, items)
+    }
+
+    #[test]
+    fn text_continues_across_a_chunk_boundary_inside_a_string() {
+        let mut scanner = ReplyScanner::default();
+        assert_eq!(
+            scanner.feed(r#"{"explanation": "Hel"#),
+            vec![ScanEvent::Text("Hel".to_string())]
+        );
+        assert_eq!(
+            scanner.feed(r#"lo world", "notes": "Bye"}"#),
+            vec![ScanEvent::Text("lo world\n\nBye".to_string())]
+        );
+        assert!(scanner.feed("\n```").is_empty());
+    }
+
+    #[test]
+    fn escapes_decode_across_chunk_boundaries() {
+        let (text, _) = scan(&[r#"{"a": "say \""#, r#"hi\" now"}"#]);
+        assert_eq!(text, r#"say "hi" now"#);
+
+        let (text, _) = scan(&[r#"{"a": "say \"#, r#""hi\"", "b": "x"}"#]);
+        assert_eq!(text, "say \"hi\"\n\nx");
+
+        let (text, _) = scan(&[r#"{"a": "caf\u00"#, r#"e9\nend"}"#]);
+        assert_eq!(text, "caf\u{e9}\nend");
+    }
+
+    #[test]
+    fn items_are_whole_objects_of_the_top_level_array() {
+        let first = r#"{"title": "a", "edits": [{"line": 1, "text": "x]}"}], "meta": {"k": "v"}}"#;
+        let second = r#"{"title": "b"}"#;
+        let reply = format!("```json\n[{}, {}]\n```", first, second);
+
+        // One character at a time, so every position is a chunk boundary
+        let chars: Vec<String> = reply.chars().map(String::from).collect();
+        let chunks: Vec<&str> = chars.iter().map(String::as_str).collect();
+        let (text, items) = scan(&chunks);
+
+        assert_eq!(items, vec![first.to_string(), second.to_string()]);
+        assert_eq!(text, "a\n\nx]}\n\nv\n\nb");
+    }
+
+    #[test]
+    fn malformed_tail_still_finishes_the_stream() {
+        let chunks = [r#"[{"title": "ok"}, "#, r#"{"title": "cut"#, "}]"];
+        let (_, items) = scan(&chunks);
+        assert_eq!(items, vec![r#"{"title": "ok"}"#.to_string()]);
+
+        let reply = chunks.concat();
+        let result = parse_json_reply::<Vec<serde_json::Value>>(&reply);
+        let done = AIStreamDone::new("request".to_string(), result);
+        assert!(done.result.is_none());
+        assert!(matches!(done.error, Some(CodifyError::Provider(_))));
+    }
+}
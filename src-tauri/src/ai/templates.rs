@@ -0,0 +1,349 @@
+use super::{count_tokens, AIState, ChatMessage};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+
+/// A command's system and user prompt, with `{{placeholder}}`s filled in
+/// when the command runs
+///
+/// Placeholders a command doesn't supply are left in the text as written.
+/// Commands that parse JSON replies still expect the format the built-in
+/// system prompt asks for, so a custom one should keep that instruction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub system: String,
+    pub user: String,
+}
+
+/// A command's prompt as shipped, and the placeholders it fills
+pub struct BuiltinTemplate {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub placeholders: &'static [&'static str],
+    pub system: &'static str,
+    pub user: &'static str,
+}
+
+/// Every AI command's prompt template, with what the command puts in each
+/// placeholder returned by list_prompt_templates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplateInfo {
+    pub name: String,
+    pub description: String,
+    pub placeholders: Vec<String>,
+    pub template: PromptTemplate, // The one in effect
+    pub default: PromptTemplate,
+    pub customized: bool,
+}
+
+pub const BUILTIN_TEMPLATES: [BuiltinTemplate; 12] = [
+    BuiltinTemplate {
+        name: "complete_code",
+        description: "Chat-model code completion at the cursor",
+        placeholders: &["language", "scope", "examples", "selection", "context"],
+        system: "You are an inline code completion engine. Reply with JSON only: \
+                 {\"code\": string, \"confidence\": number 0-1, \"alternatives\": \
+                 [{\"code\": string, \"confidence\": number 0-1}]}. \
+                 code is only the text to insert at <CURSOR>, never the surrounding code; \
+                 alternatives are up to two other insertions, each with its own confidence.",
+        user: "{{examples}}{{context}}{{selection}}Complete {{scope}} at <CURSOR>.",
+    },
+    BuiltinTemplate {
+        name: "explain_code",
+        description: "Explain a snippet, optionally within its file",
+        placeholders: &["language", "range", "context", "code"],
+        system: "You are a senior engineer explaining code inside an IDE. Reply with JSON only: \
+                 {\"summary\": string, \"details\": [string]} where details are short points \
+                 covering behaviour, notable patterns and pitfalls. Strings may use markdown; \
+                 put code examples in fenced blocks tagged with their language.",
+        user: "Language: {{language}}\n{{range}}{{context}}\nCode to explain:\n\
+               ```{{language}}\n{{code}}\n```",
+    },
+    BuiltinTemplate {
+        name: "explain_error",
+        description: "Cause and fix steps for a pasted error message or stack trace",
+        placeholders: &["language", "error", "locations", "source"],
+        system: "You diagnose errors inside an IDE. Reply with JSON only: {\"cause\": string, \
+                 \"fix_steps\": [string]} where cause names the most likely root cause in one or \
+                 two sentences and fix_steps are concrete actions in the order to take them. \
+                 Refer to code as path:line. Strings may use markdown; put code in fenced blocks \
+                 tagged with their language.",
+        user: "Language: {{language}}\nError:\n```\n{{error}}\n```\n{{locations}}{{source}}",
+    },
+    BuiltinTemplate {
+        name: "suggest_refactor",
+        description: "Refactoring suggestions as line-range edits",
+        placeholders: &["code"],
+        system: "You are a refactoring assistant inside an IDE. Reply with a JSON array only. \
+                 Each item: {\"title\": string, \"description\": string, \"start_line\": number, \
+                 \"end_line\": number, \"replacement_code\": string, \"confidence\": number 0-1}. \
+                 Line numbers refer to the numbered code, are inclusive, and replacement_code \
+                 replaces exactly those lines.",
+        user: "Suggest refactorings for:\n{{code}}",
+    },
+    BuiltinTemplate {
+        name: "fix_error",
+        description: "Fixes for a failed build or test run",
+        placeholders: &["file_path", "error_output", "located_errors", "code"],
+        system: "You fix build and test failures inside an IDE. Reply with a JSON array only of \
+                 alternative fixes, most likely first. Each item: {\"title\": string, \
+                 \"description\": string explaining the cause, \"start_line\": number, \
+                 \"end_line\": number, \"replacement_code\": string, \"confidence\": number 0-1}. \
+                 Line numbers refer to the numbered code, are inclusive, and replacement_code \
+                 replaces exactly those lines.",
+        user: "File: {{file_path}}\n\nError output:\n```\n{{error_output}}\n```\n\
+               {{located_errors}}\nCode (lines marked >> are reported in the errors):\n{{code}}",
+    },
+    BuiltinTemplate {
+        name: "generate_docstring",
+        description: "Documentation comment for a declaration",
+        placeholders: &["language", "guidance", "name", "kind", "signature", "code"],
+        system: "You write documentation comments for {{language}} code. {{guidance}} Reply \
+                 with JSON only: {\"summary\": string, \"params\": [{\"name\": string, \
+                 \"type\": string, \"description\": string}], \"returns\": {\"type\": string, \
+                 \"description\": string} or null}. Do not include comment markers.",
+        user: "Document `{{name}}` ({{kind}}), declared as:\n{{signature}}\n\nFull code:\n\
+               ```{{language}}\n{{code}}\n```",
+    },
+    BuiltinTemplate {
+        name: "extract_component",
+        description: "Extract selected JSX into a new component",
+        placeholders: &[
+            "file_path",
+            "language",
+            "file",
+            "start_line",
+            "end_line",
+            "selection",
+            "bindings",
+            "component_name",
+        ],
+        system: "You extract JSX into a new React function component. Reply with JSON only: \
+                 {\"component_name\": string, \"props\": [{\"name\": string, \"type_annotation\": \
+                 string, \"source\": string}], \"component_code\": string, \"usage\": string}. \
+                 component_code is the complete new file: its imports, a props interface when the \
+                 file is TypeScript, and `export function <component_name>`. usage is the JSX \
+                 element that replaces the selection, passing each prop its source expression. \
+                 Values, callbacks and state setters from the parent become props. Values the \
+                 parent reads from context (useContext or custom hooks wrapping it) may be read \
+                 again inside the component instead. Keep hooks called within the selection at \
+                 the top level of the new component. Preserve the markup and behavior exactly.",
+        user: "File {{file_path}} ({{language}}):\n```{{language}}\n{{file}}\n```\n\n\
+               Selection (lines {{start_line}}-{{end_line}}):\n```{{language}}\n{{selection}}\n```\
+               \n\nNames the selection uses:\n{{bindings}}\n\nComponent name: {{component_name}}",
+    },
+    BuiltinTemplate {
+        name: "generate_tests",
+        description: "A test file for a module's exports",
+        placeholders: &[
+            "framework",
+            "language",
+            "guidance",
+            "file_path",
+            "imports",
+            "exports",
+            "code",
+        ],
+        system: "You write {{framework}} tests in {{language}}. {{guidance}} Test only the \
+                 exports listed, using their real names and signatures; do not invent other \
+                 components or functions. Reply with the complete test file only, no \
+                 explanations.",
+        user: "The test file will be saved as {{file_path}}. {{imports}}Write tests for these \
+               exports:\n{{exports}}\n\nSource:\n```{{language}}\n{{code}}\n```",
+    },
+    BuiltinTemplate {
+        name: "generate_design",
+        description: "A styled UI component from a description",
+        placeholders: &[
+            "framework",
+            "guidance",
+            "component_name",
+            "description",
+            "preferences",
+        ],
+        system: "You are a UI engineer generating {{framework}} components. Reply with JSON \
+                 only: {\"component_code\": string, \"styles\": string, \"props_interface\": \
+                 string}. component_code is the full component, styles is its CSS, and \
+                 props_interface declares its props. Apply every style preference (colors, \
+                 spacing, radius) to the styling. {{guidance}}",
+        user: "Component name: {{component_name}}\nDescription: {{description}}\n\
+               Style preferences:\n{{preferences}}",
+    },
+    BuiltinTemplate {
+        name: "scaffold_feature",
+        description: "New files for a feature, in the project's layout",
+        placeholders: &[
+            "description",
+            "feature_name",
+            "layout",
+            "framework",
+            "guidance",
+        ],
+        system: "You scaffold React features. Reply with JSON only: {\"feature_name\": string, \
+                 \"component\": string, \"hook\": string | null, \"test\": string, \"styles\": \
+                 string | null}. feature_name is PascalCase. Every other field is the complete \
+                 content of one file, saved at the path the layout gives with the name filled in; \
+                 imports between the files must use those paths. hook is null when the feature \
+                 needs no state or effects of its own, and styles is null when the layout has no \
+                 stylesheet. The test uses {{framework}}. {{guidance}}",
+        user: "Feature: {{description}}\nFeature name: {{feature_name}}\n\n\
+               File layout:\n{{layout}}",
+    },
+    BuiltinTemplate {
+        name: "commit_message",
+        description: "A commit message for the staged changes",
+        placeholders: &["guidance", "files", "diff", "truncated"],
+        system: "You write git commit messages. Reply with JSON only: {\"type\": string | null, \
+                 \"scope\": string | null, \"subject\": string, \"body\": string | null}. \
+                 {{guidance}} The subject is an imperative summary under 60 characters without \
+                 a trailing period. The body explains what changed and why in short lines, or is \
+                 null for trivial changes. Describe the actual changes in the diff; never write \
+                 generic text like \"update files\".",
+        user: "Staged files:\n{{files}}\n\nStaged diff{{truncated}}:\n{{diff}}",
+    },
+    BuiltinTemplate {
+        name: "review_diff",
+        description: "Review comments on a diff, one request per chunk of it",
+        placeholders: &["focus", "files", "diff"],
+        system: "You review code changes like a careful senior engineer. Reply with a JSON array \
+                 only, empty when there is nothing worth saying. Each item: {\"file_path\": \
+                 string, \"line\": number, \"severity\": \"issue\" | \"warning\" | \"nit\", \
+                 \"message\": string}. line is the line number in the new version of the file. \
+                 Comment only on added or changed lines, be specific and actionable, and never \
+                 praise or restate the change. Concentrate on:\n{{focus}}",
+        user: "Files in this change:\n{{files}}\n\nDiff to review:\n{{diff}}",
+    },
+];
+
+pub fn builtin_template(name: &str) -> Option<&'static BuiltinTemplate> {
+    BUILTIN_TEMPLATES
+        .iter()
+        .find(|template| template.name == name)
+}
+
+impl BuiltinTemplate {
+    pub fn template(&self) -> PromptTemplate {
+        PromptTemplate {
+            system: self.system.to_string(),
+            user: self.user.to_string(),
+        }
+    }
+}
+
+/// Names of the `{{placeholder}}`s in `text`, in order
+fn placeholders(text: &str) -> Vec<&str> {
+    let mut names = vec![];
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        names.push(rest[start + 2..start + 2 + end].trim());
+        rest = &rest[start + 2 + end + 2..];
+    }
+    names
+}
+
+/// Fill `{{placeholder}}`s from `values` in a single pass, so text that came
+/// from a value (code can contain braces) is never substituted again
+pub fn render_template(text: &str, values: &[(&str, &str)]) -> String {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + end].trim();
+        let placeholder_end = start + 2 + end + 2;
+
+        rendered.push_str(&rest[..start]);
+        match values.iter().find(|(key, _)| *key == name) {
+            Some((_, value)) => rendered.push_str(value),
+            None => rendered.push_str(&rest[start..placeholder_end]),
+        }
+        rest = &rest[placeholder_end..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+impl PromptTemplate {
+    /// Check a custom template against the command it replaces the prompt of
+    pub fn validate(&self, name: &str) -> Result<(), CodifyError> {
+        let builtin = builtin_template(name)
+            .ok_or_else(|| CodifyError::NotFound(format!("Prompt template not found: {}", name)))?;
+
+        if self.user.trim().is_empty() {
+            return Err(CodifyError::InvalidInput(format!(
+                "prompt_templates[{}].user must not be empty",
+                name
+            )));
+        }
+        for placeholder in placeholders(&self.system)
+            .into_iter()
+            .chain(placeholders(&self.user))
+        {
+            if !builtin.placeholders.contains(&placeholder) {
+                return Err(CodifyError::InvalidInput(format!(
+                    "Invalid placeholder {{{{{}}}}} in prompt template {}: expected one of {}",
+                    placeholder,
+                    name,
+                    builtin.placeholders.join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AIState {
+    /// The template in effect for a command: the user's, else the built-in
+    pub fn prompt_template(&self, name: &str) -> PromptTemplate {
+        if let Some(custom) = self.prompt_templates.read().unwrap().get(name) {
+            return custom.clone();
+        }
+        builtin_template(name)
+            .map(BuiltinTemplate::template)
+            .unwrap_or_else(|| panic!("No built-in prompt template named {}", name))
+    }
+
+    /// System and user messages for a command, rendered from its template
+    pub fn prompt(&self, name: &str, values: &[(&str, &str)]) -> Vec<ChatMessage> {
+        let template = self.prompt_template(name);
+        let system = render_template(&template.system, values);
+
+        let mut messages = vec![];
+        if !system.trim().is_empty() {
+            messages.push(ChatMessage::system(system));
+        }
+        messages.push(ChatMessage::user(render_template(&template.user, values)));
+        messages
+    }
+}
+
+/// Tokens `messages` take up, for sizing what goes into a placeholder
+pub fn messages_tokens(messages: &[ChatMessage]) -> usize {
+    messages.iter().map(|m| count_tokens(&m.content)).sum()
+}
+
+/// Built-in templates in table order, each with the user's version if set
+pub fn prompt_template_infos(
+    custom: &std::collections::BTreeMap<String, PromptTemplate>,
+) -> Vec<PromptTemplateInfo> {
+    BUILTIN_TEMPLATES
+        .iter()
+        .map(|builtin| {
+            let default = builtin.template();
+            let template = custom.get(builtin.name).cloned();
+            PromptTemplateInfo {
+                name: builtin.name.to_string(),
+                description: builtin.description.to_string(),
+                placeholders: builtin.placeholders.iter().map(|p| p.to_string()).collect(),
+                customized: template
+                    .as_ref()
+                    .is_some_and(|template| *template != default),
+                template: template.unwrap_or_else(|| default.clone()),
+                default,
+            }
+        })
+        .collect()
+}
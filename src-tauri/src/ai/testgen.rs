@@ -0,0 +1,316 @@
+use super::{compiled, is_component_name};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TestFramework {
+    Jest,
+    Vitest,
+    Mocha,
+    PlaywrightComponent,
+}
+
+impl TestFramework {
+    /// Imports and assertion style the generated file must follow
+    pub fn prompt_guidance(&self) -> &'static str {
+        match self {
+            TestFramework::Jest => {
+                "Use Jest globals (describe, test, expect, jest.fn) and @testing-library/react \
+                 for components. Do not import describe/test/expect."
+            }
+            TestFramework::Vitest => {
+                "Import { describe, it, expect, vi } from 'vitest' and use vi.fn for mocks. \
+                 Use @testing-library/react for components."
+            }
+            TestFramework::Mocha => {
+                "Use Mocha's describe/it with Chai: import { expect } from 'chai'. \
+                 Use sinon for spies if needed."
+            }
+            TestFramework::PlaywrightComponent => {
+                "Import { test, expect } from '@playwright/experimental-ct-react', mount \
+                 components with the mount fixture and use locator assertions."
+            }
+        }
+    }
+
+    fn file_suffix(&self) -> &'static str {
+        match self {
+            TestFramework::PlaywrightComponent => "spec",
+            _ => "test",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestGenConfig {
+    pub framework: TestFramework,
+    pub language: String,
+    pub output_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TestTargetKind {
+    Function,
+    Component, // PascalCase function, rendered rather than called
+    Class,
+}
+
+/// An export of the code under test that the generated file covers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestTarget {
+    pub name: String, // Local name; default exports are imported under it
+    pub kind: TestTargetKind,
+    pub signature: String, // e.g. `formatDate(date: Date, locale = 'en')`
+    pub is_default: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedTests {
+    pub file_path: String,
+    pub code: String, // Just the import header for a dry run
+    pub framework: TestFramework,
+    pub file_exists: bool,
+    pub targets: Vec<TestTarget>,
+    pub import_statement: Option<String>, // None without a source path to resolve against
+    pub dry_run: bool,
+}
+
+struct Declaration {
+    offset: usize,
+    target: TestTarget,
+    exported: bool,
+}
+
+/// Top-level functions, components and classes the code exports, in source
+/// order; types, interfaces, plain constants and re-exports are skipped
+pub fn find_test_targets(code: &str) -> Vec<TestTarget> {
+    static FUNCTION: OnceLock<regex::Regex> = OnceLock::new();
+    static CLASS: OnceLock<regex::Regex> = OnceLock::new();
+    static ARROW: OnceLock<regex::Regex> = OnceLock::new();
+    static EXPORT_LIST: OnceLock<regex::Regex> = OnceLock::new();
+    static EXPORT_DEFAULT: OnceLock<regex::Regex> = OnceLock::new();
+    let function = compiled(
+        &FUNCTION,
+        r"(?m)^(export\s+(default\s+)?)?(?:async\s+)?function\s*\*?\s*([A-Za-z_$][\w$]*)\s*(?:<[^>]*>)?\s*\(([^)]*)\)",
+    );
+    let class = compiled(
+        &CLASS,
+        r"(?m)^(export\s+(default\s+)?)?(?:abstract\s+)?class\s+([A-Za-z_$][\w$]*)",
+    );
+    let arrow = compiled(
+        &ARROW,
+        r"(?m)^(export\s+)?(?:const|let)\s+([A-Za-z_$][\w$]*)\s*(?::[^=]+)?=\s*(?:async\s+)?(?:(?:React\.)?(?:memo|forwardRef)(?:<[^>]*>)?\(\s*)?(?:function\b[^(]*\(([^)]*)\)|\(([^)]*)\)[^=;]*=>|([A-Za-z_$][\w$]*)\s*=>)",
+    );
+    let export_list = compiled(&EXPORT_LIST, r"(?m)^export\s*\{([^}]*)\}\s*(from\b)?");
+    let export_default = compiled(
+        &EXPORT_DEFAULT,
+        r"(?m)^export\s+default\s+([A-Za-z_$][\w$]*)\s*;?\s*$",
+    );
+
+    let signature = |name: &str, params: &str| {
+        let params: Vec<&str> = params.split_whitespace().collect();
+        format!("{}({})", name, params.join(" "))
+    };
+    let function_kind = |name: &str| {
+        if is_component_name(name) {
+            TestTargetKind::Component
+        } else {
+            TestTargetKind::Function
+        }
+    };
+
+    let mut declarations: Vec<Declaration> = vec![];
+    for c in function.captures_iter(code) {
+        declarations.push(Declaration {
+            offset: c.get(0).map(|m| m.start()).unwrap_or_default(),
+            target: TestTarget {
+                name: c[3].to_string(),
+                kind: function_kind(&c[3]),
+                signature: signature(&c[3], &c[4]),
+                is_default: c.get(2).is_some(),
+            },
+            exported: c.get(1).is_some(),
+        });
+    }
+    for c in class.captures_iter(code) {
+        declarations.push(Declaration {
+            offset: c.get(0).map(|m| m.start()).unwrap_or_default(),
+            target: TestTarget {
+                name: c[3].to_string(),
+                kind: TestTargetKind::Class,
+                signature: format!("class {}", &c[3]),
+                is_default: c.get(2).is_some(),
+            },
+            exported: c.get(1).is_some(),
+        });
+    }
+    for c in arrow.captures_iter(code) {
+        let params = c
+            .get(3)
+            .or_else(|| c.get(4))
+            .or_else(|| c.get(5))
+            .map(|m| m.as_str())
+            .unwrap_or_default();
+        declarations.push(Declaration {
+            offset: c.get(0).map(|m| m.start()).unwrap_or_default(),
+            target: TestTarget {
+                name: c[2].to_string(),
+                kind: function_kind(&c[2]),
+                signature: signature(&c[2], params),
+                is_default: false,
+            },
+            exported: c.get(1).is_some(),
+        });
+    }
+
+    // `export { a, b as c }` and `export default a` export earlier declarations
+    for c in export_list.captures_iter(code) {
+        if c.get(2).is_some() {
+            continue;
+        }
+        for item in c[1].split(',').map(str::trim) {
+            if item.is_empty() || item.starts_with("type ") {
+                continue;
+            }
+            let (local, exported) = item
+                .split_once(" as ")
+                .map(|(local, exported)| (local.trim(), exported.trim()))
+                .unwrap_or((item, item));
+            for declaration in declarations.iter_mut().filter(|d| d.target.name == local) {
+                declaration.exported = true;
+                if exported == "default" {
+                    declaration.target.is_default = true;
+                } else if exported != local {
+                    declaration.target.signature =
+                        declaration.target.signature.replacen(local, exported, 1);
+                    declaration.target.name = exported.to_string();
+                }
+            }
+        }
+    }
+    for c in export_default.captures_iter(code) {
+        for declaration in declarations.iter_mut().filter(|d| d.target.name == c[1]) {
+            declaration.exported = true;
+            declaration.target.is_default = true;
+        }
+    }
+
+    declarations.retain(|d| d.exported);
+    declarations.sort_by_key(|d| d.offset);
+    declarations.dedup_by(|a, b| a.target.name == b.target.name);
+    declarations.into_iter().map(|d| d.target).collect()
+}
+
+/// Module specifier for importing `source_path` from `test_path`, e.g.
+/// `src/__tests__/util.test.ts` + `src/util.ts` -> `../util`
+pub fn relative_import(test_path: &str, source_path: &str) -> String {
+    let components = |path: &str| -> Vec<String> {
+        path.replace('\\', "/")
+            .split('/')
+            .filter(|part| !part.is_empty() && *part != ".")
+            .map(|part| part.to_string())
+            .collect()
+    };
+
+    let test = components(test_path);
+    let test_dir = &test[..test.len().saturating_sub(1)];
+    let mut source = components(source_path);
+    if let Some(file) = source.last_mut() {
+        for extension in [".tsx", ".ts", ".jsx", ".js", ".mjs", ".cjs"] {
+            if let Some(stem) = file.strip_suffix(extension) {
+                *file = stem.to_string();
+                break;
+            }
+        }
+    }
+
+    let common = test_dir
+        .iter()
+        .zip(&source)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut parts = vec!["..".to_string(); test_dir.len() - common];
+    parts.extend(source[common..].iter().cloned());
+
+    let specifier = parts.join("/");
+    if specifier.starts_with("..") {
+        specifier
+    } else {
+        format!("./{}", specifier)
+    }
+}
+
+/// The import line that brings every target into the test file
+pub fn import_statement(targets: &[TestTarget], specifier: &str) -> String {
+    let default = targets.iter().find(|target| target.is_default);
+    let named: Vec<&str> = targets
+        .iter()
+        .filter(|target| !target.is_default)
+        .map(|target| target.name.as_str())
+        .collect();
+
+    let bindings = match (default, named.is_empty()) {
+        (Some(default), true) => default.name.clone(),
+        (Some(default), false) => format!("{}, {{ {} }}", default.name, named.join(", ")),
+        (None, _) => format!("{{ {} }}", named.join(", ")),
+    };
+    format!("import {} from '{}';", bindings, specifier)
+}
+
+/// Detect the test framework from package.json dependencies, defaulting to Jest
+pub fn detect_test_framework(project_path: &str) -> TestFramework {
+    let manifest = Path::new(project_path).join("package.json");
+    let Ok(content) = std::fs::read_to_string(manifest) else {
+        return TestFramework::Jest;
+    };
+    let Ok(package) = serde_json::from_str::<serde_json::Value>(&content) else {
+        return TestFramework::Jest;
+    };
+
+    let has_dep = |name: &str| {
+        ["dependencies", "devDependencies"]
+            .iter()
+            .any(|section| package[section].get(name).is_some())
+    };
+
+    if has_dep("vitest") {
+        TestFramework::Vitest
+    } else if has_dep("jest") {
+        TestFramework::Jest
+    } else if has_dep("@playwright/experimental-ct-react") {
+        TestFramework::PlaywrightComponent
+    } else if has_dep("mocha") {
+        TestFramework::Mocha
+    } else {
+        TestFramework::Jest
+    }
+}
+
+/// Derive a test file path next to the source, e.g. `Button.tsx` -> `Button.test.tsx`
+pub fn default_test_path(source_path: Option<&str>, framework: TestFramework, language: &str) -> String {
+    let suffix = framework.file_suffix();
+
+    match source_path {
+        Some(source) => {
+            let path = Path::new(source);
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("generated");
+            let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("ts");
+            let file_name = format!("{}.{}.{}", stem, suffix, extension);
+
+            match path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                Some(parent) => parent.join(file_name).to_string_lossy().to_string(),
+                None => file_name,
+            }
+        }
+        None => {
+            let extension = match language {
+                "javascript" => "js",
+                "javascriptreact" => "jsx",
+                "typescriptreact" => "tsx",
+                _ => "ts",
+            };
+            format!("generated.{}.{}", suffix, extension)
+        }
+    }
+}
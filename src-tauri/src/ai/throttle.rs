@@ -0,0 +1,136 @@
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Client-side limits on provider traffic, so bursts from the editor queue
+/// here instead of turning into 429s and wasted spend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub max_concurrent: usize,    // Requests in flight at once; 0 = unlimited
+    pub requests_per_minute: u32, // Token-bucket refill rate; 0 = unlimited
+    pub burst: u32,               // Bucket size; requests allowed back-to-back
+    pub queue_timeout_ms: u64,    // How long a request may wait for a slot
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        RateLimit {
+            max_concurrent: 4,
+            requests_per_minute: 120,
+            burst: 10,
+            queue_timeout_ms: 5000,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    in_flight: usize,
+    tokens: f64,
+    refilled: Instant,
+}
+
+/// Shared gate in front of every provider call: a concurrency cap plus a
+/// token bucket, both read from the current RateLimit on each acquire so
+/// config changes apply without a restart
+#[derive(Debug)]
+pub struct Throttle {
+    bucket: Mutex<Bucket>,
+    released: tokio::sync::Notify,
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Throttle {
+            bucket: Mutex::new(Bucket {
+                in_flight: 0,
+                tokens: RateLimit::default().burst as f64,
+                refilled: Instant::now(),
+            }),
+            released: tokio::sync::Notify::new(),
+        }
+    }
+}
+
+/// Holds one in-flight slot until dropped
+pub struct ThrottlePermit<'a> {
+    throttle: &'a Throttle,
+}
+
+impl Drop for ThrottlePermit<'_> {
+    fn drop(&mut self) {
+        self.throttle.bucket.lock().unwrap().in_flight -= 1;
+        self.throttle.released.notify_waiters();
+    }
+}
+
+impl Throttle {
+    pub fn in_flight(&self) -> usize {
+        self.bucket.lock().unwrap().in_flight
+    }
+
+    /// Take a slot, or say what to wait for: Some(delay) until the next token,
+    /// None for a permit to be released
+    fn try_acquire(&self, limit: &RateLimit) -> Result<(), Option<Duration>> {
+        let mut bucket = self.bucket.lock().unwrap();
+
+        let capacity = limit.burst.max(1) as f64;
+        let per_second = limit.requests_per_minute as f64 / 60.0;
+        if limit.requests_per_minute > 0 {
+            let elapsed = bucket.refilled.elapsed().as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+            bucket.refilled = Instant::now();
+        }
+
+        if limit.max_concurrent > 0 && bucket.in_flight >= limit.max_concurrent {
+            // Woken by a released permit rather than a timer
+            return Err(None);
+        }
+        if limit.requests_per_minute > 0 {
+            if bucket.tokens < 1.0 {
+                let due = (1.0 - bucket.tokens) / per_second;
+                return Err(Some(Duration::from_secs_f64(due)));
+            }
+            bucket.tokens -= 1.0;
+        }
+
+        bucket.in_flight += 1;
+        Ok(())
+    }
+
+    /// Wait for a slot under `limit`, failing once queue_timeout_ms passes
+    pub async fn acquire(&self, limit: &RateLimit) -> Result<ThrottlePermit<'_>, CodifyError> {
+        let deadline = Instant::now() + Duration::from_millis(limit.queue_timeout_ms);
+
+        loop {
+            // Register for release notifications before checking, so a permit
+            // dropped in between isn't missed
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+
+            let wait = match self.try_acquire(limit) {
+                Ok(()) => return Ok(ThrottlePermit { throttle: self }),
+                Err(wait) => wait,
+            };
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || wait.is_some_and(|w| w > remaining) {
+                return Err(CodifyError::RateLimited(format!(
+                    "Rate limited locally: {} request(s) in flight, limit {} concurrent and {} per \
+                     minute; try again shortly",
+                    self.in_flight(),
+                    limit.max_concurrent,
+                    limit.requests_per_minute
+                )));
+            }
+
+            let sleep = wait.unwrap_or(remaining).min(remaining);
+            tokio::select! {
+                _ = released => {}
+                _ = tokio::time::sleep(sleep) => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::sync::OnceLock;
+use tiktoken_rs::CoreBPE;
+
+/// Tokens kept free for the chat template and role markers
+pub const PROMPT_OVERHEAD_TOKENS: usize = 64;
+
+fn bpe() -> Option<&'static CoreBPE> {
+    static BPE: OnceLock<Option<CoreBPE>> = OnceLock::new();
+
+    BPE.get_or_init(|| match tiktoken_rs::cl100k_base() {
+        Ok(bpe) => Some(bpe),
+        Err(e) => {
+            log::warn!("Tokenizer unavailable, falling back to estimates: {}", e);
+            None
+        }
+    })
+    .as_ref()
+}
+
+/// Count tokens with cl100k_base; other models tokenize differently, so
+/// treat this as a close estimate rather than an exact figure
+pub fn count_tokens(text: &str) -> usize {
+    match bpe() {
+        Some(bpe) => bpe.encode_with_special_tokens(text).len(),
+        // Roughly four characters per token for source code
+        None => text.chars().count().div_ceil(4),
+    }
+}
+
+/// Keep the lines closest to `focus_line` (0-based) that fit in `budget`
+/// tokens, growing the window outward one line at a time. Returns the
+/// kept text and whether anything was dropped.
+pub fn fit_around_line(text: &str, focus_line: usize, budget: usize) -> (String, bool) {
+    if count_tokens(text) <= budget {
+        return (text.to_string(), false);
+    }
+
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.is_empty() {
+        return (String::new(), false);
+    }
+
+    let focus = focus_line.min(lines.len() - 1);
+    let mut start = focus;
+    let mut end = focus + 1; // exclusive
+    let mut used = count_tokens(lines[focus]);
+
+    loop {
+        let mut grew = false;
+
+        if start > 0 {
+            let cost = count_tokens(lines[start - 1]) + 1;
+            if used + cost <= budget {
+                start -= 1;
+                used += cost;
+                grew = true;
+            }
+        }
+
+        if end < lines.len() {
+            let cost = count_tokens(lines[end]) + 1;
+            if used + cost <= budget {
+                end += 1;
+                used += cost;
+                grew = true;
+            }
+        }
+
+        if !grew {
+            break;
+        }
+    }
+
+    (lines[start..end].join("\n"), true)
+}
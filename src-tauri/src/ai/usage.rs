@@ -0,0 +1,105 @@
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Number of recent provider calls kept for get_ai_usage_log
+const USAGE_LOG_CAPACITY: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub timestamp: String,
+    pub command: String,
+    pub model: String,
+    pub endpoint: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub estimated: bool, // Counts come from the local tokenizer, not the provider
+    pub latency_ms: u64,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+impl UsageRecord {
+    /// Build a record from a provider response, preferring its `usage` field
+    /// and falling back to `prompt_estimate` and a count of the output text
+    pub fn from_response(
+        command: &str,
+        model: &str,
+        endpoint: &str,
+        latency: Duration,
+        prompt_estimate: usize,
+        result: &Result<serde_json::Value, CodifyError>,
+        api_key: Option<&str>,
+    ) -> Self {
+        let (prompt_tokens, completion_tokens, estimated, error) = match result {
+            Ok(value) => {
+                let usage = &value["usage"];
+                let completion_estimate = || {
+                    let text = value["choices"][0]["message"]["content"]
+                        .as_str()
+                        .or_else(|| value["choices"][0]["text"].as_str())
+                        .unwrap_or_default();
+                    super::count_tokens(text) as u64
+                };
+
+                match usage["prompt_tokens"].as_u64() {
+                    Some(prompt) => (
+                        prompt,
+                        usage["completion_tokens"].as_u64().unwrap_or(0),
+                        false,
+                        None,
+                    ),
+                    None => (prompt_estimate as u64, completion_estimate(), true, None),
+                }
+            }
+            Err(e) => (
+                prompt_estimate as u64,
+                0,
+                true,
+                Some(redact(e.message(), api_key)),
+            ),
+        };
+
+        UsageRecord {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            command: command.to_string(),
+            model: model.to_string(),
+            endpoint: endpoint.to_string(),
+            prompt_tokens,
+            completion_tokens,
+            estimated,
+            latency_ms: latency.as_millis() as u64,
+            success: error.is_none(),
+            error,
+        }
+    }
+}
+
+/// Providers sometimes echo the key back in error messages
+fn redact(text: &str, api_key: Option<&str>) -> String {
+    match api_key.filter(|key| !key.is_empty()) {
+        Some(key) => text.replace(key, "[redacted]"),
+        None => text.to_string(),
+    }
+}
+
+/// Ring buffer of the most recent provider calls
+#[derive(Debug, Default)]
+pub struct UsageLog {
+    records: VecDeque<UsageRecord>,
+}
+
+impl UsageLog {
+    pub fn push(&mut self, record: UsageRecord) {
+        if self.records.len() == USAGE_LOG_CAPACITY {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    /// Newest first, at most `limit` records
+    pub fn recent(&self, limit: usize) -> Vec<UsageRecord> {
+        self.records.iter().rev().take(limit).cloned().collect()
+    }
+}
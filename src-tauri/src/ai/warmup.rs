@@ -0,0 +1,67 @@
+use super::{count_tokens, AIState};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarmupPhase {
+    #[default]
+    Skipped, // Off in settings, or AI is disabled
+    Running,
+    Ready,
+    Failed,
+}
+
+/// What get_ai_status reports about the startup warm-up
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WarmupStatus {
+    pub phase: WarmupPhase,
+    pub duration_ms: Option<u64>, // Set once it has finished
+    pub error: Option<String>,
+}
+
+impl AIState {
+    pub fn warmup_status(&self) -> WarmupStatus {
+        self.warmup.lock().unwrap().clone()
+    }
+
+    /// Do the slow first-use work before the first request needs it: build
+    /// the tokenizer, then reach the provider, which for the Local provider
+    /// loads the model into memory
+    pub async fn warm_up(&self) {
+        if !self.is_enabled() {
+            return;
+        }
+        *self.warmup.lock().unwrap() = WarmupStatus {
+            phase: WarmupPhase::Running,
+            ..Default::default()
+        };
+        log::info!("Warming up the AI provider");
+        let started = std::time::Instant::now();
+
+        if let Err(e) = tokio::task::spawn_blocking(|| count_tokens("warm up")).await {
+            log::warn!("Tokenizer warm-up failed: {}", e);
+        }
+        let test = self.test_connection().await;
+
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let status = match test.error {
+            None => {
+                log::info!("AI provider warmed up in {} ms", duration_ms);
+                WarmupStatus {
+                    phase: WarmupPhase::Ready,
+                    duration_ms: Some(duration_ms),
+                    error: None,
+                }
+            }
+            Some(error) => {
+                log::warn!("AI provider warm-up failed: {}", error);
+                WarmupStatus {
+                    phase: WarmupPhase::Failed,
+                    duration_ms: Some(duration_ms),
+                    error: Some(error),
+                }
+            }
+        };
+        *self.warmup.lock().unwrap() = status;
+    }
+}
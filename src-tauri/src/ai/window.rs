@@ -0,0 +1,127 @@
+use super::{comment_style, CompletionLevel};
+use crate::error::CodifyError;
+use crate::storage::chunk_by_function;
+use serde::{Deserialize, Serialize};
+
+/// How much of the current file ai_complete_code sends: lines on each side
+/// of the cursor, per completion level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompletionWindow {
+    pub enabled: bool, // Off sends the whole file, clipped only to the context budget
+    pub line: usize,
+    pub block: usize,
+    pub component: usize,
+    pub feature: usize,
+    pub max_import_lines: usize, // File-top imports kept however far the cursor is
+}
+
+impl Default for CompletionWindow {
+    fn default() -> Self {
+        CompletionWindow {
+            enabled: true,
+            line: 30,
+            block: 60,
+            component: 150,
+            feature: 300,
+            max_import_lines: 60,
+        }
+    }
+}
+
+impl CompletionWindow {
+    pub fn validate(&self) -> Result<(), CodifyError> {
+        let levels = [
+            ("line", self.line),
+            ("block", self.block),
+            ("component", self.component),
+            ("feature", self.feature),
+        ];
+        for (name, lines) in levels {
+            if lines == 0 {
+                return Err(CodifyError::InvalidInput(format!(
+                    "Invalid completion_window.{}: must be at least 1 line",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn lines_for(&self, level: &CompletionLevel) -> usize {
+        match level {
+            CompletionLevel::Line => self.line,
+            CompletionLevel::Block => self.block,
+            CompletionLevel::Component => self.component,
+            CompletionLevel::Feature => self.feature,
+        }
+    }
+}
+
+/// Cut prefix and suffix down to the level's window around the cursor,
+/// widened to the enclosing definition (up to twice the window) and topped
+/// with the file's imports. Left-out lines become a `...` comment when the
+/// language's comment syntax is known. Returns the new prefix and suffix and
+/// whether anything was left out.
+pub fn window_around_cursor(
+    file_path: &str,
+    language: &str,
+    prefix: &str,
+    suffix: &str,
+    window: &CompletionWindow,
+    level: &CompletionLevel,
+) -> (String, String, bool) {
+    let radius = window.lines_for(level);
+    let content = format!("{}{}", prefix, suffix);
+    let lines: Vec<&str> = content.split('\n').collect();
+    let cursor = prefix.matches('\n').count(); // 0-based
+    if lines.len() <= radius * 2 + 1 {
+        return (prefix.to_string(), suffix.to_string(), false);
+    }
+
+    let mut start = cursor.saturating_sub(radius);
+    let mut end = (cursor + radius + 1).min(lines.len()); // Exclusive
+
+    let chunks = chunk_by_function(file_path, &content, language);
+    let row = cursor as u32 + 1;
+    let enclosing = chunks.iter().find(|chunk| {
+        !matches!(chunk.code_type.as_str(), "import" | "module")
+            && chunk.start_line <= row
+            && row <= chunk.end_line
+    });
+    if let Some(enclosing) = enclosing {
+        let widest_start = cursor.saturating_sub(radius * 2);
+        let widest_end = (cursor + radius * 2 + 1).min(lines.len());
+        start = start.min((enclosing.start_line as usize - 1).max(widest_start));
+        end = end.max((enclosing.end_line as usize).min(widest_end));
+    }
+
+    // Imports leading the file, unless the window already covers them
+    let header_end = chunks
+        .iter()
+        .take_while(|chunk| chunk.code_type == "import")
+        .map(|chunk| chunk.end_line as usize)
+        .max()
+        .unwrap_or(0)
+        .min(window.max_import_lines)
+        .min(start);
+
+    let elided = comment_style(language)
+        .ok()
+        .map(|style| style.comment("..."));
+    let mut before: Vec<&str> = lines[..header_end].to_vec();
+    if start > header_end {
+        before.extend(elided.as_deref());
+    }
+    before.extend(&lines[start..cursor]);
+    before.push(prefix.rsplit('\n').next().unwrap_or_default());
+
+    let mut after: Vec<&str> = vec![suffix.split('\n').next().unwrap_or_default()];
+    after.extend(&lines[cursor + 1..end]);
+    if end < lines.len() {
+        after.extend(elided.as_deref());
+    }
+
+    let windowed = start > header_end || end < lines.len();
+    (before.join("\n"), after.join("\n"), windowed)
+}
@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+
+/// Escape sequences longer than this are dropped rather than held for the
+/// next chunk, so a stray ESC can't swallow the rest of the output
+const MAX_PENDING_ESCAPE: usize = 4096;
+
+const COLOR_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// How a terminal response carries its output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum OutputFormat {
+    #[default]
+    Raw, // Exactly what the process printed, escape codes included
+    Spans, // Escape codes stripped from the text and returned as styled spans
+}
+
+/// SGR attributes in effect for a span
+///
+/// The 16 standard colors are named ("red", "bright-blue") so the UI can map
+/// them onto its theme; 256-color and truecolor values are `#rrggbb`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AnsiStyle {
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub inverse: bool,
+    pub strikethrough: bool,
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnsiSpan {
+    pub text: String,
+    pub style: AnsiStyle,
+}
+
+/// Name for one of the 16 standard colors
+fn named_color(index: u8) -> String {
+    let name = COLOR_NAMES[(index % 8) as usize];
+    if index >= 8 {
+        format!("bright-{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// A 256-color palette entry: the 16 standard colors by name, the rest as hex
+fn indexed_color(index: u8) -> String {
+    match index {
+        0..=15 => named_color(index),
+        16..=231 => {
+            const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+            let cube = index - 16;
+            let (r, g, b) = (cube / 36, (cube / 6) % 6, cube % 6);
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                LEVELS[r as usize], LEVELS[g as usize], LEVELS[b as usize]
+            )
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            format!("#{:02x}{:02x}{:02x}", gray, gray, gray)
+        }
+    }
+}
+
+/// The color an extended `38;...` or `48;...` sequence selects, consuming
+/// its parameters
+fn extended_color(params: &mut impl Iterator<Item = u32>) -> Option<String> {
+    match params.next()? {
+        5 => Some(indexed_color(params.next()?.min(255) as u8)),
+        2 => {
+            let mut channel = || params.next().map(|value| value.min(255) as u8);
+            let (r, g, b) = (channel()?, channel()?, channel()?);
+            Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+        }
+        _ => None,
+    }
+}
+
+impl AnsiStyle {
+    /// Apply the parameters of one SGR (`ESC [ ... m`) sequence
+    fn apply_sgr(&mut self, params: &str) {
+        // Parameters are `;`-separated, with `:` accepted for extended colors
+        let mut codes = params
+            .split([';', ':'])
+            .map(|code| code.parse::<u32>().unwrap_or(0));
+
+        // An empty sequence is a reset
+        if params.is_empty() {
+            *self = AnsiStyle::default();
+            return;
+        }
+
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = AnsiStyle::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                7 => self.inverse = true,
+                9 => self.strikethrough = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                27 => self.inverse = false,
+                29 => self.strikethrough = false,
+                30..=37 => self.fg = Some(named_color((code - 30) as u8)),
+                38 => self.fg = extended_color(&mut codes),
+                39 => self.fg = None,
+                40..=47 => self.bg = Some(named_color((code - 40) as u8)),
+                48 => self.bg = extended_color(&mut codes),
+                49 => self.bg = None,
+                90..=97 => self.fg = Some(named_color((code - 90 + 8) as u8)),
+                100..=107 => self.bg = Some(named_color((code - 100 + 8) as u8)),
+                _ => {} // Blink, fonts, overline and the like have no UI mapping
+            }
+        }
+    }
+}
+
+/// Splits output into styled spans, keeping the style and any escape
+/// sequence cut off at the end of a chunk for the next one
+#[derive(Default)]
+pub struct AnsiParser {
+    style: AnsiStyle,
+    pending: String,
+}
+
+impl AnsiParser {
+    /// Spans for the next chunk of output; every escape sequence is removed,
+    /// and only SGR ones affect the style
+    pub fn feed(&mut self, chunk: &str) -> Vec<AnsiSpan> {
+        let input = std::mem::take(&mut self.pending) + chunk;
+        let mut spans: Vec<AnsiSpan> = vec![];
+        let mut text = String::new();
+
+        let mut rest = input.as_str();
+        while let Some(start) = rest.find('\x1b') {
+            text.push_str(&rest[..start]);
+            let sequence = &rest[start..];
+
+            let Some(length) = escape_length(sequence) else {
+                if sequence.len() <= MAX_PENDING_ESCAPE {
+                    self.pending = sequence.to_string();
+                }
+                rest = "";
+                break;
+            };
+
+            if let Some(params) = sequence[..length]
+                .strip_prefix("\x1b[")
+                .and_then(|csi| csi.strip_suffix('m'))
+            {
+                let mut style = self.style.clone();
+                style.apply_sgr(params);
+                if style != self.style {
+                    push_span(&mut spans, std::mem::take(&mut text), &self.style);
+                    self.style = style;
+                }
+            }
+            rest = &sequence[length..];
+        }
+        text.push_str(rest);
+        push_span(&mut spans, text, &self.style);
+
+        spans
+    }
+}
+
+fn push_span(spans: &mut Vec<AnsiSpan>, text: String, style: &AnsiStyle) {
+    if text.is_empty() {
+        return;
+    }
+    match spans.last_mut() {
+        Some(last) if last.style == *style => last.text.push_str(&text),
+        _ => spans.push(AnsiSpan {
+            text,
+            style: style.clone(),
+        }),
+    }
+}
+
+/// Byte length of the escape sequence `sequence` starts with, or None when
+/// it is cut off
+fn escape_length(sequence: &str) -> Option<usize> {
+    let bytes = sequence.as_bytes();
+    match bytes.get(1)? {
+        // CSI: parameter and intermediate bytes, then a final byte in @..~
+        b'[' => bytes[2..]
+            .iter()
+            .position(|b| (0x40..=0x7e).contains(b))
+            .map(|end| end + 3),
+        // OSC (titles, hyperlinks): ends with BEL or ESC \
+        b']' => {
+            let body = &sequence[2..];
+            let bel = body.find('\x07').map(|end| end + 3);
+            let st = body.find("\x1b\\").map(|end| end + 4);
+            match (bel, st) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        }
+        // Character set selection takes one more byte
+        b'(' | b')' => sequence[2..].chars().next().map(|c| 2 + c.len_utf8()),
+        // Any other two-byte escape; step over the whole next character
+        _ => sequence[1..].chars().next().map(|c| 1 + c.len_utf8()),
+    }
+}
+
+/// The text of `spans` without styling
+pub fn spans_text(spans: &[AnsiSpan]) -> String {
+    spans.iter().map(|span| span.text.as_str()).collect()
+}
+
+/// `text` with every escape sequence removed
+pub fn strip_ansi(text: &str) -> String {
+    spans_text(&AnsiParser::default().feed(text))
+}
@@ -0,0 +1,180 @@
+use super::{DiffLineKind, FileDiff};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+
+/// Conventional-commit types the model may choose from
+const COMMIT_TYPES: &[&str] = &[
+    "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore", "revert",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum CommitStyle {
+    #[default]
+    Conventional, // type(scope): subject
+    Plain,        // Capitalised imperative subject only
+}
+
+/// The fields the model is asked for
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommitFields {
+    #[serde(rename = "type", default)]
+    pub commit_type: Option<String>,
+    #[serde(default)]
+    pub scope: Option<String>,
+    pub subject: String,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitMessage {
+    pub style: CommitStyle,
+    pub commit_type: Option<String>, // None in plain style
+    pub scope: Option<String>,
+    pub subject: String,
+    pub body: Option<String>,
+    pub message: String, // Ready to pass to `git commit -m`
+}
+
+fn diff_display_path(file: &FileDiff) -> String {
+    match (&file.old_path, &file.new_path) {
+        (Some(old), Some(new)) if old != new => format!("{} -> {}", old, new),
+        (_, Some(path)) | (Some(path), None) => path.clone(),
+        (None, None) => "(unknown)".to_string(),
+    }
+}
+
+/// One line per staged file with its change counts, so the model sees every
+/// file even when the diff itself has to be clipped
+pub fn staged_file_summary(files: &[FileDiff]) -> String {
+    files
+        .iter()
+        .map(|file| {
+            let status = match (&file.old_path, &file.new_path) {
+                (None, _) => "added",
+                (_, None) => "deleted",
+                (Some(old), Some(new)) if old != new => "renamed",
+                _ => "modified",
+            };
+            if file.binary {
+                return format!("- {} ({}, binary)", diff_display_path(file), status);
+            }
+
+            let lines = file.hunks.iter().flat_map(|h| &h.lines);
+            let added = lines
+                .clone()
+                .filter(|l| l.kind == DiffLineKind::Added)
+                .count();
+            let removed = lines.filter(|l| l.kind == DiffLineKind::Removed).count();
+            format!(
+                "- {} ({}, +{} -{})",
+                diff_display_path(file),
+                status,
+                added,
+                removed
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render parsed diffs back to compact unified text for the prompt
+pub fn render_diff(files: &[FileDiff]) -> String {
+    let mut out = String::new();
+    for file in files {
+        out.push_str(&format!("--- {}\n", diff_display_path(file)));
+        if file.binary {
+            out.push_str("Binary file changed\n");
+            continue;
+        }
+        for hunk in &file.hunks {
+            out.push_str(&format!(
+                "@@ -{},{} +{},{} @@ {}\n",
+                hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines, hunk.section
+            ));
+            for line in &hunk.lines {
+                let marker = match line.kind {
+                    DiffLineKind::Added => '+',
+                    DiffLineKind::Removed => '-',
+                    DiffLineKind::Context => ' ',
+                };
+                out.push(marker);
+                out.push_str(&line.content);
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+impl CommitStyle {
+    pub fn prompt_guidance(&self) -> String {
+        match self {
+            CommitStyle::Conventional => format!(
+                "Use Conventional Commits. type is one of {}; scope is the area touched \
+                 (a module or package name) or null when the change is broad.",
+                COMMIT_TYPES.join(", ")
+            ),
+            CommitStyle::Plain => {
+                "Write a plain message: type and scope must be null, and the subject starts \
+                 with a capital letter."
+                    .to_string()
+            }
+        }
+    }
+
+    /// Normalise the model's fields and assemble the full message
+    pub fn build(&self, fields: CommitFields) -> Result<CommitMessage, CodifyError> {
+        let subject = fields.subject.lines().next().unwrap_or("").trim();
+        let subject = subject.trim_end_matches('.').to_string();
+        if subject.is_empty() {
+            return Err(CodifyError::Provider(
+                "Model returned an empty commit subject".to_string(),
+            ));
+        }
+        let body = non_empty(fields.body);
+
+        let (commit_type, scope, subject) = match self {
+            CommitStyle::Conventional => {
+                let commit_type = non_empty(fields.commit_type)
+                    .map(|t| t.to_lowercase())
+                    .filter(|t| COMMIT_TYPES.contains(&t.as_str()))
+                    .unwrap_or_else(|| "chore".to_string());
+                (Some(commit_type), non_empty(fields.scope), subject)
+            }
+            CommitStyle::Plain => {
+                let mut chars = subject.chars();
+                let subject = match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => subject,
+                };
+                (None, None, subject)
+            }
+        };
+
+        let header = match (&commit_type, &scope) {
+            (Some(t), Some(s)) => format!("{}({}): {}", t, s, subject),
+            (Some(t), None) => format!("{}: {}", t, subject),
+            _ => subject.clone(),
+        };
+        let message = match &body {
+            Some(body) => format!("{}\n\n{}", header, body),
+            None => header,
+        };
+
+        Ok(CommitMessage {
+            style: *self,
+            commit_type,
+            scope,
+            subject,
+            body,
+            message,
+        })
+    }
+}
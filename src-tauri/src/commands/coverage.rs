@@ -0,0 +1,236 @@
+use super::strip_ansi;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Files under this share of covered statements are suggested for more tests
+pub const LOW_COVERAGE_PCT: f64 = 80.0;
+
+/// Coverage reports checked in order; istanbul writes both for Jest and Vitest
+const COVERAGE_SUMMARY: &str = "coverage/coverage-summary.json";
+const COVERAGE_FINAL: &str = "coverage/coverage-final.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileCoverage {
+    pub file: String,    // Relative to the working directory when inside it
+    pub statements: f64, // Percent covered
+}
+
+/// What a test run reported
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TestResult {
+    pub runner: Option<String>, // jest or vitest, when recognizable
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize, // Includes todo tests
+    pub total: usize,
+    pub failed_tests: Vec<String>, // Full names, when a JSON report lists them
+    pub coverage: Vec<FileCoverage>, // Least covered first; empty without a fresh report
+    pub total_coverage: Option<f64>,
+}
+
+/// The shape of `jest --json` and Vitest's json reporter, which mirrors it
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonReport {
+    num_passed_tests: usize,
+    num_failed_tests: usize,
+    #[serde(default)]
+    num_pending_tests: usize,
+    #[serde(default)]
+    num_todo_tests: usize,
+    num_total_tests: usize,
+    #[serde(default)]
+    test_results: Vec<JsonSuite>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonSuite {
+    #[serde(default)]
+    assertion_results: Vec<JsonAssertion>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonAssertion {
+    #[serde(default)]
+    full_name: String,
+    #[serde(default)]
+    title: String,
+    status: String,
+}
+
+fn detect_runner(output: &str) -> Option<String> {
+    if output.contains("vitest") || output.contains("Test Files ") {
+        Some("vitest".to_string())
+    } else if output.contains("jest") || output.contains("Test Suites:") {
+        Some("jest".to_string())
+    } else {
+        None
+    }
+}
+
+/// A JSON report printed to stdout, e.g. with `--json` or `--reporter=json`
+fn json_report(output: &str) -> Option<JsonReport> {
+    let start = output.find("{\"num")?;
+    let end = output.rfind('}')?;
+    serde_json::from_str(output.get(start..=end)?).ok()
+}
+
+/// Counts from a summary line such as Jest's `Tests: 1 failed, 24 passed,
+/// 25 total` or Vitest's `Tests  24 passed | 1 failed (25)`
+fn summary_counts(output: &str) -> Option<TestResult> {
+    let line = output.lines().map(str::trim).find(|line| {
+        line.starts_with("Tests:") || (line.starts_with("Tests ") && line.contains(" passed"))
+    })?;
+
+    let mut result = TestResult::default();
+    let words: Vec<&str> = line
+        .split(|c: char| c.is_whitespace() || matches!(c, ',' | '|' | '(' | ')'))
+        .filter(|word| !word.is_empty())
+        .collect();
+    for pair in words.windows(2) {
+        let Ok(count) = pair[0].parse::<usize>() else {
+            continue;
+        };
+        match pair[1] {
+            "passed" => result.passed = count,
+            "failed" => result.failed = count,
+            "skipped" | "todo" | "pending" => result.skipped += count,
+            "total" => result.total = count,
+            _ => {}
+        }
+    }
+    // Vitest puts the total in parentheses at the end
+    if result.total == 0 {
+        result.total = words
+            .last()
+            .and_then(|word| word.parse().ok())
+            .unwrap_or(result.passed + result.failed + result.skipped);
+    }
+    Some(result)
+}
+
+fn relative(file: &str, working_dir: &Path) -> String {
+    Path::new(file)
+        .strip_prefix(working_dir)
+        .map(|path| path.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_else(|_| file.to_string())
+}
+
+/// Read a coverage report written at or after `since`, so a stale one from
+/// an earlier run isn't presented as this run's
+fn read_fresh(path: &Path, since: SystemTime) -> Option<serde_json::Value> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    if modified < since {
+        return None;
+    }
+    let text = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+/// Per-file statement coverage from istanbul's json-summary report, or
+/// computed from its json (coverage-final) report, and the overall share
+fn read_coverage(working_dir: &Path, since: SystemTime) -> (Vec<FileCoverage>, Option<f64>) {
+    let mut files = vec![];
+    let mut total = None;
+
+    if let Some(summary) = read_fresh(&working_dir.join(COVERAGE_SUMMARY), since) {
+        for (file, entry) in summary.as_object().into_iter().flatten() {
+            let Some(pct) = entry["statements"]["pct"].as_f64() else {
+                continue;
+            };
+            if file == "total" {
+                total = Some(pct);
+            } else {
+                files.push(FileCoverage {
+                    file: relative(file, working_dir),
+                    statements: pct,
+                });
+            }
+        }
+    } else if let Some(report) = read_fresh(&working_dir.join(COVERAGE_FINAL), since) {
+        let (mut covered_all, mut count_all) = (0, 0);
+        for (file, entry) in report.as_object().into_iter().flatten() {
+            let counts: HashMap<String, u64> =
+                serde_json::from_value(entry["s"].clone()).unwrap_or_default();
+            if counts.is_empty() {
+                continue;
+            }
+            let covered = counts.values().filter(|hits| **hits > 0).count();
+            covered_all += covered;
+            count_all += counts.len();
+            files.push(FileCoverage {
+                file: relative(file, working_dir),
+                statements: covered as f64 * 100.0 / counts.len() as f64,
+            });
+        }
+        if count_all > 0 {
+            total = Some(covered_all as f64 * 100.0 / count_all as f64);
+        }
+    }
+
+    files.sort_by(|a, b| {
+        a.statements
+            .total_cmp(&b.statements)
+            .then(a.file.cmp(&b.file))
+    });
+    (files, total)
+}
+
+/// Parse a Jest or Vitest run: a JSON report in the output when there is
+/// one, else the summary line, plus coverage written by this run. None when
+/// the output has neither.
+pub fn parse_test_run(output: &str, working_dir: &Path, started: SystemTime) -> Option<TestResult> {
+    let output = strip_ansi(output);
+
+    let mut result = match json_report(&output) {
+        Some(report) => TestResult {
+            passed: report.num_passed_tests,
+            failed: report.num_failed_tests,
+            skipped: report.num_pending_tests + report.num_todo_tests,
+            total: report.num_total_tests,
+            failed_tests: report
+                .test_results
+                .iter()
+                .flat_map(|suite| &suite.assertion_results)
+                .filter(|assertion| assertion.status == "failed")
+                .map(|assertion| match assertion.full_name.is_empty() {
+                    true => assertion.title.clone(),
+                    false => assertion.full_name.clone(),
+                })
+                .collect(),
+            ..Default::default()
+        },
+        None => summary_counts(&output)?,
+    };
+    result.runner = detect_runner(&output);
+    (result.coverage, result.total_coverage) = read_coverage(working_dir, started);
+    Some(result)
+}
+
+/// Suggestions naming the failing tests and the least covered files
+pub fn test_suggestions(result: &TestResult) -> Vec<String> {
+    let mut suggestions: Vec<String> = result
+        .failed_tests
+        .iter()
+        .take(3)
+        .map(|name| format!("Fix failing test: {}", name))
+        .collect();
+    suggestions.extend(
+        result
+            .coverage
+            .iter()
+            .filter(|file| file.statements < LOW_COVERAGE_PCT)
+            .take(5)
+            .map(|file| {
+                format!(
+                    "Add tests for {} ({:.0}% of statements covered)",
+                    file.file, file.statements
+                )
+            }),
+    );
+    suggestions
+}
@@ -0,0 +1,380 @@
+use super::PackageManager;
+use crate::ai::compiled;
+use crate::error::CodifyError;
+use crate::storage::walk_project;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use tauri::Emitter;
+use tauri_plugin_shell::process::CommandEvent;
+use tauri_plugin_shell::ShellExt;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DiagnosticsBackend {
+    #[default]
+    Tsc,
+    Eslint,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub file_path: String, // Project-relative
+    pub line: u32,         // 1-based
+    pub column: u32,       // 1-based
+    pub severity: DiagnosticSeverity,
+    pub code: Option<String>, // TS2322, or the ESLint rule id
+    pub message: String,
+}
+
+/// Rendered in tsc's own format, so a diagnostic can be handed to
+/// ai_fix_error as error output
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            DiagnosticSeverity::Error => "error",
+            DiagnosticSeverity::Warning => "warning",
+        };
+        write!(
+            f,
+            "{}({},{}): {} {}: {}",
+            self.file_path,
+            self.line,
+            self.column,
+            severity,
+            self.code.as_deref().unwrap_or("lint"),
+            self.message
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticsReport {
+    pub backend: DiagnosticsBackend,
+    pub diagnostics: Vec<Diagnostic>,
+    pub cached: bool, // Served from cache because no file changed since the last run
+    pub duration_ms: u64,
+}
+
+/// Emitted as `diagnostics-progress` while a check runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticsProgress {
+    pub project_path: String,
+    pub backend: DiagnosticsBackend,
+    pub stage: String, // started, running or finished
+    pub diagnostics_found: usize,
+    pub elapsed_ms: u64,
+}
+
+struct CachedReport {
+    fingerprint: u64,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Last report per project and backend, reused while no project file changes
+#[derive(Default)]
+pub struct DiagnosticsState {
+    reports: Mutex<HashMap<(String, DiagnosticsBackend), CachedReport>>,
+}
+
+impl DiagnosticsState {
+    fn get(&self, key: &(String, DiagnosticsBackend), fingerprint: u64) -> Option<Vec<Diagnostic>> {
+        self.reports
+            .lock()
+            .unwrap()
+            .get(key)
+            .filter(|report| report.fingerprint == fingerprint)
+            .map(|report| report.diagnostics.clone())
+    }
+
+    fn insert(
+        &self,
+        key: (String, DiagnosticsBackend),
+        fingerprint: u64,
+        diagnostics: Vec<Diagnostic>,
+    ) {
+        self.reports.lock().unwrap().insert(
+            key,
+            CachedReport {
+                fingerprint,
+                diagnostics,
+            },
+        );
+    }
+}
+
+/// Hash of every project file's path, size and mtime; any edit, addition or
+/// deletion changes it
+pub fn project_fingerprint(project_path: &str) -> Result<u64, CodifyError> {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    for file in walk_project(project_path)? {
+        (file.path, file.size, file.modified).hash(&mut hasher);
+    }
+    Ok(hasher.finish())
+}
+
+impl DiagnosticsBackend {
+    fn tool(&self) -> (&'static str, &'static str) {
+        match self {
+            DiagnosticsBackend::Tsc => ("tsc", "typescript"),
+            DiagnosticsBackend::Eslint => ("eslint", "eslint"),
+        }
+    }
+
+    fn args(&self) -> Vec<&'static str> {
+        match self {
+            DiagnosticsBackend::Tsc => vec!["--noEmit", "--pretty", "false"],
+            DiagnosticsBackend::Eslint => vec![".", "--format", "json"],
+        }
+    }
+
+    /// The project's own installed binary; global installs are ignored so
+    /// results match the project's pinned version and config
+    fn binary(&self, project_path: &str) -> Result<std::path::PathBuf, CodifyError> {
+        let (tool, package) = self.tool();
+        let name = if cfg!(windows) {
+            format!("{}.cmd", tool)
+        } else {
+            tool.to_string()
+        };
+
+        let binary = Path::new(project_path)
+            .join("node_modules")
+            .join(".bin")
+            .join(name);
+        if binary.is_file() {
+            return Ok(binary);
+        }
+
+        let manager = PackageManager::detect(Path::new(project_path));
+        let add = if manager == PackageManager::Npm {
+            "install"
+        } else {
+            "add"
+        };
+        Err(CodifyError::NotFound(format!(
+            "{} not found in node_modules; add it with '{} {} -D {}'",
+            tool,
+            manager.binary(),
+            add,
+            package
+        )))
+    }
+}
+
+/// Parse `tsc --pretty false` output; indented lines continue the previous
+/// message, and project-level errors (bad tsconfig) are attributed to tsconfig.json
+pub fn parse_tsc_output(output: &str) -> Vec<Diagnostic> {
+    static LOCATED: OnceLock<regex::Regex> = OnceLock::new();
+    static GLOBAL: OnceLock<regex::Regex> = OnceLock::new();
+    let located = compiled(
+        &LOCATED,
+        r"^(.+?)\((\d+),(\d+)\): (error|warning) (TS\d+): (.*)$",
+    );
+    let global = compiled(&GLOBAL, r"^(error|warning) (TS\d+): (.*)$");
+
+    let severity = |s: &str| match s {
+        "warning" => DiagnosticSeverity::Warning,
+        _ => DiagnosticSeverity::Error,
+    };
+
+    let mut diagnostics: Vec<Diagnostic> = vec![];
+    for line in output.lines() {
+        if let Some(c) = located.captures(line) {
+            diagnostics.push(Diagnostic {
+                file_path: c[1].replace('\\', "/"),
+                line: c[2].parse().unwrap_or(1),
+                column: c[3].parse().unwrap_or(1),
+                severity: severity(&c[4]),
+                code: Some(c[5].to_string()),
+                message: c[6].trim().to_string(),
+            });
+        } else if let Some(c) = global.captures(line) {
+            diagnostics.push(Diagnostic {
+                file_path: "tsconfig.json".to_string(),
+                line: 1,
+                column: 1,
+                severity: severity(&c[1]),
+                code: Some(c[2].to_string()),
+                message: c[3].trim().to_string(),
+            });
+        } else if line.starts_with(' ') && !line.trim().is_empty() {
+            if let Some(last) = diagnostics.last_mut() {
+                last.message.push('\n');
+                last.message.push_str(line.trim());
+            }
+        }
+    }
+    diagnostics
+}
+
+/// Parse `eslint --format json` output, making paths project-relative
+pub fn parse_eslint_output(
+    output: &str,
+    project_path: &str,
+) -> Result<Vec<Diagnostic>, CodifyError> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct FileResult {
+        file_path: String,
+        messages: Vec<Message>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Message {
+        rule_id: Option<String>,
+        severity: u8, // 1 warning, 2 error
+        message: String,
+        line: Option<u32>,
+        column: Option<u32>,
+    }
+
+    let files: Vec<FileResult> = serde_json::from_str(output.trim())
+        .map_err(|e| CodifyError::Internal(format!("Could not parse eslint output: {}", e)))?;
+    let root = Path::new(project_path);
+
+    Ok(files
+        .into_iter()
+        .flat_map(|file| {
+            let path = Path::new(&file.file_path);
+            let relative = path
+                .strip_prefix(root)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .replace('\\', "/");
+
+            file.messages.into_iter().map(move |message| Diagnostic {
+                file_path: relative.clone(),
+                line: message.line.unwrap_or(1),
+                column: message.column.unwrap_or(1),
+                severity: if message.severity >= 2 {
+                    DiagnosticSeverity::Error
+                } else {
+                    DiagnosticSeverity::Warning
+                },
+                code: message.rule_id,
+                message: message.message,
+            })
+        })
+        .collect())
+}
+
+/// Run the backend over the project, emitting progress as output arrives
+pub async fn run_diagnostics(
+    app: &tauri::AppHandle,
+    state: &DiagnosticsState,
+    project_path: &str,
+    backend: DiagnosticsBackend,
+) -> Result<DiagnosticsReport, CodifyError> {
+    let started = std::time::Instant::now();
+    let progress = |stage: &str, diagnostics_found: usize| {
+        let _ = app.emit(
+            "diagnostics-progress",
+            DiagnosticsProgress {
+                project_path: project_path.to_string(),
+                backend,
+                stage: stage.to_string(),
+                diagnostics_found,
+                elapsed_ms: started.elapsed().as_millis() as u64,
+            },
+        );
+    };
+
+    let project = project_path.to_string();
+    let fingerprint = tokio::task::spawn_blocking(move || project_fingerprint(&project))
+        .await
+        .map_err(|e| CodifyError::Internal(format!("Project scan task failed: {}", e)))??;
+    let key = (project_path.to_string(), backend);
+
+    if let Some(diagnostics) = state.get(&key, fingerprint) {
+        progress("finished", diagnostics.len());
+        return Ok(DiagnosticsReport {
+            backend,
+            diagnostics,
+            cached: true,
+            duration_ms: started.elapsed().as_millis() as u64,
+        });
+    }
+
+    let binary = backend.binary(project_path)?;
+    let mut command = app
+        .shell()
+        .command(binary.to_string_lossy().to_string())
+        .args(backend.args())
+        .current_dir(project_path);
+    // The tool's shebang needs node on PATH
+    if let Some(path) = super::resolved_path() {
+        command = command.env("PATH", path);
+    }
+
+    let (mut events, _child) = command
+        .spawn()
+        .map_err(|e| CodifyError::Io(format!("Failed to run {}: {}", binary.display(), e)))?;
+    progress("started", 0);
+
+    let mut stdout = String::new();
+    let mut stderr = String::new();
+    let mut found = 0;
+    let mut code = None;
+    while let Some(event) = events.recv().await {
+        match event {
+            CommandEvent::Stdout(bytes) => {
+                let line = String::from_utf8_lossy(&bytes);
+                // ESLint's JSON only parses once complete
+                if backend == DiagnosticsBackend::Tsc {
+                    found += parse_tsc_output(&line).len();
+                    progress("running", found);
+                }
+                stdout.push_str(&line);
+                stdout.push('\n');
+            }
+            CommandEvent::Stderr(bytes) => {
+                stderr.push_str(&String::from_utf8_lossy(&bytes));
+                stderr.push('\n');
+            }
+            CommandEvent::Terminated(payload) => {
+                code = payload.code;
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let diagnostics = match backend {
+        DiagnosticsBackend::Tsc => parse_tsc_output(&stdout),
+        DiagnosticsBackend::Eslint => parse_eslint_output(&stdout, project_path)?,
+    };
+    // Both tools exit non-zero when they find problems; only an exit with no
+    // parseable findings means the tool itself failed
+    if diagnostics.is_empty() && code != Some(0) {
+        let detail = if stderr.trim().is_empty() {
+            &stdout
+        } else {
+            &stderr
+        };
+        return Err(CodifyError::Internal(format!(
+            "{} failed (exit code {:?}): {}",
+            backend.tool().0,
+            code,
+            detail.trim()
+        )));
+    }
+
+    progress("finished", diagnostics.len());
+    state.insert(key, fingerprint, diagnostics.clone());
+
+    Ok(DiagnosticsReport {
+        backend,
+        diagnostics,
+        cached: false,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
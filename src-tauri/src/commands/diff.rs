@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub content: String,
+    pub old_line: Option<u32>, // None for added lines
+    pub new_line: Option<u32>, // None for removed lines
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub section: String, // Text after the closing @@, usually the enclosing function
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub old_path: Option<String>, // None for added files
+    pub new_path: Option<String>, // None for deleted files
+    pub binary: bool,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// Strip git's a/ b/ prefixes and map /dev/null to None
+fn diff_path(raw: &str) -> Option<String> {
+    // Timestamps from `diff -u` follow a tab
+    let raw = raw.split('\t').next().unwrap_or(raw).trim_end();
+    if raw == "/dev/null" {
+        return None;
+    }
+
+    let path = raw
+        .strip_prefix("a/")
+        .or_else(|| raw.strip_prefix("b/"))
+        .unwrap_or(raw);
+    Some(path.to_string())
+}
+
+/// Parse "-l,s" or "+l" from a hunk header; a missing count means 1
+fn parse_range(range: &str) -> Option<(u32, u32)> {
+    let range = &range[1..];
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+fn parse_hunk_header(line: &str) -> Option<DiffHunk> {
+    let rest = line.strip_prefix("@@ ")?;
+    let (ranges, section) = rest.split_once(" @@").unwrap_or((rest, ""));
+    let mut ranges = ranges.split_whitespace();
+    let (old_start, old_lines) = parse_range(ranges.next()?)?;
+    let (new_start, new_lines) = parse_range(ranges.next()?)?;
+
+    Some(DiffHunk {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        section: section.trim().to_string(),
+        lines: vec![],
+    })
+}
+
+/// Parse a unified diff, as produced by `git diff` or `diff -u`, into files and hunks
+pub fn parse_unified_diff(diff: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = vec![];
+    // Lines still expected in the current hunk, so bodies that look like headers aren't misread
+    let (mut old_remaining, mut new_remaining) = (0u32, 0u32);
+    let (mut old_line, mut new_line) = (0u32, 0u32);
+    // Whether the current file is still waiting for its "--- " line
+    let mut awaiting_old_path = false;
+
+    for line in diff.lines() {
+        if old_remaining > 0 || new_remaining > 0 {
+            let Some(hunk) = files.last_mut().and_then(|file| file.hunks.last_mut()) else {
+                break;
+            };
+
+            let (kind, content) = match line.chars().next() {
+                Some('+') => (DiffLineKind::Added, &line[1..]),
+                Some('-') => (DiffLineKind::Removed, &line[1..]),
+                Some(' ') => (DiffLineKind::Context, &line[1..]),
+                // Some tools drop the space on empty context lines
+                None => (DiffLineKind::Context, ""),
+                Some('\\') => continue,
+                _ => {
+                    old_remaining = 0;
+                    new_remaining = 0;
+                    continue;
+                }
+            };
+
+            let (old, new) = match kind {
+                DiffLineKind::Added => {
+                    new_remaining = new_remaining.saturating_sub(1);
+                    new_line += 1;
+                    (None, Some(new_line - 1))
+                }
+                DiffLineKind::Removed => {
+                    old_remaining = old_remaining.saturating_sub(1);
+                    old_line += 1;
+                    (Some(old_line - 1), None)
+                }
+                DiffLineKind::Context => {
+                    old_remaining = old_remaining.saturating_sub(1);
+                    new_remaining = new_remaining.saturating_sub(1);
+                    old_line += 1;
+                    new_line += 1;
+                    (Some(old_line - 1), Some(new_line - 1))
+                }
+            };
+
+            hunk.lines.push(DiffLine {
+                kind,
+                content: content.to_string(),
+                old_line: old,
+                new_line: new,
+            });
+            continue;
+        }
+
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            // Mode-only changes have no ---/+++ lines, so take the paths from here first
+            let (old, new) = paths.split_once(" b/").unwrap_or((paths, paths));
+            files.push(FileDiff {
+                old_path: diff_path(old),
+                new_path: diff_path(new),
+                ..Default::default()
+            });
+            awaiting_old_path = true;
+        } else if let Some(path) = line.strip_prefix("--- ") {
+            // Plain unified diffs have no "diff --git" line to open a file
+            if !awaiting_old_path {
+                files.push(FileDiff::default());
+            }
+            awaiting_old_path = false;
+            if let Some(file) = files.last_mut() {
+                file.old_path = diff_path(path);
+            }
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            if let Some(file) = files.last_mut() {
+                file.new_path = diff_path(path);
+            }
+        } else if line.starts_with("Binary files ") {
+            if let Some(file) = files.last_mut() {
+                file.binary = true;
+            }
+        } else if line.starts_with("@@ ") {
+            let Some(hunk) = parse_hunk_header(line) else {
+                continue;
+            };
+            let Some(file) = files.last_mut() else {
+                continue;
+            };
+
+            old_remaining = hunk.old_lines;
+            new_remaining = hunk.new_lines;
+            old_line = hunk.old_start;
+            new_line = hunk.new_start;
+            file.hunks.push(hunk);
+        }
+    }
+
+    files
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Files read for `load_dotenv`, lowest precedence first
+const DOTENV_FILES: [&str; 2] = [".env", ".env.local"];
+
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Byte offset of the quote ending a value; backslashes escape within
+/// double quotes only
+fn closing_quote(value: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && quote == '"' {
+            escaped = true;
+        } else if c == quote {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Unescape a double-quoted value; single-quoted ones are taken literally
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Parse `.env` text into variables, later lines winning
+///
+/// Supports `KEY=value`, an optional `export ` prefix, `#` comments, and
+/// single- or double-quoted values that may span lines. `${VAR}` references
+/// are not expanded. Malformed lines are skipped and reported by line number
+/// only, since values are often secrets.
+pub fn parse_dotenv(text: &str, file_name: &str) -> HashMap<String, String> {
+    let mut vars = HashMap::new();
+    let mut lines = text.lines().enumerate();
+
+    while let Some((index, line)) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            log::warn!("Skipping malformed line {} in {}", index + 1, file_name);
+            continue;
+        };
+        let key = key.trim();
+        if !is_valid_key(key) {
+            log::warn!("Skipping malformed line {} in {}", index + 1, file_name);
+            continue;
+        }
+
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'');
+        let value = match quote {
+            Some(quote) => {
+                // Gather lines until the closing quote
+                let mut quoted = value[1..].to_string();
+                while closing_quote(&quoted, quote).is_none() {
+                    match lines.next() {
+                        Some((_, next)) => {
+                            quoted.push('\n');
+                            quoted.push_str(next);
+                        }
+                        None => break,
+                    }
+                }
+                let Some(end) = closing_quote(&quoted, quote) else {
+                    log::warn!("Unterminated quote on line {} in {}", index + 1, file_name);
+                    break;
+                };
+                if quote == '"' {
+                    unescape(&quoted[..end])
+                } else {
+                    quoted[..end].to_string()
+                }
+            }
+            // An unquoted value ends at a comment
+            None => match value.find(" #") {
+                Some(comment) => value[..comment].trim_end().to_string(),
+                None => value.trim_end().to_string(),
+            },
+        };
+        vars.insert(key.to_string(), value);
+    }
+    vars
+}
+
+/// Variables from `.env` then `.env.local` in `dir`, the latter overriding;
+/// missing files are skipped
+pub fn load_dotenv(dir: &Path) -> HashMap<String, String> {
+    let mut vars = HashMap::new();
+    for name in DOTENV_FILES {
+        let Ok(text) = std::fs::read_to_string(dir.join(name)) else {
+            continue;
+        };
+        let file_vars = parse_dotenv(&text, name);
+        log::info!("Loaded {} variable(s) from {}", file_vars.len(), name);
+        vars.extend(file_vars);
+    }
+    vars
+}
@@ -0,0 +1,196 @@
+use crate::error::CodifyError;
+use crate::storage::{detect_file_language, resolve_in_project, write_project_file};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Formatter {
+    Prettier,
+    Rustfmt,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormatResult {
+    pub formatter: Formatter,
+    pub formatted: String, // The original, untouched, when `error` is set
+    pub changed: bool,
+    pub error: Option<String>, // The formatter's complaint when it couldn't parse the input
+}
+
+impl Formatter {
+    /// The formatter for a language id, with the file extension prettier
+    /// picks its parser from
+    pub fn for_language(language: &str) -> Option<(Self, &'static str)> {
+        let prettier = |extension| Some((Formatter::Prettier, extension));
+        match language {
+            "typescript" => prettier("ts"),
+            "typescriptreact" => prettier("tsx"),
+            "javascript" => prettier("js"),
+            "javascriptreact" => prettier("jsx"),
+            "css" => prettier("css"),
+            "scss" => prettier("scss"),
+            "json" => prettier("json"),
+            "markdown" => prettier("md"),
+            "html" => prettier("html"),
+            "vue" => prettier("vue"),
+            "yaml" => prettier("yaml"),
+            "rust" => Some((Formatter::Rustfmt, "rs")),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Formatter::Prettier => "prettier",
+            Formatter::Rustfmt => "rustfmt",
+        }
+    }
+
+    /// Prettier from the project's node_modules when installed there, so its
+    /// pinned version is used; otherwise whatever is on PATH
+    fn binary(&self, project_path: &str) -> PathBuf {
+        if *self == Formatter::Prettier {
+            let name = if cfg!(windows) {
+                "prettier.cmd"
+            } else {
+                "prettier"
+            };
+            let local = Path::new(project_path)
+                .join("node_modules")
+                .join(".bin")
+                .join(name);
+            if local.is_file() {
+                return local;
+            }
+        }
+        PathBuf::from(self.name())
+    }
+
+    fn install_hint(&self) -> &'static str {
+        match self {
+            Formatter::Prettier => "add it with 'npm install -D prettier'",
+            Formatter::Rustfmt => "install it with 'rustup component add rustfmt'",
+        }
+    }
+}
+
+/// Format `code` as if it were the file at `file_path`
+///
+/// Prettier gets the path via --stdin-filepath, which is how it finds the
+/// project's .prettierrc and ignore file; rustfmt runs in the project root
+/// so rustfmt.toml applies. Input the formatter can't parse comes back
+/// unchanged with `error` set rather than as an Err.
+pub fn format_source(
+    project_path: &str,
+    file_path: &Path,
+    code: &str,
+    formatter: Formatter,
+) -> Result<FormatResult, CodifyError> {
+    let mut command = Command::new(formatter.binary(project_path));
+    match formatter {
+        Formatter::Prettier => {
+            command.arg("--stdin-filepath").arg(file_path);
+        }
+        Formatter::Rustfmt => {
+            command.args(["--emit", "stdout", "--edition", "2021"]);
+        }
+    }
+    command
+        .current_dir(project_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(path) = super::resolved_path() {
+        command.env("PATH", path);
+    }
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(CodifyError::NotFound(format!(
+                "{} not found; {}",
+                formatter.name(),
+                formatter.install_hint()
+            )));
+        }
+        Err(e) => {
+            return Err(CodifyError::Io(format!(
+                "Failed to run {}: {}",
+                formatter.name(),
+                e
+            )))
+        }
+    };
+
+    // Written from a thread so a formatter that starts printing before it has
+    // read everything can't deadlock on a full pipe
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = code.to_string();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| CodifyError::Io(format!("Failed to run {}: {}", formatter.name(), e)))?;
+    let _ = writer.join();
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Ok(FormatResult {
+            formatter,
+            formatted: code.to_string(),
+            changed: false,
+            error: Some(if stderr.is_empty() {
+                format!("{} exited with {}", formatter.name(), output.status)
+            } else {
+                stderr
+            }),
+        });
+    }
+
+    let formatted = String::from_utf8_lossy(&output.stdout).to_string();
+    Ok(FormatResult {
+        formatter,
+        changed: formatted != code,
+        formatted,
+        error: None,
+    })
+}
+
+/// Format a snippet of `language`, using the project's formatter config
+pub fn format_code_in(
+    project_path: &str,
+    code: &str,
+    language: &str,
+) -> Result<FormatResult, CodifyError> {
+    let (formatter, extension) = Formatter::for_language(language).ok_or_else(|| {
+        CodifyError::InvalidInput(format!("Formatting {} is not supported", language))
+    })?;
+    // Never written; gives prettier a parser and a place to look for config
+    let file_path = Path::new(project_path).join(format!("codify-snippet.{}", extension));
+
+    format_source(project_path, &file_path, code, formatter)
+}
+
+/// Format a project file in place; it is only rewritten when the formatter
+/// succeeded and changed something
+pub fn format_project_file(project_path: &str, path: &str) -> Result<FormatResult, CodifyError> {
+    let full_path = resolve_in_project(project_path, path)?;
+    let language = detect_file_language(&full_path);
+    let (formatter, _) = Formatter::for_language(&language).ok_or_else(|| {
+        CodifyError::InvalidInput(format!(
+            "Formatting {} files is not supported: {}",
+            language, path
+        ))
+    })?;
+
+    let code = std::fs::read_to_string(&full_path)
+        .map_err(|e| CodifyError::Io(format!("Failed to read {}: {}", path, e)))?;
+    let result = format_source(project_path, &full_path, &code, formatter)?;
+
+    if result.changed {
+        write_project_file(project_path, path, &result.formatted, false)?;
+    }
+    Ok(result)
+}
@@ -0,0 +1,320 @@
+use super::{parse_unified_diff, run_process, FileDiff, OutputFormat, TerminalCommand};
+use crate::error::CodifyError;
+use crate::storage::resolve_in_project;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+/// What git blame reports as the author of lines changed since HEAD
+pub const NOT_COMMITTED_AUTHOR: &str = "Not Committed Yet";
+
+const UNCOMMITTED_HASH: &str = "0000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GitStatus {
+    pub branch: Option<String>, // None when HEAD is detached
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    pub staged: Vec<String>,
+    pub modified: Vec<String>, // Changed in the working tree but not staged
+    pub untracked: Vec<String>,
+    pub conflicted: Vec<String>,
+}
+
+impl GitStatus {
+    pub fn is_clean(&self) -> bool {
+        self.staged.is_empty()
+            && self.modified.is_empty()
+            && self.untracked.is_empty()
+            && self.conflicted.is_empty()
+    }
+}
+
+/// Parse `git status --porcelain=v2 --branch` output
+pub fn parse_porcelain_v2(output: &str) -> GitStatus {
+    let mut status = GitStatus::default();
+
+    for line in output.lines() {
+        if let Some(header) = line.strip_prefix("# ") {
+            let mut parts = header.splitn(2, ' ');
+            let (key, value) = (
+                parts.next().unwrap_or_default(),
+                parts.next().unwrap_or_default(),
+            );
+            match key {
+                "branch.head" if value != "(detached)" => status.branch = Some(value.to_string()),
+                "branch.upstream" => status.upstream = Some(value.to_string()),
+                "branch.ab" => {
+                    for count in value.split_whitespace() {
+                        if let Some(ahead) = count.strip_prefix('+') {
+                            status.ahead = ahead.parse().unwrap_or_default();
+                        } else if let Some(behind) = count.strip_prefix('-') {
+                            status.behind = behind.parse().unwrap_or_default();
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        // Paths are the last field and may contain spaces, so split a fixed number of times
+        match line.chars().next() {
+            Some('1') => {
+                if let [_, xy, .., path] = line.splitn(9, ' ').collect::<Vec<_>>()[..] {
+                    record_change(&mut status, xy, path);
+                }
+            }
+            Some('2') => {
+                // Renames carry "path<TAB>original path"
+                if let [_, xy, .., paths] = line.splitn(10, ' ').collect::<Vec<_>>()[..] {
+                    let path = paths.split('\t').next().unwrap_or(paths);
+                    record_change(&mut status, xy, path);
+                }
+            }
+            Some('u') => {
+                if let Some(path) = line.splitn(11, ' ').nth(10) {
+                    status.conflicted.push(path.to_string());
+                }
+            }
+            Some('?') => {
+                if let Some(path) = line.strip_prefix("? ") {
+                    status.untracked.push(path.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    status
+}
+
+/// XY is the index status followed by the working tree status; '.' means unchanged
+fn record_change(status: &mut GitStatus, xy: &str, path: &str) {
+    let mut codes = xy.chars();
+    if codes.next().map(|x| x != '.').unwrap_or(false) {
+        status.staged.push(path.to_string());
+    }
+    if codes.next().map(|y| y != '.').unwrap_or(false) {
+        status.modified.push(path.to_string());
+    }
+}
+
+/// Run git with `args` in `working_dir`, returning stdout
+async fn run_git(working_dir: &str, args: &[&str]) -> Result<String, CodifyError> {
+    let command = TerminalCommand {
+        command: "git".to_string(),
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+        working_dir: working_dir.to_string(),
+        timeout_ms: Some(10_000),
+        env: Default::default(),
+        use_shell: false,
+        // Callers parse the output, so it must be complete
+        max_output_bytes: Some(usize::MAX),
+        output_format: OutputFormat::Raw,
+        load_dotenv: false,
+    };
+
+    let response = run_process(&command).await?;
+    if !response.success {
+        let error = response.error.unwrap_or_default();
+        if error.contains("not a git repository") {
+            return Err(CodifyError::InvalidInput(format!(
+                "Not a git repository: {}",
+                working_dir
+            )));
+        }
+        return Err(CodifyError::Internal(format!(
+            "git {} failed: {}",
+            args[0],
+            error.trim()
+        )));
+    }
+
+    Ok(response.output)
+}
+
+/// Run git status in `working_dir` and parse it
+pub async fn git_status(working_dir: &str) -> Result<GitStatus, CodifyError> {
+    let output = run_git(working_dir, &["status", "--porcelain=v2", "--branch"]).await?;
+    Ok(parse_porcelain_v2(&output))
+}
+
+/// Diff the working tree (or the index when `staged`) against HEAD, for one
+/// file or the whole repository
+pub async fn collect_git_diff(
+    working_dir: &str,
+    file_path: Option<&str>,
+    staged: bool,
+) -> Result<Vec<FileDiff>, CodifyError> {
+    let mut args = vec!["diff", "--no-color", "--no-ext-diff"];
+    if staged {
+        args.push("--cached");
+    }
+    if let Some(file_path) = file_path {
+        args.extend(["--", file_path]);
+    }
+
+    let output = run_git(working_dir, &args).await?;
+    Ok(parse_unified_diff(&output))
+}
+
+/// Who last changed one line of a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlameLine {
+    pub line: u32, // 1-based, in the working tree file
+    pub commit: String,
+    pub author: String,
+    pub author_email: String,
+    pub timestamp: Option<String>, // Author time, RFC 3339
+    pub summary: String,           // The commit's subject line
+    pub committed: bool,           // False for lines changed since HEAD
+}
+
+#[derive(Debug, Clone, Default)]
+struct BlameCommit {
+    author: String,
+    author_email: String,
+    timestamp: Option<String>,
+    summary: String,
+}
+
+fn uncommitted_line(line: u32) -> BlameLine {
+    BlameLine {
+        line,
+        commit: UNCOMMITTED_HASH.to_string(),
+        author: NOT_COMMITTED_AUTHOR.to_string(),
+        author_email: String::new(),
+        timestamp: None,
+        summary: String::new(),
+        committed: false,
+    }
+}
+
+/// Parse `git blame --porcelain` output; a commit's headers are only given
+/// on the first line attributed to it
+pub fn parse_blame_porcelain(output: &str) -> Vec<BlameLine> {
+    let mut commits: HashMap<String, BlameCommit> = HashMap::new();
+    let mut lines = vec![];
+    let mut current: Option<(String, u32)> = None;
+
+    for line in output.lines() {
+        // The line's content ends its entry
+        if line.starts_with('\t') {
+            let Some((hash, number)) = current.take() else {
+                continue;
+            };
+            if hash == UNCOMMITTED_HASH {
+                lines.push(uncommitted_line(number));
+                continue;
+            }
+            let commit = commits.get(&hash).cloned().unwrap_or_default();
+            lines.push(BlameLine {
+                line: number,
+                commit: hash,
+                author: commit.author,
+                author_email: commit.author_email,
+                timestamp: commit.timestamp,
+                summary: commit.summary,
+                committed: true,
+            });
+            continue;
+        }
+
+        let Some(hash) = current.as_ref().map(|(hash, _)| hash.clone()) else {
+            // `<hash> <original line> <final line> [<group size>]`
+            let mut fields = line.split(' ');
+            let hash = fields.next().unwrap_or_default();
+            let number = fields.nth(1).and_then(|n| n.parse::<u32>().ok());
+            if let Some(number) = number.filter(|_| hash.len() == 40) {
+                commits.entry(hash.to_string()).or_default();
+                current = Some((hash.to_string(), number));
+            }
+            continue;
+        };
+
+        let Some((key, value)) = line.split_once(' ') else {
+            continue;
+        };
+        let commit = commits.entry(hash).or_default();
+        match key {
+            "author" => commit.author = value.to_string(),
+            "author-mail" => {
+                commit.author_email = value.trim_matches(|c| c == '<' || c == '>').to_string()
+            }
+            "author-time" => {
+                commit.timestamp = value
+                    .parse::<i64>()
+                    .ok()
+                    .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+                    .map(|time| time.to_rfc3339())
+            }
+            "summary" => commit.summary = value.to_string(),
+            _ => {}
+        }
+    }
+
+    lines
+}
+
+/// Last blame per project file, reused while neither the file nor HEAD moves
+#[derive(Default)]
+pub struct BlameState {
+    blames: Mutex<HashMap<(String, String), (u64, Vec<BlameLine>)>>,
+}
+
+/// Blame `file_path` (project-relative) line by line
+///
+/// Files git doesn't track yet come back with every line uncommitted.
+pub async fn git_blame_file(
+    state: &BlameState,
+    project_path: &str,
+    file_path: &str,
+) -> Result<Vec<BlameLine>, CodifyError> {
+    let full_path = resolve_in_project(project_path, file_path)?;
+    let metadata = std::fs::metadata(&full_path)
+        .map_err(|_| CodifyError::NotFound(format!("File not found: {}", file_path)))?;
+    if metadata.is_dir() {
+        return Err(CodifyError::InvalidInput(format!(
+            "Path is a directory: {}",
+            file_path
+        )));
+    }
+
+    // A new commit changes the blame without touching the file
+    let head = run_git(project_path, &["rev-parse", "HEAD"])
+        .await
+        .unwrap_or_default();
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    (metadata.len(), metadata.modified().ok(), head.trim()).hash(&mut hasher);
+    let fingerprint = hasher.finish();
+
+    let key = (crate::ai::project_key(project_path), file_path.to_string());
+    if let Some((cached, lines)) = state.blames.lock().unwrap().get(&key) {
+        if *cached == fingerprint {
+            return Ok(lines.clone());
+        }
+    }
+
+    let args = ["blame", "--porcelain", "--", file_path];
+    let lines = match run_git(project_path, &args).await {
+        Ok(output) => parse_blame_porcelain(&output),
+        Err(e) if e.message().contains("no such path") || e.message().contains("no such ref") => {
+            let text = std::fs::read_to_string(&full_path)
+                .map_err(|e| CodifyError::Io(format!("Failed to read {}: {}", file_path, e)))?;
+            (1..=text.lines().count() as u32)
+                .map(uncommitted_line)
+                .collect()
+        }
+        Err(e) => return Err(e),
+    };
+
+    state
+        .blames
+        .lock()
+        .unwrap()
+        .insert(key, (fingerprint, lines.clone()));
+    Ok(lines)
+}
@@ -1,10 +1,66 @@
 use serde::{Deserialize, Serialize};
 
+use crate::ai::{
+    fit_around_line, in_project, messages_tokens, parse_json_reply, AIState, GenerationParams,
+    ProviderKind, WarmupPhase,
+};
+use crate::error::CodifyError;
+
+mod ansi;
+mod commit;
+mod coverage;
+mod diagnostics;
+mod diff;
+mod dotenv;
+mod format;
+mod git;
+mod npm;
+mod output;
+mod patch;
+mod policy;
+mod project;
+mod review;
+mod suggestions;
+mod terminal;
+
+pub use ansi::*;
+pub use commit::*;
+pub use coverage::*;
+pub use diagnostics::*;
+pub use diff::*;
+pub use dotenv::*;
+pub use format::*;
+pub use git::*;
+pub use npm::*;
+pub use output::*;
+pub use patch::*;
+pub use policy::*;
+pub use project::*;
+pub use review::*;
+pub use suggestions::*;
+pub use terminal::*;
+
+/// A process to run; see `spawn_target` for how `command` and `args` are
+/// executed in each mode
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TerminalCommand {
     pub command: String,
     pub args: Vec<String>,
     pub working_dir: String,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>, // No limit when absent
+    #[serde(default)]
+    pub env: std::collections::HashMap<String, String>, // Merged over the inherited environment
+    #[serde(default)]
+    pub use_shell: bool, // Off by default: the binary runs directly and args are never interpreted
+    #[serde(default)]
+    pub max_output_bytes: Option<usize>, // DEFAULT_MAX_OUTPUT_BYTES when absent
+    #[serde(default)]
+    pub output_format: OutputFormat,
+    /// Read `.env` and `.env.local` from the working directory into the
+    /// environment; see `process_env` for precedence
+    #[serde(default)]
+    pub load_dotenv: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,6 +69,16 @@ pub struct TerminalResponse {
     pub output: String,
     pub error: Option<String>,
     pub suggestions: Vec<String>,
+    #[serde(default)]
+    pub git_status: Option<GitStatus>, // Set for `git status`
+    #[serde(default)]
+    pub omitted_bytes: usize, // Dropped from the middle of output and error
+    #[serde(default)]
+    pub log_path: Option<String>, // Complete output, when it outgrew the cap
+    #[serde(default)]
+    pub spans: Option<Vec<AnsiSpan>>, // Styled `output` for OutputFormat::Spans
+    #[serde(default)]
+    pub test_result: Option<TestResult>, // Parsed from a `test` run's output
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +86,71 @@ pub struct DesignPrompt {
     pub description: String,
     pub component_type: String,
     pub style_preferences: std::collections::HashMap<String, String>,
+    #[serde(default)]
+    pub target_framework: Option<TargetFramework>, // Detected from the project when absent
+    #[serde(default)]
+    pub styling_mode: Option<StylingMode>, // Detected from the project when absent
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum StylingMode {
+    #[default]
+    PlainCss,
+    TailwindUtility,
+    CssModules,
+    StyledComponents,
+}
+
+impl StylingMode {
+    fn prompt_guidance(&self, component_type: &str) -> String {
+        match self {
+            StylingMode::PlainCss => {
+                "Use plain CSS class names in the component and put all rules in styles.".to_string()
+            }
+            StylingMode::TailwindUtility => {
+                "Use Tailwind utility classes inline in the component. styles must be an empty string."
+                    .to_string()
+            }
+            StylingMode::CssModules => format!(
+                "Use CSS modules: import styles from './{}.module.css' and reference classes as \
+                 styles.name. styles is the content of that .module.css file.",
+                component_type
+            ),
+            StylingMode::StyledComponents => {
+                "Use styled-components defined in the component file. styles must be an empty string."
+                    .to_string()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum TargetFramework {
+    #[default]
+    React,
+    Vue,
+    Svelte,
+    SolidJS,
+}
+
+impl TargetFramework {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TargetFramework::React => "React (TypeScript, function components)",
+            TargetFramework::Vue => "Vue 3 (single-file components with <script setup lang=\"ts\">)",
+            TargetFramework::Svelte => "Svelte (TypeScript)",
+            TargetFramework::SolidJS => "SolidJS (TypeScript)",
+        }
+    }
+
+    /// Language id of the generated component, for formatting it
+    pub fn language(&self) -> &'static str {
+        match self {
+            TargetFramework::React | TargetFramework::SolidJS => "typescriptreact",
+            TargetFramework::Vue => "vue",
+            TargetFramework::Svelte => "svelte",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,191 +159,686 @@ pub struct GeneratedDesign {
     pub styles: String,
     pub props_interface: String,
     pub preview_url: Option<String>,
+    pub styles_file: Option<String>, // Suggested file name for styles, when they live in their own file
 }
 
 /// Execute terminal command with AI assistance
 #[tauri::command]
 pub async fn execute_terminal_command(
+    app: tauri::AppHandle,
     command: TerminalCommand,
-) -> Result<TerminalResponse, String> {
+) -> Result<TerminalResponse, CodifyError> {
     log::info!("Executing terminal command: {}", command.command);
-    
-    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-    
-    let response = match command.command.as_str() {
-        "npm" => handle_npm_command(&command).await,
-        "git" => handle_git_command(&command).await,
-        "test" => handle_test_command(&command).await,
-        _ => handle_generic_command(&command).await,
-    };
-    
+
+    crate::settings::read_settings(&app)
+        .command_policy
+        .check(&command)?;
+    let started = std::time::SystemTime::now();
+    let mut response = run_process(&command).await?;
+
+    // AI suggestions are layered on top of the real output
+    match command.command.as_str() {
+        "npm" | "pnpm" | "yarn" | "bun" => handle_npm_command(&command, &mut response).await,
+        "git" => handle_git_command(&command, &mut response).await,
+        "test" => handle_test_command(&command, &mut response, started).await,
+        _ => handle_generic_command(&command, &mut response).await,
+    }
+
     Ok(response)
 }
 
-async fn handle_npm_command(command: &TerminalCommand) -> TerminalResponse {
-    if command.args.contains(&"install".to_string()) {
-        TerminalResponse {
-            success: true,
-            output: "📦 Installing dependencies...\n✓ Dependencies installed successfully".to_string(),
-            error: None,
-            suggestions: vec![
-                "Run 'npm audit' to check for security vulnerabilities".to_string(),
-                "Consider using 'npm ci' for faster installs in CI".to_string(),
-            ],
-        }
-    } else if command.args.contains(&"run".to_string()) && command.args.contains(&"build".to_string()) {
-        TerminalResponse {
-            success: true,
-            output: "🔨 Building application...\n✓ Build completed in 12.3s".to_string(),
-            error: None,
-            suggestions: vec![
-                "Your bundle size increased by 15%. Consider code splitting.".to_string(),
-            ],
-        }
-    } else {
-        TerminalResponse {
-            success: true,
-            output: format!("npm {} completed", command.args.join(" ")),
-            error: None,
-            suggestions: vec!["Use 'npm help' to see available commands".to_string()],
-        }
+/// Execute a terminal command, streaming output through events
+///
+/// Returns the command id that tags every `terminal-output` and
+/// `terminal-exit` event for this process.
+#[tauri::command]
+pub async fn stream_terminal_command(
+    app: tauri::AppHandle,
+    command: TerminalCommand,
+) -> Result<String, CodifyError> {
+    log::info!("Streaming terminal command: {}", command.command);
+
+    crate::settings::read_settings(&app)
+        .command_policy
+        .check(&command)?;
+    spawn_streaming(&app, &command)
+}
+
+/// Kill a streamed terminal command by id
+#[tauri::command]
+pub async fn kill_terminal_command(
+    registry: tauri::State<'_, ProcessRegistry>,
+    command_id: String,
+) -> Result<(), CodifyError> {
+    log::info!("Killing terminal command: {}", command_id);
+
+    registry.kill(&command_id)
+}
+
+/// Scripts from the project's package.json, with the package manager that
+/// will run them
+#[tauri::command]
+pub async fn get_npm_scripts(project_path: String) -> Result<NpmScripts, CodifyError> {
+    log::info!("Reading npm scripts for: {}", project_path);
+
+    read_npm_scripts(&project_path)
+}
+
+/// Which package manager handles a project: the one its lockfile belongs
+/// to, or npm when there is no lockfile
+#[tauri::command]
+pub async fn detect_package_manager(project_path: String) -> Result<PackageManager, CodifyError> {
+    let dir = std::path::Path::new(&project_path);
+    if !dir.is_dir() {
+        return Err(CodifyError::NotFound(format!(
+            "Project path does not exist: {}",
+            project_path
+        )));
     }
+
+    Ok(PackageManager::detect(dir))
 }
 
-async fn handle_git_command(command: &TerminalCommand) -> TerminalResponse {
-    if command.args.contains(&"status".to_string()) {
-        TerminalResponse {
-            success: true,
-            output: "On branch main\nYour branch is up to date with 'origin/main'.\n\nChanges not staged for commit:\n  modified:   src/components/Button.tsx".to_string(),
-            error: None,
-            suggestions: vec![
-                "Use 'git add .' to stage all changes".to_string(),
-                "Use 'git commit -m \"message\"' to commit changes".to_string(),
-            ],
-        }
-    } else if command.args.contains(&"commit".to_string()) {
-        TerminalResponse {
-            success: true,
-            output: "📝 Committing changes...\n✓ Committed: feat: add AI-powered terminal interface".to_string(),
-            error: None,
-            suggestions: vec![
-                "Consider adding a pre-commit hook for linting".to_string(),
-            ],
-        }
+async fn handle_npm_command(command: &TerminalCommand, response: &mut TerminalResponse) {
+    // The command ran through the detected manager, so advise in its terms
+    let pm = PackageManager::detect(std::path::Path::new(&command.working_dir));
+    let tool = pm.binary();
+
+    if !response.success {
+        let fallback = format!(
+            "Delete node_modules and run '{} install' if dependencies are out of sync",
+            tool
+        );
+        let suggestions = project_suggestions(&command.working_dir, response, &[&fallback]);
+        response.suggestions.extend(suggestions);
+    } else if command.args.contains(&"install".to_string()) {
+        response.suggestions.push(format!("Run '{} audit' to check for security vulnerabilities", tool));
+        response.suggestions.push(format!(
+            "Consider using '{}' for faster installs in CI",
+            pm.frozen_install()
+        ));
     } else {
-        TerminalResponse {
-            success: true,
-            output: format!("git {} completed", command.args.join(" ")),
-            error: None,
-            suggestions: vec!["Use 'git help' to see available commands".to_string()],
+        response.suggestions.push(format!("Use '{} help' to see available commands", tool));
+    }
+}
+
+async fn handle_git_command(command: &TerminalCommand, response: &mut TerminalResponse) {
+    let is_status = command.args.contains(&"status".to_string());
+
+    if is_status {
+        match git_status(&command.working_dir).await {
+            Ok(status) => {
+                response.suggestions.extend(git_status_suggestions(&status));
+                response.git_status = Some(status);
+            }
+            Err(e) => {
+                if e.message().starts_with("Not a git repository") {
+                    response.suggestions.push("Use 'git init' to create a repository here".to_string());
+                }
+                log::warn!("Could not read git status: {}", e);
+            }
         }
+    } else if command.args.first().is_some_and(|arg| arg == "add") && response.success {
+        response.suggestions.push("Generate a commit message for the staged changes with the AI assistant".to_string());
+    } else if command.args.contains(&"commit".to_string()) && response.success {
+        response.suggestions.push("Consider adding a pre-commit hook for linting".to_string());
+    } else if !response.success {
+        response.suggestions.push("Use 'git help' to see available commands".to_string());
     }
 }
 
-async fn handle_test_command(_command: &TerminalCommand) -> TerminalResponse {
-    TerminalResponse {
-        success: true,
-        output: "🧪 Running tests...\n✓ 24 tests passed\n⚠ 2 tests have low coverage".to_string(),
-        error: None,
-        suggestions: vec![
-            "Add tests for components/Button.tsx".to_string(),
-            "Consider increasing test coverage threshold".to_string(),
-        ],
+fn git_status_suggestions(status: &GitStatus) -> Vec<String> {
+    let mut suggestions = vec![];
+    let plural = |count: usize| if count == 1 { "" } else { "s" };
+
+    if !status.conflicted.is_empty() {
+        suggestions.push(format!(
+            "Resolve {} conflicted file{} before committing",
+            status.conflicted.len(),
+            plural(status.conflicted.len())
+        ));
+    }
+    if !status.untracked.is_empty() {
+        suggestions.push(format!(
+            "You have {} untracked file{}; add them or list them in .gitignore",
+            status.untracked.len(),
+            plural(status.untracked.len())
+        ));
+    }
+    if !status.modified.is_empty() {
+        suggestions.push(format!(
+            "Use 'git add' to stage {} modified file{}",
+            status.modified.len(),
+            plural(status.modified.len())
+        ));
     }
+    if !status.staged.is_empty() {
+        suggestions.push("Use 'git commit -m \"message\"' to commit staged changes".to_string());
+    }
+    if status.behind > 0 {
+        suggestions.push(format!(
+            "Branch is {} commit{} behind; run 'git pull'",
+            status.behind,
+            plural(status.behind as usize)
+        ));
+    } else if status.ahead > 0 {
+        suggestions.push(format!(
+            "Branch is {} commit{} ahead; run 'git push'",
+            status.ahead,
+            plural(status.ahead as usize)
+        ));
+    }
+    if status.is_clean() && status.ahead == 0 && status.behind == 0 {
+        suggestions.push("Working tree is clean".to_string());
+    }
+
+    suggestions
 }
 
-async fn handle_generic_command(command: &TerminalCommand) -> TerminalResponse {
-    TerminalResponse {
-        success: true,
-        output: format!("🤖 AI processed command: {}\nI understand you want help with: {}", 
-                       command.command, 
-                       command.args.join(" ")),
-        error: None,
-        suggestions: vec![
-            "Try: 'npm run dev' to start the development server".to_string(),
-            "Use 'help' to see available commands".to_string(),
-        ],
+/// Structured git status for the source control panel
+#[tauri::command]
+pub async fn get_git_status(project_path: String) -> Result<GitStatus, CodifyError> {
+    log::info!("Getting git status for: {}", project_path);
+
+    git_status(&project_path).await
+}
+
+/// Unified diff of the project (or one file) parsed into hunks
+#[tauri::command]
+pub async fn git_diff(
+    project_path: String,
+    file_path: Option<String>,
+    staged: Option<bool>,
+) -> Result<Vec<FileDiff>, CodifyError> {
+    log::info!("Getting git diff for: {}", project_path);
+
+    let staged = staged.unwrap_or(false);
+    collect_git_diff(&project_path, file_path.as_deref(), staged).await
+}
+
+/// Author, commit and time for each line of a project file, from
+/// `git blame --porcelain`; lines changed since HEAD are "Not Committed Yet"
+///
+/// Cached per file until the file changes or HEAD moves.
+#[tauri::command]
+pub async fn git_blame(
+    state: tauri::State<'_, BlameState>,
+    project_path: String,
+    file_path: String,
+) -> Result<Vec<BlameLine>, CodifyError> {
+    log::info!("Getting git blame for: {}", file_path);
+
+    git_blame_file(&state, &project_path, &file_path).await
+}
+
+/// Type-check (tsc --noEmit) or lint (eslint) the project and return its
+/// diagnostics
+///
+/// Emits `diagnostics-progress` events while the tool runs. Results are
+/// cached per backend until a project file changes.
+#[tauri::command]
+pub async fn get_diagnostics(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, DiagnosticsState>,
+    project_path: String,
+    backend: Option<DiagnosticsBackend>,
+) -> Result<DiagnosticsReport, CodifyError> {
+    let backend = backend.unwrap_or_default();
+    log::info!("Running {:?} diagnostics for: {}", backend, project_path);
+
+    run_diagnostics(&app, &state, &project_path, backend).await
+}
+
+/// Format a snippet with the project's formatter (prettier or rustfmt) and config
+///
+/// Input the formatter can't parse comes back unchanged with `error` set.
+#[tauri::command]
+pub async fn format_code(
+    project_path: String,
+    code: String,
+    language: String,
+) -> Result<FormatResult, CodifyError> {
+    log::info!("Formatting {} code in: {}", language, project_path);
+
+    tokio::task::spawn_blocking(move || format_code_in(&project_path, &code, &language))
+        .await
+        .map_err(|e| CodifyError::Internal(format!("Format task failed: {}", e)))?
+}
+
+/// Format a project file in place with the project's formatter and config
+#[tauri::command]
+pub async fn format_file(project_path: String, path: String) -> Result<FormatResult, CodifyError> {
+    log::info!("Formatting file: {}", path);
+
+    tokio::task::spawn_blocking(move || format_project_file(&project_path, &path))
+        .await
+        .map_err(|e| CodifyError::Internal(format!("Format task failed: {}", e)))?
+}
+
+/// Detect the project's type, language and frameworks from its manifests
+#[tauri::command]
+pub async fn detect_project_type(project_path: String) -> Result<ProjectDetection, CodifyError> {
+    log::info!("Detecting project type: {}", project_path);
+
+    detect_project(&project_path)
+}
+
+/// Find the project root above `start_path`, by `.git` and manifest files or
+/// from settings.project_roots, so commands get the right project_path
+#[tauri::command]
+pub async fn find_project_root(
+    app: tauri::AppHandle,
+    start_path: String,
+) -> Result<ProjectRoot, CodifyError> {
+    log::info!("Finding project root for: {}", start_path);
+
+    let overrides = crate::settings::read_settings(&app).project_roots;
+    find_root(&start_path, &overrides)
+}
+
+/// Apply a unified diff and/or line edits; `dry_run` previews without writing
+#[tauri::command]
+pub async fn apply_patch(
+    project_path: String,
+    diff: Option<String>,
+    edits: Option<Vec<TextEdit>>,
+    dry_run: Option<bool>,
+) -> Result<Vec<PatchFileResult>, CodifyError> {
+    log::info!("Applying patch in: {}", project_path);
+
+    tokio::task::spawn_blocking(move || {
+        apply_changes(
+            &project_path,
+            diff.as_deref(),
+            &edits.unwrap_or_default(),
+            dry_run.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("Patch task failed: {}", e)))?
+}
+
+/// `started` is when the run began, so only coverage it wrote is reported
+async fn handle_test_command(
+    command: &TerminalCommand,
+    response: &mut TerminalResponse,
+    started: std::time::SystemTime,
+) {
+    // Jest prints its summary to stderr
+    let text = format!(
+        "{}\n{}",
+        response.output,
+        response.error.as_deref().unwrap_or_default()
+    );
+    response.test_result = parse_test_run(&text, std::path::Path::new(&command.working_dir), started);
+
+    let specific = response
+        .test_result
+        .as_ref()
+        .map(test_suggestions)
+        .unwrap_or_default();
+    if !response.success {
+        let fallback = "Re-run a single failing test file to narrow down the problem";
+        let suggestions = project_suggestions(&command.working_dir, response, &[fallback]);
+        response.suggestions.extend(specific);
+        response.suggestions.extend(suggestions);
+    } else if !specific.is_empty() {
+        response.suggestions.extend(specific);
+    } else if response
+        .test_result
+        .as_ref()
+        .is_some_and(|result| result.total_coverage.is_some())
+    {
+        response.suggestions.push(format!(
+            "Coverage is at or above {}% in every file",
+            LOW_COVERAGE_PCT
+        ));
+    } else {
+        response
+            .suggestions
+            .push("Run the tests with --coverage to find under-tested files".to_string());
     }
 }
 
-/// Generate design from AI prompt
+async fn handle_generic_command(command: &TerminalCommand, response: &mut TerminalResponse) {
+    let fallback = if response.success {
+        "Open a project folder to get suggestions for its scripts"
+    } else {
+        "Use 'help' to see available commands"
+    };
+    let suggestions = project_suggestions(&command.working_dir, response, &[fallback]);
+    response.suggestions.extend(suggestions);
+}
+
+/// Generate design from AI prompt; with `project_path` the output is run
+/// through the project's formatter so it matches house style
 #[tauri::command]
-pub async fn ai_generate_design(prompt: DesignPrompt) -> Result<GeneratedDesign, String> {
+pub async fn ai_generate_design(
+    state: tauri::State<'_, AIState>,
+    prompt: DesignPrompt,
+    params: Option<GenerationParams>,
+    project_path: Option<String>,
+) -> Result<GeneratedDesign, CodifyError> {
     log::info!("Generating design from prompt: {}", prompt.description);
-    
-    tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
-    
-    let design = GeneratedDesign {
-        component_code: format!(r#"interface {}Props {{
-  variant?: 'primary' | 'secondary' | 'outline';
-  size?: 'sm' | 'md' | 'lg';
-  children: React.ReactNode;
-  onClick?: () => void;
-}}
-
-const {}: React.FC<{}Props> = ({{ 
-  variant = 'primary', 
-  size = 'md', 
-  children, 
-  onClick 
-}}) => {{
-  return (
-    <button
-      className={{`btn btn-${{variant}} btn-${{size}}`}}
-      onClick={{onClick}}
-    >
-      {{children}}
-    </button>
-  );
-}};"#, prompt.component_type, prompt.component_type, prompt.component_type),
-        styles: r#".btn {
-  padding: 0.5rem 1rem;
-  border-radius: 0.375rem;
-  font-weight: 500;
-  transition: all 0.2s;
-  border: none;
-  cursor: pointer;
-}
-
-.btn-primary {
-  background-color: #3b82f6;
-  color: white;
-}
-
-.btn-primary:hover {
-  background-color: #2563eb;
-}
-
-.btn-secondary {
-  background-color: #6b7280;
-  color: white;
-}
-
-.btn-outline {
-  background-color: transparent;
-  border: 1px solid #d1d5db;
-  color: #374151;
-}"#.to_string(),
-        props_interface: format!("interface {}Props {{\n  variant?: 'primary' | 'secondary' | 'outline';\n  size?: 'sm' | 'md' | 'lg';\n  children: React.ReactNode;\n  onClick?: () => void;\n}}", prompt.component_type),
-        preview_url: None,
+
+    let design = generate_design(&state, prompt, params, project_path.clone());
+    match project_path {
+        Some(project_path) => in_project(&project_path, design).await,
+        None => design.await,
+    }
+}
+
+async fn generate_design(
+    state: &AIState,
+    prompt: DesignPrompt,
+    params: Option<GenerationParams>,
+    project_path: Option<String>,
+) -> Result<GeneratedDesign, CodifyError> {
+    let params = state.resolve_params(params, GenerationParams::design_defaults())?;
+
+    // Fill in what the prompt leaves open from what the project already uses
+    let detection = project_path
+        .as_deref()
+        .and_then(|path| detect_project(path).ok());
+    let target_framework = prompt
+        .target_framework
+        .or_else(|| detection.as_ref()?.target_framework())
+        .unwrap_or_default();
+    let styling_mode = prompt
+        .styling_mode
+        .or_else(|| detection.as_ref()?.styling_mode())
+        .unwrap_or_default();
+
+    let mut preferences: Vec<String> = prompt
+        .style_preferences
+        .iter()
+        .map(|(key, value)| format!("- {}: {}", key, value))
+        .collect();
+    preferences.sort();
+
+    let guidance = styling_mode.prompt_guidance(&prompt.component_type);
+    let preferences = if preferences.is_empty() {
+        "- none".to_string()
+    } else {
+        preferences.join("\n")
     };
-    
-    Ok(design)
+    let messages = state.prompt(
+        "generate_design",
+        &[
+            ("framework", target_framework.display_name()),
+            ("guidance", guidance.as_str()),
+            ("component_name", prompt.component_type.as_str()),
+            ("description", prompt.description.as_str()),
+            ("preferences", preferences.as_str()),
+        ],
+    );
+
+    let reply = state.chat_with("ai_generate_design", &messages, &params).await?;
+    let sections: serde_json::Value = parse_json_reply(&reply)?;
+
+    let section = |name: &str| {
+        sections[name].as_str().map(|s| s.to_string()).ok_or_else(|| {
+            CodifyError::Provider(format!("Model output is missing the '{}' section", name))
+        })
+    };
+
+    let mut component_code = section("component_code")?;
+    let mut styles = section("styles")?;
+    let props_interface = section("props_interface")?;
+
+    let styles_file = match styling_mode {
+        StylingMode::PlainCss => Some(format!("{}.css", prompt.component_type)),
+        StylingMode::CssModules => {
+            let file_name = format!("{}.module.css", prompt.component_type);
+            if !component_code.contains(&file_name) {
+                component_code = format!(
+                    "import styles from './{}';\n\n{}",
+                    file_name, component_code
+                );
+            }
+            Some(file_name)
+        }
+        // Styling lives in the component itself
+        StylingMode::TailwindUtility | StylingMode::StyledComponents => {
+            styles.clear();
+            None
+        }
+    };
+
+    if let Some(project_path) = project_path {
+        let language = target_framework.language();
+        (component_code, styles) = tokio::task::spawn_blocking(move || {
+            // Formatting is best effort; unformatted output beats none
+            let format = |code: String, language: &str| {
+                if code.is_empty() {
+                    return code;
+                }
+                match format_code_in(&project_path, &code, language) {
+                    Ok(result) if result.error.is_none() => result.formatted,
+                    Ok(result) => {
+                        log::warn!("Generated {} did not format: {:?}", language, result.error);
+                        code
+                    }
+                    Err(e) => {
+                        log::warn!("Skipping formatting of generated {}: {}", language, e);
+                        code
+                    }
+                }
+            };
+            (format(component_code, language), format(styles, "css"))
+        })
+        .await
+        .map_err(|e| CodifyError::Internal(format!("Format task failed: {}", e)))?;
+    }
+
+    Ok(GeneratedDesign {
+        component_code,
+        styles,
+        props_interface,
+        preview_url: None,
+        styles_file,
+    })
+}
+
+/// Write a commit message for the staged changes
+#[tauri::command]
+pub async fn ai_generate_commit_message(
+    state: tauri::State<'_, AIState>,
+    project_path: String,
+    style: Option<CommitStyle>,
+    params: Option<GenerationParams>,
+) -> Result<CommitMessage, CodifyError> {
+    in_project(
+        &project_path,
+        generate_commit_message(&state, &project_path, style, params),
+    )
+    .await
+}
+
+async fn generate_commit_message(
+    state: &AIState,
+    project_path: &str,
+    style: Option<CommitStyle>,
+    params: Option<GenerationParams>,
+) -> Result<CommitMessage, CodifyError> {
+    log::info!("Generating commit message for: {}", project_path);
+
+    let params = state.resolve_params(params, GenerationParams::commit_message_defaults())?;
+    let style = style.unwrap_or_default();
+
+    let files = collect_git_diff(project_path, None, true).await?;
+    if files.is_empty() {
+        return Err(CodifyError::InvalidInput(
+            "Nothing is staged; stage changes with 'git add' first".to_string(),
+        ));
+    }
+
+    let guidance = style.prompt_guidance();
+    let summary = staged_file_summary(&files);
+    let values = [("guidance", guidance.as_str()), ("files", summary.as_str())];
+
+    let used = messages_tokens(&state.prompt("commit_message", &values));
+    let budget = state.prompt_budget(&params, used).await;
+    let (diff, clipped) = fit_around_line(&render_diff(&files), 0, budget);
+
+    let truncated = if clipped { " (truncated)" } else { "" };
+    let values = [
+        &values[..],
+        &[("diff", diff.as_str()), ("truncated", truncated)],
+    ]
+    .concat();
+    let messages = state.prompt("commit_message", &values);
+
+    let reply = state
+        .chat_with("ai_generate_commit_message", &messages, &params)
+        .await?;
+    let fields: CommitFields = parse_json_reply(&reply)?;
+
+    style.build(fields)
+}
+
+/// Review a diff from git_diff, as a human reviewer would
+///
+/// Large diffs are reviewed a chunk at a time and the comments combined.
+/// `focus` narrows what the review looks for; it covers everything when
+/// empty. Comments come back sorted by severity, then file and line.
+#[tauri::command]
+pub async fn ai_review_diff(
+    state: tauri::State<'_, AIState>,
+    project_path: String,
+    files: Vec<FileDiff>,
+    focus: Option<Vec<ReviewFocus>>,
+    params: Option<GenerationParams>,
+) -> Result<Vec<ReviewComment>, CodifyError> {
+    in_project(
+        &project_path,
+        review_diff(&state, &files, &focus.unwrap_or_default(), params),
+    )
+    .await
+}
+
+async fn review_diff(
+    state: &AIState,
+    files: &[FileDiff],
+    focus: &[ReviewFocus],
+    params: Option<GenerationParams>,
+) -> Result<Vec<ReviewComment>, CodifyError> {
+    log::info!("Reviewing a diff of {} files", files.len());
+
+    let params = state.resolve_params(params, GenerationParams::review_defaults())?;
+    if files.iter().all(|file| file.hunks.is_empty()) {
+        return Err(CodifyError::InvalidInput(
+            "Diff has no changes to review".to_string(),
+        ));
+    }
+
+    let guidance = review_guidance(focus);
+    let summary = staged_file_summary(files);
+    let values = [("focus", guidance.as_str()), ("files", summary.as_str())];
+
+    let used = messages_tokens(&state.prompt("review_diff", &values));
+    let budget = state.prompt_budget(&params, used).await;
+    let chunks = review_chunks(files, budget);
+
+    let mut comments = vec![];
+    for (i, chunk) in chunks.iter().enumerate() {
+        log::debug!("Reviewing diff chunk {} of {}", i + 1, chunks.len());
+        let values = [&values[..], &[("diff", chunk.as_str())]].concat();
+        let messages = state.prompt("review_diff", &values);
+
+        let reply = state
+            .chat_with("ai_review_diff", &messages, &params)
+            .await?;
+        comments.extend(parse_review_comments(&reply, files)?);
+    }
+    sort_review_comments(&mut comments);
+
+    Ok(comments)
 }
 
 /// Get AI system status
 #[tauri::command]
-pub async fn get_ai_status() -> Result<std::collections::HashMap<String, serde_json::Value>, String> {
+pub async fn get_ai_status(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AIState>,
+) -> Result<std::collections::HashMap<String, serde_json::Value>, CodifyError> {
     log::info!("Getting AI system status");
-    
+
+    let config = state.config.read().await.clone();
     let mut status = std::collections::HashMap::new();
-    
-    status.insert("model_loaded".to_string(), serde_json::Value::Bool(true));
-    status.insert("model_name".to_string(), serde_json::Value::String("GPT-4".to_string()));
-    status.insert("gpu_usage".to_string(), serde_json::Value::Number(serde_json::Number::from(23)));
-    status.insert("memory_usage".to_string(), serde_json::Value::Number(serde_json::Number::from(156)));
-    status.insert("inference_speed".to_string(), serde_json::Value::String("Fast".to_string()));
-    status.insert("last_activity".to_string(), serde_json::Value::String(chrono::Utc::now().to_rfc3339()));
-    
+
+    // Until something has talked to the provider, find out rather than guess;
+    // a running warm-up is already doing so
+    let enabled = state.is_enabled();
+    let contacted = state.metrics.lock().unwrap().connection_ok.is_some();
+    let warmup = state.warmup_status();
+    if enabled && !contacted && warmup.phase != WarmupPhase::Running {
+        state.test_connection().await;
+    }
+    status.insert("ai_enabled".to_string(), serde_json::json!(enabled));
+    status.insert("warmup".to_string(), serde_json::json!(warmup));
+
+    {
+        let metrics = state.metrics.lock().unwrap();
+
+        let model_loaded = enabled && metrics.connection_ok.unwrap_or(false);
+
+        status.insert("model_loaded".to_string(), serde_json::json!(model_loaded));
+        status.insert("inference_speed".to_string(), serde_json::json!(metrics.inference_speed()));
+        status.insert(
+            "average_latency_ms".to_string(),
+            serde_json::json!(metrics.average_latency_ms()),
+        );
+        status.insert(
+            "last_activity".to_string(),
+            serde_json::json!(metrics.last_activity.map(|t| t.to_rfc3339())),
+        );
+    }
+
+    let cache_stats = state.completion_cache.lock().unwrap().stats();
+    status.insert("completion_cache".to_string(), serde_json::json!(cache_stats));
+
+    status.insert("model_name".to_string(), serde_json::json!(config.model));
+    status.insert("provider".to_string(), serde_json::json!(config.provider));
+    status.insert(
+        "in_flight_requests".to_string(),
+        serde_json::json!(state.throttle.in_flight()),
+    );
+    status.insert("rate_limit".to_string(), serde_json::json!(config.rate_limit));
+
+    let feedback_enabled = state.completion_feedback();
+    let feedback = state
+        .feedback
+        .lock()
+        .unwrap()
+        .summary(&app, feedback_enabled);
+    status.insert(
+        "completion_feedback".to_string(),
+        serde_json::json!(feedback),
+    );
+
+    // Resident memory of this process in MB, which includes a local model's
+    // weights unless they were offloaded to the GPU
+    if let Some(memory_mb) = process_memory_mb() {
+        status.insert("memory_usage".to_string(), serde_json::json!(memory_mb));
+    }
+
+    if config.provider == ProviderKind::Local {
+        let local = state.local.info();
+        status.insert(
+            "gpu_offload".to_string(),
+            serde_json::json!(local.as_ref().map(|info| info.gpu_offload).unwrap_or(false)),
+        );
+        status.insert("local_model".to_string(), serde_json::json!(local));
+    }
+
     Ok(status)
-}
\ No newline at end of file
+}
+
+fn process_memory_mb() -> Option<u64> {
+    let pid = sysinfo::get_current_pid().ok()?;
+    let mut system = sysinfo::System::new();
+    system.refresh_process(pid);
+
+    system.process(pid).map(|process| process.memory() / (1024 * 1024))
+}
@@ -0,0 +1,155 @@
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum PackageManager {
+    #[default]
+    Npm,
+    Pnpm,
+    Yarn,
+    Bun,
+}
+
+impl PackageManager {
+    /// Lockfiles checked in order; the first present decides
+    const LOCKFILES: [(&'static str, PackageManager); 5] = [
+        ("pnpm-lock.yaml", PackageManager::Pnpm),
+        ("yarn.lock", PackageManager::Yarn),
+        ("bun.lockb", PackageManager::Bun),
+        ("bun.lock", PackageManager::Bun),
+        ("package-lock.json", PackageManager::Npm),
+    ];
+
+    /// The manager whose lockfile is in `project_dir`, or npm when there is none
+    pub fn detect(project_dir: &Path) -> Self {
+        Self::LOCKFILES
+            .iter()
+            .find(|(lockfile, _)| project_dir.join(lockfile).is_file())
+            .map(|(_, manager)| *manager)
+            .unwrap_or_default()
+    }
+
+    pub fn binary(&self) -> &'static str {
+        match self {
+            PackageManager::Npm => "npm",
+            PackageManager::Pnpm => "pnpm",
+            PackageManager::Yarn => "yarn",
+            PackageManager::Bun => "bun",
+        }
+    }
+
+    /// Clean install that fails rather than update the lockfile (`npm ci`)
+    pub fn frozen_install(&self) -> &'static str {
+        match self {
+            PackageManager::Npm => "npm ci",
+            PackageManager::Pnpm => "pnpm install --frozen-lockfile",
+            PackageManager::Yarn => "yarn install --frozen-lockfile",
+            PackageManager::Bun => "bun install --frozen-lockfile",
+        }
+    }
+
+    /// Rewrite npm CLI arguments as this manager's equivalent, e.g.
+    /// `install lodash` becomes `add lodash`; anything without a known
+    /// counterpart is passed through unchanged
+    pub fn translate(&self, npm_args: &[String]) -> Vec<String> {
+        let Some((subcommand, rest)) = npm_args.split_first() else {
+            return vec![];
+        };
+        if *self == PackageManager::Npm {
+            return npm_args.to_vec();
+        }
+
+        let names_packages = rest.iter().any(|arg| !arg.starts_with('-'));
+        let translated = match subcommand.as_str() {
+            "install" | "i" | "add" if names_packages => "add",
+            "install" | "i" => "install",
+            "ci" => {
+                return self
+                    .frozen_install()
+                    .split_whitespace()
+                    .skip(1)
+                    .map(|arg| arg.to_string())
+                    .collect();
+            }
+            "uninstall" | "remove" | "rm" | "un" => "remove",
+            "run-script" => "run",
+            other => other,
+        };
+
+        let mut args = vec![translated.to_string()];
+        args.extend(rest.iter().cloned());
+        args
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NpmScripts {
+    pub package_manager: PackageManager,
+    pub scripts: BTreeMap<String, String>, // Name -> command line
+}
+
+/// Read the scripts section of `project_path`/package.json
+pub fn read_npm_scripts(project_path: &str) -> Result<NpmScripts, CodifyError> {
+    let dir = Path::new(project_path);
+    let manifest = dir.join("package.json");
+
+    let text = match std::fs::read_to_string(&manifest) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(CodifyError::NotFound(format!(
+                "package.json not found in {}",
+                project_path
+            )));
+        }
+        Err(e) => {
+            return Err(CodifyError::Io(format!(
+                "Failed to read {}: {}",
+                manifest.display(),
+                e
+            )))
+        }
+    };
+    let package: serde_json::Value = serde_json::from_str(&text).map_err(|e| {
+        CodifyError::InvalidInput(format!("Invalid package.json in {}: {}", project_path, e))
+    })?;
+
+    let scripts = match &package["scripts"] {
+        serde_json::Value::Null => BTreeMap::new(),
+        serde_json::Value::Object(scripts) => scripts
+            .iter()
+            .filter_map(|(name, command)| Some((name.clone(), command.as_str()?.to_string())))
+            .collect(),
+        _ => {
+            return Err(CodifyError::InvalidInput(
+                "Invalid package.json: \"scripts\" must be an object".to_string(),
+            ))
+        }
+    };
+
+    Ok(NpmScripts {
+        package_manager: PackageManager::detect(dir),
+        scripts,
+    })
+}
+
+/// Check that `script` exists before spawning, so a typo gets a clear error
+/// instead of the package manager's
+pub fn ensure_script_exists(project_path: &str, script: &str) -> Result<(), CodifyError> {
+    let available = read_npm_scripts(project_path)?.scripts;
+    if available.contains_key(script) {
+        return Ok(());
+    }
+
+    let names: Vec<&str> = available.keys().map(|name| name.as_str()).collect();
+    Err(CodifyError::NotFound(format!(
+        "Script '{}' not found in package.json; available: {}",
+        script,
+        if names.is_empty() {
+            "none".to_string()
+        } else {
+            names.join(", ")
+        }
+    )))
+}
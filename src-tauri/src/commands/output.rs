@@ -0,0 +1,219 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Output a terminal command may return or stream when the request sets no
+/// `max_output_bytes`
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+impl OutputStream {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputStream::Stdout => "stdout",
+            OutputStream::Stderr => "stderr",
+        }
+    }
+}
+
+#[derive(Default)]
+struct CappedStream {
+    head: Vec<u8>,
+    tail: VecDeque<u8>,
+    total: usize,
+    noticed: bool, // The "output is being held" notice went out
+}
+
+impl CappedStream {
+    fn omitted(&self) -> usize {
+        self.total - self.head.len() - self.tail.len()
+    }
+}
+
+enum OutputLog {
+    Buffering(Vec<u8>), // Everything so far, until the output outgrows the cap
+    Writing(PathBuf, std::fs::File),
+    Failed,
+}
+
+/// A command's output held to a size cap
+///
+/// Each stream keeps its first and last `max_bytes / 2` bytes and drops the
+/// middle. Once either stream fills its head, everything the command
+/// printed, in order, also goes to a temp log file so nothing is lost.
+pub struct CappedOutput {
+    half: usize,
+    stdout: CappedStream,
+    stderr: CappedStream,
+    log: OutputLog,
+}
+
+impl CappedOutput {
+    pub fn new(max_bytes: usize) -> Self {
+        CappedOutput {
+            half: (max_bytes / 2).max(1),
+            stdout: CappedStream::default(),
+            stderr: CappedStream::default(),
+            log: OutputLog::Buffering(vec![]),
+        }
+    }
+
+    fn stream(&self, stream: OutputStream) -> &CappedStream {
+        match stream {
+            OutputStream::Stdout => &self.stdout,
+            OutputStream::Stderr => &self.stderr,
+        }
+    }
+
+    fn stream_mut(&mut self, stream: OutputStream) -> &mut CappedStream {
+        match stream {
+            OutputStream::Stdout => &mut self.stdout,
+            OutputStream::Stderr => &mut self.stderr,
+        }
+    }
+
+    /// Record a chunk and return the part of it that still fits in the
+    /// stream's head; the rest is only kept if it ends up in the tail
+    pub fn push<'a>(&mut self, stream: OutputStream, bytes: &'a [u8]) -> &'a [u8] {
+        self.record(bytes);
+
+        let half = self.half;
+        let capped = self.stream_mut(stream);
+        capped.total += bytes.len();
+
+        let fits = half.saturating_sub(capped.head.len()).min(bytes.len());
+        capped.head.extend_from_slice(&bytes[..fits]);
+        capped.tail.extend(&bytes[fits..]);
+        let excess = capped.tail.len().saturating_sub(half);
+        capped.tail.drain(..excess);
+
+        if fits < bytes.len() {
+            self.spill();
+        }
+        &bytes[..fits]
+    }
+
+    fn record(&mut self, bytes: &[u8]) {
+        let failed = match &mut self.log {
+            OutputLog::Buffering(transcript) => {
+                transcript.extend_from_slice(bytes);
+                false
+            }
+            OutputLog::Writing(path, file) => match file.write_all(bytes) {
+                Ok(_) => false,
+                Err(e) => {
+                    log::warn!("Failed to write {}: {}", path.display(), e);
+                    true
+                }
+            },
+            OutputLog::Failed => false,
+        };
+
+        if failed {
+            self.log = OutputLog::Failed;
+        }
+    }
+
+    /// Move the buffered transcript into a new log file
+    fn spill(&mut self) {
+        let OutputLog::Buffering(transcript) = &self.log else {
+            return;
+        };
+
+        let dir = std::env::temp_dir().join("codify-logs");
+        let path = dir.join(format!("{}.log", uuid::Uuid::new_v4()));
+        let file = std::fs::create_dir_all(&dir)
+            .and_then(|_| std::fs::File::create(&path))
+            .and_then(|mut file| file.write_all(transcript).map(|_| file));
+
+        self.log = match file {
+            Ok(file) => OutputLog::Writing(path, file),
+            Err(e) => {
+                log::warn!("Failed to write {}: {}", path.display(), e);
+                OutputLog::Failed
+            }
+        };
+    }
+
+    /// Bytes dropped from the middle of both streams
+    pub fn omitted_bytes(&self) -> usize {
+        self.stdout.omitted() + self.stderr.omitted()
+    }
+
+    /// The log holding the complete output, once there is one
+    pub fn log_path(&self) -> Option<String> {
+        match &self.log {
+            OutputLog::Writing(path, _) => Some(path.to_string_lossy().to_string()),
+            _ => None,
+        }
+    }
+
+    fn marker(&self, omitted: usize) -> String {
+        match self.log_path() {
+            Some(path) => format!(
+                "\n[... {} bytes omitted; full output in {} ...]\n",
+                omitted, path
+            ),
+            None => format!("\n[... {} bytes omitted ...]\n", omitted),
+        }
+    }
+
+    /// Everything kept for a stream, with a marker where bytes were dropped
+    pub fn text(&self, stream: OutputStream) -> String {
+        let capped = self.stream(stream);
+        let mut text = String::from_utf8_lossy(&capped.head).to_string();
+
+        let omitted = capped.omitted();
+        if omitted > 0 {
+            text.push_str(&self.marker(omitted));
+        }
+        let tail: Vec<u8> = capped.tail.iter().copied().collect();
+        text.push_str(&String::from_utf8_lossy(&tail));
+        text
+    }
+
+    /// For streaming: a one-time note when a stream's head fills and later
+    /// output is held back until the command exits
+    pub fn notice(&mut self, stream: OutputStream) -> Option<String> {
+        let half = self.half;
+        let log_path = self.log_path();
+        let capped = self.stream_mut(stream);
+        if capped.noticed || capped.total <= half {
+            return None;
+        }
+        capped.noticed = true;
+
+        Some(match log_path {
+            Some(path) => format!(
+                "\n[Output over {} bytes; the end is shown when the command exits. Full output in {}]\n",
+                half, path
+            ),
+            None => format!(
+                "\n[Output over {} bytes; the end is shown when the command exits]\n",
+                half
+            ),
+        })
+    }
+
+    /// For streaming: what was held back for a stream, to send once it exits
+    pub fn held(&self, stream: OutputStream) -> Option<String> {
+        let capped = self.stream(stream);
+        if capped.tail.is_empty() {
+            return None;
+        }
+
+        let omitted = capped.omitted();
+        let marker = if omitted > 0 {
+            self.marker(omitted)
+        } else {
+            String::new()
+        };
+        let tail: Vec<u8> = capped.tail.iter().copied().collect();
+        Some(format!("{}{}", marker, String::from_utf8_lossy(&tail)))
+    }
+}
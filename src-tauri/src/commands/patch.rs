@@ -0,0 +1,279 @@
+use super::{parse_unified_diff, DiffLineKind, FileDiff};
+use crate::error::CodifyError;
+use crate::storage::{resolve_in_project, write_project_file};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Replace lines `start_line..=end_line` (1-based) of a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextEdit {
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub replacement: String,
+    #[serde(default)]
+    pub original: Option<String>, // The lines the edit was based on, checked before applying
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchFileResult {
+    pub file_path: String,
+    pub new_content: Option<String>, // None when the file is deleted
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    pub created: bool,
+    pub deleted: bool,
+}
+
+/// A file's lines plus what is needed to write them back the same way
+struct FileText {
+    lines: Vec<String>,
+    line_ending: &'static str,
+    trailing_newline: bool,
+}
+
+impl FileText {
+    fn parse(content: &str) -> Self {
+        FileText {
+            lines: content.lines().map(str::to_string).collect(),
+            line_ending: if content.contains("\r\n") {
+                "\r\n"
+            } else {
+                "\n"
+            },
+            trailing_newline: content.is_empty() || content.ends_with('\n'),
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut content = self.lines.join(self.line_ending);
+        if self.trailing_newline && !self.lines.is_empty() {
+            content.push_str(self.line_ending);
+        }
+        content
+    }
+}
+
+fn read_existing(project_path: &str, file_path: &str) -> Result<Option<String>, CodifyError> {
+    let full_path = resolve_in_project(project_path, file_path)?;
+    if !full_path.exists() {
+        return Ok(None);
+    }
+
+    std::fs::read_to_string(&full_path)
+        .map(Some)
+        .map_err(|e| CodifyError::Io(format!("Failed to read {}: {}", file_path, e)))
+}
+
+fn stale_region(file_path: &str, start: u32, end: u32) -> CodifyError {
+    CodifyError::InvalidInput(format!(
+        "{} has changed since the edit was generated (lines {}-{}); regenerate the suggestion",
+        file_path, start, end
+    ))
+}
+
+/// Work out the result of applying one file's hunks, verifying every
+/// context and removed line against the file as it is now
+fn apply_file_diff(project_path: &str, diff: &FileDiff) -> Result<PatchFileResult, CodifyError> {
+    let file_path = diff
+        .new_path
+        .clone()
+        .or_else(|| diff.old_path.clone())
+        .ok_or_else(|| CodifyError::InvalidInput("Patch has a file without a path".to_string()))?;
+
+    if diff.binary {
+        return Err(CodifyError::InvalidInput(format!(
+            "Binary patches are not supported: {}",
+            file_path
+        )));
+    }
+
+    let original = match &diff.old_path {
+        Some(old_path) => Some(read_existing(project_path, old_path)?.ok_or_else(|| {
+            CodifyError::NotFound(format!("File to patch does not exist: {}", old_path))
+        })?),
+        None => None,
+    };
+    let mut text = FileText::parse(original.as_deref().unwrap_or_default());
+
+    let mut output: Vec<String> = vec![];
+    let mut cursor = 0usize; // Next unconsumed line of the original, 0-based
+    let (mut lines_added, mut lines_removed) = (0, 0);
+
+    for hunk in &diff.hunks {
+        // A zero-length old range starts after old_start rather than at it
+        let hunk_start = if hunk.old_lines == 0 {
+            hunk.old_start as usize
+        } else {
+            (hunk.old_start as usize).saturating_sub(1)
+        };
+        if hunk_start < cursor || hunk_start > text.lines.len() {
+            return Err(stale_region(
+                &file_path,
+                hunk.old_start,
+                hunk.old_start + hunk.old_lines,
+            ));
+        }
+
+        output.extend(text.lines[cursor..hunk_start].iter().cloned());
+        cursor = hunk_start;
+
+        for line in &hunk.lines {
+            match line.kind {
+                DiffLineKind::Added => {
+                    output.push(line.content.clone());
+                    lines_added += 1;
+                }
+                DiffLineKind::Context | DiffLineKind::Removed => {
+                    let current = text.lines.get(cursor).map(|l| l.trim_end_matches('\r'));
+                    if current != Some(line.content.trim_end_matches('\r')) {
+                        return Err(stale_region(
+                            &file_path,
+                            hunk.old_start,
+                            hunk.old_start + hunk.old_lines.saturating_sub(1),
+                        ));
+                    }
+                    if line.kind == DiffLineKind::Context {
+                        output.push(text.lines[cursor].clone());
+                    } else {
+                        lines_removed += 1;
+                    }
+                    cursor += 1;
+                }
+            }
+        }
+    }
+    output.extend(text.lines[cursor..].iter().cloned());
+
+    let deleted = diff.new_path.is_none();
+    text.lines = output;
+
+    Ok(PatchFileResult {
+        file_path,
+        new_content: if deleted { None } else { Some(text.render()) },
+        lines_added,
+        lines_removed,
+        created: original.is_none(),
+        deleted,
+    })
+}
+
+/// Work out the result of applying line edits to one file, bottom-up so
+/// earlier line numbers stay valid
+fn apply_file_edits(
+    project_path: &str,
+    file_path: &str,
+    edits: &mut [&TextEdit],
+) -> Result<PatchFileResult, CodifyError> {
+    let original = read_existing(project_path, file_path)?;
+    let mut text = FileText::parse(original.as_deref().unwrap_or_default());
+
+    edits.sort_by_key(|edit| edit.start_line);
+    for pair in edits.windows(2) {
+        if pair[1].start_line <= pair[0].end_line {
+            return Err(CodifyError::InvalidInput(format!(
+                "Edits overlap in {} at lines {}-{}",
+                file_path, pair[1].start_line, pair[0].end_line
+            )));
+        }
+    }
+
+    let (mut lines_added, mut lines_removed) = (0, 0);
+    for edit in edits.iter().rev() {
+        // end_line = start_line - 1 inserts without replacing anything
+        let start = (edit.start_line as usize).saturating_sub(1);
+        let end = edit.end_line as usize;
+        if edit.start_line == 0 || end + 1 < edit.start_line as usize || end > text.lines.len() {
+            return Err(stale_region(file_path, edit.start_line, edit.end_line));
+        }
+
+        if let Some(expected) = &edit.original {
+            let current = text.lines[start..end].join("\n");
+            let expected: Vec<&str> = expected.lines().collect();
+            if current.lines().collect::<Vec<_>>() != expected {
+                return Err(stale_region(file_path, edit.start_line, edit.end_line));
+            }
+        }
+
+        let replacement: Vec<String> = edit.replacement.lines().map(str::to_string).collect();
+        lines_removed += end - start;
+        lines_added += replacement.len();
+        text.lines.splice(start..end, replacement);
+    }
+
+    Ok(PatchFileResult {
+        file_path: file_path.to_string(),
+        new_content: Some(text.render()),
+        lines_added,
+        lines_removed,
+        created: original.is_none(),
+        deleted: false,
+    })
+}
+
+/// Apply a unified diff or a set of line edits to files in the project
+///
+/// Every file is checked and computed before anything is written, so a
+/// conflict anywhere leaves the project untouched. With `dry_run` nothing
+/// is written and the results describe what would change.
+pub fn apply_changes(
+    project_path: &str,
+    diff: Option<&str>,
+    edits: &[TextEdit],
+    dry_run: bool,
+) -> Result<Vec<PatchFileResult>, CodifyError> {
+    let mut results = vec![];
+    // Renames also need the old path removed
+    let mut removals = vec![];
+
+    if let Some(diff) = diff {
+        let files = parse_unified_diff(diff);
+        if files.is_empty() {
+            return Err(CodifyError::InvalidInput(
+                "Patch contains no file changes".to_string(),
+            ));
+        }
+
+        for file in &files {
+            let result = apply_file_diff(project_path, file)?;
+            if let (Some(old), Some(new)) = (&file.old_path, &file.new_path) {
+                if old != new {
+                    removals.push(old.clone());
+                }
+            }
+            results.push(result);
+        }
+    }
+
+    let mut by_file: BTreeMap<&str, Vec<&TextEdit>> = BTreeMap::new();
+    for edit in edits {
+        by_file
+            .entry(edit.file_path.as_str())
+            .or_default()
+            .push(edit);
+    }
+    for (file_path, mut file_edits) in by_file {
+        results.push(apply_file_edits(project_path, file_path, &mut file_edits)?);
+    }
+
+    if dry_run {
+        return Ok(results);
+    }
+
+    for result in &results {
+        match &result.new_content {
+            Some(content) => {
+                write_project_file(project_path, &result.file_path, content, false)?;
+            }
+            None => removals.push(result.file_path.clone()),
+        }
+    }
+    for path in removals {
+        let full_path = resolve_in_project(project_path, &path)?;
+        std::fs::remove_file(&full_path)
+            .map_err(|e| CodifyError::Io(format!("Failed to delete {}: {}", path, e)))?;
+        log::info!("Deleted {}", full_path.display());
+    }
+
+    Ok(results)
+}
@@ -0,0 +1,332 @@
+use super::TerminalCommand;
+use crate::error::CodifyError;
+use crate::storage::resolve_in_project;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Which programs execute_terminal_command and stream_terminal_command may
+/// run, so a suggested command can't do more than the user allows
+///
+/// This is the only gate in front of those commands: they spawn processes
+/// directly rather than through the shell plugin and its scope.
+///
+/// Names are base commands, matched case-insensitively without any directory
+/// or Windows executable extension. The denylist wins over the allowlist; an
+/// empty allowlist allows anything not denied. In shell mode every command in
+/// a pipeline or chain is checked, so denying the shells blocks `curl … | sh`,
+/// and redirects may only read or write files inside the working directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandPolicy {
+    pub enabled: bool,
+    pub allowed: Vec<String>,
+    pub denied: Vec<String>,
+}
+
+fn names(list: &[&str]) -> Vec<String> {
+    list.iter().map(|name| name.to_string()).collect()
+}
+
+impl Default for CommandPolicy {
+    fn default() -> Self {
+        CommandPolicy {
+            enabled: true,
+            allowed: names(&[
+                "npm", "pnpm", "yarn", "bun", "npx", "node", "deno", "tsc", "eslint", "prettier",
+                "vite", "test", "git", "cargo", "rustc", "rustup", "python", "python3", "pip",
+                "pip3", "pytest", "go", "make", "ls", "pwd", "echo",
+            ]),
+            denied: names(&[
+                "rm",
+                "rmdir",
+                "del",
+                "rd",
+                "format",
+                "mkfs",
+                "dd",
+                "shutdown",
+                "reboot",
+                "sudo",
+                "su",
+                "doas",
+                "chmod",
+                "chown",
+                "kill",
+                "killall",
+                "sh",
+                "bash",
+                "zsh",
+                "fish",
+                "dash",
+                "cmd",
+                "powershell",
+                "pwsh",
+                "eval",
+                "exec",
+            ]),
+        }
+    }
+}
+
+/// `program` without its directory or executable extension, lowercased
+fn base_name(program: &str) -> String {
+    let name = Path::new(program)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    for extension in [".exe", ".cmd", ".bat", ".com", ".ps1"] {
+        if let Some(stem) = name.strip_suffix(extension) {
+            return stem.to_string();
+        }
+    }
+    name
+}
+
+/// The program of each command in a shell line: the first word after every
+/// pipe, chain, subshell or substitution, skipping `VAR=value` prefixes
+///
+/// Quoting isn't understood, so an operator inside quotes starts a command
+/// too; that only makes the check stricter. The `&` of a redirect (`2>&1`,
+/// `&>`) doesn't start one.
+fn shell_programs(line: &str) -> Vec<String> {
+    let line = line
+        .replace(">&", ">")
+        .replace("<&", "<")
+        .replace("&>", ">");
+    let separated = [
+        "&&", "||", "$(", "|", ";", "&", "`", "(", ")", "{", "}", "\n",
+    ]
+    .iter()
+    .fold(line, |line, operator| line.replace(operator, "\n"));
+
+    separated
+        .lines()
+        .filter_map(|command| {
+            command
+                .split_whitespace()
+                .find(|word| !word.contains('='))
+                .map(|word| base_name(word.trim_matches(['"', '\''])))
+        })
+        .collect()
+}
+
+/// Redirects to these go nowhere, wherever the project is
+const NULL_DEVICES: [&str; 2] = ["/dev/null", "nul"];
+
+/// The file named by each `>`, `>>`, `<` or similar redirect in a shell line
+///
+/// Descriptor duplication (`2>&1`), here-documents (`<<`) and process
+/// substitution (`>(…)`) name no file and are skipped; the programs inside a
+/// substitution are checked like any other.
+fn redirect_targets(line: &str) -> Vec<String> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut targets = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        if !matches!(chars[i], '>' | '<') {
+            i += 1;
+            continue;
+        }
+
+        let mut operator = String::new();
+        while let Some(&c @ ('>' | '<')) = chars.get(i) {
+            operator.push(c);
+            i += 1;
+        }
+        if operator == ">" && chars.get(i) == Some(&'|') {
+            i += 1;
+        }
+        let duplicate = chars.get(i) == Some(&'&');
+        if duplicate {
+            i += 1;
+        }
+        while chars.get(i).is_some_and(|c| c.is_whitespace()) {
+            i += 1;
+        }
+
+        let target: String = match chars.get(i) {
+            Some(&quote @ ('"' | '\'')) => {
+                let quoted: String = chars[i + 1..].iter().take_while(|&&c| c != quote).collect();
+                i += quoted.chars().count() + 2;
+                quoted
+            }
+            _ => {
+                let word: String = chars[i..]
+                    .iter()
+                    .take_while(|c| !c.is_whitespace() && !"|;&<>()".contains(**c))
+                    .collect();
+                i += word.chars().count();
+                word
+            }
+        };
+
+        let is_descriptor = target == "-" || target.chars().all(|c| c.is_ascii_digit());
+        if operator.starts_with("<<") || target.is_empty() || (duplicate && is_descriptor) {
+            continue;
+        }
+        targets.push(target);
+    }
+    targets
+}
+
+impl CommandPolicy {
+    pub fn validate(&self) -> Result<(), CodifyError> {
+        for (list, entries) in [("allowed", &self.allowed), ("denied", &self.denied)] {
+            if entries
+                .iter()
+                .any(|name| name.trim().is_empty() || name.contains(char::is_whitespace))
+            {
+                return Err(CodifyError::InvalidInput(format!(
+                    "Invalid command_policy.{}: entries must be single command names",
+                    list
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn permits(&self, program: &str) -> Result<(), CodifyError> {
+        let listed = |list: &[String]| list.iter().any(|name| base_name(name) == program);
+        if listed(&self.denied) {
+            return Err(CodifyError::InvalidInput(format!(
+                "Command not permitted: {} is denied in settings",
+                program
+            )));
+        }
+        if !self.allowed.is_empty() && !listed(&self.allowed) {
+            return Err(CodifyError::InvalidInput(format!(
+                "Command not permitted: {} is not in the allowed commands in settings",
+                program
+            )));
+        }
+        Ok(())
+    }
+
+    /// Refuse a redirect that could touch a file outside `working_dir`
+    ///
+    /// A target that the shell would expand first (variables, `~`, globs,
+    /// substitutions) can't be checked here, so it is refused too.
+    fn permits_redirect(&self, working_dir: &str, target: &str) -> Result<(), CodifyError> {
+        if NULL_DEVICES.contains(&target.to_lowercase().as_str()) {
+            return Ok(());
+        }
+        if target.starts_with('~') || target.contains(['$', '`', '*', '?', '[', '{']) {
+            return Err(CodifyError::InvalidInput(format!(
+                "Command not permitted: redirect target {} must be a plain path",
+                target
+            )));
+        }
+        match resolve_in_project(working_dir, target) {
+            Ok(_) => Ok(()),
+            Err(CodifyError::InvalidInput(_)) => Err(CodifyError::InvalidInput(format!(
+                "Command not permitted: redirect to {} is outside the working directory",
+                target
+            ))),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Check every program `command` would run, and every file a shell line
+    /// redirects to or from, before it is spawned
+    pub fn check(&self, command: &TerminalCommand) -> Result<(), CodifyError> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        if !command.use_shell {
+            return self.permits(&base_name(&command.command));
+        }
+
+        let line = std::iter::once(command.command.as_str())
+            .chain(command.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        for program in shell_programs(&line) {
+            self.permits(&program)?;
+        }
+        for target in redirect_targets(&line) {
+            self.permits_redirect(&command.working_dir, &target)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::OutputFormat;
+
+    /// `line` run through the shell in the temp directory
+    fn shell(line: &str) -> TerminalCommand {
+        TerminalCommand {
+            command: line.to_string(),
+            args: vec![],
+            working_dir: std::env::temp_dir().to_string_lossy().to_string(),
+            timeout_ms: None,
+            env: Default::default(),
+            use_shell: true,
+            max_output_bytes: None,
+            output_format: OutputFormat::Raw,
+            load_dotenv: false,
+        }
+    }
+
+    fn check(line: &str) -> Result<(), CodifyError> {
+        CommandPolicy::default().check(&shell(line))
+    }
+
+    #[test]
+    fn allows_redirects_inside_the_working_directory() {
+        assert!(check("npm test > test.log").is_ok());
+        assert!(check("npm test >> logs/test.log").is_ok());
+        assert!(check("node < scripts/input.txt").is_ok());
+        assert!(check("echo hi > \"my notes.txt\"").is_ok());
+    }
+
+    #[test]
+    fn rejects_writes_outside_the_working_directory() {
+        assert!(matches!(
+            check("echo hi > ../outside.txt"),
+            Err(CodifyError::InvalidInput(_))
+        ));
+        assert!(check("echo hi >/etc/passwd").is_err());
+        assert!(check("echo hi >| ../outside.txt").is_err());
+        assert!(check("npm test &> ../outside.log").is_err());
+    }
+
+    #[test]
+    fn rejects_appends_outside_the_working_directory() {
+        assert!(check("echo hi >> ../outside.txt").is_err());
+        assert!(check("echo hi 2>>'/etc/profile'").is_err());
+    }
+
+    #[test]
+    fn rejects_reads_outside_the_working_directory() {
+        assert!(check("node < ../secret.txt").is_err());
+        assert!(check("node </etc/shadow").is_err());
+    }
+
+    #[test]
+    fn rejects_targets_the_shell_would_expand() {
+        assert!(check("echo hi > ~/outside.txt").is_err());
+        assert!(check("echo hi > $HOME/outside.txt").is_err());
+        assert!(check("echo hi > \"$(pwd)/../outside.txt\"").is_err());
+        assert!(check("echo hi > *.txt").is_err());
+    }
+
+    #[test]
+    fn allows_descriptors_here_documents_and_the_null_device() {
+        assert!(check("npm test 2>&1 | echo").is_ok());
+        assert!(check("npm test > /dev/null 2>&1").is_ok());
+        assert!(check("node <<EOF").is_ok());
+        assert!(check("npm test 2>&-").is_ok());
+    }
+
+    #[test]
+    fn ignores_redirect_characters_without_a_shell() {
+        let mut command = shell("echo");
+        command.use_shell = false;
+        command.args = vec![">".to_string(), "../outside.txt".to_string()];
+        assert!(CommandPolicy::default().check(&command).is_ok());
+    }
+}
@@ -0,0 +1,325 @@
+use super::{StylingMode, TargetFramework, FRAMEWORKS};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ProjectType {
+    NextJs,
+    Nuxt,
+    SvelteKit,
+    Remix,
+    Astro,
+    Angular,
+    SolidJS,
+    React,
+    Vue,
+    Svelte,
+    Node, // package.json without a recognized UI framework
+    Rust,
+    Python,
+    Unknown,
+}
+
+/// Dependencies that decide the project type, meta-frameworks first
+const TYPE_DEPENDENCIES: [(&str, ProjectType); 10] = [
+    ("next", ProjectType::NextJs),
+    ("nuxt", ProjectType::Nuxt),
+    ("@sveltejs/kit", ProjectType::SvelteKit),
+    ("@remix-run/react", ProjectType::Remix),
+    ("astro", ProjectType::Astro),
+    ("@angular/core", ProjectType::Angular),
+    ("solid-js", ProjectType::SolidJS),
+    ("react", ProjectType::React),
+    ("vue", ProjectType::Vue),
+    ("svelte", ProjectType::Svelte),
+];
+
+/// Libraries worth reporting beyond the frameworks in FRAMEWORKS
+const LIBRARIES: [(&str, &str); 6] = [
+    ("solid-js", "SolidJS"),
+    ("tailwindcss", "Tailwind CSS"),
+    ("styled-components", "styled-components"),
+    ("@tauri-apps/api", "Tauri"),
+    ("express", "Express"),
+    ("electron", "Electron"),
+];
+
+const RUST_FRAMEWORKS: [(&str, &str); 5] = [
+    ("tauri", "Tauri"),
+    ("axum", "Axum"),
+    ("actix-web", "Actix Web"),
+    ("rocket", "Rocket"),
+    ("bevy", "Bevy"),
+];
+
+const PYTHON_FRAMEWORKS: [(&str, &str); 4] = [
+    ("django", "Django"),
+    ("flask", "Flask"),
+    ("fastapi", "FastAPI"),
+    ("pytest", "pytest"),
+];
+
+/// What a project is built with, read from its manifests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectDetection {
+    pub project_type: ProjectType,
+    pub language: String, // typescript, javascript, rust, python, or unknown
+    pub frameworks: Vec<String>, // Everything recognized, e.g. Next.js, React, Tailwind CSS
+    pub confidence: f32,
+    pub evidence: Vec<String>, // The files and dependencies each conclusion rests on
+}
+
+impl ProjectType {
+    /// Config files that confirm the type when present next to package.json
+    fn config_files(&self) -> &'static [&'static str] {
+        match self {
+            ProjectType::NextJs => &["next.config.js", "next.config.mjs", "next.config.ts"],
+            ProjectType::Nuxt => &["nuxt.config.ts", "nuxt.config.js"],
+            ProjectType::SvelteKit | ProjectType::Svelte => &["svelte.config.js"],
+            ProjectType::Remix => &["remix.config.js"],
+            ProjectType::Astro => &["astro.config.mjs", "astro.config.ts"],
+            ProjectType::Angular => &["angular.json"],
+            _ => &[],
+        }
+    }
+}
+
+impl ProjectDetection {
+    /// The design target this project's components are written for
+    pub fn target_framework(&self) -> Option<TargetFramework> {
+        match self.project_type {
+            ProjectType::NextJs | ProjectType::Remix | ProjectType::React => {
+                Some(TargetFramework::React)
+            }
+            ProjectType::Nuxt | ProjectType::Vue => Some(TargetFramework::Vue),
+            ProjectType::SvelteKit | ProjectType::Svelte => Some(TargetFramework::Svelte),
+            ProjectType::SolidJS => Some(TargetFramework::SolidJS),
+            _ => None,
+        }
+    }
+
+    /// The styling approach the project already depends on, if any
+    pub fn styling_mode(&self) -> Option<StylingMode> {
+        let uses = |name: &str| self.frameworks.iter().any(|framework| framework == name);
+        if uses("Tailwind CSS") {
+            Some(StylingMode::TailwindUtility)
+        } else if uses("styled-components") {
+            Some(StylingMode::StyledComponents)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the project is written in JavaScript or TypeScript
+    pub fn is_javascript(&self) -> bool {
+        self.language == "typescript" || self.language == "javascript"
+    }
+}
+
+/// Whether a Cargo.toml lists `name` as a dependency, read line by line
+fn cargo_depends_on(manifest: &str, name: &str) -> bool {
+    manifest.lines().any(|line| {
+        line.trim_start()
+            .strip_prefix(name)
+            .is_some_and(|rest| rest.trim_start().starts_with(['=', '.']))
+    })
+}
+
+/// Inspect package.json, Cargo.toml, pyproject.toml and framework config
+/// files to decide what kind of project `project_path` is
+pub fn detect_project(project_path: &str) -> Result<ProjectDetection, CodifyError> {
+    let root = Path::new(project_path);
+    if !root.is_dir() {
+        return Err(CodifyError::NotFound(format!(
+            "Project path does not exist: {}",
+            project_path
+        )));
+    }
+    let read = |name: &str| std::fs::read_to_string(root.join(name)).ok();
+
+    let mut detection = ProjectDetection {
+        project_type: ProjectType::Unknown,
+        language: "unknown".to_string(),
+        frameworks: vec![],
+        confidence: 0.0,
+        evidence: vec![],
+    };
+
+    if let Some(manifest) = read("package.json") {
+        let package: serde_json::Value = serde_json::from_str(&manifest).unwrap_or_default();
+        let depends_on = |name: &str| {
+            ["dependencies", "devDependencies"]
+                .iter()
+                .any(|section| package[section].get(name).is_some())
+        };
+        detection.evidence.push("package.json".to_string());
+
+        for (dependency, framework) in FRAMEWORKS.iter().chain(LIBRARIES.iter()) {
+            if depends_on(dependency) && !detection.frameworks.iter().any(|f| f == framework) {
+                detection.frameworks.push(framework.to_string());
+            }
+        }
+
+        match TYPE_DEPENDENCIES
+            .iter()
+            .find(|(dependency, _)| depends_on(dependency))
+        {
+            Some((dependency, project_type)) => {
+                detection.project_type = *project_type;
+                detection.confidence = 0.85;
+                detection
+                    .evidence
+                    .push(format!("package.json depends on {}", dependency));
+
+                let configs = project_type.config_files();
+                if let Some(config) = configs.iter().find(|config| root.join(config).is_file()) {
+                    detection.confidence = 0.95;
+                    detection.evidence.push(config.to_string());
+                }
+            }
+            None => {
+                detection.project_type = ProjectType::Node;
+                detection.confidence = 0.6;
+            }
+        }
+
+        let typescript = root.join("tsconfig.json").is_file();
+        if typescript {
+            detection.evidence.push("tsconfig.json".to_string());
+        }
+        detection.language = if typescript || depends_on("typescript") {
+            "typescript".to_string()
+        } else {
+            "javascript".to_string()
+        };
+    }
+
+    // A Tauri app keeps its Rust side in src-tauri next to the web frontend
+    let cargo = ["Cargo.toml", "src-tauri/Cargo.toml"]
+        .iter()
+        .find_map(|name| read(name).map(|manifest| (*name, manifest)));
+    if let Some((name, manifest)) = cargo {
+        detection.evidence.push(name.to_string());
+        for (dependency, framework) in RUST_FRAMEWORKS {
+            if cargo_depends_on(&manifest, dependency)
+                && !detection.frameworks.iter().any(|f| f == framework)
+            {
+                detection.frameworks.push(framework.to_string());
+            }
+        }
+
+        // A web framework in package.json decides the type; plain Node doesn't
+        if name == "Cargo.toml" && detection.target_framework().is_none() {
+            detection.project_type = ProjectType::Rust;
+            detection.language = "rust".to_string();
+            detection.confidence = 0.9;
+        }
+    }
+
+    if detection.project_type == ProjectType::Unknown {
+        let python = ["pyproject.toml", "setup.py", "requirements.txt"]
+            .iter()
+            .filter_map(|name| read(name).map(|content| (*name, content)))
+            .collect::<Vec<_>>();
+
+        if let Some((name, _)) = python.first() {
+            detection.project_type = ProjectType::Python;
+            detection.language = "python".to_string();
+            // A requirements file alone could just be tooling for another project
+            detection.confidence = match *name {
+                "requirements.txt" => 0.7,
+                _ => 0.9,
+            };
+
+            for (name, content) in &python {
+                detection.evidence.push(name.to_string());
+                let content = content.to_lowercase();
+                for (package, framework) in PYTHON_FRAMEWORKS {
+                    if content.contains(package)
+                        && !detection.frameworks.iter().any(|f| f == framework)
+                    {
+                        detection.frameworks.push(framework.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(detection)
+}
+
+/// Files and directories that mark a project root
+const ROOT_MARKERS: [&str; 5] = [
+    ".git",
+    "package.json",
+    "Cargo.toml",
+    "pyproject.toml",
+    "go.mod",
+];
+
+/// Where a project really starts, for a folder that may be inside it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectRoot {
+    pub root: String,
+    pub markers: Vec<String>, // ROOT_MARKERS present in root
+    pub overridden: bool,     // Taken from settings.project_roots
+}
+
+fn markers_in(dir: &Path) -> Vec<String> {
+    ROOT_MARKERS
+        .iter()
+        .filter(|marker| dir.join(marker).exists())
+        .map(|marker| marker.to_string())
+        .collect()
+}
+
+/// Walk up from `start_path` (a folder, or a file in one) to its project root
+///
+/// The nearest folder with `.git` wins, so a package inside a monorepo
+/// resolves to the repository; without one, the nearest folder with any
+/// marker does, and failing that `start_path` itself. An entry of
+/// `overrides` (folder -> root) covering `start_path` takes precedence, the
+/// most specific folder first.
+pub fn find_root(
+    start_path: &str,
+    overrides: &std::collections::BTreeMap<String, String>,
+) -> Result<ProjectRoot, CodifyError> {
+    let start = Path::new(start_path)
+        .canonicalize()
+        .map_err(|_| CodifyError::NotFound(format!("Path does not exist: {}", start_path)))?;
+    let start = if start.is_file() {
+        start.parent().unwrap_or(&start).to_path_buf()
+    } else {
+        start
+    };
+
+    let overridden = overrides
+        .iter()
+        .filter_map(|(folder, root)| {
+            let folder = Path::new(folder).canonicalize().ok()?;
+            start.starts_with(&folder).then_some((folder, root))
+        })
+        .max_by_key(|(folder, _)| folder.components().count());
+    if let Some((_, root)) = overridden {
+        let root = Path::new(root).canonicalize().map_err(|_| {
+            CodifyError::NotFound(format!("Project root override does not exist: {}", root))
+        })?;
+        return Ok(ProjectRoot {
+            markers: markers_in(&root),
+            root: root.to_string_lossy().to_string(),
+            overridden: true,
+        });
+    }
+
+    let repository = start.ancestors().find(|dir| dir.join(".git").exists());
+    let root = repository
+        .or_else(|| start.ancestors().find(|dir| !markers_in(dir).is_empty()))
+        .unwrap_or(&start);
+
+    Ok(ProjectRoot {
+        markers: markers_in(root),
+        root: root.to_string_lossy().to_string(),
+        overridden: false,
+    })
+}
@@ -0,0 +1,165 @@
+use super::{render_diff, FileDiff};
+use crate::ai::{count_tokens, fit_around_line};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+
+/// Ordered most to least serious, so sorting puts issues first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewSeverity {
+    Issue,   // A bug or risk that should block the change
+    Warning, // Likely a problem, worth a second look
+    Nit,     // Style or polish
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewFocus {
+    Correctness,
+    Security,
+    Performance,
+    Style,
+}
+
+impl ReviewFocus {
+    fn guidance(&self) -> &'static str {
+        match self {
+            ReviewFocus::Correctness => "correctness: logic errors, edge cases, error handling",
+            ReviewFocus::Security => {
+                "security: injection, unsafe input handling, secrets, auth and permission checks"
+            }
+            ReviewFocus::Performance => {
+                "performance: needless work in loops, blocking calls, allocations, N+1 queries"
+            }
+            ReviewFocus::Style => "style: naming, readability, duplication, consistency",
+        }
+    }
+}
+
+/// What the review concentrates on; every area when `focus` is empty
+pub fn review_guidance(focus: &[ReviewFocus]) -> String {
+    let all = [
+        ReviewFocus::Correctness,
+        ReviewFocus::Security,
+        ReviewFocus::Performance,
+        ReviewFocus::Style,
+    ];
+    let focus = if focus.is_empty() { &all[..] } else { focus };
+    focus
+        .iter()
+        .map(|area| format!("- {}", area.guidance()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewComment {
+    pub file_path: String,
+    pub line: u32, // In the new file; always one the diff shows
+    pub severity: ReviewSeverity,
+    pub message: String,
+}
+
+/// The fields the model is asked for
+#[derive(Debug, Clone, Deserialize)]
+struct RawComment {
+    file_path: String,
+    line: u32,
+    #[serde(default)]
+    severity: Option<String>,
+    message: String,
+}
+
+/// The diff split into pieces that each fit `budget` tokens
+///
+/// Whole files are packed together while they fit; a file too large on its
+/// own is split between hunks, and a single hunk too large is clipped.
+pub fn review_chunks(files: &[FileDiff], budget: usize) -> Vec<String> {
+    let mut pieces = vec![];
+    for file in files.iter().filter(|file| file.new_path.is_some()) {
+        let rendered = render_diff(std::slice::from_ref(file));
+        if count_tokens(&rendered) <= budget || file.hunks.len() <= 1 {
+            pieces.push(rendered);
+            continue;
+        }
+        for hunk in &file.hunks {
+            let single = FileDiff {
+                hunks: vec![hunk.clone()],
+                ..file.clone()
+            };
+            pieces.push(render_diff(&[single]));
+        }
+    }
+
+    let mut chunks: Vec<String> = vec![];
+    let mut current = String::new();
+    for piece in pieces {
+        let (piece, _) = fit_around_line(&piece, 0, budget);
+        if !current.is_empty() && count_tokens(&current) + count_tokens(&piece) > budget {
+            chunks.push(std::mem::take(&mut current));
+        }
+        current.push_str(&piece);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Lines of `file` the diff touches or shows, in the new file
+fn new_lines(file: &FileDiff) -> Vec<u32> {
+    file.hunks
+        .iter()
+        .flat_map(|hunk| &hunk.lines)
+        .filter_map(|line| line.new_line)
+        .collect()
+}
+
+/// Keep comments on files and lines that are in the diff; a line outside
+/// every hunk moves to the nearest one shown
+pub fn parse_review_comments(
+    reply: &str,
+    files: &[FileDiff],
+) -> Result<Vec<ReviewComment>, CodifyError> {
+    let raw: Vec<RawComment> = crate::ai::parse_json_reply(reply)?;
+
+    Ok(raw
+        .into_iter()
+        .filter_map(|comment| {
+            let file = files.iter().find(|file| {
+                file.new_path
+                    .as_deref()
+                    .is_some_and(|path| path == comment.file_path.trim_start_matches("b/"))
+            })?;
+            let line = new_lines(file)
+                .into_iter()
+                .min_by_key(|line| line.abs_diff(comment.line))
+                .unwrap_or(comment.line.max(1));
+            let severity = match comment.severity.as_deref().map(str::to_lowercase) {
+                Some(s) if s == "issue" || s == "error" => ReviewSeverity::Issue,
+                Some(s) if s == "nit" => ReviewSeverity::Nit,
+                _ => ReviewSeverity::Warning,
+            };
+            let message = comment.message.trim().to_string();
+            if message.is_empty() {
+                return None;
+            }
+
+            Some(ReviewComment {
+                file_path: file.new_path.clone()?,
+                line,
+                severity,
+                message,
+            })
+        })
+        .collect())
+}
+
+/// Severity, then file and line; comments repeated across chunks are dropped
+pub fn sort_review_comments(comments: &mut Vec<ReviewComment>) {
+    comments.sort_by(|a, b| {
+        (a.severity, &a.file_path, a.line).cmp(&(b.severity, &b.file_path, b.line))
+    });
+    comments
+        .dedup_by(|a, b| a.file_path == b.file_path && a.line == b.line && a.message == b.message);
+}
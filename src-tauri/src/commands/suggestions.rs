@@ -0,0 +1,251 @@
+use super::{read_npm_scripts, strip_ansi, PackageManager, TerminalResponse};
+use crate::ai::compiled;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Dependencies that identify a framework, checked in order so meta-frameworks
+/// win over the library they build on
+pub(crate) const FRAMEWORKS: [(&str, &str); 11] = [
+    ("next", "Next.js"),
+    ("nuxt", "Nuxt"),
+    ("@sveltejs/kit", "SvelteKit"),
+    ("@remix-run/react", "Remix"),
+    ("astro", "Astro"),
+    ("@angular/core", "Angular"),
+    ("react-scripts", "Create React App"),
+    ("vite", "Vite"),
+    ("react", "React"),
+    ("vue", "Vue"),
+    ("svelte", "Svelte"),
+];
+
+/// What a command's working directory says about the project, for
+/// suggestions that name its real scripts and tools
+#[derive(Debug, Default)]
+pub struct ProjectContext {
+    pub package_manager: Option<PackageManager>, // None without a package.json
+    pub scripts: BTreeMap<String, String>,
+    pub framework: Option<&'static str>,
+    pub typescript: bool,
+    pub cargo: bool, // Has a Cargo.toml
+}
+
+impl ProjectContext {
+    pub fn load(dir: &str) -> Self {
+        let root = Path::new(dir);
+        let mut context = ProjectContext {
+            cargo: root.join("Cargo.toml").is_file(),
+            typescript: root.join("tsconfig.json").is_file(),
+            ..Default::default()
+        };
+
+        if let Ok(npm) = read_npm_scripts(dir) {
+            context.package_manager = Some(npm.package_manager);
+            context.scripts = npm.scripts;
+        }
+
+        let package: serde_json::Value = std::fs::read_to_string(root.join("package.json"))
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        let depends_on = |name: &str| {
+            ["dependencies", "devDependencies"]
+                .iter()
+                .any(|section| package[section].get(name).is_some())
+        };
+        context.framework = FRAMEWORKS
+            .iter()
+            .find(|(dependency, _)| depends_on(dependency))
+            .map(|(_, framework)| *framework);
+        context.typescript |= depends_on("typescript");
+
+        context
+    }
+
+    fn tool(&self) -> &'static str {
+        self.package_manager.unwrap_or_default().binary()
+    }
+
+    /// `<pm> run <script>` for the first of `names` the project defines
+    fn run(&self, names: &[&str]) -> Option<String> {
+        names
+            .iter()
+            .find(|name| self.scripts.contains_key(**name))
+            .map(|name| format!("{} run {}", self.tool(), name))
+    }
+}
+
+/// Suggestions for a command that failed, matched against what it printed;
+/// empty when nothing specific is recognized
+pub fn error_suggestions(error: &str, context: &ProjectContext) -> Vec<String> {
+    static MISSING_MODULE: OnceLock<regex::Regex> = OnceLock::new();
+    static MISSING_SCRIPT: OnceLock<regex::Regex> = OnceLock::new();
+    static PORT_IN_USE: OnceLock<regex::Regex> = OnceLock::new();
+    static NOT_FOUND: OnceLock<regex::Regex> = OnceLock::new();
+    let missing_module = compiled(
+        &MISSING_MODULE,
+        r#"(?:Cannot find module|Module not found: Error: Can't resolve|Failed to resolve import) ['"]([^'"]+)['"]"#,
+    );
+    let missing_script = compiled(&MISSING_SCRIPT, r#"[Mm]issing script:? "?([\w:.\-]+)"?"#);
+    let port_in_use = compiled(
+        &PORT_IN_USE,
+        r"EADDRINUSE.*?:(\d+)|[Pp]ort (\d+) is (?:already )?in use",
+    );
+    let not_found = compiled(
+        &NOT_FOUND,
+        r"(?:command not found: ([\w.\-]+)|([\w.\-]+): (?:command )?not found|'([\w.\-]+)' is not recognized)",
+    );
+
+    let tool = context.tool();
+    let mut suggestions = vec![];
+
+    if let Some(c) = missing_module.captures(error) {
+        let module = &c[1];
+        if module.starts_with('.') || module.starts_with('/') {
+            suggestions.push(format!(
+                "Check the import path '{}'; the file it points to doesn't exist",
+                module
+            ));
+        } else {
+            // `@scope/pkg/sub/path` is installed as `@scope/pkg`
+            let segments = if module.starts_with('@') { 2 } else { 1 };
+            let package: Vec<&str> = module.split('/').take(segments).collect();
+            let add = match context.package_manager.unwrap_or_default() {
+                PackageManager::Npm => "install",
+                _ => "add",
+            };
+            suggestions.push(format!(
+                "Install the missing package: '{} {} {}'",
+                tool,
+                add,
+                package.join("/")
+            ));
+        }
+    }
+
+    if let Some(c) = missing_script.captures(error) {
+        let available: Vec<&str> = context.scripts.keys().map(|name| name.as_str()).collect();
+        suggestions.push(if available.is_empty() {
+            format!(
+                "package.json has no '{}' script and no scripts at all",
+                &c[1]
+            )
+        } else {
+            format!(
+                "package.json has no '{}' script; available: {}",
+                &c[1],
+                available.join(", ")
+            )
+        });
+    }
+
+    if let Some(c) = port_in_use.captures(error) {
+        let port = c
+            .get(1)
+            .or_else(|| c.get(2))
+            .map(|m| m.as_str())
+            .unwrap_or_default();
+        suggestions.push(format!(
+            "Port {} is taken; stop the other server or start this one on another port",
+            port
+        ));
+    }
+
+    if let Some(c) = not_found.captures(error) {
+        let program = c
+            .get(1)
+            .or_else(|| c.get(2))
+            .or_else(|| c.get(3))
+            .map(|m| m.as_str())
+            .unwrap_or_default();
+        suggestions.push(match program {
+            "npm" | "node" | "npx" => {
+                "Node.js isn't on PATH; install it or check your version manager".to_string()
+            }
+            "pnpm" | "yarn" => format!(
+                "Install {0} with 'corepack enable' or 'npm install -g {0}'",
+                program
+            ),
+            "cargo" | "rustc" => "Rust isn't on PATH; install it with rustup".to_string(),
+            _ if context.package_manager.is_some() => format!(
+                "'{}' isn't installed; run '{} install' if it's a project dependency",
+                program, tool
+            ),
+            _ => format!("'{}' isn't installed or isn't on PATH", program),
+        });
+    }
+
+    if error.contains("ERESOLVE") {
+        suggestions.push(
+            "Peer dependencies conflict; align the versions or retry with '--legacy-peer-deps'"
+                .to_string(),
+        );
+    }
+    if error.contains("EACCES") || error.contains("Permission denied") {
+        suggestions.push(
+            "Permission denied; fix ownership of the directory instead of using sudo".to_string(),
+        );
+    }
+    if context.typescript && error.contains("error TS") {
+        suggestions.push(
+            "Open the diagnostics panel to see every type error with its location".to_string(),
+        );
+    }
+    if context.cargo && error.contains("error[E") {
+        suggestions.push("Run 'cargo check' for the full list of compile errors".to_string());
+    }
+    if error.contains("ENOENT") && error.contains("package.json") {
+        suggestions.push("No package.json here; run the command from the project root".to_string());
+    }
+
+    suggestions
+}
+
+/// Next steps after a command succeeded, named after the project's own scripts
+pub fn next_step_suggestions(context: &ProjectContext) -> Vec<String> {
+    let mut suggestions = vec![];
+
+    if let Some(dev) = context.run(&["dev", "start", "serve"]) {
+        suggestions.push(match context.framework {
+            Some(framework) => format!("Try: '{}' to start the {} dev server", dev, framework),
+            None => format!("Try: '{}' to start the development server", dev),
+        });
+    }
+    if let Some(test) = context.run(&["test", "test:unit"]) {
+        suggestions.push(format!("Run '{}' to check your changes", test));
+    }
+    if context.cargo {
+        suggestions.push("Try: 'cargo run' to build and run the crate".to_string());
+    }
+
+    suggestions
+}
+
+/// Suggestions for a finished command: error-specific when it failed,
+/// project-specific when it succeeded, and `fallback` when neither finds
+/// anything to say
+pub fn project_suggestions(
+    working_dir: &str,
+    response: &TerminalResponse,
+    fallback: &[&str],
+) -> Vec<String> {
+    let context = ProjectContext::load(working_dir);
+
+    let suggestions = if response.success {
+        next_step_suggestions(&context)
+    } else {
+        // Tools differ on whether errors go to stdout or stderr
+        let printed = format!(
+            "{}\n{}",
+            response.error.as_deref().unwrap_or_default(),
+            response.output
+        );
+        error_suggestions(&strip_ansi(&printed), &context)
+    };
+
+    if suggestions.is_empty() {
+        return fallback.iter().map(|s| s.to_string()).collect();
+    }
+    suggestions
+}
@@ -0,0 +1,761 @@
+use super::{
+    ensure_script_exists, load_dotenv, spans_text, strip_ansi, AnsiParser, AnsiSpan, CappedOutput,
+    OutputFormat, OutputStream, PackageManager, TerminalCommand, TerminalResponse,
+    DEFAULT_MAX_OUTPUT_BYTES,
+};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+use tokio::io::AsyncReadExt;
+use tokio::sync::mpsc;
+
+/// How long a process gets to exit after SIGTERM before it is force-killed
+const KILL_GRACE_PERIOD: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// Running streamed processes keyed by command id
+#[derive(Default)]
+pub struct ProcessRegistry {
+    children: Mutex<HashMap<String, ProcessChild>>,
+}
+
+impl ProcessRegistry {
+    fn insert(&self, command_id: &str, child: ProcessChild) {
+        self.children.lock().unwrap().insert(command_id.to_string(), child);
+    }
+
+    fn remove(&self, command_id: &str) -> Option<ProcessChild> {
+        self.children.lock().unwrap().remove(command_id)
+    }
+
+    /// Terminate a running process; unknown or finished ids are a no-op
+    pub fn kill(&self, command_id: &str) -> Result<(), CodifyError> {
+        let Some(child) = self.remove(command_id) else {
+            return Ok(());
+        };
+
+        terminate(child)
+    }
+}
+
+#[cfg(unix)]
+fn terminate(child: ProcessChild) -> Result<(), CodifyError> {
+    // Ask politely first so dev servers can clean up, then force it. The
+    // whole group is signalled so `npm run dev` takes its node child with it.
+    if let Err(e) = child.signal(libc::SIGTERM) {
+        if e.raw_os_error() != Some(libc::ESRCH) {
+            return Err(CodifyError::Io(format!("Failed to kill process: {}", e)));
+        }
+    }
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(KILL_GRACE_PERIOD).await;
+        // Harmless if the group already exited
+        child.kill();
+    });
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn terminate(child: ProcessChild) -> Result<(), CodifyError> {
+    // On Windows this is TerminateProcess; there is no graceful equivalent
+    child.kill();
+    Ok(())
+}
+
+/// What a process started by `spawn` reports, ending with `Terminated`
+enum ProcessEvent {
+    Stdout(Vec<u8>),
+    Stderr(Vec<u8>),
+    Error(String),
+    Terminated(ExitPayload),
+}
+
+struct ExitPayload {
+    code: Option<i32>,
+    signal: Option<i32>, // Unix only
+}
+
+/// A process started by `spawn`; on Unix it leads its own process group
+struct ProcessChild {
+    #[cfg(unix)]
+    pid: u32,
+    kill: mpsc::UnboundedSender<()>,
+}
+
+impl ProcessChild {
+    /// Send `signal` to the process's whole group
+    #[cfg(unix)]
+    fn signal(&self, signal: libc::c_int) -> std::io::Result<()> {
+        // A pid of 0 would signal the app's own group instead
+        if self.pid == 0 {
+            return Ok(());
+        }
+        match unsafe { libc::kill(-(self.pid as libc::pid_t), signal) } {
+            0 => Ok(()),
+            _ => Err(std::io::Error::last_os_error()),
+        }
+    }
+
+    /// Force-kill the process, and on Unix everything else in its group
+    fn kill(&self) {
+        #[cfg(unix)]
+        let _ = self.signal(libc::SIGKILL);
+        // Reaps the process; fails harmlessly once it has exited
+        let _ = self.kill.send(());
+    }
+}
+
+/// Spawn `command` with its output forwarded as events
+///
+/// On Unix the child gets a process group of its own (pgid = pid), so
+/// terminating it reaches whatever it started too, and the app's own group
+/// never receives the signal. The shell plugin can't do that, so it isn't
+/// used here and its scope doesn't apply: user commands are gated only by
+/// CommandPolicy::check, which execute_terminal_command and
+/// stream_terminal_command run before run_process or spawn_streaming.
+fn spawn(
+    mut command: std::process::Command,
+) -> std::io::Result<(mpsc::Receiver<ProcessEvent>, ProcessChild)> {
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    command
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+
+    let mut child = tokio::process::Command::from(command).spawn()?;
+    let (events, receiver) = mpsc::channel(64);
+    let (kill, mut killed) = mpsc::unbounded_channel();
+    let handle = ProcessChild {
+        #[cfg(unix)]
+        pid: child.id().unwrap_or_default(),
+        kill,
+    };
+
+    let stdout = forward_output(child.stdout.take(), events.clone(), ProcessEvent::Stdout);
+    let stderr = forward_output(child.stderr.take(), events.clone(), ProcessEvent::Stderr);
+
+    tauri::async_runtime::spawn(async move {
+        let wait = async {
+            loop {
+                tokio::select! {
+                    status = child.wait() => break status,
+                    Some(()) = killed.recv() => {
+                        let _ = child.start_kill();
+                    }
+                }
+            }
+        };
+        let (status, _, _) = tokio::join!(wait, stdout, stderr);
+
+        let exit = match status {
+            Ok(status) => ExitPayload {
+                code: status.code(),
+                #[cfg(unix)]
+                signal: std::os::unix::process::ExitStatusExt::signal(&status),
+                #[cfg(not(unix))]
+                signal: None,
+            },
+            Err(e) => {
+                let _ = events.send(ProcessEvent::Error(e.to_string())).await;
+                ExitPayload {
+                    code: None,
+                    signal: None,
+                }
+            }
+        };
+        let _ = events.send(ProcessEvent::Terminated(exit)).await;
+    });
+
+    Ok((receiver, handle))
+}
+
+/// Send whatever `pipe` yields as events until it closes
+///
+/// Chunks go out as they arrive instead of waiting for a newline, so partial
+/// lines and carriage-return progress bars are kept intact.
+async fn forward_output(
+    pipe: Option<impl tokio::io::AsyncRead + Unpin>,
+    events: mpsc::Sender<ProcessEvent>,
+    event: fn(Vec<u8>) -> ProcessEvent,
+) {
+    let Some(mut pipe) = pipe else {
+        return;
+    };
+    let mut buffer = vec![0; 8192];
+    while let Ok(read @ 1..) = pipe.read(&mut buffer).await {
+        if events.send(event(buffer[..read].to_vec())).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalOutputEvent {
+    pub command_id: String,
+    pub stream: String, // stdout or stderr
+    pub data: String,
+    pub spans: Option<Vec<AnsiSpan>>, // Styled `data` for OutputFormat::Spans
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalExitEvent {
+    pub command_id: String,
+    pub success: bool,
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+    pub error: Option<String>,
+    pub omitted_bytes: usize,
+    pub log_path: Option<String>, // Complete output, when it outgrew the cap
+}
+
+/// Decodes raw output chunks as UTF-8, holding back a trailing partial
+/// character until the next chunk completes it
+#[derive(Default)]
+struct Utf8Carry {
+    pending: Vec<u8>,
+}
+
+impl Utf8Carry {
+    fn decode(&mut self, chunk: &[u8]) -> String {
+        self.pending.extend_from_slice(chunk);
+
+        let valid_up_to = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            // error_len() == None means the input ends mid-character
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+
+        let rest = self.pending.split_off(valid_up_to);
+        let text = String::from_utf8_lossy(&self.pending).to_string();
+        self.pending = rest;
+        text
+    }
+}
+
+/// The package.json script a request runs, if any: `npm run <script>` or the
+/// "test" pseudo-command
+fn script_name(command: &TerminalCommand) -> Option<&str> {
+    let first_arg = command.args.first().map(|a| a.as_str());
+    match (command.command.as_str(), first_arg) {
+        ("test", _) => Some("test"),
+        ("npm", Some("run" | "run-script")) => command.args.get(1).map(|s| s.as_str()),
+        _ => None,
+    }
+}
+
+/// Map IDE pseudo-commands onto the real program and arguments to spawn
+///
+/// npm commands run through the project's own package manager, detected from
+/// its lockfile, so `npm install` in a pnpm project runs `pnpm install`.
+fn resolve_program(command: &TerminalCommand) -> (String, Vec<String>) {
+    let manager = || PackageManager::detect(Path::new(&command.working_dir));
+
+    match command.command.as_str() {
+        // "test" is the project's test script, not the POSIX `test` builtin
+        "test" => {
+            let mut args = vec!["run".to_string(), "test".to_string()];
+            args.extend(command.args.iter().cloned());
+            (manager().binary().to_string(), args)
+        }
+        "npm" => {
+            let manager = manager();
+            (manager.binary().to_string(), manager.translate(&command.args))
+        }
+        program => (program.to_string(), command.args.clone()),
+    }
+}
+
+/// Validate the request before anything is spawned
+fn validate(command: &TerminalCommand) -> Result<(), CodifyError> {
+    if command.command.trim().is_empty() {
+        return Err(CodifyError::InvalidInput(
+            "Command must not be empty".to_string(),
+        ));
+    }
+
+    if !Path::new(&command.working_dir).is_dir() {
+        return Err(CodifyError::NotFound(format!(
+            "Working directory does not exist: {}",
+            command.working_dir
+        )));
+    }
+
+    if let Some(script) = script_name(command) {
+        ensure_script_exists(&command.working_dir, script)?;
+    }
+
+    Ok(())
+}
+
+/// The program and arguments actually spawned for a request
+///
+/// Direct mode, the default, runs the binary itself and passes `args` through
+/// untouched, so nothing in them is expanded, split or interpreted. The
+/// program is looked up in the PATH the process will get (see `process_env`),
+/// and a program containing a path separator is taken relative to the working
+/// directory. On Windows each PATHEXT extension is tried as well, and `.cmd`
+/// shims like npm.cmd are run with their arguments escaped by the standard
+/// library.
+///
+/// With `use_shell`, the program and args are joined with spaces and the line
+/// is handed to `/bin/sh -c` on Unix or `cmd /C` on Windows, for pipes, globs
+/// and redirection. The args are then shell syntax rather than literals, and
+/// the shell searches the same PATH.
+fn spawn_target(
+    command: &TerminalCommand,
+    env: &HashMap<String, String>,
+) -> Result<(String, Vec<String>), CodifyError> {
+    let (program, args) = resolve_program(command);
+
+    if command.use_shell {
+        let line = std::iter::once(program).chain(args).collect::<Vec<_>>();
+        return Ok(shell_invocation(line.join(" ")));
+    }
+
+    let path = env
+        .get("PATH")
+        .cloned()
+        .or_else(|| std::env::var("PATH").ok())
+        .unwrap_or_default();
+    let binary = find_program(&program, &path, Path::new(&command.working_dir))
+        .ok_or_else(|| CodifyError::NotFound(format!("Program not found on PATH: {}", program)))?;
+
+    Ok((binary.to_string_lossy().to_string(), args))
+}
+
+/// Locate `program` the way a shell would, without running one
+pub(crate) fn find_program(program: &str, path: &str, working_dir: &Path) -> Option<PathBuf> {
+    if program.contains('/') || program.contains(std::path::MAIN_SEPARATOR) {
+        return executable_candidates(&working_dir.join(program))
+            .into_iter()
+            .find(|candidate| is_executable(candidate));
+    }
+
+    std::env::split_paths(path)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .flat_map(|dir| executable_candidates(&dir.join(program)))
+        .find(|candidate| is_executable(candidate))
+}
+
+#[cfg(unix)]
+fn executable_candidates(base: &Path) -> Vec<PathBuf> {
+    vec![base.to_path_buf()]
+}
+
+/// `base` itself when it has an extension, then `base` with each PATHEXT one
+#[cfg(not(unix))]
+fn executable_candidates(base: &Path) -> Vec<PathBuf> {
+    let extensions = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+
+    let mut candidates = vec![];
+    if base.extension().is_some() {
+        candidates.push(base.to_path_buf());
+    }
+    for extension in extensions.split(';').filter(|e| !e.is_empty()) {
+        let mut name = base.as_os_str().to_owned();
+        name.push(extension);
+        candidates.push(PathBuf::from(name));
+    }
+    candidates
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(unix)]
+fn shell_invocation(line: String) -> (String, Vec<String>) {
+    ("/bin/sh".to_string(), vec!["-c".to_string(), line])
+}
+
+#[cfg(not(unix))]
+fn shell_invocation(line: String) -> (String, Vec<String>) {
+    ("cmd".to_string(), vec!["/C".to_string(), line])
+}
+
+/// Build the process for a request, ready to `spawn`
+fn build_command(command: &TerminalCommand) -> Result<std::process::Command, CodifyError> {
+    let env = process_env(command);
+    let (program, args) = spawn_target(command, &env)?;
+
+    let mut process = std::process::Command::new(program);
+    process
+        .args(args)
+        .current_dir(&command.working_dir)
+        .envs(env);
+    // Don't flash a console window for every command
+    #[cfg(windows)]
+    std::os::windows::process::CommandExt::creation_flags(&mut process, 0x0800_0000);
+    Ok(process)
+}
+
+/// Environment overrides for a spawned process
+///
+/// Precedence, lowest to highest: `.env` then `.env.local` when
+/// `load_dotenv` is set, the app's inherited environment, the PATH resolved
+/// from the user's login shell, then `command.env`. As with dotenv tools, a
+/// file never overrides a variable the environment already has. A PATH
+/// supplied in `command.env` replaces the resolved one entirely.
+fn process_env(command: &TerminalCommand) -> HashMap<String, String> {
+    let mut env = HashMap::new();
+
+    if command.load_dotenv {
+        let vars = load_dotenv(Path::new(&command.working_dir));
+        env.extend(
+            vars.into_iter()
+                .filter(|(key, _)| std::env::var_os(key).is_none()),
+        );
+    }
+
+    if let Some(path) = resolved_path() {
+        env.insert("PATH".to_string(), path.clone());
+    }
+
+    env.extend(command.env.iter().map(|(k, v)| (k.clone(), v.clone())));
+    env
+}
+
+/// PATH as the user's login shell sees it, merged with the inherited one
+///
+/// GUI apps on macOS start with a minimal PATH (/usr/bin:/bin:...), so node,
+/// nvm shims and Homebrew binaries would not be found without this.
+#[cfg(unix)]
+pub(crate) fn resolved_path() -> Option<&'static String> {
+    static PATH: std::sync::OnceLock<Option<String>> = std::sync::OnceLock::new();
+
+    PATH.get_or_init(|| {
+        const MARKER: &str = "__CODIFY_PATH__";
+
+        let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+        let login_path = std::process::Command::new(&shell)
+            .arg("-ilc")
+            .arg(format!("printf '{MARKER}%s{MARKER}' \"$PATH\""))
+            .stdin(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .output()
+            .ok()
+            .and_then(|output| {
+                // Interactive rc files may print banners, so look for the markers
+                let text = String::from_utf8_lossy(&output.stdout).to_string();
+                let start = text.find(MARKER)? + MARKER.len();
+                let end = start + text[start..].find(MARKER)?;
+                Some(text[start..end].to_string())
+            });
+
+        let inherited = std::env::var("PATH").unwrap_or_default();
+        let home = std::env::var("HOME").unwrap_or_default();
+        let fallbacks = [
+            "/opt/homebrew/bin".to_string(),
+            "/usr/local/bin".to_string(),
+            format!("{}/.cargo/bin", home),
+        ];
+
+        let mut seen = std::collections::HashSet::new();
+        let merged: Vec<String> = login_path
+            .iter()
+            .flat_map(|p| p.split(':').map(|s| s.to_string()).collect::<Vec<_>>())
+            .chain(inherited.split(':').map(|s| s.to_string()))
+            .chain(fallbacks)
+            .filter(|dir| !dir.is_empty() && seen.insert(dir.clone()))
+            .collect();
+
+        if merged.is_empty() {
+            None
+        } else {
+            Some(merged.join(":"))
+        }
+    })
+    .as_ref()
+}
+
+/// Windows GUI apps inherit the full user PATH, so nothing extra is needed
+#[cfg(not(unix))]
+pub(crate) fn resolved_path() -> Option<&'static String> {
+    None
+}
+
+fn output_cap(command: &TerminalCommand) -> CappedOutput {
+    CappedOutput::new(command.max_output_bytes.unwrap_or(DEFAULT_MAX_OUTPUT_BYTES))
+}
+
+fn timeout_message(timeout_ms: u64) -> String {
+    format!("Command timed out after {}ms and was killed", timeout_ms)
+}
+
+/// Run the command to completion and capture its output
+///
+/// Doesn't check the command policy; a command that came from the user must
+/// pass CommandPolicy::check first.
+pub async fn run_process(command: &TerminalCommand) -> Result<TerminalResponse, CodifyError> {
+    validate(command)?;
+
+    let (mut events, child) = spawn(build_command(command)?)
+        .map_err(|e| CodifyError::Io(format!("Failed to run '{}': {}", command.command, e)))?;
+
+    let mut output = output_cap(command);
+    let mut status: Option<ExitPayload> = None;
+
+    let collect = async {
+        while let Some(event) = events.recv().await {
+            match event {
+                ProcessEvent::Stdout(bytes) => {
+                    output.push(OutputStream::Stdout, &bytes);
+                }
+                ProcessEvent::Stderr(bytes) => {
+                    output.push(OutputStream::Stderr, &bytes);
+                }
+                ProcessEvent::Terminated(payload) => {
+                    status = Some(payload);
+                    break;
+                }
+                _ => {}
+            }
+        }
+    };
+
+    let timed_out = match command.timeout_ms {
+        Some(ms) => tokio::time::timeout(std::time::Duration::from_millis(ms), collect)
+            .await
+            .is_err(),
+        None => {
+            collect.await;
+            false
+        }
+    };
+
+    if timed_out {
+        child.kill();
+    }
+
+    let stdout = output.text(OutputStream::Stdout);
+    let stderr = output.text(OutputStream::Stderr);
+    let code = status.as_ref().and_then(|s| s.code);
+    let success = !timed_out && code == Some(0);
+
+    let error = if success {
+        None
+    } else if timed_out {
+        Some(timeout_message(command.timeout_ms.unwrap_or_default()))
+    } else if !stderr.trim().is_empty() {
+        Some(stderr.clone())
+    } else {
+        Some(match code {
+            Some(code) => format!("Process exited with code {}", code),
+            None => "Process was terminated by a signal".to_string(),
+        })
+    };
+
+    let omitted_bytes = output.omitted_bytes();
+    let log_path = output.log_path();
+
+    // Keep stderr visible in the transcript even when the command succeeds
+    let output = if stderr.is_empty() {
+        stdout
+    } else if stdout.is_empty() {
+        stderr
+    } else {
+        format!("{}\n{}", stdout, stderr)
+    };
+
+    let (output, error, spans) = match command.output_format {
+        OutputFormat::Raw => (output, error, None),
+        OutputFormat::Spans => {
+            let spans = AnsiParser::default().feed(&output);
+            let error = error.map(|e| strip_ansi(&e));
+            (spans_text(&spans), error, Some(spans))
+        }
+    };
+
+    let mut suggestions = vec![];
+    if timed_out {
+        suggestions.push("The command timed out; increase timeout_ms if it needs longer".to_string());
+    }
+
+    Ok(TerminalResponse {
+        success,
+        output,
+        error,
+        suggestions,
+        git_status: None,
+        omitted_bytes,
+        log_path,
+        spans,
+        test_result: None,
+    })
+}
+
+/// Spawn the command and stream its output as `terminal-output` events,
+/// finishing with a `terminal-exit` event. Returns the command id immediately.
+///
+/// Like run_process, expects the command to have passed CommandPolicy::check.
+pub fn spawn_streaming(app: &tauri::AppHandle, command: &TerminalCommand) -> Result<String, CodifyError> {
+    validate(command)?;
+
+    let command_id = uuid::Uuid::new_v4().to_string();
+
+    let (mut events, child) = spawn(build_command(command)?)
+        .map_err(|e| CodifyError::Io(format!("Failed to run '{}': {}", command.command, e)))?;
+
+    app.state::<ProcessRegistry>().insert(&command_id, child);
+
+    let app = app.clone();
+    let id = command_id.clone();
+    let timeout_ms = command.timeout_ms;
+    let mut output = output_cap(command);
+    let mut emitter = OutputEvents::new(&app, &id, command.output_format);
+
+    tauri::async_runtime::spawn(async move {
+        let deadline = timeout_ms
+            .map(|ms| tokio::time::Instant::now() + std::time::Duration::from_millis(ms));
+        let mut stdout = Utf8Carry::default();
+        let mut stderr = Utf8Carry::default();
+        let mut error = None;
+
+        loop {
+            let next = match deadline {
+                Some(deadline) => match tokio::time::timeout_at(deadline, events.recv()).await {
+                    Ok(next) => next,
+                    Err(_) => {
+                        // Kill the child and stop draining; dropping the receiver ends this task
+                        if let Some(child) = app.state::<ProcessRegistry>().remove(&id) {
+                            child.kill();
+                        }
+                        emitter.exit(
+                            &output,
+                            TerminalExitEvent {
+                                command_id: id.clone(),
+                                success: false,
+                                code: None,
+                                signal: None,
+                                error: Some(timeout_message(timeout_ms.unwrap_or_default())),
+                                omitted_bytes: 0,
+                                log_path: None,
+                            },
+                        );
+                        break;
+                    }
+                },
+                None => events.recv().await,
+            };
+
+            let Some(event) = next else {
+                break;
+            };
+
+            // Only the part that fits under the cap goes out live
+            let (stream, data) = match event {
+                ProcessEvent::Stdout(bytes) => {
+                    let visible = output.push(OutputStream::Stdout, &bytes);
+                    (OutputStream::Stdout, stdout.decode(visible))
+                }
+                ProcessEvent::Stderr(bytes) => {
+                    let visible = output.push(OutputStream::Stderr, &bytes);
+                    (OutputStream::Stderr, stderr.decode(visible))
+                }
+                ProcessEvent::Error(message) => {
+                    error = Some(message);
+                    continue;
+                }
+                ProcessEvent::Terminated(payload) => {
+                    app.state::<ProcessRegistry>().remove(&id);
+                    emitter.exit(
+                        &output,
+                        TerminalExitEvent {
+                            command_id: id.clone(),
+                            success: payload.code == Some(0),
+                            code: payload.code,
+                            signal: payload.signal,
+                            error: error.take(),
+                            omitted_bytes: 0,
+                            log_path: None,
+                        },
+                    );
+                    break;
+                }
+                _ => continue,
+            };
+
+            emitter.emit(stream, data);
+            if let Some(notice) = output.notice(stream) {
+                emitter.emit(stream, notice);
+            }
+        }
+    });
+
+    Ok(command_id)
+}
+
+/// Sends a streamed command's `terminal-output` and `terminal-exit` events,
+/// in the output format it asked for
+struct OutputEvents {
+    app: tauri::AppHandle,
+    command_id: String,
+    parsers: Option<[AnsiParser; 2]>, // stdout, stderr; None for raw output
+}
+
+impl OutputEvents {
+    fn new(app: &tauri::AppHandle, command_id: &str, format: OutputFormat) -> Self {
+        OutputEvents {
+            app: app.clone(),
+            command_id: command_id.to_string(),
+            parsers: match format {
+                OutputFormat::Raw => None,
+                OutputFormat::Spans => Some(Default::default()),
+            },
+        }
+    }
+
+    fn emit(&mut self, stream: OutputStream, data: String) {
+        if data.is_empty() {
+            return;
+        }
+
+        // Each stream keeps its own style across chunks
+        let (data, spans) = match &mut self.parsers {
+            Some(parsers) => {
+                let spans = parsers[stream as usize].feed(&data);
+                (spans_text(&spans), Some(spans))
+            }
+            None => (data, None),
+        };
+
+        let _ = self.app.emit(
+            "terminal-output",
+            TerminalOutputEvent {
+                command_id: self.command_id.clone(),
+                stream: stream.name().to_string(),
+                data,
+                spans,
+            },
+        );
+    }
+
+    /// Send the output the cap held back, then `exit` with the cap's totals
+    fn exit(&mut self, output: &CappedOutput, mut exit: TerminalExitEvent) {
+        for stream in [OutputStream::Stdout, OutputStream::Stderr] {
+            if let Some(held) = output.held(stream) {
+                self.emit(stream, held);
+            }
+        }
+
+        exit.omitted_bytes = output.omitted_bytes();
+        exit.log_path = output.log_path();
+        let _ = self.app.emit("terminal-exit", exit);
+    }
+}
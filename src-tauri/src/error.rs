@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+/// Error returned by every command, serialized as `{"code": ..., "message": ...}`
+///
+/// `code` is stable for the frontend to branch on; `message` is for people.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "code", content = "message", rename_all = "snake_case")]
+pub enum CodifyError {
+    Provider(String),     // The model provider failed or replied with something unusable
+    Auth(String),         // The provider rejected the credentials
+    RateLimited(String),  // Throttled locally or by the provider (429)
+    Timeout(String),      // A request or process ran out of time
+    Io(String),           // Reading or writing local files failed
+    NotFound(String),     // A file, project, model or record doesn't exist
+    InvalidInput(String), // The arguments can't be acted on as given
+    Cancelled(String),    // Superseded or cancelled by the user
+    Busy(String),         // The project index is locked by another write; retry
+    Unavailable(String),  // AI is switched off in settings
+    Internal(String),     // Anything else
+}
+
+impl CodifyError {
+    pub fn message(&self) -> &str {
+        match self {
+            CodifyError::Provider(message)
+            | CodifyError::Auth(message)
+            | CodifyError::RateLimited(message)
+            | CodifyError::Timeout(message)
+            | CodifyError::Io(message)
+            | CodifyError::NotFound(message)
+            | CodifyError::InvalidInput(message)
+            | CodifyError::Cancelled(message)
+            | CodifyError::Busy(message)
+            | CodifyError::Unavailable(message)
+            | CodifyError::Internal(message) => message,
+        }
+    }
+}
+
+impl std::fmt::Display for CodifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for CodifyError {}
+
+/// An error with nothing more specific to say about it; code that knows what
+/// failed should construct that variant directly
+impl From<String> for CodifyError {
+    fn from(message: String) -> Self {
+        CodifyError::Internal(message)
+    }
+}
+
+impl From<&str> for CodifyError {
+    fn from(message: &str) -> Self {
+        CodifyError::from(message.to_string())
+    }
+}
+
+impl From<std::io::Error> for CodifyError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => CodifyError::NotFound(e.to_string()),
+            std::io::ErrorKind::TimedOut => CodifyError::Timeout(e.to_string()),
+            _ => CodifyError::Io(e.to_string()),
+        }
+    }
+}
@@ -2,10 +2,16 @@
 mod ai;
 mod storage;
 mod commands;
+mod settings;
+mod lsp;
+mod error;
 
 use ai::*;
 use storage::*;
 use commands::*;
+use settings::*;
+use lsp::*;
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -13,23 +19,120 @@ pub fn run() {
     .plugin(tauri_plugin_log::Builder::new().build())
     .plugin(tauri_plugin_fs::init())
     .plugin(tauri_plugin_shell::init())
+    .manage(AIState::default())
+    .manage(IndexerState::default())
+    .manage(VectorStores::default())
+    .manage(ConversationStore::default())
+    .manage(SnippetStore::default())
+    .manage(ProcessRegistry::default())
+    .manage(WatcherState::default())
+    .manage(DependencyGraphState::default())
+    .manage(ProjectStatsState::default())
+    .manage(ReindexState::default())
+    .manage(TailState::default())
+    .manage(DiagnosticsState::default())
+    .manage(BlameState::default())
+    .manage(LspState::default())
     .invoke_handler(tauri::generate_handler![
       // AI Commands
       ai_complete_code,
+      select_completion,
+      record_completion_feedback,
+      clear_completion_cache,
+      cancel_ai_request,
+      scan_for_secrets,
+      configure_completion_cache,
       ai_explain_code,
+      ai_explain_code_batch,
+      ai_explain_code_stream,
       ai_suggest_refactor,
+      ai_suggest_refactor_legacy,
+      ai_suggest_refactor_stream,
+      ai_extract_component,
+      ai_scaffold_feature,
       ai_generate_tests,
+      ai_fix_error,
+      ai_generate_docstring,
+      create_conversation,
+      send_message,
+      get_conversation,
+      list_conversations,
+      get_ai_usage_log,
+      estimate_cost,
+      test_ai_connection,
+      list_models,
+      set_active_model,
       
       // Storage Commands
       get_project_files,
+      find_files,
+      search_file_content,
+      read_file,
+      tail_file,
+      stop_tail_file,
+      write_file,
+      save_snippet,
+      list_snippets,
+      delete_snippet,
       search_code_semantic,
+      find_similar_code,
+      detect_duplicates,
       store_code_embedding,
+      store_code_embeddings,
+      delete_code_embedding,
+      delete_embeddings_for_file,
+      clear_index,
+      get_index_info,
+      get_embedding_cache_stats,
+      purge_embedding_cache,
+      generate_embedding,
+      generate_file_embeddings,
+      get_embedding_dimension,
+      index_project,
+      reindex_with_model,
+      cancel_index,
+      export_index,
+      import_index,
+      watch_project,
+      stop_watch_project,
       get_ai_suggested_files,
+      project_dependency_graph,
+      analyze_project,
       
       // General Commands
       execute_terminal_command,
+      stream_terminal_command,
+      kill_terminal_command,
+      get_npm_scripts,
+      detect_package_manager,
+      get_git_status,
+      git_diff,
+      git_blame,
+      get_diagnostics,
+      format_code,
+      format_file,
+      detect_project_type,
+      find_project_root,
+      apply_patch,
       ai_generate_design,
+      ai_generate_commit_message,
+      ai_review_diff,
       get_ai_status,
+      
+      // Settings Commands
+      load_settings,
+      save_settings,
+      set_project_model,
+      list_prompt_templates,
+      set_prompt_template,
+      list_workspaces,
+      add_workspace_folder,
+      remove_workspace_folder,
+      
+      // LSP Commands
+      lsp_hover,
+      lsp_definition,
+      lsp_stop,
     ])
     .setup(|app| {
       if cfg!(debug_assertions) {
@@ -40,6 +143,26 @@ pub fn run() {
         )?;
       }
       
+      let settings = read_settings(app.handle());
+      tauri::async_runtime::block_on(apply_settings(&app.state::<AIState>(), &settings));
+
+      // In the background, so a slow provider or model load never delays the window
+      if settings.warmup_on_startup {
+        let handle = app.handle().clone();
+        tauri::async_runtime::spawn(async move {
+          handle.state::<AIState>().warm_up().await;
+        });
+      }
+
+      // Migrate the indexes of known projects up front, also in the background
+      let projects: Vec<String> = settings.project_paths().into_iter().map(String::from).collect();
+      let handle = app.handle().clone();
+      tauri::async_runtime::spawn_blocking(move || {
+        handle
+          .state::<VectorStores>()
+          .migrate_existing(projects.iter().map(String::as_str));
+      });
+      
       log::info!("ProjectCode AI-Powered IDE starting...");
       Ok(())
     })
@@ -0,0 +1,372 @@
+use crate::error::CodifyError;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout};
+use tokio::sync::oneshot;
+
+/// How long a server may take to answer `initialize`; rust-analyzer and
+/// tsserver load the whole project first
+const INITIALIZE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long any other request may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value, CodifyError>>>>>;
+
+/// A running language server spoken to over stdio with JSON-RPC
+pub struct LspClient {
+    name: String,
+    writer: Arc<tokio::sync::Mutex<ChildStdin>>,
+    pending: Pending,
+    next_id: AtomicU64,
+    alive: Arc<AtomicBool>,
+    versions: Mutex<HashMap<String, i32>>, // Open documents by URI
+    _child: Child,                         // Killed when the client is dropped
+}
+
+/// `file://` URI for an absolute path, percent-encoding what URIs can't hold
+pub fn path_to_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let path = if path.starts_with('/') {
+        path
+    } else {
+        // Windows drive paths, C:/...
+        format!("/{}", path)
+    };
+
+    let mut uri = String::from("file://");
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// The path a `file://` URI names; None for other schemes
+pub fn uri_to_path(uri: &str) -> Option<std::path::PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+
+    let mut bytes = vec![];
+    let mut rest = encoded.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let decoded = match (byte, tail.get(..2)) {
+            (b'%', Some(hex)) => std::str::from_utf8(hex)
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match decoded {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+
+    let path = String::from_utf8_lossy(&bytes).to_string();
+    // `/C:/...` on Windows
+    let path = match path.as_bytes() {
+        [b'/', drive, b':', ..] if cfg!(windows) && drive.is_ascii_alphabetic() => {
+            path[1..].to_string()
+        }
+        _ => path,
+    };
+    Some(std::path::PathBuf::from(path))
+}
+
+async fn write_message(
+    writer: &tokio::sync::Mutex<ChildStdin>,
+    message: &Value,
+) -> Result<(), CodifyError> {
+    let body = message.to_string();
+    let framed = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+    let failed =
+        |e: std::io::Error| CodifyError::Io(format!("Failed to write to language server: {}", e));
+
+    let mut writer = writer.lock().await;
+    writer.write_all(framed.as_bytes()).await.map_err(failed)?;
+    writer.flush().await.map_err(failed)
+}
+
+/// One message from the server, or None once it closes stdout
+async fn read_message(reader: &mut BufReader<ChildStdout>) -> Option<Value> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await.ok()? == 0 {
+            return None;
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let mut body = vec![0; length?];
+    reader.read_exact(&mut body).await.ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+/// Answer requests the server sends us; servers wait on some of these
+/// (configuration, progress tokens) before they answer ours
+fn server_request_result(method: &str, params: &Value) -> Value {
+    match method {
+        "workspace/configuration" => {
+            let items = params["items"].as_array().map_or(0, |items| items.len());
+            Value::Array(vec![Value::Null; items])
+        }
+        _ => Value::Null,
+    }
+}
+
+/// Route server output: responses to their waiting request, server requests
+/// answered, notifications dropped
+async fn read_loop(
+    name: String,
+    stdout: ChildStdout,
+    writer: Arc<tokio::sync::Mutex<ChildStdin>>,
+    pending: Pending,
+    alive: Arc<AtomicBool>,
+) {
+    let mut reader = BufReader::new(stdout);
+    while let Some(message) = read_message(&mut reader).await {
+        let id = message.get("id").cloned();
+        match (message["method"].as_str(), id) {
+            (Some(method), Some(id)) => {
+                let result = server_request_result(method, &message["params"]);
+                let reply = json!({"jsonrpc": "2.0", "id": id, "result": result});
+                if let Err(e) = write_message(&writer, &reply).await {
+                    log::warn!("{}: {}", name, e);
+                }
+            }
+            (Some(method), None) => {
+                if method == "window/logMessage" {
+                    log::debug!("{}: {}", name, message["params"]["message"]);
+                }
+            }
+            (None, Some(id)) => {
+                let Some(id) = id.as_u64() else {
+                    continue;
+                };
+                let Some(sender) = pending.lock().unwrap().remove(&id) else {
+                    continue;
+                };
+                let result = match message.get("error") {
+                    Some(error) => Err(CodifyError::Internal(format!(
+                        "Language server error: {}",
+                        error["message"].as_str().unwrap_or("unknown error")
+                    ))),
+                    None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                let _ = sender.send(result);
+            }
+            (None, None) => {}
+        }
+    }
+
+    log::warn!("Language server exited: {}", name);
+    alive.store(false, Ordering::SeqCst);
+    for (_, sender) in pending.lock().unwrap().drain() {
+        let _ = sender.send(Err(CodifyError::Internal(format!(
+            "Language server exited: {}",
+            name
+        ))));
+    }
+}
+
+impl LspClient {
+    /// Spawn `program` and complete the initialize handshake for `root`
+    pub async fn start(
+        name: &str,
+        program: &Path,
+        args: &[&str],
+        root: &Path,
+    ) -> Result<LspClient, CodifyError> {
+        let mut child = tokio::process::Command::new(program)
+            .args(args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                CodifyError::Io(format!("Failed to start language server {}: {}", name, e))
+            })?;
+
+        let (Some(stdin), Some(stdout), Some(stderr)) =
+            (child.stdin.take(), child.stdout.take(), child.stderr.take())
+        else {
+            return Err(CodifyError::Io(format!(
+                "Failed to start language server {}: no stdio",
+                name
+            )));
+        };
+
+        let writer = Arc::new(tokio::sync::Mutex::new(stdin));
+        let pending: Pending = Arc::new(Mutex::new(HashMap::new()));
+        let alive = Arc::new(AtomicBool::new(true));
+
+        tokio::spawn(read_loop(
+            name.to_string(),
+            stdout,
+            writer.clone(),
+            pending.clone(),
+            alive.clone(),
+        ));
+        let server = name.to_string();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                log::debug!("{} stderr: {}", server, line);
+            }
+        });
+
+        let client = LspClient {
+            name: name.to_string(),
+            writer,
+            pending,
+            next_id: AtomicU64::new(1),
+            alive,
+            versions: Mutex::new(HashMap::new()),
+            _child: child,
+        };
+
+        let root_uri = path_to_uri(root);
+        let folder_name = root
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let params = json!({
+            "processId": std::process::id(),
+            "clientInfo": {"name": "Codify"},
+            "rootUri": root_uri,
+            "workspaceFolders": [{"uri": root_uri, "name": folder_name}],
+            "capabilities": {
+                "textDocument": {
+                    "synchronization": {"dynamicRegistration": false},
+                    "hover": {"contentFormat": ["markdown", "plaintext"]},
+                    "definition": {"linkSupport": true},
+                },
+                "workspace": {"workspaceFolders": true, "configuration": true},
+            },
+        });
+        client
+            .request_within("initialize", params, INITIALIZE_TIMEOUT)
+            .await
+            .map_err(|e| {
+                let message = format!("Language server {} failed to initialize: {}", name, e);
+                match e {
+                    CodifyError::Timeout(_) => CodifyError::Timeout(message),
+                    _ => CodifyError::Internal(message),
+                }
+            })?;
+        client.notify("initialized", json!({})).await?;
+
+        log::info!("Started language server {} for {}", name, root.display());
+        Ok(client)
+    }
+
+    /// False once the server has exited; it is started again on next use
+    pub fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::SeqCst)
+    }
+
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value, CodifyError> {
+        self.request_within(method, params, REQUEST_TIMEOUT).await
+    }
+
+    async fn request_within(
+        &self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, CodifyError> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, sender);
+
+        let message = json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params});
+        if let Err(e) = write_message(&self.writer, &message).await {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(e);
+        }
+
+        match tokio::time::timeout(timeout, receiver).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(CodifyError::Internal(format!(
+                "Language server exited: {}",
+                self.name
+            ))),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                Err(CodifyError::Timeout(format!(
+                    "Language server {} timed out after {}s on {}",
+                    self.name,
+                    timeout.as_secs(),
+                    method
+                )))
+            }
+        }
+    }
+
+    pub async fn notify(&self, method: &str, params: Value) -> Result<(), CodifyError> {
+        let message = json!({"jsonrpc": "2.0", "method": method, "params": params});
+        write_message(&self.writer, &message).await
+    }
+
+    /// Send the document's current text: didOpen the first time, a full
+    /// didChange after that
+    pub async fn sync_document(
+        &self,
+        uri: &str,
+        language_id: &str,
+        text: &str,
+    ) -> Result<(), CodifyError> {
+        let version = {
+            let mut versions = self.versions.lock().unwrap();
+            let version = versions.entry(uri.to_string()).or_insert(0);
+            *version += 1;
+            *version
+        };
+
+        if version == 1 {
+            self.notify(
+                "textDocument/didOpen",
+                json!({"textDocument": {
+                    "uri": uri,
+                    "languageId": language_id,
+                    "version": version,
+                    "text": text,
+                }}),
+            )
+            .await
+        } else {
+            self.notify(
+                "textDocument/didChange",
+                json!({
+                    "textDocument": {"uri": uri, "version": version},
+                    "contentChanges": [{"text": text}],
+                }),
+            )
+            .await
+        }
+    }
+}
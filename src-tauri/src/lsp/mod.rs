@@ -0,0 +1,308 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::ai::{project_key, Position};
+use crate::error::CodifyError;
+use crate::storage::{detect_language, resolve_in_project};
+
+mod client;
+mod servers;
+
+pub use client::*;
+pub use servers::*;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LspRange {
+    pub start: Position,
+    pub end: Position, // Exclusive, as the server reports it
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LspHover {
+    pub contents: String, // Markdown; code blocks fenced with their language
+    pub range: Option<LspRange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LspLocation {
+    pub file_path: String, // Project-relative, or absolute outside the project (e.g. a library)
+    pub range: LspRange,
+}
+
+/// Running language servers, one per server per workspace
+#[derive(Default)]
+pub struct LspState {
+    // Held while a server starts, so concurrent requests don't start two
+    servers: tokio::sync::Mutex<HashMap<(String, &'static str), Arc<LspClient>>>,
+}
+
+impl LspState {
+    /// The workspace's server, started on first use or after it exited
+    async fn server(
+        &self,
+        project_path: &str,
+        spec: &'static ServerSpec,
+    ) -> Result<Arc<LspClient>, CodifyError> {
+        let key = (project_key(project_path), spec.name);
+        let mut servers = self.servers.lock().await;
+        if let Some(client) = servers.get(&key).filter(|client| client.is_alive()) {
+            return Ok(client.clone());
+        }
+
+        let program = locate_server(spec, project_path)?;
+        let root = Path::new(project_path).canonicalize().map_err(|_| {
+            CodifyError::NotFound(format!("Project path does not exist: {}", project_path))
+        })?;
+        let client = Arc::new(LspClient::start(spec.name, &program, spec.args, &root).await?);
+        servers.insert(key, client.clone());
+        Ok(client)
+    }
+
+    /// Stop a workspace's servers; returns how many were running
+    pub async fn stop(&self, project_path: &str) -> usize {
+        let workspace = project_key(project_path);
+        let mut servers = self.servers.lock().await;
+        let before = servers.len();
+        // Dropping the last handle kills the process
+        servers.retain(|(key, _), _| *key != workspace);
+        before - servers.len()
+    }
+}
+
+/// A file synced to its server, ready for position requests
+struct OpenDocument {
+    client: Arc<LspClient>,
+    uri: String,
+    text: String,
+    root: std::path::PathBuf,
+}
+
+/// Sync `path` to its language's server, sending `content` (an unsaved
+/// editor buffer) in place of the file on disk when given
+async fn open_document(
+    state: &LspState,
+    project_path: &str,
+    path: &str,
+    content: Option<String>,
+) -> Result<OpenDocument, CodifyError> {
+    let full_path = resolve_in_project(project_path, path)?;
+    let root = Path::new(project_path).canonicalize().map_err(|_| {
+        CodifyError::NotFound(format!("Project path does not exist: {}", project_path))
+    })?;
+    let text = match content {
+        Some(content) => content,
+        None => tokio::fs::read_to_string(&full_path)
+            .await
+            .map_err(|e| CodifyError::Io(format!("Failed to read {}: {}", path, e)))?,
+    };
+
+    let language = detect_language(&full_path, Some(&text));
+    let spec = server_for(&language).ok_or_else(|| {
+        CodifyError::InvalidInput(format!(
+            "Language {} is not supported by a language server",
+            language
+        ))
+    })?;
+    let client = state.server(project_path, spec).await?;
+
+    let uri = path_to_uri(&full_path);
+    client.sync_document(&uri, &language, &text).await?;
+
+    Ok(OpenDocument {
+        client,
+        uri,
+        text,
+        root,
+    })
+}
+
+/// LSP position: 0-based line, and the column in UTF-16 code units
+fn to_lsp_position(text: &str, position: &Position) -> Value {
+    let line = position.line.max(1) - 1;
+    let character: usize = text
+        .lines()
+        .nth(line as usize)
+        .unwrap_or_default()
+        .chars()
+        .take(position.column.max(1) as usize - 1)
+        .map(char::len_utf16)
+        .sum();
+    json!({"line": line, "character": character})
+}
+
+/// Back to our 1-based line and character column; without the text, UTF-16
+/// units are taken as characters
+fn from_lsp_position(text: Option<&str>, position: &Value) -> Position {
+    let line = position["line"].as_u64().unwrap_or(0) as u32;
+    let character = position["character"].as_u64().unwrap_or(0) as usize;
+
+    let column = match text.and_then(|text| text.lines().nth(line as usize)) {
+        Some(line_text) => {
+            let mut units = 0;
+            line_text
+                .chars()
+                .take_while(|c| {
+                    units += c.len_utf16();
+                    units <= character
+                })
+                .count()
+        }
+        None => character,
+    };
+    Position {
+        line: line + 1,
+        column: column as u32 + 1,
+    }
+}
+
+fn from_lsp_range(text: Option<&str>, range: &Value) -> LspRange {
+    LspRange {
+        start: from_lsp_position(text, &range["start"]),
+        end: from_lsp_position(text, &range["end"]),
+    }
+}
+
+/// Hover contents in any of the shapes servers send, as one markdown string
+fn hover_text(contents: &Value) -> String {
+    match contents {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(hover_text)
+            .filter(|text| !text.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        // MarkedString with a language, or MarkupContent
+        Value::Object(_) => match (contents["language"].as_str(), contents["value"].as_str()) {
+            (Some(language), Some(value)) => format!("```{}\n{}\n```", language, value),
+            (None, Some(value)) => value.to_string(),
+            _ => String::new(),
+        },
+        _ => String::new(),
+    }
+}
+
+/// Hover info (types, docs) for the symbol at `position`; None when the
+/// server has nothing to show there
+#[tauri::command]
+pub async fn lsp_hover(
+    state: tauri::State<'_, LspState>,
+    project_path: String,
+    path: String,
+    position: Position,
+    content: Option<String>,
+) -> Result<Option<LspHover>, CodifyError> {
+    log::info!(
+        "LSP hover in {} at {}:{}",
+        path,
+        position.line,
+        position.column
+    );
+
+    let document = open_document(&state, &project_path, &path, content).await?;
+    let result = document
+        .client
+        .request(
+            "textDocument/hover",
+            json!({
+                "textDocument": {"uri": document.uri},
+                "position": to_lsp_position(&document.text, &position),
+            }),
+        )
+        .await?;
+    if result.is_null() {
+        return Ok(None);
+    }
+
+    let contents = hover_text(&result["contents"]);
+    if contents.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(LspHover {
+        contents,
+        range: result
+            .get("range")
+            .map(|range| from_lsp_range(Some(&document.text), range)),
+    }))
+}
+
+/// Where the symbol at `position` is defined; empty when the server can't
+/// resolve it
+#[tauri::command]
+pub async fn lsp_definition(
+    state: tauri::State<'_, LspState>,
+    project_path: String,
+    path: String,
+    position: Position,
+    content: Option<String>,
+) -> Result<Vec<LspLocation>, CodifyError> {
+    log::info!(
+        "LSP definition in {} at {}:{}",
+        path,
+        position.line,
+        position.column
+    );
+
+    let document = open_document(&state, &project_path, &path, content).await?;
+    let result = document
+        .client
+        .request(
+            "textDocument/definition",
+            json!({
+                "textDocument": {"uri": document.uri},
+                "position": to_lsp_position(&document.text, &position),
+            }),
+        )
+        .await?;
+
+    // Location, Location[] or LocationLink[]
+    let targets = match result {
+        Value::Array(items) => items,
+        Value::Null => vec![],
+        single => vec![single],
+    };
+
+    let mut locations = vec![];
+    for target in &targets {
+        let uri = target["targetUri"].as_str().or(target["uri"].as_str());
+        let Some(target_path) = uri.and_then(uri_to_path) else {
+            continue;
+        };
+        let range = match target.get("targetSelectionRange") {
+            Some(range) => range,
+            None => &target["range"],
+        };
+
+        // Columns are converted against the text the server saw
+        let text = if uri == Some(document.uri.as_str()) {
+            Some(document.text.clone())
+        } else {
+            tokio::fs::read_to_string(&target_path).await.ok()
+        };
+        let file_path = match target_path.strip_prefix(&document.root) {
+            Ok(relative) => relative.to_string_lossy().replace('\\', "/"),
+            Err(_) => target_path.to_string_lossy().to_string(),
+        };
+
+        locations.push(LspLocation {
+            file_path,
+            range: from_lsp_range(text.as_deref(), range),
+        });
+    }
+    Ok(locations)
+}
+
+/// Stop the project's language servers, e.g. when it is closed; returns how
+/// many were running
+#[tauri::command]
+pub async fn lsp_stop(
+    state: tauri::State<'_, LspState>,
+    project_path: String,
+) -> Result<usize, CodifyError> {
+    log::info!("Stopping language servers for: {}", project_path);
+
+    Ok(state.stop(&project_path).await)
+}
@@ -0,0 +1,58 @@
+use crate::commands::find_program;
+use crate::error::CodifyError;
+use std::path::{Path, PathBuf};
+
+/// A language server Codify knows how to run
+pub struct ServerSpec {
+    pub name: &'static str, // Its binary, and the key one workspace's server is shared under
+    pub args: &'static [&'static str],
+    pub install: &'static str, // How to get it when it isn't found
+}
+
+const TYPESCRIPT: ServerSpec = ServerSpec {
+    name: "typescript-language-server",
+    args: &["--stdio"],
+    install: "npm install -D typescript-language-server typescript",
+};
+
+const RUST: ServerSpec = ServerSpec {
+    name: "rust-analyzer",
+    args: &[],
+    install: "rustup component add rust-analyzer",
+};
+
+const PYTHON: ServerSpec = ServerSpec {
+    name: "pyright-langserver",
+    args: &["--stdio"],
+    install: "npm install -g pyright",
+};
+
+/// The server for a detect_language id; TypeScript's serves JavaScript too
+pub fn server_for(language: &str) -> Option<&'static ServerSpec> {
+    match language {
+        "typescript" | "typescriptreact" | "javascript" | "javascriptreact" => Some(&TYPESCRIPT),
+        "rust" => Some(&RUST),
+        "python" => Some(&PYTHON),
+        _ => None,
+    }
+}
+
+/// The server binary, preferring the project's own node_modules/.bin over PATH
+pub fn locate_server(spec: &ServerSpec, project_path: &str) -> Result<PathBuf, CodifyError> {
+    let root = Path::new(project_path);
+    let mut dirs = vec![root.join("node_modules").join(".bin")];
+    if let Some(path) = std::env::var_os("PATH") {
+        dirs.extend(std::env::split_paths(&path));
+    }
+    let path = std::env::join_paths(dirs)
+        .map_err(|e| CodifyError::Internal(format!("Invalid PATH: {}", e)))?
+        .to_string_lossy()
+        .to_string();
+
+    find_program(spec.name, &path, root).ok_or_else(|| {
+        CodifyError::NotFound(format!(
+            "Language server not found: {}; install it with '{}'",
+            spec.name, spec.install
+        ))
+    })
+}
@@ -0,0 +1,43 @@
+use crate::ai::ProviderKind;
+
+const KEYCHAIN_SERVICE: &str = "codify";
+
+fn entry(provider: &ProviderKind) -> Option<keyring::Entry> {
+    keyring::Entry::new(
+        KEYCHAIN_SERVICE,
+        &format!("{:?}-api-key", provider).to_lowercase(),
+    )
+    .ok()
+}
+
+/// Store a provider's API key in the OS keychain; false if no keychain is usable
+pub fn store_api_key(provider: &ProviderKind, key: &str) -> bool {
+    match entry(provider).map(|entry| entry.set_password(key)) {
+        Some(Ok(())) => true,
+        Some(Err(e)) => {
+            log::warn!("Failed to store API key in keychain: {}", e);
+            false
+        }
+        None => false,
+    }
+}
+
+pub fn load_api_key(provider: &ProviderKind) -> Option<String> {
+    match entry(provider)?.get_password() {
+        Ok(key) => Some(key),
+        Err(keyring::Error::NoEntry) => None,
+        Err(e) => {
+            log::warn!("Failed to read API key from keychain: {}", e);
+            None
+        }
+    }
+}
+
+pub fn delete_api_key(provider: &ProviderKind) {
+    if let Some(entry) = entry(provider) {
+        match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => log::warn!("Failed to remove API key from keychain: {}", e),
+        }
+    }
+}
@@ -0,0 +1,499 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use tauri::Manager;
+
+use crate::ai::{
+    builtin_template, default_prices, project_key, prompt_template_infos, set_secret_policy,
+    AIState, CompletionRag, CompletionWindow, GenerationParams, ModelConfig, ModelOverride,
+    ModelPrice, PromptTemplate, PromptTemplateInfo, SecretPolicy, DEFAULT_SEARCH_DEBOUNCE_MS,
+    MAX_SEARCH_DEBOUNCE_MS,
+};
+use crate::commands::CommandPolicy;
+use crate::error::CodifyError;
+use crate::storage::{
+    set_fallback_chunking, set_file_limits, write_project_file, FallbackChunking, FileLimits,
+    Workspace,
+};
+
+mod keychain;
+
+pub use keychain::*;
+
+const SETTINGS_FILE: &str = "settings.json";
+
+/// Everything the user can configure, persisted as JSON in the app config dir
+///
+/// The API key is kept in the OS keychain when one is available and is only
+/// written to the JSON file as a fallback.
+///
+/// Model settings resolve per project: a `projects` override wins over
+/// `model`, which wins over ModelConfig's built-in defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub ai_enabled: bool, // Off: AI commands fail as unavailable; editor features are unaffected
+    pub auto_reindex: bool, // Re-embed changed files of an indexed project as they are saved
+    /// Reach the provider (loading a local model) at launch so the first
+    /// request is fast; costs a one-token request on paid providers
+    pub warmup_on_startup: bool,
+    /// Keep a local record of which completions were accepted; nothing is sent anywhere
+    pub completion_feedback: bool,
+    pub model: ModelConfig,
+    pub generation: GenerationParams, // User defaults, below per-request overrides
+    pub projects: BTreeMap<String, ModelOverride>, // Keyed by project path
+    pub workspaces: BTreeMap<String, Workspace>, // Keyed by workspace id
+    /// Project roots chosen by the user over find_project_root's detection,
+    /// keyed by the folder (and everything under it) they apply to
+    pub project_roots: BTreeMap<String, String>,
+    pub prompt_templates: BTreeMap<String, PromptTemplate>, // Keyed by template name
+    pub file_limits: FileLimits,
+    /// Line windows for indexing files tree-sitter can't parse
+    pub fallback_chunking: FallbackChunking,
+    /// Programs the terminal commands may run
+    pub command_policy: CommandPolicy,
+    pub completion_rag: CompletionRag,
+    /// Lines of the current file around the cursor that completions see
+    pub completion_window: CompletionWindow,
+    /// How long search_code_semantic waits before searching for a session,
+    /// so typing doesn't embed every keystroke
+    pub search_debounce_ms: u64,
+    /// Dollars per 1,000 tokens, keyed by model name, for estimate_cost
+    pub prices: BTreeMap<String, ModelPrice>,
+    /// What to do with API keys and other secrets in requests to a remote
+    /// provider; credential files are also hidden and left unindexed unless off
+    pub secret_policy: SecretPolicy,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            ai_enabled: true,
+            auto_reindex: true,
+            warmup_on_startup: false,
+            completion_feedback: true,
+            model: ModelConfig::default(),
+            generation: GenerationParams::default(),
+            projects: BTreeMap::new(),
+            workspaces: BTreeMap::new(),
+            project_roots: BTreeMap::new(),
+            prompt_templates: BTreeMap::new(),
+            file_limits: FileLimits::default(),
+            fallback_chunking: FallbackChunking::default(),
+            command_policy: CommandPolicy::default(),
+            completion_rag: CompletionRag::default(),
+            completion_window: CompletionWindow::default(),
+            search_debounce_ms: DEFAULT_SEARCH_DEBOUNCE_MS,
+            prices: default_prices(),
+            secret_policy: SecretPolicy::default(),
+        }
+    }
+}
+
+fn validate_model(model: &ModelConfig, prefix: &str) -> Result<(), CodifyError> {
+    if model.base_url.trim().is_empty() {
+        return Err(CodifyError::InvalidInput(format!(
+            "{}.base_url must not be empty",
+            prefix
+        )));
+    }
+    if model.max_context_tokens == 0 {
+        return Err(CodifyError::InvalidInput(format!(
+            "{}.max_context_tokens must be greater than 0",
+            prefix
+        )));
+    }
+    if model.request_timeout_ms == Some(0) {
+        return Err(CodifyError::InvalidInput(format!(
+            "{}.request_timeout_ms must be greater than 0",
+            prefix
+        )));
+    }
+    Ok(())
+}
+
+impl Settings {
+    fn validate(&self) -> Result<(), CodifyError> {
+        validate_model(&self.model, "model")?;
+        for (project, model_override) in &self.projects {
+            let merged = model_override.apply(self.model.clone());
+            validate_model(&merged, &format!("projects[{}]", project))?;
+        }
+        // A template for a command that no longer exists is ignored, not fatal
+        for (name, template) in &self.prompt_templates {
+            if builtin_template(name).is_some() {
+                template.validate(name)?;
+            }
+        }
+        for (folder, root) in &self.project_roots {
+            if root.trim().is_empty() {
+                return Err(CodifyError::InvalidInput(format!(
+                    "project_roots[{}] must not be empty",
+                    folder
+                )));
+            }
+        }
+        self.file_limits.validate()?;
+        self.fallback_chunking.validate()?;
+        self.command_policy.validate()?;
+        self.completion_rag.validate()?;
+        self.completion_window.validate()?;
+        if self.search_debounce_ms > MAX_SEARCH_DEBOUNCE_MS {
+            return Err(CodifyError::InvalidInput(format!(
+                "Invalid search_debounce_ms: must be at most {}",
+                MAX_SEARCH_DEBOUNCE_MS
+            )));
+        }
+        for (model, price) in &self.prices {
+            price.validate(model)?;
+        }
+        self.generation.validate()
+    }
+
+    /// Every project path the settings mention: per-project overrides,
+    /// workspace folders and chosen project roots
+    pub fn project_paths(&self) -> BTreeSet<&str> {
+        let folders = self.workspaces.values().flat_map(|w| &w.folders);
+        self.projects
+            .keys()
+            .map(String::as_str)
+            .chain(folders.map(|folder| folder.path.as_str()))
+            .chain(self.project_roots.values().map(String::as_str))
+            .collect()
+    }
+}
+
+fn settings_path(app: &tauri::AppHandle) -> Result<PathBuf, CodifyError> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(SETTINGS_FILE))
+        .map_err(|e| CodifyError::Io(format!("Failed to locate config directory: {}", e)))
+}
+
+/// Read settings from disk, falling back to defaults when the file is
+/// missing (first run) or unusable
+pub fn read_settings(app: &tauri::AppHandle) -> Settings {
+    let path = match settings_path(app) {
+        Ok(path) => path,
+        Err(e) => {
+            log::warn!("{}; using default settings", e);
+            return Settings::default();
+        }
+    };
+
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return Settings::default();
+    };
+
+    let mut settings = match serde_json::from_str::<Settings>(&text) {
+        Ok(settings) => settings,
+        Err(e) => {
+            log::warn!(
+                "Settings file {} is corrupt ({}); using defaults",
+                path.display(),
+                e
+            );
+            return Settings::default();
+        }
+    };
+
+    if let Err(e) = settings.validate() {
+        log::warn!(
+            "Settings file {} is invalid ({}); using defaults",
+            path.display(),
+            e
+        );
+        return Settings::default();
+    }
+
+    if settings.model.api_key.is_none() {
+        settings.model.api_key = load_api_key(&settings.model.provider);
+    }
+
+    settings
+}
+
+/// Validate and persist settings, moving the API key into the keychain when possible
+pub fn write_settings(app: &tauri::AppHandle, settings: &Settings) -> Result<(), CodifyError> {
+    settings.validate()?;
+
+    let path = settings_path(app)?;
+    let dir = path
+        .parent()
+        .ok_or_else(|| CodifyError::Io("Invalid config directory".to_string()))?;
+    std::fs::create_dir_all(dir)
+        .map_err(|e| CodifyError::Io(format!("Failed to create config directory: {}", e)))?;
+
+    let mut on_disk = settings.clone();
+    match &settings.model.api_key {
+        Some(key) => {
+            if store_api_key(&settings.model.provider, key) {
+                on_disk.model.api_key = None;
+            } else {
+                log::warn!(
+                    "Keychain unavailable; storing the API key in {}",
+                    path.display()
+                );
+            }
+        }
+        None => delete_api_key(&settings.model.provider),
+    }
+
+    let json = serde_json::to_string_pretty(&on_disk)
+        .map_err(|e| CodifyError::Internal(format!("Failed to encode settings: {}", e)))?;
+    write_project_file(&dir.to_string_lossy(), SETTINGS_FILE, &json, false)?;
+
+    Ok(())
+}
+
+/// Make `settings` the live configuration
+pub async fn apply_settings(state: &AIState, settings: &Settings) {
+    // A project on another provider needs that provider's key
+    let projects = settings
+        .projects
+        .iter()
+        .map(|(project, model_override)| {
+            let mut model_override = model_override.clone();
+            model_override.api_key = model_override
+                .provider
+                .as_ref()
+                .filter(|provider| **provider != settings.model.provider)
+                .and_then(load_api_key);
+            (project_key(project), model_override)
+        })
+        .collect();
+
+    *state.config.write().await = settings.model.clone();
+    *state.user_params.write().unwrap() = settings.generation.clone();
+    *state.project_overrides.write().unwrap() = projects;
+    set_file_limits(&settings.file_limits);
+    set_fallback_chunking(&settings.fallback_chunking);
+    set_secret_policy(settings.secret_policy);
+    *state.prompt_templates.write().unwrap() = settings
+        .prompt_templates
+        .iter()
+        .map(|(name, template)| (name.clone(), template.clone()))
+        .collect();
+    *state.completion_rag.write().unwrap() = settings.completion_rag.clone();
+    *state.completion_window.write().unwrap() = settings.completion_window.clone();
+    *state.prices.write().unwrap() = settings.prices.clone();
+    state
+        .enabled
+        .store(settings.ai_enabled, std::sync::atomic::Ordering::Relaxed);
+    state
+        .auto_reindex
+        .store(settings.auto_reindex, std::sync::atomic::Ordering::Relaxed);
+    state.search_debounce_ms.store(
+        settings.search_debounce_ms,
+        std::sync::atomic::Ordering::Relaxed,
+    );
+    state.completion_feedback.store(
+        settings.completion_feedback,
+        std::sync::atomic::Ordering::Relaxed,
+    );
+}
+
+/// Save and apply a new global chat model
+pub async fn set_chat_model(
+    app: &tauri::AppHandle,
+    state: &AIState,
+    model: &str,
+) -> Result<(), CodifyError> {
+    let mut settings = read_settings(app);
+    settings.model.model = model.to_string();
+
+    write_settings(app, &settings)?;
+    apply_settings(state, &settings).await;
+    Ok(())
+}
+
+/// Save and apply a new embedding model for `project_path`: in its override
+/// when that already sets one, otherwise globally
+pub async fn set_embedding_model(
+    app: &tauri::AppHandle,
+    state: &AIState,
+    project_path: &str,
+    model: &str,
+) -> Result<(), CodifyError> {
+    let mut settings = read_settings(app);
+    let key = project_key(project_path);
+    let project_override = settings
+        .projects
+        .iter_mut()
+        .find(|(project, model_override)| {
+            project_key(project) == key && model_override.embedding_model.is_some()
+        });
+    match project_override {
+        Some((_, model_override)) => model_override.embedding_model = Some(model.to_string()),
+        None => settings.model.embedding_model = model.to_string(),
+    }
+
+    write_settings(app, &settings)?;
+    apply_settings(state, &settings).await;
+    Ok(())
+}
+
+/// Load saved settings (or defaults on first run) and make them active
+#[tauri::command]
+pub async fn load_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AIState>,
+) -> Result<Settings, CodifyError> {
+    log::info!("Loading settings");
+
+    let settings = read_settings(&app);
+    apply_settings(&state, &settings).await;
+
+    Ok(settings)
+}
+
+/// Persist settings and make them active
+#[tauri::command]
+pub async fn save_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AIState>,
+    settings: Settings,
+) -> Result<(), CodifyError> {
+    log::info!("Saving settings");
+
+    write_settings(&app, &settings)?;
+    apply_settings(&state, &settings).await;
+
+    Ok(())
+}
+
+/// Set or, with no config, remove a project's model override, then persist
+/// and apply it
+///
+/// The override applies to AI commands that run against the project, on top
+/// of the global model settings.
+#[tauri::command]
+pub async fn set_project_model(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AIState>,
+    project_path: String,
+    config: Option<ModelOverride>,
+) -> Result<(), CodifyError> {
+    log::info!("Setting model override for: {}", project_path);
+
+    let mut settings = read_settings(&app);
+    let key = project_key(&project_path);
+    match config {
+        Some(config) => {
+            settings.projects.insert(key, config);
+        }
+        None => {
+            settings.projects.remove(&key);
+        }
+    }
+
+    write_settings(&app, &settings)?;
+    apply_settings(&state, &settings).await;
+
+    Ok(())
+}
+
+/// Every AI command's prompt template, with the user's customizations
+#[tauri::command]
+pub async fn list_prompt_templates(
+    app: tauri::AppHandle,
+) -> Result<Vec<PromptTemplateInfo>, CodifyError> {
+    Ok(prompt_template_infos(&read_settings(&app).prompt_templates))
+}
+
+/// Replace a command's prompt template or, with no template, restore the
+/// built-in one, then persist and apply it
+///
+/// Templates use `{{placeholder}}`s from the command's placeholder list; an
+/// unknown placeholder is rejected.
+#[tauri::command]
+pub async fn set_prompt_template(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AIState>,
+    name: String,
+    template: Option<PromptTemplate>,
+) -> Result<PromptTemplateInfo, CodifyError> {
+    log::info!("Setting prompt template: {}", name);
+
+    let mut settings = read_settings(&app);
+    match template {
+        Some(template) => {
+            template.validate(&name)?;
+            settings.prompt_templates.insert(name.clone(), template);
+        }
+        None => {
+            settings.prompt_templates.remove(&name);
+        }
+    }
+
+    write_settings(&app, &settings)?;
+    apply_settings(&state, &settings).await;
+
+    prompt_template_infos(&settings.prompt_templates)
+        .into_iter()
+        .find(|info| info.name == name)
+        .ok_or_else(|| CodifyError::NotFound(format!("Prompt template not found: {}", name)))
+}
+
+/// Saved workspaces, in id order
+#[tauri::command]
+pub async fn list_workspaces(app: tauri::AppHandle) -> Result<Vec<Workspace>, CodifyError> {
+    Ok(read_settings(&app).workspaces.into_values().collect())
+}
+
+/// Add a root folder to a workspace and persist it; with no workspace_id a
+/// new workspace named after the folder is created around it
+#[tauri::command]
+pub async fn add_workspace_folder(
+    app: tauri::AppHandle,
+    workspace_id: Option<String>,
+    path: String,
+    name: Option<String>,
+) -> Result<Workspace, CodifyError> {
+    log::info!("Adding workspace folder: {}", path);
+
+    let mut settings = read_settings(&app);
+    let mut workspace = match workspace_id {
+        Some(id) => settings
+            .workspaces
+            .remove(&id)
+            .ok_or_else(|| CodifyError::NotFound(format!("Workspace not found: {}", id)))?,
+        None => Workspace::new(
+            &std::path::Path::new(&path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.clone()),
+        ),
+    };
+    workspace.add_folder(&path, name)?;
+
+    settings
+        .workspaces
+        .insert(workspace.id.clone(), workspace.clone());
+    write_settings(&app, &settings)?;
+
+    Ok(workspace)
+}
+
+/// Remove a root folder from a workspace and persist it; the workspace is
+/// kept even when its last folder goes
+#[tauri::command]
+pub async fn remove_workspace_folder(
+    app: tauri::AppHandle,
+    workspace_id: String,
+    path: String,
+) -> Result<Workspace, CodifyError> {
+    log::info!("Removing workspace folder: {}", path);
+
+    let mut settings = read_settings(&app);
+    let workspace = settings
+        .workspaces
+        .get_mut(&workspace_id)
+        .ok_or_else(|| CodifyError::NotFound(format!("Workspace not found: {}", workspace_id)))?;
+    workspace.remove_folder(&path)?;
+    let workspace = workspace.clone();
+
+    write_settings(&app, &settings)?;
+
+    Ok(workspace)
+}
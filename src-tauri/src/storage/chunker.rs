@@ -0,0 +1,577 @@
+use super::{is_js_family, CodeEmbedding};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tree_sitter::{Language, Node, Parser};
+
+/// Upper bound on chunk length so one huge definition doesn't dominate a vector
+const MAX_CHUNK_LINES: usize = 200;
+
+/// Default window for languages without a grammar
+pub const FALLBACK_CHUNK_LINES: usize = 120;
+pub const FALLBACK_OVERLAP_LINES: usize = 20;
+
+static CHUNK_LINES: AtomicUsize = AtomicUsize::new(FALLBACK_CHUNK_LINES);
+static OVERLAP_LINES: AtomicUsize = AtomicUsize::new(FALLBACK_OVERLAP_LINES);
+
+/// Fixed line windows for files tree-sitter can't split into definitions
+///
+/// Smaller windows make search results more precise at the cost of more
+/// rows; overlap repeats lines on both sides of a boundary so code near one
+/// isn't cut off from its context. Files already indexed keep their chunks
+/// until they change or the index is rebuilt.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FallbackChunking {
+    pub chunk_lines: usize,
+    pub overlap_lines: usize, // Shared by neighbouring windows; less than chunk_lines
+}
+
+impl Default for FallbackChunking {
+    fn default() -> Self {
+        FallbackChunking {
+            chunk_lines: FALLBACK_CHUNK_LINES,
+            overlap_lines: FALLBACK_OVERLAP_LINES,
+        }
+    }
+}
+
+impl FallbackChunking {
+    pub fn validate(&self) -> Result<(), CodifyError> {
+        if self.chunk_lines == 0 || self.chunk_lines > MAX_CHUNK_LINES {
+            return Err(CodifyError::InvalidInput(format!(
+                "Invalid fallback_chunking.chunk_lines: must be between 1 and {}",
+                MAX_CHUNK_LINES
+            )));
+        }
+        if self.overlap_lines >= self.chunk_lines {
+            return Err(CodifyError::InvalidInput(
+                "Invalid fallback_chunking.overlap_lines: must be less than chunk_lines"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The fallback window currently in effect
+pub fn fallback_chunking() -> FallbackChunking {
+    FallbackChunking {
+        chunk_lines: CHUNK_LINES.load(Ordering::Relaxed),
+        overlap_lines: OVERLAP_LINES.load(Ordering::Relaxed),
+    }
+}
+
+pub fn set_fallback_chunking(chunking: &FallbackChunking) {
+    CHUNK_LINES.store(chunking.chunk_lines, Ordering::Relaxed);
+    OVERLAP_LINES.store(chunking.overlap_lines, Ordering::Relaxed);
+}
+
+/// A run of top-level syntax nodes that becomes one chunk (rows are 0-based, inclusive)
+struct Segment<'tree> {
+    start: usize,
+    end: usize,
+    kind: &'static str,
+    loose: bool, // Top-level statements that aren't definitions
+    nodes: Vec<Node<'tree>>,
+}
+
+fn grammar(language: &str) -> Option<Language> {
+    match language {
+        // TSX needs its own grammar; the plain one rejects JSX
+        "typescriptreact" => Some(tree_sitter_typescript::language_tsx()),
+        "typescript" => Some(tree_sitter_typescript::language_typescript()),
+        "javascript" | "javascriptreact" => Some(tree_sitter_javascript::language()),
+        "rust" => Some(tree_sitter_rust::language()),
+        "python" => Some(tree_sitter_python::language()),
+        _ => None,
+    }
+}
+
+/// Comments and attributes attach to the definition that follows them
+fn is_leading_trivia(kind: &str) -> bool {
+    matches!(
+        kind,
+        "comment" | "line_comment" | "block_comment" | "attribute_item" | "inner_attribute_item"
+    )
+}
+
+/// Classify a top-level node, returning its code_type if it is a definition
+fn definition_kind(node: Node, language: &str) -> Option<&'static str> {
+    match language {
+        language if is_js_family(language) => match node.kind() {
+            "import_statement" => Some("import"),
+            "function_declaration" | "generator_function_declaration" | "function_signature" => {
+                Some("function")
+            }
+            "class_declaration" | "abstract_class_declaration" => Some("class"),
+            "interface_declaration" | "type_alias_declaration" | "enum_declaration" => Some("type"),
+            "internal_module" | "module" => Some("module"),
+            "lexical_declaration" | "variable_declaration" => {
+                if declares_function(node) {
+                    Some("function")
+                } else {
+                    Some("variable")
+                }
+            }
+            // `export { a } from "./a"` and `export * from "./a"` are re-exports
+            "export_statement" if node.child_by_field_name("source").is_some() => Some("import"),
+            "export_statement" => node
+                .child_by_field_name("declaration")
+                .and_then(|declaration| definition_kind(declaration, language))
+                .or_else(|| match node.child_by_field_name("value")?.kind() {
+                    "arrow_function" | "function" | "function_expression" => Some("function"),
+                    "class" => Some("class"),
+                    _ => None,
+                }),
+            _ => None,
+        },
+        "rust" => match node.kind() {
+            "use_declaration" | "extern_crate_declaration" => Some("import"),
+            "function_item" | "function_signature_item" | "macro_definition" => Some("function"),
+            "impl_item" => Some("class"),
+            "struct_item" | "enum_item" | "union_item" | "trait_item" | "type_item" => Some("type"),
+            "mod_item" => Some("module"),
+            "const_item" | "static_item" => Some("variable"),
+            _ => None,
+        },
+        "python" => match node.kind() {
+            "import_statement" | "import_from_statement" | "future_import_statement" => {
+                Some("import")
+            }
+            "function_definition" => Some("function"),
+            "class_definition" => Some("class"),
+            "decorated_definition" => node
+                .child_by_field_name("definition")
+                .and_then(|definition| definition_kind(definition, language)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether a `const`/`let`/`var` declaration binds a function value
+fn declares_function(node: Node) -> bool {
+    let mut cursor = node.walk();
+    let declares = node.named_children(&mut cursor).any(|declarator| {
+        declarator
+            .child_by_field_name("value")
+            .map(|value| {
+                matches!(
+                    value.kind(),
+                    "arrow_function" | "function" | "function_expression" | "generator_function"
+                )
+            })
+            .unwrap_or(false)
+    });
+    declares
+}
+
+fn text<'a>(node: Node, source: &'a str) -> &'a str {
+    node.utf8_text(source.as_bytes()).unwrap_or_default()
+}
+
+/// Module paths named by an import node
+fn import_sources(node: Node, source: &str, language: &str) -> Vec<String> {
+    match (language, node.kind()) {
+        (language, _) if is_js_family(language) => node
+            .child_by_field_name("source")
+            .map(|s| {
+                vec![text(s, source)
+                    .trim_matches(|c| c == '"' || c == '\'' || c == '`')
+                    .to_string()]
+            })
+            .unwrap_or_default(),
+        ("python", "import_statement") => {
+            let mut cursor = node.walk();
+            let sources = node
+                .named_children(&mut cursor)
+                .filter_map(|child| match child.kind() {
+                    "dotted_name" => Some(text(child, source).to_string()),
+                    "aliased_import" => child
+                        .child_by_field_name("name")
+                        .map(|name| text(name, source).to_string()),
+                    _ => None,
+                })
+                .collect();
+            sources
+        }
+        ("python", _) => node
+            .child_by_field_name("module_name")
+            .map(|name| vec![text(name, source).to_string()])
+            .unwrap_or_default(),
+        ("rust", "extern_crate_declaration") => node
+            .child_by_field_name("name")
+            .map(|name| vec![text(name, source).to_string()])
+            .unwrap_or_default(),
+        ("rust", _) => node
+            .child_by_field_name("argument")
+            .map(|argument| {
+                vec![text(argument, source)
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join("")]
+            })
+            .unwrap_or_default(),
+        _ => vec![],
+    }
+}
+
+/// Every identifier under `node`, in a pre-order walk
+fn collect_identifiers<'a>(node: Node, source: &'a str, out: &mut HashSet<&'a str>) {
+    let mut cursor = node.walk();
+    loop {
+        let current = cursor.node();
+        if matches!(current.kind(), "identifier" | "type_identifier") {
+            out.insert(text(current, source));
+        }
+
+        if cursor.goto_first_child() {
+            continue;
+        }
+        loop {
+            if cursor.goto_next_sibling() {
+                break;
+            }
+            if !cursor.goto_parent() {
+                return;
+            }
+        }
+    }
+}
+
+fn end_row(node: Node) -> usize {
+    let end = node.end_position();
+    // A node ending at column 0 finished on the previous line
+    if end.column == 0 && end.row > node.start_position().row {
+        end.row - 1
+    } else {
+        end.row
+    }
+}
+
+/// Group the root's children into definition segments; anything that isn't a
+/// definition is gathered into "module" segments and consecutive imports merge
+fn segments<'tree>(root: Node<'tree>, language: &str) -> Vec<Segment<'tree>> {
+    let mut segments: Vec<Segment> = vec![];
+    let mut leading: Option<usize> = None;
+
+    let mut cursor = root.walk();
+    for child in root.named_children(&mut cursor) {
+        if is_leading_trivia(child.kind()) {
+            leading.get_or_insert(child.start_position().row);
+            continue;
+        }
+
+        let start = leading.take().unwrap_or(child.start_position().row);
+        let definition = definition_kind(child, language);
+        let loose = definition.is_none();
+        let kind = definition.unwrap_or("module");
+
+        match segments.last_mut() {
+            Some(last) if (kind == "import" && last.kind == "import") || (loose && last.loose) => {
+                last.end = end_row(child);
+                last.nodes.push(child);
+            }
+            _ => segments.push(Segment {
+                start,
+                end: end_row(child),
+                kind,
+                loose,
+                nodes: vec![child],
+            }),
+        }
+    }
+
+    // Trailing comments belong with whatever came last
+    if let Some(start) = leading {
+        let end = end_row(root);
+        match segments.last_mut() {
+            Some(last) => last.end = end,
+            None => segments.push(Segment {
+                start,
+                end,
+                kind: "module",
+                loose: true,
+                nodes: vec![],
+            }),
+        }
+    }
+
+    segments
+}
+
+/// Build chunk rows for `[start, end)` lines, splitting anything over MAX_CHUNK_LINES
+fn push_windows(
+    chunks: &mut Vec<CodeEmbedding>,
+    lines: &[&str],
+    file_path: &str,
+    language: &str,
+    (start, end): (usize, usize),
+    kind: &str,
+    dependencies: &[String],
+) {
+    for window_start in (start..end).step_by(MAX_CHUNK_LINES) {
+        let window_end = (window_start + MAX_CHUNK_LINES).min(end);
+        let text = lines[window_start..window_end].join("\n");
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        chunks.push(CodeEmbedding {
+            id: uuid::Uuid::new_v4().to_string(),
+            file_path: file_path.to_string(),
+            start_line: window_start as u32 + 1,
+            end_line: window_end as u32,
+            code_type: kind.to_string(),
+            language: language.to_string(),
+            content: text,
+            embedding: vec![],
+            dependencies: dependencies.to_vec(),
+            distance: None,
+            root: None,
+        });
+    }
+}
+
+/// Build chunk rows for a whole file from overlapping fixed-size windows
+fn push_fallback_windows(
+    chunks: &mut Vec<CodeEmbedding>,
+    lines: &[&str],
+    file_path: &str,
+    language: &str,
+) {
+    let FallbackChunking {
+        chunk_lines,
+        overlap_lines,
+    } = fallback_chunking();
+    let step = chunk_lines.saturating_sub(overlap_lines).max(1);
+
+    let mut start = 0;
+    while start < lines.len() {
+        let end = (start + chunk_lines).min(lines.len());
+        push_windows(
+            chunks,
+            lines,
+            file_path,
+            language,
+            (start, end),
+            "module",
+            &[],
+        );
+        if end == lines.len() {
+            break;
+        }
+        start += step;
+    }
+}
+
+/// Split a file into one chunk per top-level definition using tree-sitter
+///
+/// Each chunk's `dependencies` lists the modules it uses: the sources of an
+/// import chunk, or for other chunks the sources of the imported symbols they
+/// reference. Languages without a grammar, and files that fail to parse, fall
+/// back to line windows sized by FallbackChunking. The returned rows have
+/// empty vectors.
+pub fn chunk_by_function(file_path: &str, content: &str, language: &str) -> Vec<CodeEmbedding> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut chunks = vec![];
+
+    let tree = grammar(language).and_then(|grammar| {
+        let mut parser = Parser::new();
+        parser.set_language(&grammar).ok()?;
+        parser.parse(content, None)
+    });
+
+    let Some(tree) = tree else {
+        push_fallback_windows(&mut chunks, &lines, file_path, language);
+        return chunks;
+    };
+
+    let segments = segments(tree.root_node(), language);
+
+    // Imported symbol -> the module it came from
+    let mut imports: Vec<(HashSet<&str>, String)> = vec![];
+    for segment in segments.iter().filter(|s| s.kind == "import") {
+        for node in &segment.nodes {
+            let mut symbols = HashSet::new();
+            collect_identifiers(*node, content, &mut symbols);
+            for module in import_sources(*node, content, language) {
+                imports.push((symbols.clone(), module));
+            }
+        }
+    }
+
+    for segment in &segments {
+        let mut dependencies: Vec<String> = if segment.kind == "import" {
+            segment
+                .nodes
+                .iter()
+                .flat_map(|node| import_sources(*node, content, language))
+                .collect()
+        } else {
+            let mut used = HashSet::new();
+            for node in &segment.nodes {
+                collect_identifiers(*node, content, &mut used);
+            }
+            imports
+                .iter()
+                .filter(|(symbols, _)| !symbols.is_disjoint(&used))
+                .map(|(_, module)| module.clone())
+                .collect()
+        };
+        dependencies.sort();
+        dependencies.dedup();
+
+        let end = (segment.end + 1).min(lines.len());
+        push_windows(
+            &mut chunks,
+            &lines,
+            file_path,
+            language,
+            (segment.start, end),
+            segment.kind,
+            &dependencies,
+        );
+    }
+
+    chunks
+}
+
+/// Where a documentation comment for a definition belongs
+#[derive(Debug, Clone)]
+pub struct DefinitionSite {
+    pub kind: &'static str, // function, class, type, ...
+    pub name: String,
+    pub signature: String, // Declaration text up to its body
+    pub insert_row: usize, // 0-based row the comment is inserted before
+    pub indent: String,    // Leading whitespace for the comment lines
+}
+
+/// The node whose `body` field holds a definition's body, looking through
+/// export, decorator and `const f = () => {}` wrappers
+fn body_node(node: Node) -> Option<Node> {
+    if let Some(body) = node.child_by_field_name("body") {
+        return Some(body);
+    }
+    if let Some(inner) = node
+        .child_by_field_name("declaration")
+        .or_else(|| node.child_by_field_name("definition"))
+    {
+        return body_node(inner);
+    }
+
+    let mut cursor = node.walk();
+    let declarator = node
+        .named_children(&mut cursor)
+        .find(|child| child.kind() == "variable_declarator");
+    declarator?
+        .child_by_field_name("value")
+        .and_then(|value| value.child_by_field_name("body"))
+}
+
+fn definition_name(node: Node, source: &str) -> String {
+    if let Some(name) = node
+        .child_by_field_name("name")
+        .or_else(|| node.child_by_field_name("type")) // impl blocks
+    {
+        return text(name, source).to_string();
+    }
+    if let Some(inner) = node
+        .child_by_field_name("declaration")
+        .or_else(|| node.child_by_field_name("definition"))
+    {
+        return definition_name(inner, source);
+    }
+
+    let mut cursor = node.walk();
+    let name = node
+        .named_children(&mut cursor)
+        .find(|child| child.kind() == "variable_declarator")
+        .and_then(|declarator| declarator.child_by_field_name("name"))
+        .map(|name| text(name, source).to_string());
+    name.unwrap_or_default()
+}
+
+fn leading_whitespace(line: &str) -> String {
+    line.chars().take_while(|c| c.is_whitespace()).collect()
+}
+
+/// Find the first function, class or type definition in `content` and where
+/// its doc comment goes: above it (including any `export` and Rust attributes),
+/// or for Python, as the first statement of its body
+pub fn find_definition(content: &str, language: &str) -> Option<DefinitionSite> {
+    let mut parser = Parser::new();
+    parser.set_language(&grammar(language)?).ok()?;
+    let tree = parser.parse(content, None)?;
+    let lines: Vec<&str> = content.lines().collect();
+
+    // Pre-order walk so the outermost definition wins over what it contains
+    let mut cursor = tree.root_node().walk();
+    let (node, kind) = loop {
+        let node = cursor.node();
+        let kind = match node.kind() {
+            "method_definition" => Some("function"),
+            _ => definition_kind(node, language)
+                .filter(|kind| !matches!(*kind, "import" | "variable" | "module")),
+        };
+        if let Some(kind) = kind {
+            break (node, kind);
+        }
+
+        if cursor.goto_first_child() {
+            continue;
+        }
+        loop {
+            if cursor.goto_next_sibling() {
+                break;
+            }
+            if !cursor.goto_parent() {
+                return None;
+            }
+        }
+    };
+
+    let body = body_node(node);
+    let signature = match body {
+        Some(body) => &content[node.start_byte()..body.start_byte()],
+        None => lines.get(node.start_position().row).copied().unwrap_or_default(),
+    }
+    .trim()
+    .trim_end_matches(':')
+    .to_string();
+
+    let (insert_row, indent) = if language == "python" {
+        let header_row = node.start_position().row;
+        match body.map(|b| b.start_position().row).filter(|row| *row > header_row) {
+            Some(row) => {
+                let line = lines.get(row).copied().unwrap_or_default();
+                (row, leading_whitespace(line))
+            }
+            // `def f(): return 1` has no body line to sit above
+            None => {
+                let header = leading_whitespace(lines[header_row]);
+                (header_row + 1, format!("{}    ", header))
+            }
+        }
+    } else {
+        let mut start = node;
+        while let Some(previous) = start.prev_named_sibling() {
+            if !matches!(previous.kind(), "attribute_item") {
+                break;
+            }
+            start = previous;
+        }
+        let row = start.start_position().row;
+        (row, leading_whitespace(lines.get(row).copied().unwrap_or_default()))
+    };
+
+    Some(DefinitionSite {
+        kind,
+        name: definition_name(node, content),
+        signature,
+        insert_row,
+        indent,
+    })
+}
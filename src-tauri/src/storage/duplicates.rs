@@ -0,0 +1,163 @@
+use super::{CodeEmbedding, SearchBackend, VectorDb};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Similarity two chunks need to count as duplicates when the caller doesn't
+/// pass a threshold
+pub const DEFAULT_DUPLICATE_THRESHOLD: f32 = 0.95;
+
+/// Nearest neighbours looked up per chunk, in any language; copies beyond
+/// this many of the same code still join the group through the neighbours
+/// they share
+const DUPLICATE_NEIGHBOURS: usize = 8;
+
+/// Chunks shorter than this (getters, one-line wrappers) are too small to be
+/// worth extracting and would swamp the results
+const MIN_DUPLICATE_LINES: u32 = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateMember {
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub code_type: String,
+    pub language: String,
+    pub content: String,
+}
+
+/// Chunks that are copies or near-copies of each other
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    /// The weakest link holding the group together
+    pub similarity: f32,
+    /// In file and line order
+    pub members: Vec<DuplicateMember>,
+}
+
+impl From<CodeEmbedding> for DuplicateMember {
+    fn from(chunk: CodeEmbedding) -> Self {
+        DuplicateMember {
+            file_path: chunk.file_path,
+            start_line: chunk.start_line,
+            end_line: chunk.end_line,
+            code_type: chunk.code_type,
+            language: chunk.language,
+            content: chunk.content,
+        }
+    }
+}
+
+/// Whether two chunks cover the same lines, e.g. a class and one of its methods
+fn overlaps(a: &CodeEmbedding, b: &CodeEmbedding) -> bool {
+    a.file_path == b.file_path && a.start_line <= b.end_line && b.start_line <= a.end_line
+}
+
+/// Disjoint sets over chunk positions, tracking each set's weakest link
+struct Groups {
+    parent: Vec<usize>,
+    similarity: Vec<f32>,
+}
+
+impl Groups {
+    fn new(size: usize) -> Self {
+        Groups {
+            parent: (0..size).collect(),
+            similarity: vec![1.0; size],
+        }
+    }
+
+    fn root(&mut self, mut node: usize) -> usize {
+        while self.parent[node] != node {
+            self.parent[node] = self.parent[self.parent[node]];
+            node = self.parent[node];
+        }
+        node
+    }
+
+    fn join(&mut self, a: usize, b: usize, similarity: f32) {
+        let (a, b) = (self.root(a), self.root(b));
+        let weakest = self.similarity[a].min(self.similarity[b]).min(similarity);
+        self.parent[b] = a;
+        self.similarity[a] = weakest;
+    }
+}
+
+/// Group indexed chunks whose similarity reaches `threshold`, most similar
+/// groups first
+///
+/// Each chunk is compared only with its nearest neighbours from the HNSW
+/// index, so the cost grows with the index size rather than its square;
+/// neighbours in another language are dropped afterwards. Without the VSS
+/// extension every lookup scans the index, which is slow on large projects.
+/// Chunks overlapping each other in the same file are never paired.
+pub fn find_duplicates(db: &VectorDb, threshold: f32) -> Result<Vec<DuplicateGroup>, CodifyError> {
+    if db.backend() == SearchBackend::ExactScan {
+        log::warn!("VSS extension unavailable, duplicate detection compares every chunk");
+    }
+    let chunks: Vec<CodeEmbedding> = db
+        .chunks_with_vectors()?
+        .into_iter()
+        .filter(|chunk| chunk.end_line.saturating_sub(chunk.start_line) + 1 >= MIN_DUPLICATE_LINES)
+        .collect();
+    let positions: HashMap<&str, usize> = chunks
+        .iter()
+        .enumerate()
+        .map(|(position, chunk)| (chunk.id.as_str(), position))
+        .collect();
+
+    let mut groups = Groups::new(chunks.len());
+    let mut paired = vec![false; chunks.len()];
+
+    for (position, chunk) in chunks.iter().enumerate() {
+        // One extra, since the closest match is the chunk itself
+        for neighbour in db.nearest(&chunk.embedding, DUPLICATE_NEIGHBOURS + 1)? {
+            let similarity = 1.0 - neighbour.distance.unwrap_or(1.0);
+            if similarity < threshold {
+                break;
+            }
+            let Some(&other) = positions.get(neighbour.id.as_str()) else {
+                continue;
+            };
+            let other_chunk = &chunks[other];
+            if other == position
+                || other_chunk.language != chunk.language
+                || overlaps(chunk, other_chunk)
+            {
+                continue;
+            }
+
+            groups.join(position, other, similarity);
+            paired[position] = true;
+            paired[other] = true;
+        }
+    }
+
+    let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+    for position in (0..chunks.len()).filter(|&position| paired[position]) {
+        let root = groups.root(position);
+        members.entry(root).or_default().push(position);
+    }
+
+    let mut chunks: Vec<Option<CodeEmbedding>> = chunks.into_iter().map(Some).collect();
+    let mut duplicates: Vec<DuplicateGroup> = members
+        .into_iter()
+        .map(|(root, positions)| DuplicateGroup {
+            similarity: groups.similarity[root],
+            // Positions follow the index's file and line order
+            members: positions
+                .into_iter()
+                .filter_map(|position| chunks[position].take())
+                .map(DuplicateMember::from)
+                .collect(),
+        })
+        .collect();
+
+    duplicates.sort_by(|a, b| {
+        b.similarity
+            .total_cmp(&a.similarity)
+            .then(b.members.len().cmp(&a.members.len()))
+    });
+
+    Ok(duplicates)
+}
@@ -0,0 +1,64 @@
+use super::{embed_chunks, CodeEmbedding, VectorDb};
+use crate::ai::AIState;
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmbeddingCacheStats {
+    pub entries: usize,
+    pub hits: u64,   // Chunks served from the cache, across all runs
+    pub misses: u64, // Chunks that had to be embedded
+}
+
+/// Stable key for a chunk's text; unlike `DefaultHasher`, SHA-256 doesn't
+/// change between builds, so the cache survives upgrades
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// `embed_chunks`, reusing vectors stored for identical content under the
+/// same embedding model and caching the new ones. Returns (hits, misses).
+pub async fn embed_chunks_cached(
+    state: &AIState,
+    db: &mut VectorDb,
+    command: &str,
+    chunks: &mut [CodeEmbedding],
+) -> Result<(usize, usize), CodifyError> {
+    let model = state.active_config().await.embedding_model;
+    let hashes: Vec<String> = chunks
+        .iter()
+        .map(|chunk| content_hash(&chunk.content))
+        .collect();
+    let cached = db.cached_embeddings(&model, &hashes)?;
+
+    let mut missing: Vec<CodeEmbedding> = vec![];
+    let mut missing_at = vec![];
+    for (index, (chunk, hash)) in chunks.iter_mut().zip(&hashes).enumerate() {
+        match cached.get(hash) {
+            Some(vector) => chunk.embedding = vector.clone(),
+            None => {
+                missing.push(chunk.clone());
+                missing_at.push(index);
+            }
+        }
+    }
+
+    let misses = missing.len();
+    if misses > 0 {
+        embed_chunks(state, command, &mut missing).await?;
+
+        let entries: Vec<(&str, &[f32])> = missing_at
+            .iter()
+            .zip(&missing)
+            .map(|(index, chunk)| (hashes[*index].as_str(), chunk.embedding.as_slice()))
+            .collect();
+        db.cache_embeddings(&model, &entries)?;
+
+        for (index, chunk) in missing_at.into_iter().zip(missing) {
+            chunks[index].embedding = chunk.embedding;
+        }
+    }
+
+    Ok((hashes.len() - misses, misses))
+}
@@ -0,0 +1,32 @@
+use super::CodeEmbedding;
+use crate::ai::AIState;
+use crate::error::CodifyError;
+
+/// Inputs sent per embeddings request when indexing many chunks
+pub const EMBEDDING_BATCH_SIZE: usize = 64;
+
+/// Fill in the vectors for `chunks`, batching requests to the provider
+pub async fn embed_chunks(
+    state: &AIState,
+    command: &str,
+    chunks: &mut [CodeEmbedding],
+) -> Result<(), CodifyError> {
+    for batch in chunks.chunks_mut(EMBEDDING_BATCH_SIZE) {
+        let inputs: Vec<String> = batch.iter().map(|chunk| chunk.content.clone()).collect();
+        let vectors = state.embed(command, &inputs).await?;
+
+        if vectors.len() != batch.len() {
+            return Err(CodifyError::Provider(format!(
+                "Provider returned {} embeddings for {} inputs",
+                vectors.len(),
+                batch.len()
+            )));
+        }
+
+        for (chunk, vector) in batch.iter_mut().zip(vectors) {
+            chunk.embedding = vector;
+        }
+    }
+
+    Ok(())
+}
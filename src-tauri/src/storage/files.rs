@@ -0,0 +1,406 @@
+use super::{detect_file_language, detect_language};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Default for the largest file read_file will return in full; bigger files
+/// need a byte range
+pub const MAX_READ_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Default for the largest file that is sniffed, parsed or indexed
+pub const MAX_INDEXABLE_BYTES: u64 = 1024 * 1024;
+
+static MAX_READ_SIZE: AtomicU64 = AtomicU64::new(MAX_READ_BYTES);
+static MAX_INDEXABLE_SIZE: AtomicU64 = AtomicU64::new(MAX_INDEXABLE_BYTES);
+
+/// How much of a file is sniffed for NUL bytes when detecting binaries
+const BINARY_SNIFF_BYTES: usize = 8192;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64, // Exclusive
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileContent {
+    pub path: String,
+    pub content: String,
+    pub size: u64, // Size of the whole file, not just the returned range
+    pub language: String,
+    pub line_count: usize,
+    pub modified: String,
+    pub range: Option<ByteRange>,
+}
+
+/// Resolve `path` against the project root, refusing anything that escapes it
+///
+/// Works for paths that don't exist yet: `..` is resolved lexically and the
+/// nearest existing ancestor is canonicalized, so symlinks pointing outside
+/// the project are caught too.
+pub fn resolve_in_project(project_path: &str, path: &str) -> Result<PathBuf, CodifyError> {
+    let root = Path::new(project_path).canonicalize().map_err(|_| {
+        CodifyError::NotFound(format!("Project path does not exist: {}", project_path))
+    })?;
+
+    let requested = Path::new(path);
+    let joined = if requested.is_absolute() {
+        requested.to_path_buf()
+    } else {
+        root.join(requested)
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+
+    // Canonicalize the deepest part that exists and re-attach the rest
+    let mut existing = normalized.as_path();
+    let mut missing = vec![];
+    while !existing.exists() {
+        let Some(parent) = existing.parent() else {
+            break;
+        };
+        if let Some(name) = existing.file_name() {
+            missing.push(name.to_os_string());
+        }
+        existing = parent;
+    }
+
+    let mut resolved = existing
+        .canonicalize()
+        .map_err(|e| CodifyError::Io(format!("Failed to resolve {}: {}", path, e)))?;
+    for name in missing.into_iter().rev() {
+        resolved.push(name);
+    }
+
+    if !resolved.starts_with(&root) {
+        return Err(CodifyError::InvalidInput(format!(
+            "Path is outside the project: {}",
+            path
+        )));
+    }
+
+    Ok(resolved)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileWriteResult {
+    pub path: String,
+    pub size: u64,
+    pub modified: String,
+}
+
+fn modified_timestamp(metadata: &std::fs::Metadata) -> String {
+    metadata
+        .modified()
+        .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339())
+        .unwrap_or_default()
+}
+
+/// File size thresholds from settings, shared by every storage command
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileLimits {
+    pub max_read_size: u64, // read_file without a byte range refuses bigger files
+    pub max_indexable_size: u64, // Bigger files are listed but never sniffed, parsed or embedded
+}
+
+impl Default for FileLimits {
+    fn default() -> Self {
+        FileLimits {
+            max_read_size: MAX_READ_BYTES,
+            max_indexable_size: MAX_INDEXABLE_BYTES,
+        }
+    }
+}
+
+impl FileLimits {
+    pub fn validate(&self) -> Result<(), CodifyError> {
+        if self.max_read_size == 0 {
+            return Err(CodifyError::InvalidInput(
+                "file_limits.max_read_size must be greater than 0".to_string(),
+            ));
+        }
+        if self.max_indexable_size == 0 {
+            return Err(CodifyError::InvalidInput(
+                "file_limits.max_indexable_size must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The limits currently in effect
+pub fn file_limits() -> FileLimits {
+    FileLimits {
+        max_read_size: MAX_READ_SIZE.load(Ordering::Relaxed),
+        max_indexable_size: MAX_INDEXABLE_SIZE.load(Ordering::Relaxed),
+    }
+}
+
+pub fn set_file_limits(limits: &FileLimits) {
+    MAX_READ_SIZE.store(limits.max_read_size, Ordering::Relaxed);
+    MAX_INDEXABLE_SIZE.store(limits.max_indexable_size, Ordering::Relaxed);
+}
+
+pub(crate) fn looks_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0)
+}
+
+/// Whether a file on disk looks binary, judged by a NUL byte in its first
+/// few kilobytes; unreadable files count as text
+pub fn is_binary(path: &Path) -> bool {
+    let Ok(file) = std::fs::File::open(path) else {
+        return false;
+    };
+    let mut head = Vec::with_capacity(BINARY_SNIFF_BYTES);
+    match file.take(BINARY_SNIFF_BYTES as u64).read_to_end(&mut head) {
+        Ok(_) => looks_binary(&head),
+        Err(_) => false,
+    }
+}
+
+/// Read a text file inside the project, optionally only a byte range of it
+pub fn read_project_file(
+    project_path: &str,
+    path: &str,
+    range: Option<ByteRange>,
+) -> Result<FileContent, CodifyError> {
+    let full_path = resolve_in_project(project_path, path)?;
+
+    let metadata = std::fs::metadata(&full_path).map_err(|e| {
+        let message = format!("Failed to read {}: {}", path, e);
+        match e.kind() {
+            std::io::ErrorKind::NotFound => CodifyError::NotFound(message),
+            _ => CodifyError::Io(message),
+        }
+    })?;
+    if !metadata.is_file() {
+        return Err(CodifyError::InvalidInput(format!("Not a file: {}", path)));
+    }
+
+    let size = metadata.len();
+    let modified = modified_timestamp(&metadata);
+    let max_read_size = file_limits().max_read_size;
+
+    let bytes = match range {
+        Some(range) => {
+            if range.start > range.end {
+                return Err(CodifyError::InvalidInput(format!(
+                    "Invalid byte range {}..{}",
+                    range.start, range.end
+                )));
+            }
+            let end = range.end.min(size);
+            let length = end.saturating_sub(range.start);
+            if length > max_read_size {
+                return Err(CodifyError::InvalidInput(format!(
+                    "Byte range exceeds the {} byte limit",
+                    max_read_size
+                )));
+            }
+
+            let mut file = std::fs::File::open(&full_path)
+                .map_err(|e| CodifyError::Io(format!("Failed to open {}: {}", path, e)))?;
+            file.seek(SeekFrom::Start(range.start))
+                .map_err(|e| CodifyError::Io(format!("Failed to seek in {}: {}", path, e)))?;
+
+            let mut bytes = Vec::with_capacity(length as usize);
+            file.take(length)
+                .read_to_end(&mut bytes)
+                .map_err(|e| CodifyError::Io(format!("Failed to read {}: {}", path, e)))?;
+            bytes
+        }
+        None => {
+            if size > max_read_size {
+                return Err(CodifyError::InvalidInput(format!(
+                    "File is too large to open ({} bytes, limit {}); request a byte range instead",
+                    size, max_read_size
+                )));
+            }
+            std::fs::read(&full_path)
+                .map_err(|e| CodifyError::Io(format!("Failed to read {}: {}", path, e)))?
+        }
+    };
+
+    if looks_binary(&bytes) {
+        return Err(CodifyError::InvalidInput(format!(
+            "Binary files cannot be opened as text: {}",
+            path
+        )));
+    }
+
+    let content = match range {
+        // A range may cut a multi-byte character in half at either end
+        Some(_) => String::from_utf8_lossy(&bytes).to_string(),
+        None => String::from_utf8(bytes).map_err(|_| {
+            CodifyError::InvalidInput(format!("File is not valid UTF-8 text: {}", path))
+        })?,
+    };
+
+    // Ranged reads don't see the file head, so sniff it from disk instead
+    let language = match range {
+        Some(range) if range.start > 0 => detect_file_language(&full_path),
+        _ => detect_language(&full_path, Some(&content)),
+    };
+
+    Ok(FileContent {
+        path: path.to_string(),
+        line_count: content.lines().count(),
+        language,
+        content,
+        size,
+        modified,
+        range,
+    })
+}
+
+/// Move the finished temporary file to `target`
+///
+/// A rename replaces whatever is there; with `create_only` the file is hard
+/// linked instead, which fails with AlreadyExists rather than clobbering a
+/// file that appeared after the caller checked.
+fn publish(temp_path: &Path, target: &Path, create_only: bool) -> std::io::Result<()> {
+    if !create_only {
+        return std::fs::rename(temp_path, target);
+    }
+    std::fs::hard_link(temp_path, target)?;
+    let _ = std::fs::remove_file(temp_path);
+    Ok(())
+}
+
+/// Write a file inside the project atomically, creating parent directories
+///
+/// The content goes to a temporary file in the same directory which is then
+/// renamed over the target, so a crash never leaves a half-written file.
+/// With `create_only` an existing file is left untouched and an error returned,
+/// even one created while the content was being written.
+pub fn write_project_file(
+    project_path: &str,
+    path: &str,
+    content: &str,
+    create_only: bool,
+) -> Result<FileWriteResult, CodifyError> {
+    let full_path = resolve_in_project(project_path, path)?;
+
+    if full_path.is_dir() {
+        return Err(CodifyError::InvalidInput(format!(
+            "Path is a directory: {}",
+            path
+        )));
+    }
+    if create_only && full_path.exists() {
+        return Err(CodifyError::InvalidInput(format!(
+            "File already exists: {}",
+            path
+        )));
+    }
+
+    let parent = full_path
+        .parent()
+        .ok_or_else(|| CodifyError::InvalidInput(format!("Invalid file path: {}", path)))?;
+    std::fs::create_dir_all(parent)
+        .map_err(|e| CodifyError::Io(format!("Failed to create directory for {}: {}", path, e)))?;
+
+    let file_name = full_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp_path = parent.join(format!(".{}.{}.tmp", file_name, uuid::Uuid::new_v4()));
+
+    let written = (|| {
+        let mut file = std::fs::File::create(&temp_path)?;
+        std::io::Write::write_all(&mut file, content.as_bytes())?;
+        file.sync_all()?;
+        publish(&temp_path, &full_path, create_only)
+    })();
+
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&temp_path);
+        if e.kind() == std::io::ErrorKind::AlreadyExists {
+            return Err(CodifyError::InvalidInput(format!(
+                "File already exists: {}",
+                path
+            )));
+        }
+        return Err(CodifyError::Io(format!("Failed to write {}: {}", path, e)));
+    }
+
+    let metadata = std::fs::metadata(&full_path)
+        .map_err(|e| CodifyError::Io(format!("Failed to read back {}: {}", path, e)))?;
+
+    log::info!("Wrote {} bytes to {}", metadata.len(), full_path.display());
+
+    Ok(FileWriteResult {
+        path: path.to_string(),
+        size: metadata.len(),
+        modified: modified_timestamp(&metadata),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh, empty project directory
+    fn project() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("codify-files-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn create_only_keeps_an_existing_file() {
+        let root = project();
+        std::fs::write(root.join("keep.txt"), "original").unwrap();
+
+        let result = write_project_file(&root.to_string_lossy(), "keep.txt", "replaced", true);
+
+        assert!(matches!(result, Err(CodifyError::InvalidInput(_))));
+        assert_eq!(
+            std::fs::read_to_string(root.join("keep.txt")).unwrap(),
+            "original"
+        );
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn create_only_publish_never_clobbers() {
+        // As if the target appeared after write_project_file checked for it
+        let root = project();
+        let (temp, target) = (root.join(".new.tmp"), root.join("new.txt"));
+        std::fs::write(&temp, "replaced").unwrap();
+        std::fs::write(&target, "original").unwrap();
+
+        let error = publish(&temp, &target, true).unwrap_err();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "original");
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn create_only_writes_a_new_file() {
+        let root = project();
+
+        write_project_file(&root.to_string_lossy(), "dir/new.txt", "content", true).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(root.join("dir/new.txt")).unwrap(),
+            "content"
+        );
+        let leftovers = std::fs::read_dir(root.join("dir")).unwrap().count();
+        assert_eq!(leftovers, 1);
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,132 @@
+use super::{
+    import_specifiers, is_js_family, resolve_import_target, walk_project, ImportTarget, PathAliases,
+};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnresolvedImport {
+    pub file_path: String,
+    pub specifier: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DependencyGraph {
+    pub imports: BTreeMap<String, Vec<String>>, // File -> project files it imports
+    pub dependents: BTreeMap<String, Vec<String>>, // File -> files importing it
+    pub unresolved: Vec<UnresolvedImport>, // Imports of project paths that don't exist
+}
+
+/// Import specifiers parsed from one file, valid while its mtime is unchanged
+struct ParsedFile {
+    modified: String,
+    language: String,
+    specifiers: Vec<String>,
+}
+
+fn has_import_parser(language: &str) -> bool {
+    is_js_family(language) || matches!(language, "python" | "rust")
+}
+
+fn project_key(project_path: &str) -> String {
+    project_path.trim_end_matches(['/', '\\']).to_string()
+}
+
+/// Per-project cache of parsed imports for project_dependency_graph
+///
+/// Only the parse step is cached. Resolution reruns on every build because a
+/// new file or tsconfig change can fix or break imports in unchanged files.
+#[derive(Default)]
+pub struct DependencyGraphState {
+    projects: Mutex<HashMap<String, HashMap<String, ParsedFile>>>,
+}
+
+impl DependencyGraphState {
+    /// Forget a file's parsed imports, e.g. after the watcher saw it change
+    pub fn invalidate(&self, project_path: &str, file_path: &str) {
+        if let Some(files) = self
+            .projects
+            .lock()
+            .unwrap()
+            .get_mut(&project_key(project_path))
+        {
+            files.remove(file_path);
+        }
+    }
+
+    pub fn build(&self, project_path: &str) -> Result<DependencyGraph, CodifyError> {
+        let files = walk_project(project_path)?;
+        let known: HashSet<String> = files.iter().map(|f| f.path.clone()).collect();
+        let aliases = PathAliases::load(project_path);
+        let root = std::path::Path::new(project_path);
+
+        // Parse outside the lock so one slow project doesn't block invalidation
+        let key = project_key(project_path);
+        let mut cache = self
+            .projects
+            .lock()
+            .unwrap()
+            .remove(&key)
+            .unwrap_or_default();
+        cache.retain(|path, _| known.contains(path));
+
+        for file in files.iter().filter(|f| has_import_parser(&f.file_type)) {
+            let fresh = cache
+                .get(&file.path)
+                .is_some_and(|parsed| parsed.modified == file.modified);
+            if fresh || file.too_large || file.is_binary {
+                continue;
+            }
+
+            let Ok(content) = std::fs::read_to_string(root.join(&file.path)) else {
+                continue;
+            };
+            cache.insert(
+                file.path.clone(),
+                ParsedFile {
+                    modified: file.modified.clone(),
+                    language: file.file_type.clone(),
+                    specifiers: import_specifiers(&file.path, &content, &file.file_type),
+                },
+            );
+        }
+
+        let mut graph = DependencyGraph::default();
+        for (path, parsed) in &cache {
+            let mut imported = vec![];
+            for specifier in &parsed.specifiers {
+                match resolve_import_target(path, specifier, &parsed.language, &known, &aliases) {
+                    ImportTarget::File(target) if target != *path => imported.push(target),
+                    ImportTarget::Unresolved => graph.unresolved.push(UnresolvedImport {
+                        file_path: path.clone(),
+                        specifier: specifier.clone(),
+                    }),
+                    _ => {}
+                }
+            }
+            imported.sort();
+            imported.dedup();
+
+            for target in &imported {
+                graph
+                    .dependents
+                    .entry(target.clone())
+                    .or_default()
+                    .push(path.clone());
+            }
+            graph.imports.insert(path.clone(), imported);
+        }
+
+        for dependents in graph.dependents.values_mut() {
+            dependents.sort();
+        }
+        graph
+            .unresolved
+            .sort_by(|a, b| (&a.file_path, &a.specifier).cmp(&(&b.file_path, &b.specifier)));
+
+        self.projects.lock().unwrap().insert(key, cache);
+        Ok(graph)
+    }
+}
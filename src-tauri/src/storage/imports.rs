@@ -0,0 +1,391 @@
+use super::{chunk_by_function, is_js_family};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Extensions tried, in order, for an extensionless JS/TS import
+const JS_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs", "d.ts"];
+
+/// What an import specifier points at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportTarget {
+    File(String), // Project-relative path
+    External,     // A package, crate or installed module outside the project
+    Unresolved,   // Looks like a project path but no such file exists
+}
+
+/// `require("x")` and `import("x")` calls, which aren't import statements
+fn js_call_imports(content: &str) -> Vec<String> {
+    static CALL: OnceLock<regex::Regex> = OnceLock::new();
+    let call = CALL.get_or_init(|| {
+        regex::Regex::new(r#"\b(?:require|import)\s*\(\s*['"`]([^'"`]+)['"`]\s*\)"#).unwrap()
+    });
+
+    call.captures_iter(content)
+        .map(|captures| captures[1].to_string())
+        .collect()
+}
+
+/// Module specifiers imported by a file, as written in its source
+///
+/// Covers import statements, re-exports (`export ... from`) and, for JS/TS,
+/// `require()` and dynamic `import()` calls with a literal argument.
+pub fn import_specifiers(file_path: &str, content: &str, language: &str) -> Vec<String> {
+    let mut specifiers: Vec<String> = chunk_by_function(file_path, content, language)
+        .into_iter()
+        .filter(|chunk| chunk.code_type == "import")
+        .flat_map(|chunk| chunk.dependencies)
+        .collect();
+    if is_js_family(language) {
+        specifiers.extend(js_call_imports(content));
+    }
+    specifiers.sort();
+    specifiers.dedup();
+    specifiers
+}
+
+/// Join a relative path onto a project-relative directory, resolving `.` and
+/// `..` lexically; None if it climbs above the project root
+fn join_relative(dir: &str, relative: &str) -> Option<String> {
+    let mut parts: Vec<&str> = dir.split('/').filter(|p| !p.is_empty()).collect();
+    for part in relative.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+fn child(dir: &str, name: &str) -> String {
+    if dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+fn parent_dir(file_path: &str) -> &str {
+    file_path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("")
+}
+
+fn first_existing(
+    candidates: impl IntoIterator<Item = String>,
+    files: &HashSet<String>,
+) -> Option<String> {
+    candidates
+        .into_iter()
+        .find(|candidate| files.contains(candidate))
+}
+
+/// A JS/TS path as written, with an extension added, or as a directory index
+fn resolve_js_path(base: &str, files: &HashSet<String>) -> Option<String> {
+    let mut candidates = vec![base.to_string()];
+    candidates.extend(JS_EXTENSIONS.iter().map(|ext| format!("{}.{}", base, ext)));
+    candidates.extend(
+        JS_EXTENSIONS
+            .iter()
+            .map(|ext| format!("{}/index.{}", base, ext)),
+    );
+    // TS sources are imported with .js extensions under NodeNext resolution
+    if let Some(stem) = base.strip_suffix(".js") {
+        candidates.extend(["ts", "tsx"].iter().map(|ext| format!("{}.{}", stem, ext)));
+    }
+
+    first_existing(candidates, files)
+}
+
+fn resolve_python(from_file: &str, module: &str, files: &HashSet<String>) -> Option<String> {
+    let dots = module.chars().take_while(|c| *c == '.').count();
+    let path = module[dots..].replace('.', "/");
+
+    let bases: Vec<String> = if dots > 0 {
+        // One dot is the current package; each extra dot climbs a level
+        let mut base = parent_dir(from_file).to_string();
+        for _ in 1..dots {
+            base = join_relative(&base, "..")?;
+        }
+        vec![base]
+    } else {
+        // Absolute imports resolve from the root, a src/ layout, or alongside the file
+        vec![
+            String::new(),
+            "src".to_string(),
+            parent_dir(from_file).to_string(),
+        ]
+    };
+
+    let mut candidates = vec![];
+    for base in &bases {
+        let Some(joined) = join_relative(base, &path) else {
+            continue;
+        };
+        if !path.is_empty() {
+            candidates.push(format!("{}.py", joined));
+        }
+        candidates.push(child(&joined, "__init__.py"));
+    }
+
+    first_existing(candidates, files)
+}
+
+/// Directory holding a Rust file's child modules
+fn rust_module_dir(file_path: &str) -> String {
+    let dir = parent_dir(file_path);
+    match file_path.rsplit('/').next().unwrap_or(file_path) {
+        "mod.rs" | "lib.rs" | "main.rs" => dir.to_string(),
+        name => join_relative(dir, name.trim_end_matches(".rs")).unwrap_or_default(),
+    }
+}
+
+/// Nearest ancestor directory with a lib.rs or main.rs
+fn rust_crate_root(from_file: &str, files: &HashSet<String>) -> Option<String> {
+    let mut dir = parent_dir(from_file).to_string();
+    loop {
+        if files.contains(&child(&dir, "lib.rs")) || files.contains(&child(&dir, "main.rs")) {
+            return Some(dir);
+        }
+        if dir.is_empty() {
+            return None;
+        }
+        dir = parent_dir(&dir).to_string();
+    }
+}
+
+fn resolve_rust(from_file: &str, path: &str, files: &HashSet<String>) -> Option<String> {
+    // `crate::a::{b, c}` names module a; braces list items inside it
+    let path = path
+        .split('{')
+        .next()
+        .unwrap_or(path)
+        .trim_end_matches("::");
+    let mut segments: Vec<&str> = path.split("::").filter(|s| !s.is_empty()).collect();
+
+    let mut base = match segments.first().copied()? {
+        "crate" => rust_crate_root(from_file, files)?,
+        "self" => rust_module_dir(from_file),
+        "super" => parent_dir(&rust_module_dir(from_file)).to_string(),
+        // External crates and std have no file in the project
+        _ => return None,
+    };
+    segments.remove(0);
+    while segments.first() == Some(&"super") {
+        base = parent_dir(&base).to_string();
+        segments.remove(0);
+    }
+
+    // The path may end in items rather than modules, so take the longest
+    // module prefix; an empty prefix is the base module itself
+    (0..=segments.len()).rev().find_map(|len| {
+        let joined = join_relative(&base, &segments[..len].join("/"))?;
+        let mut candidates = vec![format!("{}.rs", joined), child(&joined, "mod.rs")];
+        if len == 0 {
+            candidates.extend([child(&joined, "lib.rs"), child(&joined, "main.rs")]);
+        }
+        first_existing(candidates, files)
+    })
+}
+
+/// Remove comments and trailing commas so tsconfig-style JSON parses
+fn strip_jsonc(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => {
+                while chars.peek().is_some_and(|next| *next != '\n') {
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+
+    static TRAILING_COMMA: OnceLock<regex::Regex> = OnceLock::new();
+    TRAILING_COMMA
+        .get_or_init(|| regex::Regex::new(r",(\s*[}\]])").unwrap())
+        .replace_all(&out, "$1")
+        .into_owned()
+}
+
+/// `baseUrl` and `paths` from the project's tsconfig.json or jsconfig.json,
+/// with targets made project-relative
+#[derive(Debug, Clone, Default)]
+pub struct PathAliases {
+    base_url: Option<String>,
+    paths: Vec<(String, Vec<String>)>, // Pattern -> targets, longest prefix first
+}
+
+impl PathAliases {
+    pub fn load(project_path: &str) -> Self {
+        for name in ["tsconfig.json", "jsconfig.json"] {
+            let Ok(text) = std::fs::read_to_string(Path::new(project_path).join(name)) else {
+                continue;
+            };
+            match serde_json::from_str::<serde_json::Value>(&strip_jsonc(&text)) {
+                Ok(config) => return Self::from_config(&config),
+                Err(e) => log::warn!("Ignoring path aliases in unparseable {}: {}", name, e),
+            }
+        }
+        Self::default()
+    }
+
+    fn from_config(config: &serde_json::Value) -> Self {
+        let options = &config["compilerOptions"];
+        let base_url = options["baseUrl"]
+            .as_str()
+            .and_then(|base| join_relative("", base));
+        // `paths` targets are relative to baseUrl, or to the config without one
+        let base = base_url.clone().unwrap_or_default();
+
+        let mut paths: Vec<(String, Vec<String>)> = options["paths"]
+            .as_object()
+            .map(|paths| {
+                paths
+                    .iter()
+                    .map(|(pattern, targets)| {
+                        let targets = targets
+                            .as_array()
+                            .map(|targets| {
+                                targets
+                                    .iter()
+                                    .filter_map(|target| target.as_str())
+                                    .filter_map(|target| join_relative(&base, target))
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+                        (pattern.clone(), targets)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        // TypeScript prefers the pattern with the longest prefix before `*`
+        paths.sort_by_key(|(pattern, _)| {
+            std::cmp::Reverse(pattern.split('*').next().unwrap_or("").len())
+        });
+
+        PathAliases { base_url, paths }
+    }
+
+    /// The text a `paths` pattern's `*` captures from `specifier`
+    fn capture<'a>(pattern: &str, specifier: &'a str) -> Option<&'a str> {
+        match pattern.split_once('*') {
+            Some((prefix, suffix)) => specifier.strip_prefix(prefix)?.strip_suffix(suffix),
+            None => (pattern == specifier).then_some(""),
+        }
+    }
+
+    /// Whether a `paths` pattern claims this specifier
+    fn matches(&self, specifier: &str) -> bool {
+        self.paths
+            .iter()
+            .any(|(pattern, _)| Self::capture(pattern, specifier).is_some())
+    }
+
+    /// Project paths a bare specifier may refer to, in the order TypeScript tries them
+    fn candidates(&self, specifier: &str) -> Vec<String> {
+        let mut candidates = vec![];
+        for (pattern, targets) in &self.paths {
+            if let Some(captured) = Self::capture(pattern, specifier) {
+                candidates.extend(
+                    targets
+                        .iter()
+                        .map(|target| target.replacen('*', captured, 1)),
+                );
+            }
+        }
+        if let Some(base) = &self.base_url {
+            candidates.extend(join_relative(base, specifier));
+        }
+        candidates
+    }
+}
+
+/// Classify an import specifier and resolve it to a project-relative file
+///
+/// `files` holds every project-relative path. Bare package imports (npm
+/// packages, external crates, installed Python modules) are External; paths
+/// that are clearly meant to be in the project but don't exist are Unresolved.
+pub fn resolve_import_target(
+    from_file: &str,
+    specifier: &str,
+    language: &str,
+    files: &HashSet<String>,
+    aliases: &PathAliases,
+) -> ImportTarget {
+    let found = |path: Option<String>, local: bool| match path {
+        Some(path) => ImportTarget::File(path),
+        None if local => ImportTarget::Unresolved,
+        None => ImportTarget::External,
+    };
+
+    match language {
+        language if is_js_family(language) => {
+            if specifier.starts_with('.') {
+                let path = join_relative(parent_dir(from_file), specifier)
+                    .and_then(|base| resolve_js_path(&base, files));
+                return found(path, true);
+            }
+            let path = aliases
+                .candidates(specifier)
+                .iter()
+                .find_map(|candidate| resolve_js_path(candidate, files));
+            found(path, aliases.matches(specifier))
+        }
+        "python" => found(
+            resolve_python(from_file, specifier, files),
+            specifier.starts_with('.'),
+        ),
+        "rust" => {
+            let local = matches!(
+                specifier.split("::").next(),
+                Some("crate" | "self" | "super")
+            );
+            found(resolve_rust(from_file, specifier, files), local)
+        }
+        _ => ImportTarget::External,
+    }
+}
+
+/// The project file an import specifier names, if any
+pub fn resolve_import(
+    from_file: &str,
+    specifier: &str,
+    language: &str,
+    files: &HashSet<String>,
+    aliases: &PathAliases,
+) -> Option<String> {
+    match resolve_import_target(from_file, specifier, language, files, aliases) {
+        ImportTarget::File(path) => Some(path),
+        _ => None,
+    }
+}
@@ -0,0 +1,399 @@
+use super::{
+    chunk_by_function, embed_chunks_cached, file_limits, looks_binary, walk_project, ProjectFile,
+    VectorDb, VectorStores, WriteBatch,
+};
+use crate::ai::{count_tokens, find_secrets, secret_policy, AIState, SecretPolicy};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+
+/// Languages the chunker understands well enough to index
+pub(crate) const INDEXABLE_LANGUAGES: &[&str] = &[
+    "typescript",
+    "typescriptreact",
+    "javascript",
+    "javascriptreact",
+    "rust",
+    "python",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexProgress {
+    pub index_id: String,
+    pub project_path: String,
+    pub processed: usize,
+    pub total: usize,
+    pub current_file: String,
+}
+
+/// Sent as `index-started` when a run begins, with the id to pass to cancel_index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexStarted {
+    pub index_id: String,
+    pub project_path: String,
+}
+
+/// Sent as `index-cancelled` with what was done before the run stopped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexCancelled {
+    pub index_id: String,
+    pub project_path: String,
+    pub summary: IndexSummary,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexSummary {
+    pub index_id: String,
+    pub files_indexed: usize,
+    pub files_skipped: usize,
+    pub files_excluded: usize, // Binary or over max_indexable_size
+    pub files_removed: usize,
+    pub chunks_stored: usize,
+    pub cache_hits: usize, // Chunks whose vectors came from the embedding cache
+    pub cache_misses: usize,
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReindexSummary {
+    pub previous_model: Option<String>,
+    pub model: String,
+    pub dimension: usize,
+    /// Whether the old vectors were dropped; false when they already came
+    /// from `model` and only changed files were re-embedded
+    pub cleared: bool,
+    pub index: IndexSummary,
+}
+
+/// What index_project would send to the embedding model
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexTokenCount {
+    pub files: usize,
+    pub chunks: usize,
+    pub tokens: usize,
+}
+
+/// Tracks running index jobs so they can be cancelled, keyed by project
+/// path, with each run's id and cancellation token
+#[derive(Default)]
+pub struct IndexerState {
+    running: Mutex<HashMap<String, (String, CancellationToken)>>,
+}
+
+impl IndexerState {
+    fn start(&self, project_path: &str) -> Result<(String, CancellationToken), CodifyError> {
+        let mut running = self.running.lock().unwrap();
+        if running.contains_key(project_path) {
+            return Err(CodifyError::Busy(format!(
+                "Indexing is already running for {}",
+                project_path
+            )));
+        }
+
+        let index_id = uuid::Uuid::new_v4().to_string();
+        let token = CancellationToken::new();
+        running.insert(project_path.to_string(), (index_id.clone(), token.clone()));
+        Ok((index_id, token))
+    }
+
+    fn finish(&self, project_path: &str) {
+        self.running.lock().unwrap().remove(project_path);
+    }
+
+    pub(crate) fn is_running(&self, project_path: &str) -> bool {
+        self.running.lock().unwrap().contains_key(project_path)
+    }
+
+    /// Request cancellation of the run with this id; returns false when it
+    /// has already finished
+    pub fn cancel_by_id(&self, index_id: &str) -> bool {
+        let running = self.running.lock().unwrap();
+        match running.values().find(|(id, _)| id == index_id) {
+            Some((_, token)) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Incrementally index a project, emitting `index-started`, then
+/// `index-progress` events, and `index-cancelled` if it is stopped
+pub async fn run_index(
+    app: &tauri::AppHandle,
+    ai: &AIState,
+    indexer: &IndexerState,
+    project_path: &str,
+) -> Result<IndexSummary, CodifyError> {
+    let (index_id, token) = indexer.start(project_path)?;
+    let _ = app.emit(
+        "index-started",
+        IndexStarted {
+            index_id: index_id.clone(),
+            project_path: project_path.to_string(),
+        },
+    );
+
+    let result = index_files(app, ai, &token, &index_id, project_path).await;
+    indexer.finish(project_path);
+
+    let cancelled = result.as_ref().ok().filter(|summary| summary.cancelled);
+    if let Some(summary) = cancelled {
+        log::info!("Indexing cancelled after {} files", summary.files_indexed);
+        let _ = app.emit(
+            "index-cancelled",
+            IndexCancelled {
+                index_id,
+                project_path: project_path.to_string(),
+                summary: summary.clone(),
+            },
+        );
+    }
+    result
+}
+
+/// Rebuild the index with the configured embedding model, first clearing it
+/// when its vectors came from another model or have another dimension
+pub async fn run_reindex(
+    app: &tauri::AppHandle,
+    ai: &AIState,
+    indexer: &IndexerState,
+    project_path: &str,
+) -> Result<ReindexSummary, CodifyError> {
+    if indexer.is_running(project_path) {
+        return Err(CodifyError::Busy(format!(
+            "Indexing is already running for {}",
+            project_path
+        )));
+    }
+
+    let model = ai.active_config().await.embedding_model;
+    let dimension = ai
+        .embed("reindex_with_model", &["dimension probe".to_string()])
+        .await?
+        .first()
+        .map(|vector| vector.len())
+        .ok_or_else(|| CodifyError::Provider("Provider returned no embedding".to_string()))?;
+
+    let mut db = app.state::<VectorStores>().open(project_path)?;
+    let previous = db.info()?;
+    let cleared = previous.model.as_ref().is_some_and(|built| *built != model)
+        || previous.dimension.is_some_and(|built| built != dimension);
+    if cleared {
+        log::info!(
+            "Clearing index built with {} ({} dimensions) for {} ({} dimensions)",
+            previous.model.as_deref().unwrap_or("an unknown model"),
+            previous.dimension.unwrap_or_default(),
+            model,
+            dimension
+        );
+        db.clear()?;
+    }
+    drop(db);
+
+    let index = run_index(app, ai, indexer, project_path).await?;
+    Ok(ReindexSummary {
+        previous_model: previous.model,
+        model,
+        dimension,
+        cleared,
+        index,
+    })
+}
+
+/// Finished files are written in batches, each in one transaction.
+/// Cancelling stops between files, or abandons the file being embedded, and
+/// writes the files already finished; the index never holds a partly
+/// stored file.
+async fn index_files(
+    app: &tauri::AppHandle,
+    ai: &AIState,
+    token: &CancellationToken,
+    index_id: &str,
+    project_path: &str,
+) -> Result<IndexSummary, CodifyError> {
+    let mut summary = IndexSummary {
+        index_id: index_id.to_string(),
+        ..Default::default()
+    };
+
+    // The walk itself runs on until done, but its result is dropped
+    let root = project_path.to_string();
+    let walk = tokio::task::spawn_blocking(move || walk_project(&root));
+    let files = tokio::select! {
+        _ = token.cancelled() => {
+            summary.cancelled = true;
+            return Ok(summary);
+        }
+        files = walk => {
+            files.map_err(|e| CodifyError::Internal(format!("File traversal failed: {}", e)))??
+        }
+    };
+
+    let model = ai.active_config().await.embedding_model;
+    let mut db = app.state::<VectorStores>().open(project_path)?;
+    let indexed = db.indexed_files()?;
+
+    // Drop rows for files that were deleted since the last run
+    let present: HashSet<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    for file_path in indexed.keys().filter(|path| !present.contains(path.as_str())) {
+        db.delete_file(file_path)?;
+        summary.files_removed += 1;
+    }
+
+    let (candidates, excluded): (Vec<_>, Vec<_>) = files
+        .iter()
+        .filter(|f| INDEXABLE_LANGUAGES.contains(&f.file_type.as_str()))
+        .partition(|f| !f.is_binary && !f.too_large);
+
+    // Drop what an earlier run indexed before the file grew or the limit shrank
+    for file in excluded {
+        if indexed.contains_key(&file.path) {
+            db.delete_file(&file.path)?;
+        }
+        summary.files_excluded += 1;
+    }
+    let total = candidates.len();
+    let mut batch = WriteBatch::default();
+
+    for (processed, file) in candidates.into_iter().enumerate() {
+        if token.is_cancelled() {
+            summary.cancelled = true;
+            break;
+        }
+
+        let _ = app.emit(
+            "index-progress",
+            IndexProgress {
+                index_id: index_id.to_string(),
+                project_path: project_path.to_string(),
+                processed,
+                total,
+                current_file: file.path.clone(),
+            },
+        );
+
+        if indexed.get(&file.path) == Some(&file.modified) {
+            summary.files_skipped += 1;
+            continue;
+        }
+
+        let stored = tokio::select! {
+            _ = token.cancelled() => {
+                summary.cancelled = true;
+                break;
+            }
+            stored = index_file(ai, &mut db, &mut batch, "index_project", project_path, file) => stored?,
+        };
+        let Some(stored) = stored else {
+            summary.files_skipped += 1;
+            continue;
+        };
+
+        summary.files_indexed += 1;
+        summary.chunks_stored += stored.chunks;
+        summary.cache_hits += stored.cache_hits;
+        summary.cache_misses += stored.cache_misses;
+
+        if batch.is_due() {
+            db.write_batch(&mut batch, &model)?;
+        }
+    }
+    db.write_batch(&mut batch, &model)?;
+
+    db.record_cache_lookups(summary.cache_hits, summary.cache_misses)?;
+    Ok(summary)
+}
+
+pub(crate) struct FileIndexed {
+    pub chunks: usize,
+    pub cache_hits: usize,
+    pub cache_misses: usize,
+}
+
+/// Re-chunk and re-embed one file, adding its rows and indexed marker to
+/// `batch` to replace the old ones; None when the file can't be read as text,
+/// is over max_indexable_size or, unless secret_policy is off, holds secrets
+pub(crate) async fn index_file(
+    ai: &AIState,
+    db: &mut VectorDb,
+    batch: &mut WriteBatch,
+    command: &str,
+    project_path: &str,
+    file: &ProjectFile,
+) -> Result<Option<FileIndexed>, CodifyError> {
+    let full_path = std::path::Path::new(project_path).join(&file.path);
+    let Ok(bytes) = tokio::fs::read(&full_path).await else {
+        return Ok(None);
+    };
+    let Some(content) = indexable_text(bytes) else {
+        return Ok(None);
+    };
+    if holds_secrets(file, &content) {
+        // Rows from before the secret was added would still leak it into search
+        db.delete_file(&file.path)?;
+        log::info!("Not indexing {}: it appears to hold secrets", file.path);
+        return Ok(None);
+    }
+
+    let mut chunks = chunk_by_function(&file.path, &content, &file.file_type);
+    let (cache_hits, cache_misses) = embed_chunks_cached(ai, db, command, &mut chunks).await?;
+
+    let count = chunks.len();
+    batch.replace_file(&file.path, &file.modified, chunks);
+
+    Ok(Some(FileIndexed {
+        chunks: count,
+        cache_hits,
+        cache_misses,
+    }))
+}
+
+/// A credentials file, or one with likely secrets in it, while secret_policy is on
+fn holds_secrets(file: &ProjectFile, content: &str) -> bool {
+    secret_policy() != SecretPolicy::Off && (file.secret || !find_secrets(content).is_empty())
+}
+
+/// File contents as text, unless over max_indexable_size, binary or not UTF-8;
+/// checked again at read time since the file may have changed since it was listed
+fn indexable_text(bytes: Vec<u8>) -> Option<String> {
+    if bytes.len() as u64 > file_limits().max_indexable_size || looks_binary(&bytes) {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Chunk every file index_project would and count the tokens it would embed,
+/// without calling the model; unchanged files and cached chunks are counted
+/// too, so this is the cost of indexing from scratch
+pub fn count_index_tokens(project_path: &str) -> Result<IndexTokenCount, CodifyError> {
+    let mut count = IndexTokenCount::default();
+
+    for file in walk_project(project_path)?
+        .iter()
+        .filter(|f| INDEXABLE_LANGUAGES.contains(&f.file_type.as_str()))
+        .filter(|f| !f.is_binary && !f.too_large)
+    {
+        let full_path = std::path::Path::new(project_path).join(&file.path);
+        let Some(content) = std::fs::read(&full_path).ok().and_then(indexable_text) else {
+            continue;
+        };
+        if holds_secrets(file, &content) {
+            continue;
+        }
+
+        let chunks = chunk_by_function(&file.path, &content, &file.file_type);
+        count.files += 1;
+        count.chunks += chunks.len();
+        count.tokens += chunks
+            .iter()
+            .map(|chunk| count_tokens(&chunk.content))
+            .sum::<usize>();
+    }
+
+    Ok(count)
+}
@@ -0,0 +1,129 @@
+use std::io::Read;
+use std::path::Path;
+
+/// How much of a file's start is inspected for shebangs and React imports
+pub const LANGUAGE_SNIFF_BYTES: usize = 4096;
+
+/// Canonical language id for a file, shared by the file tree, read_file,
+/// the chunker and the AI commands
+///
+/// The extension decides in most cases. `content` (only its first
+/// LANGUAGE_SNIFF_BYTES are looked at, so callers with the full text and
+/// callers with just the head agree) resolves extensionless scripts via their
+/// shebang and plain `.js` files that are really React components.
+/// Unrecognized files are "unknown".
+pub fn detect_language(path: &Path, content: Option<&str>) -> String {
+    let head = content.map(|text| {
+        let mut end = text.len().min(LANGUAGE_SNIFF_BYTES);
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        &text[..end]
+    });
+
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+
+    let language = match extension.as_deref() {
+        Some("ts" | "mts" | "cts") => "typescript",
+        Some("tsx") => "typescriptreact",
+        Some("jsx") => "javascriptreact",
+        Some("js" | "mjs" | "cjs") => {
+            if head.map(imports_react).unwrap_or(false) {
+                "javascriptreact"
+            } else {
+                "javascript"
+            }
+        }
+        Some("rs") => "rust",
+        Some("py" | "pyi") => "python",
+        Some("css") => "css",
+        Some("scss") => "scss",
+        Some("json") => "json",
+        Some("md" | "mdx") => "markdown",
+        Some("html" | "htm") => "html",
+        Some("vue") => "vue",
+        Some("svelte") => "svelte",
+        Some("yml" | "yaml") => "yaml",
+        Some("toml") => "toml",
+        Some("sh" | "bash" | "zsh") => "shell",
+        Some("go") => "go",
+        Some("sql") => "sql",
+        Some(_) => "unknown",
+        None => match file_name {
+            "Dockerfile" => "dockerfile",
+            "Makefile" => "makefile",
+            _ => head.and_then(shebang_language).unwrap_or("unknown"),
+        },
+    };
+
+    language.to_string()
+}
+
+/// Detect a file's language, reading its head from disk only when the
+/// extension alone can't decide
+pub fn detect_file_language(path: &Path) -> String {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    if !matches!(extension, "" | "js" | "mjs" | "cjs") {
+        return detect_language(path, None);
+    }
+
+    let mut head = Vec::with_capacity(LANGUAGE_SNIFF_BYTES);
+    let read = std::fs::File::open(path).and_then(|file| {
+        file.take(LANGUAGE_SNIFF_BYTES as u64)
+            .read_to_end(&mut head)
+    });
+
+    match read {
+        Ok(_) => detect_language(path, Some(&String::from_utf8_lossy(&head))),
+        Err(_) => detect_language(path, None),
+    }
+}
+
+/// Whether a language id is one of the TypeScript/JavaScript family
+pub fn is_js_family(language: &str) -> bool {
+    matches!(
+        language,
+        "typescript" | "typescriptreact" | "javascript" | "javascriptreact"
+    )
+}
+
+fn imports_react(head: &str) -> bool {
+    [
+        "from 'react'",
+        "from \"react\"",
+        "require('react')",
+        "require(\"react\")",
+    ]
+    .iter()
+    .any(|pattern| head.contains(pattern))
+}
+
+fn shebang_language(head: &str) -> Option<&'static str> {
+    let mut parts = head.lines().next()?.strip_prefix("#!")?.split_whitespace();
+    let program = parts.next()?.rsplit('/').next()?;
+
+    // `#!/usr/bin/env -S deno run` names the interpreter after env's flags
+    let interpreter = if program == "env" {
+        parts.find(|part| !part.starts_with('-'))?
+    } else {
+        program
+    };
+
+    match interpreter {
+        "node" | "nodejs" => Some("javascript"),
+        "deno" | "ts-node" | "tsx" => Some("typescript"),
+        name if name.starts_with("python") => Some("python"),
+        "sh" | "bash" | "zsh" | "dash" => Some("shell"),
+        _ => None,
+    }
+}
@@ -1,5 +1,60 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use tauri::Manager;
+
+use crate::ai::{in_project, secret_policy, AIState, SecretPolicy};
+use crate::error::CodifyError;
+
+mod chunker;
+mod duplicates;
+mod embedding_cache;
+mod embeddings;
+mod files;
+mod graph;
+mod imports;
+mod indexer;
+mod language;
+mod reindex;
+mod related;
+mod search;
+mod similar;
+mod snippets;
+mod stats;
+mod tail;
+mod transfer;
+mod vector_db;
+mod walker;
+mod watcher;
+mod workspace;
+
+pub use chunker::*;
+pub use duplicates::*;
+pub use embedding_cache::*;
+pub use embeddings::*;
+pub use files::*;
+pub use graph::*;
+pub use imports::*;
+pub use indexer::*;
+pub use language::*;
+pub use reindex::*;
+pub use related::*;
+pub use search::*;
+pub use similar::*;
+pub use snippets::*;
+pub use stats::*;
+pub use tail::*;
+pub use transfer::*;
+pub use vector_db::*;
+pub use walker::*;
+pub use watcher::*;
+pub use workspace::*;
+
+/// Result cap for find_files when the caller doesn't pass a limit
+const DEFAULT_FIND_LIMIT: usize = 500;
+
+/// Extra results find_similar_code fetches to make up for the ones it drops
+/// for overlapping the snippet itself
+const SIMILAR_CODE_SLACK: usize = 10;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectFile {
@@ -9,6 +64,35 @@ pub struct ProjectFile {
     pub size: u64,
     pub modified: String,
     pub ai_relevance: Option<f32>,
+    #[serde(default)]
+    pub root: Option<String>, // Workspace folder the path is relative to
+    #[serde(default)]
+    pub is_binary: bool, // A NUL byte near the start; never read as text
+    #[serde(default)]
+    pub too_large: bool, // Over max_indexable_size, so not sniffed, parsed or indexed
+    #[serde(default)]
+    pub secret: bool, // Named like a credentials file, e.g. .env or *.pem
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum FileSortKey {
+    Name,
+    Size,
+    Modified,
+    AiRelevance,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectFilePage {
+    pub files: Vec<ProjectFile>,
+    pub total_count: usize,
+    pub offset: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,131 +106,933 @@ pub struct CodeEmbedding {
     pub content: String,
     pub embedding: Vec<f32>, // Vector embedding
     pub dependencies: Vec<String>,
+    #[serde(default)]
+    pub distance: Option<f32>, // Cosine distance, set on search results
+    #[serde(default)]
+    pub root: Option<String>, // Workspace folder, set on workspace search results
+}
+
+/// Optional narrowing for semantic search; every filter that is set must match
+///
+/// `path_glob` uses SQL GLOB semantics, where `*` also matches `/`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchFilters {
+    #[serde(default)]
+    pub languages: Vec<String>, // Any of these
+    #[serde(default)]
+    pub code_types: Vec<String>, // Any of these, e.g. function, class
+    pub path_prefix: Option<String>,
+    pub path_glob: Option<String>,
 }
 
 /// Get project file structure
+///
+/// With no paging arguments the full listing is returned. When sorting by
+/// `AiRelevance`, files without a score (`None`) always sort last regardless
+/// of the requested order. With `workspace_id` every root is listed and each
+/// file carries its root's name. Credential files are left out unless
+/// secret_policy is off.
 #[tauri::command]
-pub async fn get_project_files(project_path: String) -> Result<Vec<ProjectFile>, String> {
-    log::info!("Getting project files for: {}", project_path);
-    
-    // Simulate file system traversal
-    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
-    
-    let files = vec![
-        ProjectFile {
-            path: "src/components/Button.tsx".to_string(),
-            name: "Button.tsx".to_string(),
-            file_type: "typescript".to_string(),
-            size: 2048,
-            modified: "2024-01-15T10:30:00Z".to_string(),
-            ai_relevance: Some(0.95),
-        },
-        ProjectFile {
-            path: "src/utils/helpers.ts".to_string(),
-            name: "helpers.ts".to_string(),
-            file_type: "typescript".to_string(),
-            size: 1024,
-            modified: "2024-01-14T15:20:00Z".to_string(),
-            ai_relevance: Some(0.80),
-        },
-        ProjectFile {
-            path: "src/styles/globals.css".to_string(),
-            name: "globals.css".to_string(),
-            file_type: "css".to_string(),
-            size: 4096,
-            modified: "2024-01-13T09:15:00Z".to_string(),
-            ai_relevance: Some(0.60),
-        },
-        ProjectFile {
-            path: "package.json".to_string(),
-            name: "package.json".to_string(),
-            file_type: "json".to_string(),
-            size: 512,
-            modified: "2024-01-12T14:45:00Z".to_string(),
-            ai_relevance: None,
+pub async fn get_project_files(
+    app: tauri::AppHandle,
+    project_path: Option<String>,
+    workspace_id: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    sort_by: Option<FileSortKey>,
+    order: Option<SortOrder>,
+) -> Result<ProjectFilePage, CodifyError> {
+    let roots = command_roots(&app, project_path, workspace_id)?;
+    log::info!("Getting project files for: {}", describe_roots(&roots));
+
+    let mut files = tokio::task::spawn_blocking(move || {
+        let mut files = vec![];
+        for root in roots {
+            let found = walk_project(&root.path)?;
+            files.extend(found.into_iter().map(|file| ProjectFile {
+                root: root.name.clone(),
+                ..file
+            }));
+        }
+        Ok::<_, CodifyError>(files)
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("File traversal failed: {}", e)))??;
+    if secret_policy() != SecretPolicy::Off {
+        files.retain(|file| !file.secret);
+    }
+
+    if let Some(key) = sort_by {
+        sort_project_files(&mut files, key, order.unwrap_or(SortOrder::Ascending));
+    }
+
+    let total_count = files.len();
+    let offset = offset.unwrap_or(0);
+    let files = files
+        .into_iter()
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+
+    Ok(ProjectFilePage {
+        files,
+        total_count,
+        offset,
+    })
+}
+
+/// Find files by name or path pattern, e.g. `**/*.test.ts`, honoring the same
+/// ignore rules as get_project_files
+#[tauri::command]
+pub async fn find_files(
+    app: tauri::AppHandle,
+    project_path: Option<String>,
+    workspace_id: Option<String>,
+    glob: String,
+    case_insensitive: Option<bool>,
+    limit: Option<usize>,
+) -> Result<Vec<ProjectFile>, CodifyError> {
+    let roots = command_roots(&app, project_path, workspace_id)?;
+    log::info!(
+        "Finding files matching {} in {}",
+        glob,
+        describe_roots(&roots)
+    );
+
+    let limit = limit.unwrap_or(DEFAULT_FIND_LIMIT);
+    tokio::task::spawn_blocking(move || {
+        let mut files: Vec<ProjectFile> = vec![];
+        for root in roots {
+            let found = find_project_files(
+                &root.path,
+                &glob,
+                case_insensitive.unwrap_or(false),
+                limit - files.len(),
+            )?;
+            files.extend(found.into_iter().map(|file| ProjectFile {
+                root: root.name.clone(),
+                ..file
+            }));
+            if files.len() >= limit {
+                break;
+            }
+        }
+        Ok::<_, CodifyError>(files)
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("File search task failed: {}", e)))?
+}
+
+/// Find-in-files: literal or regex search across the project's text files
+#[tauri::command]
+pub async fn search_file_content(
+    app: tauri::AppHandle,
+    project_path: Option<String>,
+    workspace_id: Option<String>,
+    query: String,
+    is_regex: Option<bool>,
+    case_sensitive: Option<bool>,
+    max_matches: Option<usize>,
+) -> Result<ContentSearchResult, CodifyError> {
+    log::info!("Searching file content for: {}", query);
+
+    let roots = command_roots(&app, project_path, workspace_id)?;
+    let matcher = compile_query(&query, is_regex.unwrap_or(false), case_sensitive.unwrap_or(false))?;
+    let max_matches = max_matches.unwrap_or(DEFAULT_MAX_MATCHES);
+
+    tokio::task::spawn_blocking(move || {
+        let mut result = ContentSearchResult::default();
+        for root in roots {
+            let found = search_content(&root.path, &matcher, max_matches - result.matches.len())?;
+            result.files_searched += found.files_searched;
+            let matches = found.matches.into_iter().map(|found| ContentMatch {
+                root: root.name.clone(),
+                ..found
+            });
+            result.matches.extend(matches);
+            if found.truncated {
+                result.truncated = true;
+                break;
+            }
+        }
+        Ok::<_, CodifyError>(result)
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("Content search task failed: {}", e)))?
+}
+
+fn sort_project_files(files: &mut [ProjectFile], key: FileSortKey, order: SortOrder) {
+    use std::cmp::Ordering;
+
+    let directed = |ordering: Ordering| match order {
+        SortOrder::Ascending => ordering,
+        SortOrder::Descending => ordering.reverse(),
+    };
+
+    files.sort_by(|a, b| match key {
+        FileSortKey::Name => directed(a.name.to_lowercase().cmp(&b.name.to_lowercase())),
+        FileSortKey::Size => directed(a.size.cmp(&b.size)),
+        // RFC 3339 timestamps in UTC sort correctly as strings
+        FileSortKey::Modified => directed(a.modified.cmp(&b.modified)),
+        FileSortKey::AiRelevance => match (a.ai_relevance, b.ai_relevance) {
+            (Some(x), Some(y)) => directed(x.total_cmp(&y)),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
         },
-    ];
-    
-    Ok(files)
+    });
+}
+
+/// Read a text file from the project, optionally just a byte range of it
+#[tauri::command]
+pub async fn read_file(
+    project_path: String,
+    path: String,
+    range: Option<ByteRange>,
+) -> Result<FileContent, CodifyError> {
+    log::info!("Reading file: {}", path);
+
+    tokio::task::spawn_blocking(move || read_project_file(&project_path, &path, range))
+        .await
+        .map_err(|e| CodifyError::Internal(format!("File read task failed: {}", e)))?
+}
+
+/// Read the last lines of a project file, e.g. a dev server's log; with
+/// `follow`, new lines keep arriving as `tail-output` events until
+/// stop_tail_file is called with the returned tail id
+#[tauri::command]
+pub async fn tail_file(
+    app: tauri::AppHandle,
+    project_path: String,
+    path: String,
+    from_end_lines: Option<usize>,
+    follow: Option<bool>,
+) -> Result<TailResult, CodifyError> {
+    log::info!("Tailing file: {}", path);
+
+    let from_end_lines = from_end_lines.unwrap_or(DEFAULT_TAIL_LINES);
+    let follow = follow.unwrap_or(false);
+    tokio::task::spawn_blocking(move || {
+        tail_project_file(&app, &project_path, &path, from_end_lines, follow)
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("File tail task failed: {}", e)))?
+}
+
+/// Stop following a file started by tail_file; false if it already stopped
+#[tauri::command]
+pub async fn stop_tail_file(
+    tails: tauri::State<'_, TailState>,
+    tail_id: String,
+) -> Result<bool, CodifyError> {
+    log::info!("Stopping tail: {}", tail_id);
+
+    Ok(tails.stop(&tail_id))
+}
+
+/// Save code to the snippet library, e.g. a CompletionResult's or
+/// GeneratedDesign's code with `source` naming which
+#[tauri::command]
+pub async fn save_snippet(
+    app: tauri::AppHandle,
+    store: tauri::State<'_, SnippetStore>,
+    name: String,
+    language: String,
+    code: String,
+    tags: Option<Vec<String>>,
+    source: Option<String>,
+) -> Result<Snippet, CodifyError> {
+    log::info!("Saving snippet: {}", name);
+
+    let snippet = Snippet::new(&name, &language, &code, &tags.unwrap_or_default(), source)?;
+    store.save(&app, snippet)
+}
+
+/// Saved snippets matching the filter, newest first
+#[tauri::command]
+pub async fn list_snippets(
+    app: tauri::AppHandle,
+    store: tauri::State<'_, SnippetStore>,
+    filter: Option<SnippetFilter>,
+) -> Result<Vec<Snippet>, CodifyError> {
+    store.list(&app, &filter.unwrap_or_default())
+}
+
+/// Delete a snippet; false if there was none with that id
+#[tauri::command]
+pub async fn delete_snippet(
+    app: tauri::AppHandle,
+    store: tauri::State<'_, SnippetStore>,
+    id: String,
+) -> Result<bool, CodifyError> {
+    log::info!("Deleting snippet: {}", id);
+
+    store.delete(&app, &id)
+}
+
+/// Atomically write a file in the project; `create_only` refuses to overwrite
+#[tauri::command]
+pub async fn write_file(
+    project_path: String,
+    path: String,
+    content: String,
+    create_only: Option<bool>,
+) -> Result<FileWriteResult, CodifyError> {
+    log::info!("Writing file: {}", path);
+
+    tokio::task::spawn_blocking(move || {
+        write_project_file(&project_path, &path, &content, create_only.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("File write task failed: {}", e)))?
 }
 
 /// Search code semantically
+///
+/// With `workspace_id` each root's index is searched with the query embedded
+/// by that root's embedding model, and the closest `top_k` overall returned.
+/// Roots that haven't been indexed are skipped.
+///
+/// A search box passes a `session_id`: the search then waits out the
+/// debounce interval, and a newer query for the same session cancels it
+/// wherever it has got to, so only the latest query's results come back.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn search_code_semantic(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AIState>,
     query: String,
-    project_path: String,
-) -> Result<Vec<CodeEmbedding>, String> {
+    project_path: Option<String>,
+    workspace_id: Option<String>,
+    top_k: Option<usize>,
+    language: Option<String>,
+    filters: Option<SearchFilters>,
+    session_id: Option<String>,
+) -> Result<Vec<CodeEmbedding>, CodifyError> {
     log::info!("Semantic code search for: {}", query);
-    
-    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
-    
-    let results = vec![
-        CodeEmbedding {
-            id: uuid::Uuid::new_v4().to_string(),
-            file_path: "src/components/Button.tsx".to_string(),
-            start_line: 15,
-            end_line: 25,
-            code_type: "function".to_string(),
-            language: "typescript".to_string(),
-            content: "const Button = ({ children, variant, ...props }) => { ... }".to_string(),
-            embedding: vec![0.1, 0.2, 0.3], // Simplified embedding
-            dependencies: vec!["React".to_string()],
-        },
-        CodeEmbedding {
-            id: uuid::Uuid::new_v4().to_string(),
-            file_path: "src/hooks/useButton.ts".to_string(),
-            start_line: 5,
-            end_line: 20,
-            code_type: "hook".to_string(),
-            language: "typescript".to_string(),
-            content: "export const useButton = (props) => { ... }".to_string(),
-            embedding: vec![0.15, 0.25, 0.35],
-            dependencies: vec!["React".to_string()],
-        },
-    ];
-    
+
+    let roots = command_roots(&app, project_path, workspace_id)?;
+    let top_k = top_k.unwrap_or(10);
+    let mut filters = filters.unwrap_or_default();
+    filters.languages.extend(language);
+
+    let search = search_roots_semantic(
+        &app,
+        &state,
+        "search_code_semantic",
+        roots,
+        &query,
+        top_k,
+        &filters,
+    );
+    let mut results = match session_id {
+        Some(session_id) => {
+            let request = state.requests.begin(&format!("search:{}", session_id));
+            let debounce = state.search_debounce();
+            request
+                .run(async {
+                    tokio::time::sleep(debounce).await;
+                    search.await
+                })
+                .await?
+        }
+        None => search.await?,
+    };
+    results.truncate(top_k);
+
+    Ok(results)
+}
+
+/// Find indexed code similar to a snippet, e.g. the editor selection
+///
+/// Runs the same vector search as search_code_semantic with the snippet as the
+/// query, limited to its language. Chunks overlapping `location`, the
+/// snippet's own place in the project, are left out.
+#[tauri::command]
+pub async fn find_similar_code(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AIState>,
+    code: String,
+    language: String,
+    top_k: Option<usize>,
+    project_path: Option<String>,
+    workspace_id: Option<String>,
+    location: Option<SnippetLocation>,
+) -> Result<Vec<SimilarCode>, CodifyError> {
+    log::info!("Finding code similar to a {} snippet", language);
+
+    if code.trim().is_empty() {
+        return Err(CodifyError::InvalidInput(
+            "Code must not be empty".to_string(),
+        ));
+    }
+
+    let roots = command_roots(&app, project_path, workspace_id)?;
+    let top_k = top_k.unwrap_or(10);
+    let filters = SearchFilters {
+        languages: vec![language],
+        ..Default::default()
+    };
+
+    let fetch = top_k + SIMILAR_CODE_SLACK;
+    let results = search_roots_semantic(
+        &app,
+        &state,
+        "find_similar_code",
+        roots,
+        &code,
+        fetch,
+        &filters,
+    )
+    .await?;
+
+    let own_location = |chunk: &CodeEmbedding| {
+        location
+            .as_ref()
+            .is_some_and(|location| location.contains(chunk))
+    };
+    Ok(results
+        .into_iter()
+        .filter(|chunk| !own_location(chunk))
+        .take(top_k)
+        .map(SimilarCode::from)
+        .collect())
+}
+
+/// Find copy-pasted code: groups of indexed chunks at least `threshold`
+/// similar to each other (0.95 by default), most similar first
+#[tauri::command]
+pub async fn detect_duplicates(
+    app: tauri::AppHandle,
+    project_path: String,
+    threshold: Option<f32>,
+) -> Result<Vec<DuplicateGroup>, CodifyError> {
+    let threshold = threshold.unwrap_or(DEFAULT_DUPLICATE_THRESHOLD);
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(CodifyError::InvalidInput(format!(
+            "Invalid threshold {}: must be between 0 and 1",
+            threshold
+        )));
+    }
+    log::info!(
+        "Detecting duplicates in {} at threshold {}",
+        project_path,
+        threshold
+    );
+
+    tokio::task::spawn_blocking(move || {
+        find_duplicates(&app.state::<VectorStores>().open(&project_path)?, threshold)
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("Duplicate detection task failed: {}", e)))?
+}
+
+/// Embed `query` and search every root's index for its nearest `limit`
+/// chunks, merged by distance; workspace folders that fail are skipped
+async fn search_roots_semantic(
+    app: &tauri::AppHandle,
+    state: &AIState,
+    command: &str,
+    roots: Vec<SearchRoot>,
+    query: &str,
+    limit: usize,
+    filters: &SearchFilters,
+) -> Result<Vec<CodeEmbedding>, CodifyError> {
+    // Roots sharing an embedding model share the query vector
+    let mut query_embeddings: HashMap<String, Vec<f32>> = HashMap::new();
+    let mut results = vec![];
+    for root in roots {
+        let model = in_project(&root.path, state.active_config())
+            .await
+            .embedding_model;
+        let query_embedding = match query_embeddings.get(&model) {
+            Some(embedding) => embedding.clone(),
+            None => {
+                let embedding = in_project(&root.path, state.embed(command, &[query.to_string()]))
+                    .await?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| {
+                        CodifyError::Provider(
+                            "Provider returned no embedding for the query".to_string(),
+                        )
+                    })?;
+                query_embeddings.insert(model, embedding.clone());
+                embedding
+            }
+        };
+
+        let (name, path, filters) = (root.name.clone(), root.path.clone(), filters.clone());
+        let app = app.clone();
+        let found = tokio::task::spawn_blocking(move || {
+            let db = app.state::<VectorStores>().open(&path)?;
+            db.search(&query_embedding, limit, &filters)
+        })
+        .await
+        .map_err(|e| CodifyError::Internal(format!("Semantic search task failed: {}", e)))?;
+
+        match found {
+            Ok(found) => results.extend(found.into_iter().map(|embedding| CodeEmbedding {
+                root: name.clone(),
+                ..embedding
+            })),
+            Err(e) if root.name.is_some() => {
+                log::warn!("Skipping workspace folder {}: {}", root.path, e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    results.sort_by(|a, b| {
+        let distance = |embedding: &CodeEmbedding| embedding.distance.unwrap_or(f32::MAX);
+        distance(a).total_cmp(&distance(b))
+    });
+
     Ok(results)
 }
 
+/// Roots for a command given either a project or a workspace
+fn command_roots(
+    app: &tauri::AppHandle,
+    project_path: Option<String>,
+    workspace_id: Option<String>,
+) -> Result<Vec<SearchRoot>, CodifyError> {
+    let workspaces = match workspace_id {
+        Some(_) => crate::settings::read_settings(app).workspaces,
+        None => Default::default(),
+    };
+    search_roots(&workspaces, project_path, workspace_id)
+}
+
+fn describe_roots(roots: &[SearchRoot]) -> String {
+    roots
+        .iter()
+        .map(|root| root.path.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Generate an embedding vector for a single piece of code
+#[tauri::command]
+pub async fn generate_embedding(
+    state: tauri::State<'_, AIState>,
+    content: String,
+    language: String,
+) -> Result<Vec<f32>, CodifyError> {
+    log::info!("Generating embedding for {} snippet", language);
+
+    state
+        .embed("generate_embedding", &[content])
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| CodifyError::Provider("Provider returned no embedding".to_string()))
+}
+
+/// Chunk a file by function and embed every chunk, ready for store_code_embedding
+#[tauri::command]
+pub async fn generate_file_embeddings(
+    state: tauri::State<'_, AIState>,
+    file_path: String,
+    content: String,
+    language: String,
+) -> Result<Vec<CodeEmbedding>, CodifyError> {
+    log::info!("Generating embeddings for: {}", file_path);
+
+    let mut chunks = chunk_by_function(&file_path, &content, &language);
+    embed_chunks(&state, "generate_file_embeddings", &mut chunks).await?;
+
+    Ok(chunks)
+}
+
+/// Report the configured embedding model's vector dimension
+#[tauri::command]
+pub async fn get_embedding_dimension(state: tauri::State<'_, AIState>) -> Result<usize, CodifyError> {
+    let probe = state.embed("get_embedding_dimension", &["dimension probe".to_string()]).await?;
+
+    probe
+        .first()
+        .map(|vector| vector.len())
+        .ok_or_else(|| CodifyError::Provider("Provider returned no embedding".to_string()))
+}
+
 /// Store code embeddings
+///
+/// The first vector stored fixes the index's dimension; later vectors of a
+/// different length are rejected. With `normalize`, vectors are scaled to unit
+/// length before they are written.
 #[tauri::command]
-pub async fn store_code_embedding(embedding: CodeEmbedding) -> Result<String, String> {
+pub async fn store_code_embedding(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AIState>,
+    project_path: String,
+    embedding: CodeEmbedding,
+    normalize: Option<bool>,
+) -> Result<String, CodifyError> {
     log::info!("Storing code embedding for: {}", embedding.file_path);
-    
-    // In real implementation, this would store in DuckDB with VSS extension
-    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-    
-    Ok(embedding.id)
+
+    let ids = store_code_embeddings(app, state, project_path, vec![embedding], normalize).await?;
+    Ok(ids.into_iter().next().unwrap_or_default())
+}
+
+/// store_code_embedding for many rows at once, written in one transaction:
+/// either every row is stored or, on any error, none is. Returns their ids.
+#[tauri::command]
+pub async fn store_code_embeddings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AIState>,
+    project_path: String,
+    mut embeddings: Vec<CodeEmbedding>,
+    normalize: Option<bool>,
+) -> Result<Vec<String>, CodifyError> {
+    log::info!("Storing {} code embeddings", embeddings.len());
+
+    if normalize.unwrap_or(false) {
+        for embedding in &mut embeddings {
+            l2_normalize(&mut embedding.embedding);
+        }
+    }
+    let model = in_project(&project_path, state.active_config())
+        .await
+        .embedding_model;
+
+    tokio::task::spawn_blocking(move || {
+        let ids = embeddings.iter().map(|e| e.id.clone()).collect();
+        let mut batch = WriteBatch::default();
+        batch.push(embeddings);
+        app.state::<VectorStores>()
+            .open(&project_path)?
+            .write_batch(&mut batch, &model)
+            .map(|()| ids)
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("Embedding storage task failed: {}", e)))?
+}
+
+/// Report the index's vector dimension, embedding model and row counts, and
+/// whether it was built with a different model than the configured one
+#[tauri::command]
+pub async fn get_index_info(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AIState>,
+    project_path: String,
+) -> Result<IndexInfo, CodifyError> {
+    let model = in_project(&project_path, state.active_config())
+        .await
+        .embedding_model;
+
+    let mut info = tokio::task::spawn_blocking(move || {
+        app.state::<VectorStores>().open(&project_path)?.info()
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("Index info task failed: {}", e)))??;
+    info.needs_reindex = info.model.as_ref().is_some_and(|built| *built != model);
+    info.configured_model = Some(model);
+    Ok(info)
+}
+
+/// Delete one embedding by id, returning the number of rows removed
+#[tauri::command]
+pub async fn delete_code_embedding(
+    app: tauri::AppHandle,
+    project_path: String,
+    id: String,
+) -> Result<usize, CodifyError> {
+    log::info!("Deleting code embedding: {}", id);
+
+    tokio::task::spawn_blocking(move || {
+        app.state::<VectorStores>()
+            .open(&project_path)?
+            .delete_by_id(&id)
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("Embedding delete task failed: {}", e)))?
+}
+
+/// Delete every embedding stored for a file, e.g. after it was deleted or renamed
+#[tauri::command]
+pub async fn delete_embeddings_for_file(
+    app: tauri::AppHandle,
+    project_path: String,
+    file_path: String,
+) -> Result<usize, CodifyError> {
+    log::info!("Deleting embeddings for: {}", file_path);
+
+    tokio::task::spawn_blocking(move || {
+        app.state::<VectorStores>()
+            .open(&project_path)?
+            .delete_file(&file_path)
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("Embedding delete task failed: {}", e)))?
+}
+
+/// Remove every embedding in the project's index
+#[tauri::command]
+pub async fn clear_index(
+    app: tauri::AppHandle,
+    project_path: String,
+) -> Result<usize, CodifyError> {
+    log::info!("Clearing index for: {}", project_path);
+
+    tokio::task::spawn_blocking(move || app.state::<VectorStores>().open(&project_path)?.clear())
+        .await
+        .map_err(|e| CodifyError::Internal(format!("Index clear task failed: {}", e)))?
+}
+
+/// Hit/miss totals and size of the project's embedding cache
+#[tauri::command]
+pub async fn get_embedding_cache_stats(
+    app: tauri::AppHandle,
+    project_path: String,
+) -> Result<EmbeddingCacheStats, CodifyError> {
+    tokio::task::spawn_blocking(move || {
+        app.state::<VectorStores>()
+            .open(&project_path)?
+            .embedding_cache_stats()
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("Embedding cache task failed: {}", e)))?
+}
+
+/// Empty the project's embedding cache, returning the number of entries removed
+#[tauri::command]
+pub async fn purge_embedding_cache(
+    app: tauri::AppHandle,
+    project_path: String,
+) -> Result<usize, CodifyError> {
+    log::info!("Purging embedding cache for: {}", project_path);
+
+    tokio::task::spawn_blocking(move || {
+        app.state::<VectorStores>()
+            .open(&project_path)?
+            .purge_embedding_cache()
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("Embedding cache task failed: {}", e)))?
+}
+
+/// Index a whole project incrementally, skipping files that haven't changed
+///
+/// The run's id arrives in the `index-started` event and on every
+/// `index-progress` event; pass it to cancel_index to stop the run.
+#[tauri::command]
+pub async fn index_project(
+    app: tauri::AppHandle,
+    ai: tauri::State<'_, AIState>,
+    indexer: tauri::State<'_, IndexerState>,
+    project_path: String,
+) -> Result<IndexSummary, CodifyError> {
+    log::info!("Indexing project: {}", project_path);
+
+    let summary = in_project(&project_path, run_index(&app, &ai, &indexer, &project_path)).await?;
+
+    log::info!(
+        "Indexed {} files ({} skipped, {} removed, {} cached chunks reused)",
+        summary.files_indexed,
+        summary.files_skipped,
+        summary.files_removed,
+        summary.cache_hits
+    );
+
+    Ok(summary)
+}
+
+/// Switch the project's embedding model and rebuild its index to match,
+/// emitting `index-progress` events
+///
+/// Vectors from different models can't be compared, so the index is cleared
+/// when the model or vector dimension changed; otherwise only changed files
+/// are re-embedded. Without `new_model`, the configured model is used. The
+/// new model is saved to settings before the rebuild starts.
+#[tauri::command]
+pub async fn reindex_with_model(
+    app: tauri::AppHandle,
+    ai: tauri::State<'_, AIState>,
+    indexer: tauri::State<'_, IndexerState>,
+    project_path: String,
+    new_model: Option<String>,
+) -> Result<ReindexSummary, CodifyError> {
+    log::info!("Reindexing project: {}", project_path);
+
+    if let Some(model) = new_model.as_deref().map(str::trim) {
+        if model.is_empty() {
+            return Err(CodifyError::InvalidInput(
+                "Embedding model must not be empty".to_string(),
+            ));
+        }
+        crate::settings::set_embedding_model(&app, &ai, &project_path, model).await?;
+    }
+
+    let summary = in_project(
+        &project_path,
+        run_reindex(&app, &ai, &indexer, &project_path),
+    )
+    .await?;
+
+    log::info!(
+        "Reindexed with {} ({} files, cleared: {})",
+        summary.model,
+        summary.index.files_indexed,
+        summary.cleared
+    );
+
+    Ok(summary)
+}
+
+/// Write the project's index, vectors included, to a file teammates can
+/// import instead of embedding the project themselves
+#[tauri::command]
+pub async fn export_index(
+    app: tauri::AppHandle,
+    project_path: String,
+    path: String,
+) -> Result<IndexExportSummary, CodifyError> {
+    log::info!("Exporting index of {} to {}", project_path, path);
+
+    tokio::task::spawn_blocking(move || {
+        export_index_to(&app.state::<VectorStores>(), &project_path, &path)
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("Index export task failed: {}", e)))?
+}
+
+/// Load an export_index file, merging it into the index (the default) or
+/// replacing the index with it
+///
+/// The export must come from the configured embedding model. Rows for files
+/// this project doesn't have are skipped and counted.
+#[tauri::command]
+pub async fn import_index(
+    app: tauri::AppHandle,
+    ai: tauri::State<'_, AIState>,
+    indexer: tauri::State<'_, IndexerState>,
+    project_path: String,
+    path: String,
+    mode: Option<ImportMode>,
+) -> Result<IndexImportSummary, CodifyError> {
+    log::info!("Importing index for {} from {}", project_path, path);
+
+    if indexer.is_running(&project_path) {
+        return Err(CodifyError::Busy(format!(
+            "Indexing is already running for {}",
+            project_path
+        )));
+    }
+    let model = in_project(&project_path, ai.active_config())
+        .await
+        .embedding_model;
+    let mode = mode.unwrap_or_default();
+
+    let summary = tokio::task::spawn_blocking(move || {
+        let stores = app.state::<VectorStores>();
+        import_index_from(&stores, &project_path, &path, &model, mode)
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("Index import task failed: {}", e)))??;
+
+    log::info!(
+        "Imported {} rows ({} skipped)",
+        summary.imported,
+        summary.skipped
+    );
+
+    Ok(summary)
+}
+
+/// Stop the index run with this id, keeping the files it already stored;
+/// an `index-cancelled` event reports what it did before stopping
+#[tauri::command]
+pub async fn cancel_index(
+    indexer: tauri::State<'_, IndexerState>,
+    index_id: String,
+) -> Result<bool, CodifyError> {
+    log::info!("Cancelling index run: {}", index_id);
+
+    Ok(indexer.cancel_by_id(&index_id))
+}
+
+/// Watch a project for changes, emitting `file-change` events
+#[tauri::command]
+pub async fn watch_project(
+    app: tauri::AppHandle,
+    watchers: tauri::State<'_, WatcherState>,
+    project_path: String,
+) -> Result<(), CodifyError> {
+    log::info!("Watching project: {}", project_path);
+
+    watchers.watch(&app, &project_path)
+}
+
+/// Stop the watcher started by watch_project
+#[tauri::command]
+pub async fn stop_watch_project(
+    watchers: tauri::State<'_, WatcherState>,
+    project_path: String,
+) -> Result<bool, CodifyError> {
+    log::info!("Stopping watcher for: {}", project_path);
+
+    Ok(watchers.stop(&project_path))
+}
+
+/// Build the project's import graph: which files each file imports, which
+/// files import it, and imports that point at missing project paths
+///
+/// Parsed imports are cached per file and invalidated by watch_project.
+#[tauri::command]
+pub async fn project_dependency_graph(
+    app: tauri::AppHandle,
+    project_path: String,
+) -> Result<DependencyGraph, CodifyError> {
+    log::info!("Building dependency graph for: {}", project_path);
+
+    tokio::task::spawn_blocking(move || {
+        app.state::<DependencyGraphState>().build(&project_path)
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("Dependency graph task failed: {}", e)))?
+}
+
+/// Totals for a project dashboard: files, non-blank lines and definitions
+/// per language, average file size and the share of test files
+///
+/// Per-file results are cached until the file changes, so repeat calls only
+/// re-read what was edited.
+#[tauri::command]
+pub async fn analyze_project(
+    app: tauri::AppHandle,
+    project_path: String,
+) -> Result<ProjectStats, CodifyError> {
+    log::info!("Analyzing project: {}", project_path);
+
+    tokio::task::spawn_blocking(move || app.state::<ProjectStatsState>().analyze(&project_path))
+        .await
+        .map_err(|e| CodifyError::Internal(format!("Project analysis task failed: {}", e)))?
 }
 
 /// Get AI-suggested files based on current context
+///
+/// Files the current one imports score 1.0; semantically similar files from
+/// the embedding index score up to 0.8. Sorted by relevance, highest first.
 #[tauri::command]
 pub async fn get_ai_suggested_files(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AIState>,
     current_file: String,
     project_path: String,
-) -> Result<Vec<ProjectFile>, String> {
+    limit: Option<usize>,
+) -> Result<Vec<ProjectFile>, CodifyError> {
     log::info!("Getting AI-suggested files for: {}", current_file);
-    
-    tokio::time::sleep(std::time::Duration::from_millis(250)).await;
-    
-    let suggested = vec![
-        ProjectFile {
-            path: "src/components/Button.tsx".to_string(),
-            name: "Button.tsx".to_string(),
-            file_type: "typescript".to_string(),
-            size: 2048,
-            modified: "2024-01-15T10:30:00Z".to_string(),
-            ai_relevance: Some(0.95),
-        },
-        ProjectFile {
-            path: "src/utils/helpers.ts".to_string(),
-            name: "helpers.ts".to_string(),
-            file_type: "typescript".to_string(),
-            size: 1024,
-            modified: "2024-01-14T15:20:00Z".to_string(),
-            ai_relevance: Some(0.80),
-        },
-    ];
-    
-    Ok(suggested)
-}
\ No newline at end of file
+
+    in_project(
+        &project_path,
+        suggest_related_files(
+            &app,
+            &state,
+            &project_path,
+            &current_file,
+            limit.unwrap_or(DEFAULT_SUGGESTION_LIMIT),
+        ),
+    )
+    .await
+}
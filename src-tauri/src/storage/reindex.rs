@@ -0,0 +1,227 @@
+use super::{
+    detect_file_language, file_limits, index_file, is_binary, FileChangeKind, IndexerState,
+    ProjectFile, VectorDb, VectorStores, WriteBatch, INDEXABLE_LANGUAGES,
+};
+use crate::ai::{in_project, is_secret_file, AIState};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+/// Quiet period after the last change before re-embedding, so a run of saves
+/// becomes one batch
+const REINDEX_DELAY: Duration = Duration::from_secs(2);
+
+/// Emitted as `index-updated` after each batch of watched changes is applied
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexUpdated {
+    pub project_path: String,
+    pub files_reindexed: usize,
+    pub files_removed: usize,
+    pub errors: Vec<String>, // One per file that failed; the rest were still applied
+}
+
+struct Pending {
+    changes: HashMap<String, FileChangeKind>, // Latest change per project-relative path
+    due: Instant,
+}
+
+/// Changed files waiting to be re-embedded, per project; a project has an
+/// entry exactly while its worker task is alive
+#[derive(Default)]
+pub struct ReindexState {
+    pending: Mutex<HashMap<String, Pending>>,
+}
+
+impl ReindexState {
+    /// Queue watched changes for a project that has an index, starting its
+    /// worker when none is running
+    pub fn enqueue(
+        &self,
+        app: &tauri::AppHandle,
+        project_path: &str,
+        changes: Vec<(String, FileChangeKind)>,
+    ) {
+        if changes.is_empty() || !VectorDb::db_path(project_path).exists() {
+            return;
+        }
+
+        let mut pending = self.pending.lock().unwrap();
+        let start = !pending.contains_key(project_path);
+        let entry = pending
+            .entry(project_path.to_string())
+            .or_insert_with(|| Pending {
+                changes: HashMap::new(),
+                due: Instant::now(),
+            });
+        entry.changes.extend(changes);
+        entry.due = Instant::now() + REINDEX_DELAY;
+
+        if start {
+            let app = app.clone();
+            let project = project_path.to_string();
+            tauri::async_runtime::spawn(async move { run_worker(app, project).await });
+        }
+    }
+
+    /// The batch to apply once the project has been quiet long enough, or
+    /// how long to wait; None (and the entry removed) when nothing is left
+    fn take(
+        &self,
+        project_path: &str,
+    ) -> Option<Result<HashMap<String, FileChangeKind>, Duration>> {
+        let mut pending = self.pending.lock().unwrap();
+        let entry = pending.get_mut(project_path)?;
+
+        if entry.changes.is_empty() {
+            pending.remove(project_path);
+            return None;
+        }
+        let now = Instant::now();
+        if entry.due > now {
+            return Some(Err(entry.due - now));
+        }
+        Some(Ok(std::mem::take(&mut entry.changes)))
+    }
+
+    /// Put a batch back, e.g. while a full index run owns the database;
+    /// changes queued since it was taken are newer and win
+    fn requeue(&self, project_path: &str, batch: HashMap<String, FileChangeKind>) {
+        let mut pending = self.pending.lock().unwrap();
+        if let Some(entry) = pending.get_mut(project_path) {
+            for (path, kind) in batch {
+                entry.changes.entry(path).or_insert(kind);
+            }
+            entry.due = Instant::now() + REINDEX_DELAY;
+        }
+    }
+}
+
+async fn run_worker(app: tauri::AppHandle, project_path: String) {
+    let state = app.state::<ReindexState>();
+
+    while let Some(next) = state.take(&project_path) {
+        let batch = match next {
+            Ok(batch) => batch,
+            Err(wait) => {
+                tokio::time::sleep(wait).await;
+                continue;
+            }
+        };
+
+        // index_project already picks up every change; try again after it
+        if app.state::<IndexerState>().is_running(&project_path) {
+            state.requeue(&project_path, batch);
+            continue;
+        }
+
+        let ai = app.state::<AIState>();
+        if !ai.is_enabled() {
+            log::info!("AI is disabled; dropping {} watched change(s)", batch.len());
+            continue;
+        }
+
+        let stores = app.state::<VectorStores>();
+        let updated = in_project(
+            &project_path,
+            reindex_batch(&ai, &stores, &project_path, batch),
+        )
+        .await;
+        log::info!(
+            "Re-indexed {} file(s) and removed {} in {}",
+            updated.files_reindexed,
+            updated.files_removed,
+            project_path
+        );
+        let _ = app.emit("index-updated", updated);
+    }
+}
+
+/// Apply one batch: re-embed created and modified files, drop deleted ones
+async fn reindex_batch(
+    ai: &AIState,
+    stores: &VectorStores,
+    project_path: &str,
+    batch: HashMap<String, FileChangeKind>,
+) -> IndexUpdated {
+    let mut updated = IndexUpdated {
+        project_path: project_path.to_string(),
+        ..Default::default()
+    };
+
+    let model = ai.active_config().await.embedding_model;
+    let mut db = match stores.open(project_path) {
+        Ok(db) => db,
+        Err(e) => {
+            updated.errors.push(e.to_string());
+            return updated;
+        }
+    };
+
+    for (path, kind) in batch {
+        let full_path = Path::new(project_path).join(&path);
+        let metadata = match kind {
+            FileChangeKind::Deleted => None,
+            _ => std::fs::metadata(&full_path).ok(),
+        };
+
+        // Gone by the time the batch ran counts as deleted
+        let Some(metadata) = metadata else {
+            match db.delete_file(&path) {
+                Ok(_) => updated.files_removed += 1,
+                Err(e) => updated.errors.push(format!("{}: {}", path, e)),
+            }
+            continue;
+        };
+
+        let language = detect_file_language(&full_path);
+        if !metadata.is_file() || !INDEXABLE_LANGUAGES.contains(&language.as_str()) {
+            continue;
+        }
+        let too_large = metadata.len() > file_limits().max_indexable_size;
+        let binary = !too_large && is_binary(&full_path);
+        if too_large || binary {
+            // An earlier, smaller version may still be in the index
+            match db.delete_file(&path) {
+                Ok(_) => updated.files_removed += 1,
+                Err(e) => updated.errors.push(format!("{}: {}", path, e)),
+            }
+            continue;
+        }
+
+        let name = full_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let file = ProjectFile {
+            secret: is_secret_file(&name),
+            name,
+            path: path.clone(),
+            file_type: language,
+            size: metadata.len(),
+            // Same format as walk_project, so index_project sees the file as current
+            modified: metadata
+                .modified()
+                .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339())
+                .unwrap_or_default(),
+            ai_relevance: None,
+            root: None,
+            is_binary: binary,
+            too_large,
+        };
+
+        let mut batch = WriteBatch::default();
+        let stored = index_file(ai, &mut db, &mut batch, "auto_reindex", project_path, &file)
+            .await
+            .and_then(|stored| db.write_batch(&mut batch, &model).map(|()| stored));
+        match stored {
+            Ok(Some(_)) => updated.files_reindexed += 1,
+            Ok(None) => {}
+            Err(e) => updated.errors.push(format!("{}: {}", path, e)),
+        }
+    }
+
+    updated
+}
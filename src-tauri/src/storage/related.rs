@@ -0,0 +1,160 @@
+use super::{
+    import_specifiers, read_project_file, resolve_import, resolve_in_project, walk_project,
+    PathAliases, ProjectFile, SearchFilters, VectorStores,
+};
+use crate::ai::{fit_around_line, AIState};
+use crate::error::CodifyError;
+use std::collections::{HashMap, HashSet};
+use tauri::Manager;
+
+/// Result cap for get_ai_suggested_files when the caller doesn't pass one
+pub const DEFAULT_SUGGESTION_LIMIT: usize = 10;
+
+/// Direct imports always outrank semantic matches, whose similarity is
+/// scaled into [0, SEMANTIC_WEIGHT]
+const IMPORT_RELEVANCE: f32 = 1.0;
+const SEMANTIC_WEIGHT: f32 = 0.8;
+
+/// Chunks pulled from the index before they are grouped by file
+const SEMANTIC_CANDIDATES: usize = 50;
+
+/// How much of the current file is embedded as the similarity query
+const QUERY_TOKENS: usize = 1024;
+
+struct CurrentFile {
+    path: String, // Project-relative
+    content: String,
+    imports: Vec<String>,
+    files: Vec<ProjectFile>,
+    indexed: bool,
+}
+
+fn load_current(
+    stores: &VectorStores,
+    project_path: &str,
+    current_file: &str,
+) -> Result<CurrentFile, CodifyError> {
+    let file = read_project_file(project_path, current_file, None)?;
+
+    let root = std::path::Path::new(project_path)
+        .canonicalize()
+        .map_err(|_| {
+            CodifyError::NotFound(format!("Project path does not exist: {}", project_path))
+        })?;
+    let resolved = resolve_in_project(project_path, current_file)?;
+    let path = resolved
+        .strip_prefix(&root)
+        .unwrap_or(&resolved)
+        .to_string_lossy()
+        .replace('\\', "/");
+
+    let files = walk_project(project_path)?;
+    let known: HashSet<String> = files.iter().map(|f| f.path.clone()).collect();
+    let aliases = PathAliases::load(project_path);
+    let imports = import_specifiers(&path, &file.content, &file.language)
+        .iter()
+        .filter_map(|specifier| {
+            resolve_import(&path, specifier, &file.language, &known, &aliases)
+        })
+        .filter(|imported| *imported != path)
+        .collect();
+
+    let indexed = stores.open(project_path)?.info()?.rows > 0;
+
+    Ok(CurrentFile {
+        path,
+        content: file.content,
+        imports,
+        files,
+        indexed,
+    })
+}
+
+/// Best similarity per file for chunks near the current file's content
+async fn semantic_scores(
+    app: &tauri::AppHandle,
+    ai: &AIState,
+    project_path: &str,
+    current: &CurrentFile,
+) -> Result<HashMap<String, f32>, CodifyError> {
+    let (query, _) = fit_around_line(&current.content, 0, QUERY_TOKENS);
+    let embedding = ai
+        .embed("get_ai_suggested_files", &[query])
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| CodifyError::Provider("Provider returned no embedding".to_string()))?;
+
+    let (app, project_path) = (app.clone(), project_path.to_string());
+    let chunks = tokio::task::spawn_blocking(move || {
+        app.state::<VectorStores>().open(&project_path)?.search(
+            &embedding,
+            SEMANTIC_CANDIDATES,
+            &SearchFilters::default(),
+        )
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("Semantic search task failed: {}", e)))??;
+
+    let mut scores = HashMap::new();
+    for chunk in chunks.into_iter().filter(|c| c.file_path != current.path) {
+        let similarity = (1.0 - chunk.distance.unwrap_or(1.0)).clamp(0.0, 1.0);
+        let score = scores.entry(chunk.file_path).or_insert(0.0_f32);
+        *score = score.max(similarity * SEMANTIC_WEIGHT);
+    }
+    Ok(scores)
+}
+
+/// Rank files related to `current_file`: the files it imports first, then
+/// files with semantically similar code from the embedding index
+///
+/// When the project has no index, or the embedding provider is unreachable,
+/// only import analysis is used.
+pub async fn suggest_related_files(
+    app: &tauri::AppHandle,
+    ai: &AIState,
+    project_path: &str,
+    current_file: &str,
+    limit: usize,
+) -> Result<Vec<ProjectFile>, CodifyError> {
+    let (project, file) = (project_path.to_string(), current_file.to_string());
+    let handle = app.clone();
+    let current = tokio::task::spawn_blocking(move || {
+        load_current(&handle.state::<VectorStores>(), &project, &file)
+    })
+    .await
+    .map_err(|e| CodifyError::Internal(format!("File analysis task failed: {}", e)))??;
+
+    let mut scores: HashMap<String, f32> = HashMap::new();
+    if current.indexed {
+        match semantic_scores(app, ai, project_path, &current).await {
+            Ok(semantic) => scores.extend(semantic),
+            Err(e) => log::warn!(
+                "Semantic suggestions unavailable, using imports only: {}",
+                e
+            ),
+        }
+    }
+    for imported in &current.imports {
+        scores.insert(imported.clone(), IMPORT_RELEVANCE);
+    }
+
+    let mut suggested: Vec<ProjectFile> = current
+        .files
+        .into_iter()
+        .filter_map(|mut file| {
+            file.ai_relevance = Some(*scores.get(&file.path)?);
+            Some(file)
+        })
+        .collect();
+
+    suggested.sort_by(|a, b| {
+        b.ai_relevance
+            .unwrap_or(0.0)
+            .total_cmp(&a.ai_relevance.unwrap_or(0.0))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    suggested.truncate(limit);
+
+    Ok(suggested)
+}
@@ -0,0 +1,102 @@
+use super::{file_limits, looks_binary, walk_project};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+
+/// Result cap for search_file_content when the caller doesn't pass one
+pub const DEFAULT_MAX_MATCHES: usize = 1000;
+
+/// Longer lines (usually minified code) are cut in results
+const MAX_LINE_TEXT_CHARS: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentMatch {
+    pub file_path: String,
+    pub line_number: usize, // 1-based
+    pub line_text: String,
+    pub start_column: usize, // 0-based character offsets into the line, end exclusive
+    pub end_column: usize,
+    #[serde(default)]
+    pub root: Option<String>, // Workspace folder the path is relative to
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContentSearchResult {
+    pub matches: Vec<ContentMatch>,
+    pub files_searched: usize,
+    pub truncated: bool, // Stopped at max_matches
+}
+
+/// Build the matcher for a query; literal queries are escaped first
+pub fn compile_query(
+    query: &str,
+    is_regex: bool,
+    case_sensitive: bool,
+) -> Result<regex::Regex, CodifyError> {
+    if query.is_empty() {
+        return Err(CodifyError::InvalidInput(
+            "Search query must not be empty".to_string(),
+        ));
+    }
+
+    let pattern = if is_regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+
+    regex::RegexBuilder::new(&pattern)
+        .case_insensitive(!case_sensitive)
+        .build()
+        .map_err(|e| CodifyError::InvalidInput(format!("Invalid regular expression: {}", e)))
+}
+
+/// Search every text file in the project line by line, honoring the same
+/// ignore rules as the file tree and skipping binary and oversized files
+pub fn search_content(
+    project_path: &str,
+    matcher: &regex::Regex,
+    max_matches: usize,
+) -> Result<ContentSearchResult, CodifyError> {
+    let root = std::path::Path::new(project_path);
+    let mut result = ContentSearchResult::default();
+
+    let max_read_size = file_limits().max_read_size;
+    for file in walk_project(project_path)? {
+        if file.is_binary || file.size > max_read_size {
+            continue;
+        }
+
+        let Ok(bytes) = std::fs::read(root.join(&file.path)) else {
+            continue;
+        };
+        // Files over max_indexable_size weren't sniffed during the walk
+        if looks_binary(&bytes) {
+            continue;
+        }
+        let Ok(content) = String::from_utf8(bytes) else {
+            continue;
+        };
+        result.files_searched += 1;
+
+        for (index, line) in content.lines().enumerate() {
+            for found in matcher.find_iter(line) {
+                if result.matches.len() >= max_matches {
+                    result.truncated = true;
+                    return Ok(result);
+                }
+
+                let start_column = line[..found.start()].chars().count();
+                result.matches.push(ContentMatch {
+                    file_path: file.path.clone(),
+                    line_number: index + 1,
+                    line_text: line.chars().take(MAX_LINE_TEXT_CHARS).collect(),
+                    start_column,
+                    end_column: start_column + found.as_str().chars().count(),
+                    root: None,
+                });
+            }
+        }
+    }
+
+    Ok(result)
+}
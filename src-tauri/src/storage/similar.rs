@@ -0,0 +1,52 @@
+use super::CodeEmbedding;
+use serde::{Deserialize, Serialize};
+
+/// Where a snippet passed to find_similar_code came from, e.g. the editor
+/// selection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnippetLocation {
+    pub file_path: String, // Relative to the project (or workspace folder)
+    pub start_line: u32,
+    pub end_line: u32,
+    #[serde(default)]
+    pub root: Option<String>, // Workspace folder name, for workspace searches
+}
+
+impl SnippetLocation {
+    /// Whether an indexed chunk overlaps this location
+    pub fn contains(&self, chunk: &CodeEmbedding) -> bool {
+        let normalize = |path: &str| path.replace('\\', "/").trim_start_matches("./").to_string();
+
+        chunk.root == self.root
+            && normalize(&chunk.file_path) == normalize(&self.file_path)
+            && chunk.start_line <= self.end_line
+            && self.start_line <= chunk.end_line
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarCode {
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub code_type: String,
+    pub language: String,
+    pub content: String,
+    pub similarity: f32, // Cosine similarity to the snippet; 1.0 is identical
+    pub root: Option<String>, // Workspace folder, set on workspace results
+}
+
+impl From<CodeEmbedding> for SimilarCode {
+    fn from(chunk: CodeEmbedding) -> Self {
+        SimilarCode {
+            similarity: 1.0 - chunk.distance.unwrap_or(1.0),
+            file_path: chunk.file_path,
+            start_line: chunk.start_line,
+            end_line: chunk.end_line,
+            code_type: chunk.code_type,
+            language: chunk.language,
+            content: chunk.content,
+            root: chunk.root,
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::Manager;
+
+const SNIPPETS_FILE: &str = "snippets.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    pub id: String,
+    pub name: String,
+    pub language: String,
+    pub code: String,
+    pub tags: Vec<String>, // Lowercased, without duplicates
+    /// Where it was saved from, e.g. `completion` or `design`
+    pub source: Option<String>,
+    pub created_at: String,
+}
+
+/// Which snippets list_snippets returns; every given field must match
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnippetFilter {
+    pub query: Option<String>,    // Case-insensitive, in the name or code
+    pub language: Option<String>, // Case-insensitive
+    pub tags: Vec<String>,        // The snippet must have all of them
+}
+
+impl SnippetFilter {
+    fn matches(&self, snippet: &Snippet) -> bool {
+        if let Some(language) = &self.language {
+            if !snippet.language.eq_ignore_ascii_case(language.trim()) {
+                return false;
+            }
+        }
+        if let Some(query) = &self.query {
+            let query = query.trim().to_lowercase();
+            if !snippet.name.to_lowercase().contains(&query)
+                && !snippet.code.to_lowercase().contains(&query)
+            {
+                return false;
+            }
+        }
+        normalize_tags(&self.tags)
+            .iter()
+            .all(|tag| snippet.tags.contains(tag))
+    }
+}
+
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = vec![];
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+impl Snippet {
+    pub fn new(
+        name: &str,
+        language: &str,
+        code: &str,
+        tags: &[String],
+        source: Option<String>,
+    ) -> Result<Self, CodifyError> {
+        if name.trim().is_empty() {
+            return Err(CodifyError::InvalidInput(
+                "Snippet name must not be empty".to_string(),
+            ));
+        }
+        if code.trim().is_empty() {
+            return Err(CodifyError::InvalidInput(
+                "Snippet code must not be empty".to_string(),
+            ));
+        }
+
+        Ok(Snippet {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.trim().to_string(),
+            language: language.trim().to_lowercase(),
+            code: code.to_string(),
+            tags: normalize_tags(tags),
+            source,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+/// The user's snippet library, one JSON file under the app data dir
+#[derive(Default)]
+pub struct SnippetStore {
+    // Held across read-modify-write so concurrent saves don't drop snippets
+    lock: Mutex<()>,
+}
+
+impl SnippetStore {
+    fn path(app: &tauri::AppHandle) -> Result<PathBuf, CodifyError> {
+        app.path()
+            .app_data_dir()
+            .map(|dir| dir.join(SNIPPETS_FILE))
+            .map_err(|e| CodifyError::Io(format!("Failed to locate data directory: {}", e)))
+    }
+
+    fn read(app: &tauri::AppHandle) -> Result<Vec<Snippet>, CodifyError> {
+        let path = Self::path(app)?;
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            return Ok(vec![]);
+        };
+        serde_json::from_str(&text)
+            .map_err(|e| CodifyError::Internal(format!("Snippet library is corrupt: {}", e)))
+    }
+
+    fn write(app: &tauri::AppHandle, snippets: &[Snippet]) -> Result<(), CodifyError> {
+        let path = Self::path(app)?;
+        let dir = path
+            .parent()
+            .ok_or_else(|| CodifyError::Io("Failed to locate data directory".to_string()))?;
+        std::fs::create_dir_all(dir)
+            .map_err(|e| CodifyError::Io(format!("Failed to create data directory: {}", e)))?;
+
+        let json = serde_json::to_string_pretty(snippets)
+            .map_err(|e| CodifyError::Internal(format!("Failed to encode snippets: {}", e)))?;
+        super::write_project_file(&dir.to_string_lossy(), SNIPPETS_FILE, &json, false)?;
+        Ok(())
+    }
+
+    pub fn save(&self, app: &tauri::AppHandle, snippet: Snippet) -> Result<Snippet, CodifyError> {
+        let _guard = self.lock.lock().unwrap();
+
+        let mut snippets = Self::read(app)?;
+        snippets.push(snippet.clone());
+        Self::write(app, &snippets)?;
+        Ok(snippet)
+    }
+
+    /// Matching snippets, newest first
+    pub fn list(
+        &self,
+        app: &tauri::AppHandle,
+        filter: &SnippetFilter,
+    ) -> Result<Vec<Snippet>, CodifyError> {
+        let _guard = self.lock.lock().unwrap();
+
+        let mut snippets: Vec<Snippet> = Self::read(app)?
+            .into_iter()
+            .filter(|snippet| filter.matches(snippet))
+            .collect();
+        snippets.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(snippets)
+    }
+
+    /// False when no snippet has that id
+    pub fn delete(&self, app: &tauri::AppHandle, id: &str) -> Result<bool, CodifyError> {
+        let _guard = self.lock.lock().unwrap();
+
+        let mut snippets = Self::read(app)?;
+        let before = snippets.len();
+        snippets.retain(|snippet| snippet.id != id);
+        if snippets.len() == before {
+            return Ok(false);
+        }
+        Self::write(app, &snippets)?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,193 @@
+use super::{chunk_by_function, find_definition, is_js_family, walk_project};
+use crate::ai::is_component_name;
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+
+/// Totals for the files of one language
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LanguageStats {
+    pub language: String,
+    pub files: usize,
+    pub lines: usize, // Non-blank lines
+    pub functions: usize,
+    pub classes: usize,
+    pub components: usize, // PascalCase functions and classes in JSX-family files
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectStats {
+    pub total_files: usize,
+    pub total_lines: usize,
+    pub total_size: u64, // Bytes
+    pub average_file_size: u64,
+    pub functions: usize,
+    pub classes: usize,
+    pub components: usize,
+    pub test_files: usize,
+    pub test_file_ratio: f64, // Test files over source files the chunker parses
+    /// Binary or over max_indexable_size; counted in files and size only
+    pub skipped_files: usize,
+    pub languages: Vec<LanguageStats>, // Most lines first
+}
+
+/// What one file contributes, valid while its mtime is unchanged
+#[derive(Debug, Clone, Default)]
+struct FileStats {
+    modified: String,
+    lines: usize,
+    functions: usize,
+    classes: usize,
+    components: usize,
+}
+
+/// Languages the chunker has a grammar for, whose files count as source code
+fn is_source_language(language: &str) -> bool {
+    is_js_family(language) || matches!(language, "rust" | "python")
+}
+
+/// Test files by the usual naming conventions of JS, Python and Rust projects
+pub fn is_test_path(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.contains(".test.")
+        || name.contains(".spec.")
+        || (name.starts_with("test_") && name.ends_with(".py"))
+        || name.ends_with("_test.py")
+        || path
+            .split('/')
+            .rev()
+            .skip(1)
+            .any(|dir| matches!(dir, "__tests__" | "tests" | "test"))
+}
+
+fn analyze_file(path: &str, content: &str, language: &str) -> FileStats {
+    let lines = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .count();
+    let mut stats = FileStats {
+        lines,
+        ..Default::default()
+    };
+    if !is_source_language(language) {
+        return stats;
+    }
+
+    let jsx = matches!(language, "javascriptreact" | "typescriptreact");
+    for chunk in chunk_by_function(path, content, language) {
+        match chunk.code_type.as_str() {
+            "function" => stats.functions += 1,
+            "class" => stats.classes += 1,
+            _ => continue,
+        }
+
+        let named_component = || {
+            find_definition(&chunk.content, language)
+                .is_some_and(|definition| is_component_name(&definition.name))
+        };
+        if jsx && named_component() {
+            stats.components += 1;
+        }
+    }
+    stats
+}
+
+/// Per-project cache of per-file stats for analyze_project
+#[derive(Default)]
+pub struct ProjectStatsState {
+    projects: Mutex<HashMap<String, HashMap<String, FileStats>>>,
+}
+
+impl ProjectStatsState {
+    /// Forget a file's stats, e.g. after the watcher saw it change
+    pub fn invalidate(&self, project_path: &str, file_path: &str) {
+        if let Some(files) = self
+            .projects
+            .lock()
+            .unwrap()
+            .get_mut(&crate::ai::project_key(project_path))
+        {
+            files.remove(file_path);
+        }
+    }
+
+    /// Walk the project and total up its files, re-reading only those that
+    /// changed since the last call
+    pub fn analyze(&self, project_path: &str) -> Result<ProjectStats, CodifyError> {
+        let files = walk_project(project_path)?;
+        let root = std::path::Path::new(project_path);
+
+        // Parse outside the lock so one slow project doesn't block invalidation
+        let key = crate::ai::project_key(project_path);
+        let mut cache = self
+            .projects
+            .lock()
+            .unwrap()
+            .remove(&key)
+            .unwrap_or_default();
+        let known: HashSet<&str> = files.iter().map(|file| file.path.as_str()).collect();
+        cache.retain(|path, _| known.contains(path.as_str()));
+
+        let mut stats = ProjectStats::default();
+        let mut languages: BTreeMap<String, LanguageStats> = BTreeMap::new();
+        let mut code_files = 0;
+        for file in &files {
+            stats.total_files += 1;
+            stats.total_size += file.size;
+            if file.is_binary || file.too_large {
+                stats.skipped_files += 1;
+                continue;
+            }
+
+            let fresh = cache
+                .get(&file.path)
+                .is_some_and(|cached| cached.modified == file.modified);
+            if !fresh {
+                let Ok(content) = std::fs::read_to_string(root.join(&file.path)) else {
+                    stats.skipped_files += 1;
+                    continue;
+                };
+                let mut analyzed = analyze_file(&file.path, &content, &file.file_type);
+                analyzed.modified = file.modified.clone();
+                cache.insert(file.path.clone(), analyzed);
+            }
+            let file_stats = &cache[&file.path];
+
+            let language = languages.entry(file.file_type.clone()).or_default();
+            language.files += 1;
+            language.lines += file_stats.lines;
+            language.functions += file_stats.functions;
+            language.classes += file_stats.classes;
+            language.components += file_stats.components;
+
+            if is_source_language(&file.file_type) {
+                code_files += 1;
+                if is_test_path(&file.path) {
+                    stats.test_files += 1;
+                }
+            }
+        }
+
+        stats.languages = languages
+            .into_iter()
+            .map(|(language, totals)| LanguageStats { language, ..totals })
+            .collect();
+        stats
+            .languages
+            .sort_by(|a, b| b.lines.cmp(&a.lines).then(a.language.cmp(&b.language)));
+        stats.total_lines = stats.languages.iter().map(|l| l.lines).sum();
+        stats.functions = stats.languages.iter().map(|l| l.functions).sum();
+        stats.classes = stats.languages.iter().map(|l| l.classes).sum();
+        stats.components = stats.languages.iter().map(|l| l.components).sum();
+        if stats.total_files > 0 {
+            stats.average_file_size = stats.total_size / stats.total_files as u64;
+        }
+        if code_files > 0 {
+            stats.test_file_ratio = stats.test_files as f64 / code_files as f64;
+        }
+
+        self.projects.lock().unwrap().insert(key, cache);
+        Ok(stats)
+    }
+}
@@ -0,0 +1,282 @@
+use super::{file_limits, is_binary, resolve_in_project};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+/// Lines tail_file returns when the caller doesn't say
+pub const DEFAULT_TAIL_LINES: usize = 100;
+
+/// How often a followed file is checked for growth and rotation
+const TAIL_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Most a followed file is read per poll, so a burst can't balloon one event
+const TAIL_READ_BYTES: u64 = 1024 * 1024;
+
+/// Block size when scanning backwards for the last lines
+const TAIL_SCAN_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TailResult {
+    pub path: String,
+    pub lines: Vec<String>, // Up to the requested count, oldest first
+    pub size: u64,
+    pub tail_id: Option<String>, // Set when following; pass to stop_tail_file
+}
+
+/// Emitted as `tail-output` as a followed file grows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TailOutputEvent {
+    pub tail_id: String,
+    pub lines: Vec<String>,
+    pub rotated: bool, // The file was replaced or truncated and is read from the start again
+}
+
+/// Emitted as `tail-stopped` when following ends
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TailStoppedEvent {
+    pub tail_id: String,
+    pub error: Option<String>, // None when stopped with stop_tail_file
+}
+
+/// Followed files keyed by tail id, each with its stop flag
+#[derive(Default)]
+pub struct TailState {
+    tails: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl TailState {
+    /// Stop following a file; unknown or finished ids return false
+    pub fn stop(&self, tail_id: &str) -> bool {
+        match self.tails.lock().unwrap().remove(tail_id) {
+            Some(stop) => {
+                stop.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Identity of the file behind a path, to notice it being replaced
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> u128 {
+    use std::os::unix::fs::MetadataExt;
+    ((metadata.dev() as u128) << 64) | metadata.ino() as u128
+}
+
+#[cfg(not(unix))]
+fn file_id(metadata: &std::fs::Metadata) -> u128 {
+    metadata
+        .created()
+        .ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or(0)
+}
+
+/// Split on newlines, dropping the `\r` of CRLF endings
+fn split_lines(text: &str) -> Vec<String> {
+    text.lines().map(|line| line.to_string()).collect()
+}
+
+/// The last `count` complete lines before `end`, and the offset just past the
+/// last newline; at most max_read_size bytes are scanned
+fn last_lines(
+    file: &mut std::fs::File,
+    end: u64,
+    count: usize,
+) -> Result<(Vec<String>, u64), CodifyError> {
+    let limit = end.saturating_sub(file_limits().max_read_size);
+    let mut start = end;
+    let mut bytes: Vec<u8> = vec![];
+
+    // Read backwards until there is one newline more than the lines wanted
+    while start > limit && bytes.iter().filter(|b| **b == b'\n').count() <= count {
+        let block = TAIL_SCAN_BYTES.min(start - limit);
+        start -= block;
+        let mut chunk = vec![0; block as usize];
+        file.seek(SeekFrom::Start(start))
+            .and_then(|_| file.read_exact(&mut chunk))
+            .map_err(|e| CodifyError::Io(format!("Failed to read file: {}", e)))?;
+        chunk.extend_from_slice(&bytes);
+        bytes = chunk;
+    }
+
+    // A trailing partial line is still being written; following picks it up
+    let complete = bytes.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+    let text = String::from_utf8_lossy(&bytes[..complete]);
+    // The first line may have been cut by the scan window; it is dropped
+    // here whenever there are enough lines after it
+    let mut lines = split_lines(&text);
+    let skip = lines.len().saturating_sub(count);
+
+    Ok((lines.split_off(skip), start + complete as u64))
+}
+
+/// Read the last `from_end_lines` lines of a project file and, with
+/// `follow`, keep emitting `tail-output` events as it grows, like `tail -f`
+///
+/// A followed file that is replaced (log rotation) or truncated is reopened
+/// and read from the start. Following stops with stop_tail_file, or on its
+/// own with a `tail-stopped` error if the file can't be read.
+pub fn tail_project_file(
+    app: &tauri::AppHandle,
+    project_path: &str,
+    path: &str,
+    from_end_lines: usize,
+    follow: bool,
+) -> Result<TailResult, CodifyError> {
+    let full_path = resolve_in_project(project_path, path)?;
+
+    let metadata = std::fs::metadata(&full_path).map_err(|e| {
+        let message = format!("Failed to read {}: {}", path, e);
+        match e.kind() {
+            std::io::ErrorKind::NotFound => CodifyError::NotFound(message),
+            _ => CodifyError::Io(message),
+        }
+    })?;
+    if !metadata.is_file() {
+        return Err(CodifyError::InvalidInput(format!("Not a file: {}", path)));
+    }
+    if is_binary(&full_path) {
+        return Err(CodifyError::InvalidInput(format!(
+            "Binary files cannot be tailed: {}",
+            path
+        )));
+    }
+
+    let mut file = std::fs::File::open(&full_path)
+        .map_err(|e| CodifyError::Io(format!("Failed to open {}: {}", path, e)))?;
+    let size = metadata.len();
+    let (lines, offset) = last_lines(&mut file, size, from_end_lines)?;
+
+    let tail_id = follow.then(|| {
+        let tail_id = uuid::Uuid::new_v4().to_string();
+        let stop = Arc::new(AtomicBool::new(false));
+        app.state::<TailState>()
+            .tails
+            .lock()
+            .unwrap()
+            .insert(tail_id.clone(), stop.clone());
+
+        let (app, id) = (app.clone(), tail_id.clone());
+        let follower = Follower {
+            path: full_path,
+            file,
+            file_id: file_id(&metadata),
+            offset,
+            partial: vec![],
+        };
+        tauri::async_runtime::spawn_blocking(move || follow_file(app, id, follower, stop));
+        tail_id
+    });
+
+    Ok(TailResult {
+        path: path.to_string(),
+        lines,
+        size,
+        tail_id,
+    })
+}
+
+struct Follower {
+    path: PathBuf,
+    file: std::fs::File,
+    file_id: u128,
+    offset: u64,
+    partial: Vec<u8>, // Bytes after the last newline read so far
+}
+
+impl Follower {
+    /// New complete lines since the last poll, and whether the file was
+    /// rotated; a path that is briefly missing mid-rotation yields nothing
+    fn poll(&mut self) -> Result<(Vec<String>, bool), CodifyError> {
+        let Ok(metadata) = std::fs::metadata(&self.path) else {
+            return Ok((vec![], false));
+        };
+
+        let rotated = file_id(&metadata) != self.file_id || metadata.len() < self.offset;
+        if rotated {
+            self.file = reopen(&self.path)?;
+            self.file_id = file_id(&metadata);
+            self.offset = 0;
+            self.partial.clear();
+        }
+
+        let available = metadata.len().saturating_sub(self.offset);
+        if available == 0 {
+            return Ok((vec![], rotated));
+        }
+
+        let mut bytes = vec![];
+        self.file
+            .seek(SeekFrom::Start(self.offset))
+            .and_then(|_| {
+                (&mut self.file)
+                    .take(available.min(TAIL_READ_BYTES))
+                    .read_to_end(&mut bytes)
+            })
+            .map_err(|e| {
+                CodifyError::Io(format!("Failed to read {}: {}", self.path.display(), e))
+            })?;
+        self.offset += bytes.len() as u64;
+
+        self.partial.extend_from_slice(&bytes);
+        let Some(last_newline) = self.partial.iter().rposition(|b| *b == b'\n') else {
+            return Ok((vec![], rotated));
+        };
+        let complete: Vec<u8> = self.partial.drain(..=last_newline).collect();
+
+        Ok((split_lines(&String::from_utf8_lossy(&complete)), rotated))
+    }
+}
+
+fn reopen(path: &Path) -> Result<std::fs::File, CodifyError> {
+    std::fs::File::open(path)
+        .map_err(|e| CodifyError::Io(format!("Failed to reopen {}: {}", path.display(), e)))
+}
+
+fn follow_file(
+    app: tauri::AppHandle,
+    tail_id: String,
+    mut follower: Follower,
+    stop: Arc<AtomicBool>,
+) {
+    let error = loop {
+        std::thread::sleep(TAIL_POLL_INTERVAL);
+        if stop.load(Ordering::SeqCst) {
+            break None;
+        }
+
+        match follower.poll() {
+            Ok((lines, rotated)) if !lines.is_empty() || rotated => {
+                let _ = app.emit(
+                    "tail-output",
+                    TailOutputEvent {
+                        tail_id: tail_id.clone(),
+                        lines,
+                        rotated,
+                    },
+                );
+            }
+            Ok(_) => {}
+            Err(e) => {
+                log::warn!("Stopped following {}: {}", follower.path.display(), e);
+                break Some(e.to_string());
+            }
+        }
+    };
+
+    app.state::<TailState>()
+        .tails
+        .lock()
+        .unwrap()
+        .remove(&tail_id);
+    let _ = app.emit("tail-stopped", TailStoppedEvent { tail_id, error });
+}
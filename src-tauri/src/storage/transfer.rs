@@ -0,0 +1,191 @@
+use super::{content_hash, resolve_in_project, CodeEmbedding, VectorStores, WriteBatch};
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Bumped when the export layout changes incompatibly
+const EXPORT_VERSION: u32 = 1;
+
+/// A project's index as written by export_index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexExport {
+    pub version: u32,
+    pub model: String, // Embedding model every vector came from
+    pub dimension: usize,
+    pub exported_at: String, // RFC 3339
+    pub rows: Vec<CodeEmbedding>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Files in the export replace their rows; other files keep theirs
+    #[default]
+    Merge,
+    /// The whole index is cleared first
+    Replace,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexExportSummary {
+    pub path: String,
+    pub rows: usize,
+    pub files: usize,
+    pub model: String,
+    pub dimension: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexImportSummary {
+    pub mode: ImportMode,
+    pub imported: usize,
+    pub files: usize,
+    /// Rows left out: a vector of the wrong length or with NaN values, or a
+    /// file that isn't in this project
+    pub skipped: usize,
+    pub model: String,
+    pub dimension: usize,
+}
+
+/// Write every row of the project's index, vectors included, to `path`
+pub fn export_index_to(
+    stores: &VectorStores,
+    project_path: &str,
+    path: &str,
+) -> Result<IndexExportSummary, CodifyError> {
+    let db = stores.open(project_path)?;
+    let info = db.info()?;
+    let (Some(model), Some(dimension)) = (info.model, info.dimension) else {
+        return Err(CodifyError::InvalidInput(
+            "Invalid export: the index is empty; index the project first".to_string(),
+        ));
+    };
+
+    let rows = db.chunks_with_vectors()?;
+    let files = rows
+        .iter()
+        .map(|row| row.file_path.as_str())
+        .collect::<HashSet<_>>()
+        .len();
+    let export = IndexExport {
+        version: EXPORT_VERSION,
+        model: model.clone(),
+        dimension,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        rows,
+    };
+
+    let file = std::fs::File::create(path)
+        .map_err(|e| CodifyError::Io(format!("Failed to create index export {}: {}", path, e)))?;
+    serde_json::to_writer(std::io::BufWriter::new(file), &export)
+        .map_err(|e| CodifyError::Io(format!("Failed to write index export {}: {}", path, e)))?;
+
+    Ok(IndexExportSummary {
+        path: path.to_string(),
+        rows: export.rows.len(),
+        files,
+        model,
+        dimension,
+    })
+}
+
+/// Load an export_index file into the project's index
+///
+/// The export must come from `model`, the configured embedding model, since
+/// its vectors are compared with queries embedded by it; merging also needs
+/// the existing index's dimension to match. Imported vectors go into the
+/// embedding cache too, so the next index_project reuses them instead of
+/// embedding the same code again.
+pub fn import_index_from(
+    stores: &VectorStores,
+    project_path: &str,
+    path: &str,
+    model: &str,
+    mode: ImportMode,
+) -> Result<IndexImportSummary, CodifyError> {
+    let file = std::fs::File::open(path).map_err(|e| {
+        let message = format!("Failed to open index export {}: {}", path, e);
+        match e.kind() {
+            std::io::ErrorKind::NotFound => CodifyError::NotFound(message),
+            _ => CodifyError::Io(message),
+        }
+    })?;
+    let export: IndexExport = serde_json::from_reader(std::io::BufReader::new(file))
+        .map_err(|e| CodifyError::InvalidInput(format!("Invalid index export {}: {}", path, e)))?;
+
+    if export.version != EXPORT_VERSION {
+        return Err(CodifyError::InvalidInput(format!(
+            "Invalid index export: version {} is not supported (expected {})",
+            export.version, EXPORT_VERSION
+        )));
+    }
+    if export.model != model {
+        return Err(CodifyError::InvalidInput(format!(
+            "Invalid index export: built with {} but the configured embedding model is {}",
+            export.model, model
+        )));
+    }
+
+    let mut db = stores.open(project_path)?;
+    if mode == ImportMode::Merge {
+        if let Some(existing) = db.dimension().filter(|d| *d != export.dimension) {
+            return Err(CodifyError::InvalidInput(format!(
+                "Invalid index export: its {}-dimensional vectors can't merge into an index of {}; import with replace",
+                export.dimension, existing
+            )));
+        }
+    }
+
+    let (rows, skipped): (Vec<CodeEmbedding>, Vec<CodeEmbedding>) =
+        export.rows.into_iter().partition(|row| {
+            row.embedding.len() == export.dimension
+                && row.embedding.iter().all(|v| v.is_finite())
+                && resolve_in_project(project_path, &row.file_path).is_ok_and(|p| p.is_file())
+        });
+    // Don't clear the index for an export of some other project
+    if rows.is_empty() {
+        return Err(CodifyError::InvalidInput(format!(
+            "Invalid index export: none of its {} rows match files in this project",
+            skipped.len()
+        )));
+    }
+
+    match mode {
+        ImportMode::Replace => {
+            db.clear()?;
+        }
+        ImportMode::Merge => {
+            let files: HashSet<&str> = rows.iter().map(|row| row.file_path.as_str()).collect();
+            for file_path in files {
+                db.delete_file(file_path)?;
+            }
+        }
+    }
+
+    let hashes: Vec<String> = rows.iter().map(|row| content_hash(&row.content)).collect();
+    let entries: Vec<(&str, &[f32])> = hashes
+        .iter()
+        .zip(&rows)
+        .map(|(hash, row)| (hash.as_str(), row.embedding.as_slice()))
+        .collect();
+    db.cache_embeddings(model, &entries)?;
+
+    let files = rows
+        .iter()
+        .map(|row| row.file_path.as_str())
+        .collect::<HashSet<_>>()
+        .len();
+    let imported = rows.len();
+    let mut batch = WriteBatch::default();
+    batch.push(rows);
+    db.write_batch(&mut batch, model)?;
+
+    Ok(IndexImportSummary {
+        mode,
+        imported,
+        files,
+        skipped: skipped.len(),
+        model: model.to_string(),
+        dimension: export.dimension,
+    })
+}
@@ -0,0 +1,877 @@
+use super::{
+    fallback_chunking, CodeEmbedding, EmbeddingCacheStats, FallbackChunking, SearchFilters,
+};
+use crate::error::CodifyError;
+use duckdb::types::Value;
+use duckdb::{params, params_from_iter, Connection, OptionalExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Per-project directory holding Codify's index files
+pub const INDEX_DIR: &str = ".codify";
+
+const DB_FILE: &str = "index.duckdb";
+
+/// A WriteBatch asks to be flushed at this many rows...
+const BATCH_ROWS: usize = 512;
+/// ...or once its oldest row has waited this long
+const BATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Error for an index another process holds open
+pub const INDEX_BUSY_ERROR: &str =
+    "Index is busy: another operation is writing to it; try again shortly";
+
+/// One open database per project index, shared by every VectorDb handle
+///
+/// DuckDB refuses a second open of a file another database instance holds,
+/// so the indexer, the watcher's re-indexing and searches all connect to
+/// this one. Handles get their own connections, so reads run side by side;
+/// writes take `writer` so they never conflict with each other.
+struct SharedDb {
+    conn: Mutex<Connection>, // Cloned for each handle; VSS is loaded once, here
+    writer: tokio::sync::Mutex<()>,
+    backend: SearchBackend,
+}
+
+/// The shared database of every project index opened so far, keyed by its
+/// canonical path
+#[derive(Default)]
+pub struct VectorStores {
+    databases: Mutex<HashMap<PathBuf, Arc<SharedDb>>>,
+}
+
+/// Wait for any other write to the index to finish
+///
+/// Writes are short and queue in turn, so waiting beats failing. Handles are
+/// used from async tasks and blocking threads alike; block_in_place lets the
+/// wait block on either.
+fn lock_writes(shared: &SharedDb) -> tokio::sync::MutexGuard<'_, ()> {
+    tokio::task::block_in_place(|| shared.writer.blocking_lock())
+}
+
+/// Rows collected to be written together by VectorDb::write_batch
+#[derive(Debug, Default)]
+pub struct WriteBatch {
+    rows: Vec<CodeEmbedding>,
+    files: Vec<(String, String)>, // Replaced files with the modified time to record
+    started: Option<Instant>,
+}
+
+impl WriteBatch {
+    /// Add rows to insert or replace by id
+    pub fn push(&mut self, rows: impl IntoIterator<Item = CodeEmbedding>) {
+        self.started.get_or_insert_with(Instant::now);
+        self.rows.extend(rows);
+    }
+
+    /// Replace every row of `file_path` with `chunks` and record it as indexed
+    pub fn replace_file(&mut self, file_path: &str, modified: &str, chunks: Vec<CodeEmbedding>) {
+        self.files
+            .push((file_path.to_string(), modified.to_string()));
+        self.push(chunks);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty() && self.files.is_empty()
+    }
+
+    /// Big or old enough that it should be written now
+    pub fn is_due(&self) -> bool {
+        self.rows.len() >= BATCH_ROWS
+            || self
+                .started
+                .is_some_and(|started| started.elapsed() >= BATCH_INTERVAL)
+    }
+}
+
+pub struct VectorDb {
+    conn: Connection,
+    shared: Arc<SharedDb>,
+    dimension: Option<usize>,
+    model: Option<String>, // Embedding model the stored vectors came from
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexInfo {
+    pub backend: SearchBackend,
+    pub dimension: Option<usize>, // None until the first vector is stored
+    pub model: Option<String>,
+    pub rows: usize,
+    pub files: usize,
+    pub configured_model: Option<String>, // The embedding model new vectors would come from
+    /// The index was built with another model, so searches would compare
+    /// incompatible vectors until reindex_with_model runs
+    pub needs_reindex: bool,
+    pub fallback_chunking: FallbackChunking, // Windows for languages without a grammar
+}
+
+/// How semantic search finds the nearest vectors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchBackend {
+    Hnsw, // The VSS extension's approximate index
+    /// Every row is compared to the query; exact and slower on large
+    /// projects. Used where the VSS extension can't be loaded.
+    ExactScan,
+}
+
+fn db_err(e: duckdb::Error) -> CodifyError {
+    let message = e.to_string();
+    // Another process, e.g. a second window, has the file open
+    if message.contains("Could not set lock") {
+        return CodifyError::Busy(INDEX_BUSY_ERROR.to_string());
+    }
+    CodifyError::Internal(format!("Vector store error: {}", message))
+}
+
+/// Scale a vector to unit length in place; zero vectors are left alone
+pub fn l2_normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+/// Format a vector as a DuckDB list literal so it can be cast to FLOAT[N]
+pub(crate) fn vector_literal(vector: &[f32]) -> String {
+    let values: Vec<String> = vector.iter().map(|v| v.to_string()).collect();
+    format!("[{}]", values.join(","))
+}
+
+impl VectorStores {
+    /// Open and migrate the index of every project in `project_paths` that
+    /// has one; called once at startup so the first request doesn't wait on it
+    pub fn migrate_existing<'a>(&self, project_paths: impl IntoIterator<Item = &'a str>) {
+        for project_path in project_paths {
+            if !VectorDb::db_path(project_path).exists() {
+                continue;
+            }
+            if let Err(e) = self.shared(project_path) {
+                log::warn!("Failed to migrate the index of {}: {}", project_path, e);
+            }
+        }
+    }
+
+    /// A handle on the project's index, opening (or creating) the shared
+    /// database and running migrations the first time
+    pub fn open(&self, project_path: &str) -> Result<VectorDb, CodifyError> {
+        VectorDb::connect(self.shared(project_path)?)
+    }
+
+    fn shared(&self, project_path: &str) -> Result<Arc<SharedDb>, CodifyError> {
+        let path = VectorDb::db_path(project_path);
+        let dir = path.parent().unwrap_or(Path::new(project_path));
+        std::fs::create_dir_all(dir)
+            .map_err(|e| CodifyError::Io(format!("Failed to create index directory: {}", e)))?;
+        // One key however the project path is spelled
+        let path = dir
+            .canonicalize()
+            .map_err(|e| CodifyError::Io(format!("Failed to open index directory: {}", e)))?
+            .join(DB_FILE);
+
+        // Held while opening so two callers can't both open the file
+        let mut databases = self.databases.lock().unwrap();
+        if let Some(shared) = databases.get(&path) {
+            return Ok(shared.clone());
+        }
+
+        let conn = Connection::open(&path).map_err(db_err)?;
+
+        // Search still works without the extension, by comparing every row
+        let backend = match load_vss(&conn) {
+            Ok(()) => SearchBackend::Hnsw,
+            Err(e) => {
+                log::warn!(
+                    "VSS extension unavailable, semantic search for {} falls back to exact scans: {}",
+                    path.display(),
+                    e
+                );
+                // An HNSW index saved by an earlier run can't be used or maintained
+                if let Err(e) = conn.execute_batch("DROP INDEX IF EXISTS code_embeddings_hnsw;") {
+                    log::warn!("Failed to drop the HNSW index: {}", e);
+                }
+                SearchBackend::ExactScan
+            }
+        };
+
+        migrate(&conn, backend)?;
+
+        let shared = Arc::new(SharedDb {
+            conn: Mutex::new(conn),
+            writer: tokio::sync::Mutex::new(()),
+            backend,
+        });
+        databases.insert(path, shared.clone());
+        Ok(shared)
+    }
+}
+
+/// Load the VSS extension, downloading it only when it isn't installed yet
+fn load_vss(conn: &Connection) -> Result<(), duckdb::Error> {
+    if let Err(e) = conn.execute_batch("LOAD vss;") {
+        log::info!("VSS extension not loaded ({}), installing it", e);
+        conn.execute_batch("INSTALL vss; LOAD vss;")?;
+    }
+    conn.execute_batch("SET hnsw_enable_experimental_persistence = true;")
+}
+
+/// Bring the schema up to date: the metadata tables always, and the
+/// embeddings table and its HNSW index once a dimension has been recorded
+fn migrate(conn: &Connection, backend: SearchBackend) -> Result<(), CodifyError> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS index_meta (key VARCHAR PRIMARY KEY, value VARCHAR);
+         CREATE TABLE IF NOT EXISTS indexed_files (file_path VARCHAR PRIMARY KEY, modified VARCHAR NOT NULL);
+         CREATE TABLE IF NOT EXISTS embedding_cache (
+             content_hash VARCHAR NOT NULL,
+             model VARCHAR NOT NULL,
+             embedding VARCHAR NOT NULL,
+             PRIMARY KEY (content_hash, model)
+         );",
+    )
+    .map_err(db_err)?;
+
+    let dimension: Option<String> = conn
+        .query_row(
+            "SELECT value FROM index_meta WHERE key = 'dimension'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(db_err)?;
+    match dimension.and_then(|value| value.parse().ok()) {
+        Some(dimension) => create_embeddings_table(conn, dimension, backend),
+        None => Ok(()),
+    }
+}
+
+fn create_embeddings_table(
+    conn: &Connection,
+    dimension: usize,
+    backend: SearchBackend,
+) -> Result<(), CodifyError> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS code_embeddings (
+            id VARCHAR PRIMARY KEY,
+            file_path VARCHAR NOT NULL,
+            start_line INTEGER NOT NULL,
+            end_line INTEGER NOT NULL,
+            code_type VARCHAR NOT NULL,
+            language VARCHAR NOT NULL,
+            content VARCHAR NOT NULL,
+            embedding FLOAT[{dimension}] NOT NULL,
+            dependencies VARCHAR NOT NULL
+        );"
+    ))
+    .map_err(db_err)?;
+    if backend == SearchBackend::Hnsw {
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS code_embeddings_hnsw
+                ON code_embeddings USING HNSW (embedding) WITH (metric = 'cosine');",
+        )
+        .map_err(db_err)?;
+    }
+    Ok(())
+}
+
+impl VectorDb {
+    pub fn db_path(project_path: &str) -> PathBuf {
+        Path::new(project_path).join(INDEX_DIR).join(DB_FILE)
+    }
+
+    /// A new connection to `shared`
+    fn connect(shared: Arc<SharedDb>) -> Result<Self, CodifyError> {
+        let conn = shared.conn.lock().unwrap().try_clone().map_err(db_err)?;
+        if shared.backend == SearchBackend::Hnsw {
+            conn.execute_batch("SET hnsw_enable_experimental_persistence = true;")
+                .map_err(db_err)?;
+        }
+
+        let mut db = VectorDb {
+            conn,
+            shared,
+            dimension: None,
+            model: None,
+        };
+        db.dimension = db.meta("dimension")?.and_then(|value| value.parse().ok());
+        db.model = db.meta("model")?;
+
+        Ok(db)
+    }
+
+    pub fn dimension(&self) -> Option<usize> {
+        self.dimension
+    }
+
+    pub fn backend(&self) -> SearchBackend {
+        self.shared.backend
+    }
+
+    /// Create the embeddings table and HNSW index for `dimension` if missing,
+    /// or verify that an existing table matches it
+    pub fn ensure_table(&mut self, dimension: usize) -> Result<(), CodifyError> {
+        let shared = self.shared.clone();
+        let _writing = lock_writes(&shared);
+        self.create_table(dimension)
+    }
+
+    /// ensure_table with the write lock already held
+    fn create_table(&mut self, dimension: usize) -> Result<(), CodifyError> {
+        // Another handle may have created it since this one opened
+        if self.dimension.is_none() {
+            self.dimension = self.meta("dimension")?.and_then(|value| value.parse().ok());
+        }
+        if let Some(existing) = self.dimension {
+            if existing != dimension {
+                let built_with = self
+                    .model
+                    .as_deref()
+                    .map(|model| format!(" built with {}", model))
+                    .unwrap_or_default();
+                return Err(CodifyError::InvalidInput(format!(
+                    "Embedding dimension mismatch: the index{} stores {}-dimensional vectors but got {}. \
+                     If the embedding model changed, clear and re-index the project.",
+                    built_with, existing, dimension
+                )));
+            }
+            return Ok(());
+        }
+
+        if dimension == 0 {
+            return Err(CodifyError::InvalidInput(
+                "Embedding vector is empty".to_string(),
+            ));
+        }
+
+        // A new index: its dimension is only known from the first vectors
+        create_embeddings_table(&self.conn, dimension, self.backend())?;
+        self.set_meta("dimension", &dimension.to_string())?;
+        self.dimension = Some(dimension);
+        Ok(())
+    }
+
+    /// Write and empty `batch` in one transaction: every replaced file's old
+    /// rows and marker go, then the new rows and markers are written. On any
+    /// failure nothing is written, so the index never holds part of a batch.
+    /// `model` is the embedding model the vectors came from.
+    pub fn write_batch(&mut self, batch: &mut WriteBatch, model: &str) -> Result<(), CodifyError> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        if let Some(bad) = batch
+            .rows
+            .iter()
+            .find(|row| row.embedding.iter().any(|v| !v.is_finite()))
+        {
+            return Err(CodifyError::InvalidInput(format!(
+                "Embedding for {} contains NaN or infinite values",
+                bad.file_path
+            )));
+        }
+
+        let shared = self.shared.clone();
+        let _writing = lock_writes(&shared);
+        if let Some(first) = batch.rows.first() {
+            self.create_table(first.embedding.len())?;
+            self.record_model(model)?;
+        }
+
+        self.conn
+            .execute_batch("BEGIN TRANSACTION")
+            .map_err(db_err)?;
+        match self.write_rows(batch) {
+            Ok(()) => self.conn.execute_batch("COMMIT").map_err(db_err)?,
+            Err(e) => {
+                if let Err(rollback) = self.conn.execute_batch("ROLLBACK") {
+                    log::warn!("Failed to roll back index write: {}", rollback);
+                }
+                return Err(e);
+            }
+        }
+
+        *batch = WriteBatch::default();
+        Ok(())
+    }
+
+    /// write_batch's statements, inside its transaction
+    fn write_rows(&self, batch: &WriteBatch) -> Result<(), CodifyError> {
+        if self.dimension.is_some() {
+            for (file_path, _) in &batch.files {
+                self.conn
+                    .execute(
+                        "DELETE FROM code_embeddings WHERE file_path = ?",
+                        params![file_path],
+                    )
+                    .map_err(db_err)?;
+            }
+        }
+
+        if let Some(dimension) = self.dimension.filter(|_| !batch.rows.is_empty()) {
+            let mut insert = self
+                .conn
+                .prepare(&format!(
+                    "INSERT OR REPLACE INTO code_embeddings
+                     (id, file_path, start_line, end_line, code_type, language, content, embedding, dependencies)
+                     VALUES (?, ?, ?, ?, ?, ?, ?, CAST(? AS FLOAT[{dimension}]), ?)"
+                ))
+                .map_err(db_err)?;
+            for row in &batch.rows {
+                if row.embedding.len() != dimension {
+                    return Err(CodifyError::InvalidInput(format!(
+                        "Embedding dimension mismatch: the index stores {}-dimensional vectors but {} has {}",
+                        dimension,
+                        row.file_path,
+                        row.embedding.len()
+                    )));
+                }
+                let dependencies = serde_json::to_string(&row.dependencies).map_err(|e| {
+                    CodifyError::Internal(format!("Failed to encode dependencies: {}", e))
+                })?;
+                insert
+                    .execute(params![
+                        row.id,
+                        row.file_path,
+                        row.start_line,
+                        row.end_line,
+                        row.code_type,
+                        row.language,
+                        row.content,
+                        vector_literal(&row.embedding),
+                        dependencies,
+                    ])
+                    .map_err(db_err)?;
+            }
+        }
+
+        for (file_path, modified) in &batch.files {
+            self.conn
+                .execute(
+                    "INSERT OR REPLACE INTO indexed_files (file_path, modified) VALUES (?, ?)",
+                    params![file_path, modified],
+                )
+                .map_err(db_err)?;
+        }
+        Ok(())
+    }
+
+    /// Note the embedding model new vectors come from, with the write lock held
+    fn record_model(&mut self, model: &str) -> Result<(), CodifyError> {
+        if self.model.as_deref() != Some(model) {
+            if let Some(previous) = &self.model {
+                log::warn!(
+                    "Index built with {} is now receiving vectors from {}",
+                    previous,
+                    model
+                );
+            }
+            self.set_meta("model", model)?;
+            self.model = Some(model.to_string());
+        }
+        Ok(())
+    }
+
+    /// Modified timestamps of every file currently in the index
+    pub fn indexed_files(&self) -> Result<std::collections::HashMap<String, String>, CodifyError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT file_path, modified FROM indexed_files")
+            .map_err(db_err)?;
+
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(db_err)?;
+
+        rows.collect::<Result<_, _>>().map_err(db_err)
+    }
+
+    /// Remove every embedding for a file along with its indexed marker,
+    /// returning the number of embedding rows deleted
+    pub fn delete_file(&self, file_path: &str) -> Result<usize, CodifyError> {
+        let _writing = lock_writes(&self.shared);
+        let removed = if self.dimension.is_some() {
+            self.conn
+                .execute("DELETE FROM code_embeddings WHERE file_path = ?", params![file_path])
+                .map_err(db_err)?
+        } else {
+            0
+        };
+
+        self.conn
+            .execute("DELETE FROM indexed_files WHERE file_path = ?", params![file_path])
+            .map_err(db_err)?;
+
+        Ok(removed)
+    }
+
+    /// Remove a single embedding row, returning how many rows were deleted
+    pub fn delete_by_id(&self, id: &str) -> Result<usize, CodifyError> {
+        if self.dimension.is_none() {
+            return Ok(0);
+        }
+
+        let _writing = lock_writes(&self.shared);
+        self.conn
+            .execute("DELETE FROM code_embeddings WHERE id = ?", params![id])
+            .map_err(db_err)
+    }
+
+    /// Wipe every embedding and indexed-file marker
+    ///
+    /// The embeddings table is dropped rather than emptied, along with the
+    /// recorded dimension and model, so the next insert may use a different
+    /// embedding model. The embedding cache is kept; it is keyed by model.
+    pub fn clear(&mut self) -> Result<usize, CodifyError> {
+        let shared = self.shared.clone();
+        let _writing = lock_writes(&shared);
+        let removed = if self.dimension.is_some() {
+            let removed = self
+                .conn
+                .execute("DELETE FROM code_embeddings", [])
+                .map_err(db_err)?;
+            self.conn
+                .execute_batch(
+                    "DROP INDEX IF EXISTS code_embeddings_hnsw;
+                     DROP TABLE IF EXISTS code_embeddings;
+                     DELETE FROM index_meta WHERE key IN ('dimension', 'model');",
+                )
+                .map_err(db_err)?;
+            removed
+        } else {
+            0
+        };
+
+        self.conn
+            .execute("DELETE FROM indexed_files", [])
+            .map_err(db_err)?;
+
+        self.dimension = None;
+        self.model = None;
+        Ok(removed)
+    }
+
+    /// Dimension, model and size of the index
+    pub fn info(&self) -> Result<IndexInfo, CodifyError> {
+        let count = |sql: &str| -> Result<usize, CodifyError> {
+            self.conn
+                .query_row(sql, [], |row| row.get::<_, i64>(0))
+                .map(|n| n as usize)
+                .map_err(db_err)
+        };
+
+        let rows = if self.dimension.is_some() {
+            count("SELECT COUNT(*) FROM code_embeddings")?
+        } else {
+            0
+        };
+
+        Ok(IndexInfo {
+            backend: self.backend(),
+            dimension: self.dimension,
+            model: self.model.clone(),
+            rows,
+            files: count("SELECT COUNT(*) FROM indexed_files")?,
+            configured_model: None,
+            needs_reindex: false,
+            fallback_chunking: fallback_chunking(),
+        })
+    }
+
+    /// Vectors cached for any of `hashes` under `model`, keyed by hash
+    pub fn cached_embeddings(
+        &self,
+        model: &str,
+        hashes: &[String],
+    ) -> Result<std::collections::HashMap<String, Vec<f32>>, CodifyError> {
+        if hashes.is_empty() {
+            return Ok(std::collections::HashMap::new());
+        }
+
+        let placeholders = vec!["?"; hashes.len()].join(", ");
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT content_hash, embedding FROM embedding_cache
+                 WHERE model = ? AND content_hash IN ({placeholders})"
+            ))
+            .map_err(db_err)?;
+
+        let values = std::iter::once(model.to_string()).chain(hashes.iter().cloned());
+        let rows = stmt
+            .query_map(params_from_iter(values), |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(db_err)?;
+
+        let mut cached = std::collections::HashMap::new();
+        for row in rows {
+            let (hash, embedding) = row.map_err(db_err)?;
+            // A corrupt entry is just a miss; it gets overwritten on re-embed
+            if let Ok(vector) = serde_json::from_str::<Vec<f32>>(&embedding) {
+                cached.insert(hash, vector);
+            }
+        }
+        Ok(cached)
+    }
+
+    /// Remember vectors by content hash for `model`
+    pub fn cache_embeddings(
+        &self,
+        model: &str,
+        entries: &[(&str, &[f32])],
+    ) -> Result<(), CodifyError> {
+        let _writing = lock_writes(&self.shared);
+        for (hash, vector) in entries {
+            self.conn
+                .execute(
+                    "INSERT OR REPLACE INTO embedding_cache (content_hash, model, embedding) VALUES (?, ?, ?)",
+                    params![hash, model, vector_literal(vector)],
+                )
+                .map_err(db_err)?;
+        }
+        Ok(())
+    }
+
+    fn meta(&self, key: &str) -> Result<Option<String>, CodifyError> {
+        self.conn
+            .query_row("SELECT value FROM index_meta WHERE key = ?", params![key], |row| {
+                row.get::<_, String>(0)
+            })
+            .optional()
+            .map_err(db_err)
+    }
+
+    fn meta_counter(&self, key: &str) -> Result<u64, CodifyError> {
+        Ok(self.meta(key)?.and_then(|v| v.parse().ok()).unwrap_or(0))
+    }
+
+    fn set_meta(&self, key: &str, value: &str) -> Result<(), CodifyError> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO index_meta (key, value) VALUES (?, ?)",
+                params![key, value],
+            )
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    /// Add one index run's cache lookups to the persisted totals
+    pub fn record_cache_lookups(&self, hits: usize, misses: usize) -> Result<(), CodifyError> {
+        let _writing = lock_writes(&self.shared);
+        let hits = self.meta_counter("cache_hits")? + hits as u64;
+        let misses = self.meta_counter("cache_misses")? + misses as u64;
+
+        self.set_meta("cache_hits", &hits.to_string())?;
+        self.set_meta("cache_misses", &misses.to_string())
+    }
+
+    pub fn embedding_cache_stats(&self) -> Result<EmbeddingCacheStats, CodifyError> {
+        let entries: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM embedding_cache", [], |row| row.get(0))
+            .map_err(db_err)?;
+
+        Ok(EmbeddingCacheStats {
+            entries: entries as usize,
+            hits: self.meta_counter("cache_hits")?,
+            misses: self.meta_counter("cache_misses")?,
+        })
+    }
+
+    /// Drop every cached vector and reset the hit/miss totals, returning the
+    /// number of entries removed
+    pub fn purge_embedding_cache(&self) -> Result<usize, CodifyError> {
+        let _writing = lock_writes(&self.shared);
+        let removed = self
+            .conn
+            .execute("DELETE FROM embedding_cache", [])
+            .map_err(db_err)?;
+
+        self.conn
+            .execute(
+                "DELETE FROM index_meta WHERE key IN ('cache_hits', 'cache_misses')",
+                [],
+            )
+            .map_err(db_err)?;
+
+        Ok(removed)
+    }
+
+    /// Nearest-neighbour search by cosine distance, closest first
+    ///
+    /// Filters become WHERE clauses ANDed together ahead of the distance sort,
+    /// so a filter that matches nothing yields an empty result, not an error.
+    /// The same query runs on either backend; only its speed differs.
+    pub fn search(
+        &self,
+        query: &[f32],
+        top_k: usize,
+        filters: &SearchFilters,
+    ) -> Result<Vec<CodeEmbedding>, CodifyError> {
+        // Nothing has been stored yet, so there is no table to search
+        let Some(dimension) = self.dimension else {
+            return Ok(vec![]);
+        };
+
+        if query.len() != dimension {
+            return Err(CodifyError::InvalidInput(format!(
+                "Query embedding has dimension {}, index expects {}",
+                query.len(),
+                dimension
+            )));
+        }
+
+        let mut values = vec![Value::Text(vector_literal(query))];
+        let mut clauses = vec![];
+
+        let mut any_of = |column: &str, options: &[String], values: &mut Vec<Value>| {
+            if options.is_empty() {
+                return;
+            }
+            let placeholders = vec!["?"; options.len()].join(", ");
+            clauses.push(format!("{} IN ({})", column, placeholders));
+            values.extend(options.iter().cloned().map(Value::Text));
+        };
+        any_of("language", &filters.languages, &mut values);
+        any_of("code_type", &filters.code_types, &mut values);
+
+        if let Some(prefix) = &filters.path_prefix {
+            clauses.push("starts_with(file_path, ?)".to_string());
+            values.push(Value::Text(prefix.clone()));
+        }
+        if let Some(glob) = &filters.path_glob {
+            clauses.push("file_path GLOB ?".to_string());
+            values.push(Value::Text(glob.clone()));
+        }
+        values.push(Value::BigInt(top_k as i64));
+
+        let where_clause = if clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", clauses.join(" AND "))
+        };
+
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT id, file_path, start_line, end_line, code_type, language, content, dependencies,
+                        CAST(array_cosine_distance(embedding, CAST(? AS FLOAT[{dimension}])) AS DOUBLE) AS distance
+                 FROM code_embeddings
+                 {where_clause}
+                 ORDER BY distance
+                 LIMIT ?"
+            ))
+            .map_err(db_err)?;
+
+        let rows = stmt
+            .query_map(params_from_iter(values), row_to_embedding)
+            .map_err(db_err)?;
+
+        rows.collect::<Result<Vec<_>, _>>().map_err(db_err)
+    }
+
+    /// The `limit` chunks nearest to `query`, closest first, with no filters
+    ///
+    /// The query vector and limit are written into the SQL as constants, the
+    /// shape DuckDB's HNSW optimizer rewrites into an index lookup; search's
+    /// bound parameters and WHERE clause make it scan every row instead.
+    pub fn nearest(&self, query: &[f32], limit: usize) -> Result<Vec<CodeEmbedding>, CodifyError> {
+        let Some(dimension) = self.dimension else {
+            return Ok(vec![]);
+        };
+        if query.len() != dimension {
+            return Err(CodifyError::InvalidInput(format!(
+                "Query embedding has dimension {}, index expects {}",
+                query.len(),
+                dimension
+            )));
+        }
+        if query.iter().any(|v| !v.is_finite()) {
+            return Err(CodifyError::InvalidInput(
+                "Query embedding contains NaN or infinite values".to_string(),
+            ));
+        }
+
+        let distance = format!(
+            "array_cosine_distance(embedding, {}::FLOAT[{}])",
+            vector_literal(query),
+            dimension
+        );
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT id, file_path, start_line, end_line, code_type, language, content, dependencies,
+                        CAST({distance} AS DOUBLE) AS distance
+                 FROM code_embeddings
+                 ORDER BY {distance}
+                 LIMIT {limit}"
+            ))
+            .map_err(db_err)?;
+
+        let rows = stmt.query_map([], row_to_embedding).map_err(db_err)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(db_err)
+    }
+
+    /// Every stored chunk with its vector, in file and line order
+    pub fn chunks_with_vectors(&self) -> Result<Vec<CodeEmbedding>, CodifyError> {
+        if self.dimension.is_none() {
+            return Ok(vec![]);
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, file_path, start_line, end_line, code_type, language, content, dependencies,
+                        NULL AS distance, CAST(embedding AS VARCHAR)
+                 FROM code_embeddings
+                 ORDER BY file_path, start_line",
+            )
+            .map_err(db_err)?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row_to_embedding(row)?, row.get::<_, String>(9)?))
+            })
+            .map_err(db_err)?;
+
+        let mut chunks = vec![];
+        for row in rows {
+            let (mut chunk, literal) = row.map_err(db_err)?;
+            chunk.embedding = parse_vector_literal(&chunk.id, &literal)?;
+            chunks.push(chunk);
+        }
+        Ok(chunks)
+    }
+}
+
+/// The reverse of vector_literal
+fn parse_vector_literal(id: &str, literal: &str) -> Result<Vec<f32>, CodifyError> {
+    literal
+        .trim_matches(|c| c == '[' || c == ']')
+        .split(',')
+        .map(|value| value.trim().parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| CodifyError::Internal(format!("Invalid stored vector for {}: {}", id, e)))
+}
+
+/// Map a result row (without the vector itself) back into a CodeEmbedding
+fn row_to_embedding(row: &duckdb::Row) -> duckdb::Result<CodeEmbedding> {
+    let dependencies: String = row.get(7)?;
+    let distance: Option<f64> = row.get(8)?;
+
+    Ok(CodeEmbedding {
+        id: row.get(0)?,
+        file_path: row.get(1)?,
+        start_line: row.get(2)?,
+        end_line: row.get(3)?,
+        code_type: row.get(4)?,
+        language: row.get(5)?,
+        content: row.get(6)?,
+        // Vectors are large and the UI never needs them, so search results omit them
+        embedding: vec![],
+        dependencies: serde_json::from_str(&dependencies).unwrap_or_default(),
+        distance: distance.map(|distance| distance as f32),
+        root: None,
+    })
+}
@@ -0,0 +1,117 @@
+use super::{detect_file_language, file_limits, is_binary, ProjectFile, INDEX_DIR};
+use crate::ai::is_secret_file;
+use crate::error::CodifyError;
+use std::path::Path;
+
+/// Directories that are never useful to show or index, even without a .gitignore
+const ALWAYS_IGNORED: &[&str] = &[
+    "node_modules",
+    "target",
+    ".git",
+    ".next",
+    "dist",
+    "out",
+    "build",
+    INDEX_DIR,
+];
+
+/// Walk the project honoring .gitignore and the built-in ignore list
+pub fn walk_project(project_path: &str) -> Result<Vec<ProjectFile>, CodifyError> {
+    let root = Path::new(project_path);
+    if !root.is_dir() {
+        return Err(CodifyError::NotFound(format!(
+            "Project path does not exist: {}",
+            project_path
+        )));
+    }
+
+    let walker = ignore::WalkBuilder::new(root)
+        .hidden(false)
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || entry
+                    .file_name()
+                    .to_str()
+                    .map(|name| !ALWAYS_IGNORED.contains(&name))
+                    .unwrap_or(true)
+        })
+        .build();
+
+    let max_indexable_size = file_limits().max_indexable_size;
+    let mut files = Vec::new();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                log::warn!("Skipping unreadable entry: {}", e);
+                continue;
+            }
+        };
+
+        if !entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+            continue;
+        }
+
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        let path = entry.path();
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let modified = metadata
+            .modified()
+            .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339())
+            .unwrap_or_default();
+        let too_large = metadata.len() > max_indexable_size;
+
+        let name = entry.file_name().to_string_lossy().to_string();
+        files.push(ProjectFile {
+            path: relative.to_string_lossy().replace('\\', "/"),
+            secret: is_secret_file(&name),
+            name,
+            file_type: detect_file_language(path),
+            size: metadata.len(),
+            modified,
+            ai_relevance: None,
+            root: None,
+            is_binary: !too_large && is_binary(path),
+            too_large,
+        });
+    }
+
+    Ok(files)
+}
+
+/// Whether any component of a project-relative path is on the built-in ignore list
+pub fn is_always_ignored(relative: &Path) -> bool {
+    relative.components().any(|component| {
+        component
+            .as_os_str()
+            .to_str()
+            .map(|name| ALWAYS_IGNORED.contains(&name))
+            .unwrap_or(false)
+    })
+}
+
+/// Files whose project-relative path matches `pattern`, in traversal order
+///
+/// `*` and `?` also match `/`, so `*.test.ts` finds test files at any depth;
+/// `**/` and `{a,b}` alternation are supported as well.
+pub fn find_project_files(
+    project_path: &str,
+    pattern: &str,
+    case_insensitive: bool,
+    limit: usize,
+) -> Result<Vec<ProjectFile>, CodifyError> {
+    let matcher = globset::GlobBuilder::new(pattern)
+        .case_insensitive(case_insensitive)
+        .build()
+        .map_err(|e| CodifyError::InvalidInput(format!("Invalid glob '{}': {}", pattern, e)))?
+        .compile_matcher();
+
+    Ok(walk_project(project_path)?
+        .into_iter()
+        .filter(|file| matcher.is_match(&file.path))
+        .take(limit)
+        .collect())
+}
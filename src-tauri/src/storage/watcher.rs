@@ -0,0 +1,144 @@
+use super::{is_always_ignored, DependencyGraphState, ProjectStatsState, ReindexState};
+use crate::ai::AIState;
+use crate::error::CodifyError;
+use notify_debouncer_full::notify::event::{EventKind, ModifyKind};
+use notify_debouncer_full::notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, FileIdCache, FileIdMap};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+/// Quiet period before a burst of filesystem events is delivered
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileChangeKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChangeEvent {
+    pub project_path: String,
+    pub path: String, // Relative to the project, forward slashes
+    pub kind: FileChangeKind,
+}
+
+/// Active project watchers keyed by project path; dropping one stops it
+#[derive(Default)]
+pub struct WatcherState {
+    watchers: Mutex<HashMap<String, Debouncer<RecommendedWatcher, FileIdMap>>>,
+}
+
+impl WatcherState {
+    /// Start watching a project, emitting `file-change` events; a project
+    /// that is already watched is left as is
+    pub fn watch(&self, app: &tauri::AppHandle, project_path: &str) -> Result<(), CodifyError> {
+        let mut watchers = self.watchers.lock().unwrap();
+        if watchers.contains_key(project_path) {
+            return Ok(());
+        }
+
+        let root = Path::new(project_path).canonicalize().map_err(|_| {
+            CodifyError::NotFound(format!("Project path does not exist: {}", project_path))
+        })?;
+        let (gitignore, _) = ignore::gitignore::Gitignore::new(root.join(".gitignore"));
+
+        let app = app.clone();
+        let project = project_path.to_string();
+        let event_root = root.clone();
+
+        let mut debouncer = new_debouncer(DEBOUNCE, None, move |result: DebounceEventResult| {
+            let events = match result {
+                Ok(events) => events,
+                Err(errors) => {
+                    for e in errors {
+                        log::warn!("File watcher error: {}", e);
+                    }
+                    return;
+                }
+            };
+
+            // Later events for the same path win, so one burst yields one event per file
+            let mut changes: Vec<(PathBuf, FileChangeKind)> = vec![];
+            for event in events {
+                for path in &event.paths {
+                    let Some(kind) = change_kind(&event.kind, path) else {
+                        continue;
+                    };
+                    changes.retain(|(existing, _)| existing != path);
+                    changes.push((path.clone(), kind));
+                }
+            }
+
+            let mut reindex = vec![];
+            for (path, kind) in changes {
+                let Ok(relative) = path.strip_prefix(&event_root) else {
+                    continue;
+                };
+                if is_always_ignored(relative)
+                    || gitignore
+                        .matched_path_or_any_parents(relative, path.is_dir())
+                        .is_ignore()
+                {
+                    continue;
+                }
+
+                let relative = relative.to_string_lossy().replace('\\', "/");
+                app.state::<DependencyGraphState>().invalidate(&project, &relative);
+                app.state::<ProjectStatsState>().invalidate(&project, &relative);
+                if !path.is_dir() {
+                    reindex.push((relative.clone(), kind));
+                }
+
+                let _ = app.emit(
+                    "file-change",
+                    FileChangeEvent {
+                        project_path: project.clone(),
+                        path: relative,
+                        kind,
+                    },
+                );
+            }
+
+            if app.state::<AIState>().auto_reindex() {
+                app.state::<ReindexState>().enqueue(&app, &project, reindex);
+            }
+        })
+        .map_err(|e| CodifyError::Io(format!("Failed to start file watcher: {}", e)))?;
+
+        debouncer
+            .watcher()
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(|e| CodifyError::Io(format!("Failed to watch {}: {}", project_path, e)))?;
+        debouncer.cache().add_root(&root, RecursiveMode::Recursive);
+
+        watchers.insert(project_path.to_string(), debouncer);
+        Ok(())
+    }
+
+    /// Stop watching a project; returns false when it wasn't being watched
+    pub fn stop(&self, project_path: &str) -> bool {
+        self.watchers.lock().unwrap().remove(project_path).is_some()
+    }
+}
+
+/// Collapse notify's detailed event kinds into what the file tree cares about
+fn change_kind(kind: &EventKind, path: &Path) -> Option<FileChangeKind> {
+    match kind {
+        EventKind::Create(_) => Some(FileChangeKind::Created),
+        EventKind::Remove(_) => Some(FileChangeKind::Deleted),
+        // A rename removes the old path and creates the new one
+        EventKind::Modify(ModifyKind::Name(_)) => Some(if path.exists() {
+            FileChangeKind::Created
+        } else {
+            FileChangeKind::Deleted
+        }),
+        EventKind::Modify(_) => Some(FileChangeKind::Modified),
+        _ => None,
+    }
+}
@@ -0,0 +1,127 @@
+use crate::ai::project_key;
+use crate::error::CodifyError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceFolder {
+    pub name: String, // Unique within the workspace; tags results from this root
+    pub path: String,
+}
+
+/// Several project roots opened together, e.g. the packages of a monorepo
+///
+/// Each root keeps its own embedding index; workspace-wide searches query
+/// every root and merge the results.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    pub folders: Vec<WorkspaceFolder>,
+}
+
+impl Workspace {
+    pub fn new(name: &str) -> Self {
+        Workspace {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            folders: vec![],
+        }
+    }
+
+    /// Add a root, named after its directory unless `name` is given
+    pub fn add_folder(&mut self, path: &str, name: Option<String>) -> Result<(), CodifyError> {
+        let dir = Path::new(path);
+        if !dir.is_dir() {
+            return Err(CodifyError::NotFound(format!(
+                "Project path does not exist: {}",
+                path
+            )));
+        }
+
+        let path = project_key(path);
+        if self.folders.iter().any(|folder| folder.path == path) {
+            return Err(CodifyError::InvalidInput(format!(
+                "Folder already exists in the workspace: {}",
+                path
+            )));
+        }
+
+        let name = name.unwrap_or_else(|| {
+            dir.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.clone())
+        });
+        if name.trim().is_empty() {
+            return Err(CodifyError::InvalidInput(
+                "Folder name must not be empty".to_string(),
+            ));
+        }
+        if self.folders.iter().any(|folder| folder.name == name) {
+            return Err(CodifyError::InvalidInput(format!(
+                "A folder named '{}' already exists in the workspace; pass a different name",
+                name
+            )));
+        }
+
+        self.folders.push(WorkspaceFolder { name, path });
+        Ok(())
+    }
+
+    pub fn remove_folder(&mut self, path: &str) -> Result<(), CodifyError> {
+        let path = project_key(path);
+        let before = self.folders.len();
+        self.folders.retain(|folder| folder.path != path);
+
+        if self.folders.len() == before {
+            return Err(CodifyError::NotFound(format!(
+                "Folder not found in the workspace: {}",
+                path
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A directory a file or search command runs over
+#[derive(Debug, Clone)]
+pub struct SearchRoot {
+    pub name: Option<String>, // The workspace folder name; None for a plain project
+    pub path: String,
+}
+
+/// The roots a command covers: every folder of `workspace_id`, or just
+/// `project_path` when no workspace is given
+pub fn search_roots(
+    workspaces: &BTreeMap<String, Workspace>,
+    project_path: Option<String>,
+    workspace_id: Option<String>,
+) -> Result<Vec<SearchRoot>, CodifyError> {
+    match (workspace_id, project_path) {
+        (Some(id), _) => {
+            let workspace = workspaces
+                .get(&id)
+                .ok_or_else(|| CodifyError::NotFound(format!("Workspace not found: {}", id)))?;
+            if workspace.folders.is_empty() {
+                return Err(CodifyError::InvalidInput(format!(
+                    "Invalid workspace: '{}' has no folders",
+                    workspace.name
+                )));
+            }
+
+            Ok(workspace
+                .folders
+                .iter()
+                .map(|folder| SearchRoot {
+                    name: Some(folder.name.clone()),
+                    path: folder.path.clone(),
+                })
+                .collect())
+        }
+        (None, Some(path)) => Ok(vec![SearchRoot { name: None, path }]),
+        (None, None) => Err(CodifyError::InvalidInput(
+            "Invalid arguments: pass project_path or workspace_id".to_string(),
+        )),
+    }
+}
@@ -98,6 +98,7 @@ export default function App() {
     if (currentSuggestion) {
       // In real implementation, this would insert the code at cursor position
       setCode(prevCode => prevCode + '\n\n// AI Generated:\n' + currentSuggestion.code);
+      AIService.selectCompletion(currentSuggestion.id).catch(() => {});
     }
     setShowSuggestion(false);
     setCurrentSuggestion(null);
@@ -111,7 +112,7 @@ export default function App() {
   const handleAIExplain = async () => {
     try {
       const explanation = await AIService.explainCode(code);
-      alert(explanation); // In real implementation, show in a proper modal
+      alert(explanation.summary); // In real implementation, show in a proper modal
     } catch (error) {
       console.error('AI explain failed:', error);
     }
@@ -119,8 +120,13 @@ export default function App() {
 
   const handleAIRefactor = async () => {
     try {
-      const suggestions = await AIService.suggestRefactor(code);
-      alert('Refactoring suggestions:\n' + suggestions.join('\n')); // In real implementation, show in a proper modal
+      const context: AIContext = {
+        project_path: "/current/project",
+        current_file: "app.tsx",
+        cursor_position: { line: 1, column: 1 }
+      };
+      const suggestions = await AIService.suggestRefactor(code, context);
+      alert('Refactoring suggestions:\n' + suggestions.map(s => s.title).join('\n')); // In real implementation, show in a proper modal
     } catch (error) {
       console.error('AI refactor failed:', error);
     }
@@ -129,7 +135,7 @@ export default function App() {
   const handleGenerateTests = async () => {
     try {
       const tests = await AIService.generateTests(code);
-      alert('Generated tests:\n' + tests); // In real implementation, show in a proper modal
+      alert('Generated tests:\n' + tests.code); // In real implementation, show in a proper modal
     } catch (error) {
       console.error('AI test generation failed:', error);
     }
@@ -133,7 +133,9 @@ export function Sidebar() {
   const getFileIcon = (fileType: string) => {
     switch (fileType) {
       case 'typescript':
+      case 'typescriptreact':
       case 'javascript':
+      case 'javascriptreact':
         return Code;
       case 'json':
         return Braces;
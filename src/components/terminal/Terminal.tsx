@@ -2,7 +2,7 @@
 
 import { useState, useRef, useEffect } from "react";
 import { Terminal as TerminalIcon, Zap, AlertTriangle } from "lucide-react";
-import { AIService } from "@/lib/tauri-api";
+import { AIService, errorMessage } from "@/lib/tauri-api";
 
 interface TerminalLine {
   type: "command" | "output" | "error" | "suggestion";
@@ -65,7 +65,7 @@ export function Terminal() {
     } catch (error) {
       setHistory(prev => [...prev, {
         type: "error",
-        content: `Error executing command: ${error}`,
+        content: `Error executing command: ${errorMessage(error)}`,
         timestamp: new Date()
       }]);
     } finally {